@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::OuterGap;
+use crate::{Anchor, LayoutAction, OuterGap, RuleSize};
 
 /// Cursor warp mode - controls when the mouse cursor follows focus
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -21,12 +21,148 @@ pub enum AutoRaiseMode {
     Enabled,
 }
 
+/// New window tag policy - controls which tags new windows are assigned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NewWindowTagPolicy {
+    /// Display's current visible tags
+    #[default]
+    Current,
+    /// Tag with the fewest windows on the display
+    Emptiest,
+    /// Tags the app was last used on
+    AppSticky,
+}
+
+/// Orphan policy - controls where windows go when their display is disconnected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanPolicy {
+    /// Move all windows to the main display
+    #[default]
+    Main,
+    /// Distribute windows evenly across remaining displays
+    Spread,
+    /// Move windows to a display showing one of their tags
+    ByTag,
+}
+
+/// Animation easing curve for window transitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationEasing {
+    Linear,
+    #[default]
+    EaseOut,
+    EaseInOut,
+}
+
+impl AnimationEasing {
+    /// Map linear progress (0.0..=1.0) to eased progress
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            AnimationEasing::Linear => t,
+            AnimationEasing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            AnimationEasing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// Gap scale mode - controls how outer gap values map to each display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GapScaleMode {
+    /// Gap values are points, identical on every display
+    #[default]
+    Off,
+    /// Gap values are physical pixels, divided by each display's backing scale factor
+    Auto,
+}
+
+/// Whether directional window-focus considers floating windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusFloatingMode {
+    /// Floating windows are candidates like tiled ones (default)
+    #[default]
+    On,
+    /// Floating windows are skipped
+    Off,
+    /// Floating windows are only reached when no tiled window is in that direction
+    TiledFirst,
+}
+
+/// Raise policy - which window is raised after each retile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RaisePolicy {
+    /// Keep the stacking the layout engine asks for (default)
+    #[default]
+    None,
+    /// Raise the focused window on top
+    FocusedTop,
+    /// Raise the window in the main (first) layout slot on top
+    MainTop,
+}
+
+/// Float visibility - whether floating windows follow tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FloatVisibility {
+    /// Floating windows are shown only while one of their tags is visible (default)
+    #[default]
+    Tagged,
+    /// Floating windows stay on their display whatever tags are visible
+    Always,
+}
+
+/// Points in the retile cycle where a user hook can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// Before the layout engines are asked for a layout
+    PreRetile,
+    /// After the layout has been applied
+    PostRetile,
+}
+
+/// Tag mode - whether tag-view switches one display or all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMode {
+    /// Each display has its own visible tags
+    #[default]
+    PerOutput,
+    /// Tag changes apply to every display at once
+    Global,
+}
+
+/// Hotkey backend - who listens for bound key presses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyBackend {
+    /// yashiki registers global hotkeys itself
+    #[default]
+    Internal,
+    /// No global hotkeys; bindings run via `trigger` (e.g. from Karabiner or skhd)
+    External,
+}
+
 /// Window status - indicates whether a window is managed or ignored
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum WindowStatus {
     Managed,
     Ignored,
+    /// One of yashiki's own windows
+    Internal,
 }
 
 /// Button state matcher for window rules
@@ -235,6 +371,15 @@ pub struct RuleMatcher {
     /// Match against zoom button state
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zoom_button: Option<ButtonState>,
+    /// Condition: visible tags of the window's display must intersect this bitmask
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when_tag: Option<u32>,
+    /// Condition: the window's display, by name pattern or by ID when the pattern is a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when_output: Option<GlobPattern>,
+    /// Match against the command names of the window process's ancestors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_exec: Option<GlobPattern>,
 }
 
 /// Extended window attributes for rule matching
@@ -248,6 +393,14 @@ pub struct ExtendedWindowAttributes {
     pub fullscreen_button: ButtonInfo,
     pub minimize_button: ButtonInfo,
     pub zoom_button: ButtonInfo,
+    /// Rule context: visible tags of the window's display (filled by the daemon)
+    pub visible_tags: u32,
+    /// Rule context: name of the window's display (filled by the daemon)
+    pub output_name: Option<String>,
+    /// Rule context: ID of the window's display (filled by the daemon)
+    pub output_id: Option<u32>,
+    /// Command names of the window process's ancestors, nearest first
+    pub parent_execs: Vec<String>,
 }
 
 impl RuleMatcher {
@@ -263,6 +416,9 @@ impl RuleMatcher {
             fullscreen_button: None,
             minimize_button: None,
             zoom_button: None,
+            when_tag: None,
+            when_output: None,
+            parent_exec: None,
         }
     }
 
@@ -282,6 +438,9 @@ impl RuleMatcher {
             fullscreen_button: None,
             minimize_button: None,
             zoom_button: None,
+            when_tag: None,
+            when_output: None,
+            parent_exec: None,
         }
     }
 
@@ -303,6 +462,9 @@ impl RuleMatcher {
             fullscreen_button: None,
             minimize_button: None,
             zoom_button: None,
+            when_tag: None,
+            when_output: None,
+            parent_exec: None,
         }
     }
 
@@ -330,9 +492,29 @@ impl RuleMatcher {
             fullscreen_button,
             minimize_button,
             zoom_button,
+            when_tag: None,
+            when_output: None,
+            parent_exec: None,
         }
     }
 
+    /// Restrict this matcher to a tag and/or output context
+    pub fn with_conditions(
+        mut self,
+        when_tag: Option<u32>,
+        when_output: Option<GlobPattern>,
+    ) -> Self {
+        self.when_tag = when_tag;
+        self.when_output = when_output;
+        self
+    }
+
+    /// Only match windows whose process was spawned (directly or not) by a matching command
+    pub fn with_parent_exec(mut self, parent_exec: Option<GlobPattern>) -> Self {
+        self.parent_exec = parent_exec;
+        self
+    }
+
     /// Check if this matcher matches the given window attributes (basic version without extended attrs).
     /// For subrole matching, the "AX" prefix is optional in both pattern and value.
     pub fn matches(
@@ -418,6 +600,32 @@ impl RuleMatcher {
             .map(|expected| ext.zoom_button.matches(expected))
             .unwrap_or(true);
 
+        // Context conditions
+        let when_tag_matches = self
+            .when_tag
+            .map(|mask| ext.visible_tags & mask != 0)
+            .unwrap_or(true);
+        let when_output_matches = self
+            .when_output
+            .as_ref()
+            .map(|p| {
+                let id_matches = p
+                    .pattern()
+                    .parse::<u32>()
+                    .is_ok_and(|id| ext.output_id == Some(id));
+                id_matches
+                    || ext
+                        .output_name
+                        .as_deref()
+                        .is_some_and(|name| p.matches(name))
+            })
+            .unwrap_or(true);
+        let parent_exec_matches = self
+            .parent_exec
+            .as_ref()
+            .map(|p| ext.parent_execs.iter().any(|name| p.matches(name)))
+            .unwrap_or(true);
+
         app_matches
             && app_id_matches
             && title_matches
@@ -428,6 +636,9 @@ impl RuleMatcher {
             && fullscreen_button_matches
             && minimize_button_matches
             && zoom_button_matches
+            && when_tag_matches
+            && when_output_matches
+            && parent_exec_matches
     }
 
     /// Check if window level matches the expected value
@@ -473,6 +684,11 @@ impl RuleMatcher {
         let title_spec = self.title.as_ref().map(|p| p.specificity()).unwrap_or(0);
         let ax_id_spec = self.ax_id.as_ref().map(|p| p.specificity()).unwrap_or(0);
         let subrole_spec = self.subrole.as_ref().map(|p| p.specificity()).unwrap_or(0);
+        let parent_exec_spec = self
+            .parent_exec
+            .as_ref()
+            .map(|p| p.specificity())
+            .unwrap_or(0);
 
         // Window level specificity
         let window_level_spec = self
@@ -501,13 +717,23 @@ impl RuleMatcher {
             }
             + if self.zoom_button.is_some() { 20 } else { 0 };
 
+        // Conditions: conditional rules take priority over unconditional ones
+        let condition_spec = if self.when_tag.is_some() { 20 } else { 0 }
+            + self
+                .when_output
+                .as_ref()
+                .map(|p| p.specificity())
+                .unwrap_or(0);
+
         app_spec
             + app_id_spec
             + title_spec
             + ax_id_spec
             + subrole_spec
+            + parent_exec_spec
             + window_level_spec
             + button_spec
+            + condition_spec
     }
 
     /// Check if this matcher has any extended matchers (window_level or buttons)
@@ -520,6 +746,19 @@ impl RuleMatcher {
     }
 }
 
+/// What window-close does for a window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseBehavior {
+    /// Press the close button, falling back to Cmd-W
+    #[default]
+    Close,
+    /// Hide the application instead of closing the window
+    Hide,
+    /// Quit the application
+    Quit,
+}
+
 /// Action to apply when a rule matches
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
@@ -534,10 +773,37 @@ pub enum RuleAction {
     Tags { tags: u32 },
     /// Set initial display
     Output { output: OutputSpecifier },
-    /// Set initial position (for floating windows)
-    Position { x: i32, y: i32 },
+    /// Set initial position (for floating windows).
+    /// With an anchor, x and y are offsets from that edge or corner of the display.
+    Position {
+        x: i32,
+        y: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        anchor: Option<Anchor>,
+    },
     /// Set initial dimensions (for floating windows)
-    Dimensions { width: u32, height: u32 },
+    Dimensions { width: RuleSize, height: RuleSize },
+    /// Override what window-close does
+    CloseBehavior { behavior: CloseBehavior },
+    /// Position the window when tiling, but keep its current size
+    NoManageResize,
+    /// Sync the app's windows at most once per interval on move/resize events
+    Throttle { ms: u32 },
+    /// Opacity of the window while it is not focused, in percent
+    Opacity { percent: u32 },
+}
+
+impl RuleAction {
+    /// Whether a `--dynamic` rule can re-apply this action to a live window
+    pub fn supports_dynamic(&self) -> bool {
+        matches!(
+            self,
+            RuleAction::Float
+                | RuleAction::NoFloat
+                | RuleAction::Tags { .. }
+                | RuleAction::Output { .. }
+        )
+    }
 }
 
 /// A window rule: a matcher + action pair
@@ -545,11 +811,23 @@ pub enum RuleAction {
 pub struct WindowRule {
     pub matcher: RuleMatcher,
     pub action: RuleAction,
+    /// Re-applied when a managed window's title changes to match, not only on creation
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dynamic: bool,
 }
 
 impl WindowRule {
     pub fn new(matcher: RuleMatcher, action: RuleAction) -> Self {
-        Self { matcher, action }
+        Self {
+            matcher,
+            action,
+            dynamic: false,
+        }
+    }
+
+    pub fn with_dynamic(mut self, dynamic: bool) -> Self {
+        self.dynamic = dynamic;
+        self
     }
 
     pub fn specificity(&self) -> u32 {
@@ -580,9 +858,28 @@ pub struct RuleInfo {
     pub minimize_button: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zoom_button: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when_tag: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when_output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_exec: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dynamic: bool,
     pub action: String,
 }
 
+/// Suggested rule for a managed window with unusual attributes (rule-suggest output)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleSuggestion {
+    pub window_id: u32,
+    pub app_name: String,
+    /// Why the window looks unusual
+    pub reason: String,
+    /// CLI arguments for the suggested rule, e.g. `rule-add --app-id ... float`
+    pub command: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Command {
@@ -593,8 +890,56 @@ pub enum Command {
     WindowSwap {
         direction: Direction,
     },
-    WindowClose,
+    /// Focus a dialog's parent window, or the main window from a stacked window
+    WindowFocusParent,
+    /// Focus the focused window's dialog, or the last stacked window from the main window
+    WindowFocusChild,
+    /// Focus a window by ID, switching its display to the window's tag if it is hidden
+    WindowFocusId {
+        window_id: u32,
+    },
+    /// Focus the next visible window of an app, wrapping
+    AppCycle {
+        app_id: String,
+    },
+    WindowClose {
+        #[serde(default)]
+        force_quit_app: bool,
+    },
+    /// Hide the focused window's app (NSRunningApplication hide).
+    /// Its windows keep their tags and are left out of layouts until the app returns.
+    WindowHideApp,
+    /// Unhide the app most recently hidden with window-hide-app (all of them with `all`)
+    WindowUnhideApp {
+        #[serde(default)]
+        all: bool,
+    },
     WindowToggleFloat,
+    /// Exempt a window (focused window if window_id is None) from retile geometry
+    WindowToggleManual {
+        #[serde(default)]
+        window_id: Option<u32>,
+    },
+    /// Mark a window (focused window if window_id is None) as needing attention.
+    /// Cleared when the window is focused.
+    WindowSetUrgent {
+        #[serde(default)]
+        window_id: Option<u32>,
+        enabled: bool,
+    },
+    WindowZoom,
+    /// Move a window to the focused output and show it there (most recently focused
+    /// window on another output if window_id is None)
+    WindowBring {
+        #[serde(default)]
+        window_id: Option<u32>,
+    },
+    /// Swap the focused floating window with the main tiled window
+    WindowSwapFloat,
+    /// Hide the focused window off every tag, pushing it on the stash stack
+    WindowStash,
+    /// Bring the most recently stashed window back on the focused display's tags
+    WindowUnstash,
     WindowToggleFullscreen,
     WindowMoveToTag {
         tags: u32,
@@ -613,6 +958,30 @@ pub enum Command {
         output: Option<OutputSpecifier>,
     },
     TagViewLast,
+    /// Mark tags as float-only (windows on them always float), or unmark them
+    TagSetFloat {
+        tags: u32,
+        enabled: bool,
+    },
+    /// Input source (e.g. `com.apple.keylayout.US`) selected when the tags are viewed
+    TagSetInputSource {
+        tags: u32,
+        input_source: Option<String>,
+    },
+    TagGetInputSource {
+        tags: u32,
+    },
+    /// Move some tiled windows from `from` tags to `to` tags:
+    /// the last `count` in layout order, or every other one
+    TagDistribute {
+        from: u32,
+        to: u32,
+        #[serde(default)]
+        count: Option<u32>,
+        #[serde(default)]
+        every_other: bool,
+        output: Option<OutputSpecifier>,
+    },
 
     // Output (display) operations
     OutputFocus {
@@ -621,6 +990,11 @@ pub enum Command {
     OutputSend {
         direction: OutputDirection,
     },
+    /// Stop managing windows on an output until re-enabled, or resume managing them
+    OutputIgnore {
+        output: Option<OutputSpecifier>,
+        enabled: bool,
+    },
 
     // Layout operations
     LayoutSetDefault {
@@ -640,19 +1014,52 @@ pub enum Command {
         cmd: String,
         args: Vec<String>,
     },
+    /// Standard adjustment mapped to the current layout engine's own command
+    LayoutAction {
+        action: LayoutAction,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Retile one output, or every output when none is given (or with `all`)
     Retile {
         output: Option<OutputSpecifier>,
+        #[serde(default)]
+        all: bool,
     },
 
     // Keybinding operations
     Bind {
         key: String,
         action: Box<Command>,
+        /// Run on a second press of the key within the double-press timeout
+        #[serde(default)]
+        double: bool,
+        /// Still deliver the keystroke to the focused app
+        #[serde(default)]
+        passthrough: bool,
     },
     Unbind {
         key: String,
     },
     ListBindings,
+    TriggerBinding {
+        key: String,
+    },
+    SetHotkeyBackend {
+        backend: HotkeyBackend,
+    },
+    GetHotkeyBackend,
+    /// Stop handling hotkeys (e.g. for games and VMs), except the listed keys and
+    /// keys bound to bindings-resume or bindings-toggle
+    BindingsSuspend {
+        #[serde(default)]
+        except: Vec<String>,
+    },
+    BindingsResume,
+    BindingsToggle {
+        #[serde(default)]
+        except: Vec<String>,
+    },
 
     // Queries
     ListWindows {
@@ -660,21 +1067,59 @@ pub enum Command {
         all: bool,
         #[serde(default)]
         debug: bool,
+        #[serde(default)]
+        why: bool,
     },
     ListOutputs,
     GetState,
+    /// Full state (windows, displays, orders, rules, gaps) for bug reports
+    StateDump,
+    /// Per-command latency percentiles
+    Stats,
+    /// Recent asynchronous failures (layout engine errors, windows that could not be moved)
+    LastErrors,
+    /// Time window listing, AX attribute fetches and move/resize per app
+    BenchAx {
+        #[serde(default = "default_bench_iterations")]
+        iterations: u32,
+    },
     FocusedWindow,
+    /// The focused display
+    FocusedOutput,
+    /// Detailed info for one window (focused window if window_id is None)
+    WindowInfo {
+        #[serde(default)]
+        window_id: Option<u32>,
+    },
 
     // Exec
     Exec {
         command: String,
         #[serde(default)]
         track: bool,
+        /// When set, `command` is spawned directly with these arguments instead of via the shell
+        #[serde(default)]
+        args: Option<Vec<String>>,
+        /// Skip launching if a process started with the same name is still running
+        #[serde(default)]
+        unique: Option<String>,
     },
+    /// Focus a window matching every given matcher, or run `command` if there is none
     ExecOrFocus {
-        app_name: String,
+        /// Exact app display name
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_name: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_id: Option<GlobPattern>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<GlobPattern>,
         command: String,
+        /// Pull a hidden window to the focused display's visible tags instead of
+        /// switching its display to the window's tag
+        #[serde(default)]
+        bring: bool,
     },
+    ListExecs,
 
     // Exec path
     GetExecPath,
@@ -696,6 +1141,7 @@ pub enum Command {
     },
     ListRules,
     ApplyRules,
+    RuleSuggest,
 
     // Cursor warp
     SetCursorWarp {
@@ -710,13 +1156,125 @@ pub enum Command {
     },
     GetAutoRaise,
 
+    // New window tag policy
+    SetNewWindowTag {
+        policy: NewWindowTagPolicy,
+    },
+    GetNewWindowTag,
+
+    // Orphan policy
+    SetOrphanPolicy {
+        policy: OrphanPolicy,
+    },
+    GetOrphanPolicy,
+
+    // Tag mode
+    SetTagMode {
+        mode: TagMode,
+    },
+    GetTagMode,
+
+    // Animation (0ms duration disables)
+    SetAnimation {
+        duration_ms: u32,
+        easing: AnimationEasing,
+    },
+    GetAnimation,
+
+    // Menu bar indicator
+    SetMenubarIndicator {
+        enabled: bool,
+    },
+    GetMenubarIndicator,
+
+    // App hiding
+    SetHideApps {
+        enabled: bool,
+    },
+    GetHideApps,
+
+    // Shadows of tiled windows
+    SetWindowShadows {
+        enabled: bool,
+    },
+    GetWindowShadows,
+
+    // Tiles grown into the gaps between them, so rounded corners look evenly spaced
+    SetCornerCompensation {
+        px: u32,
+    },
+    GetCornerCompensation,
+
+    // Apps excluded from observation and management (glob on bundle identifier)
+    ObserverExclude {
+        app_id: GlobPattern,
+    },
+    ObserverInclude {
+        app_id: GlobPattern,
+    },
+    ListObserverExcludes,
+
+    // Gap scale
+    SetGapScale {
+        mode: GapScaleMode,
+    },
+    GetGapScale,
+
+    // Directional focus
+    SetFocusIncludeFloating {
+        mode: FocusFloatingMode,
+    },
+    GetFocusIncludeFloating,
+
+    // Raise policy
+    SetRaisePolicy {
+        policy: RaisePolicy,
+    },
+    GetRaisePolicy,
+
     // Outer gap
     SetOuterGap {
         values: Vec<String>,
     },
     GetOuterGap,
 
+    // Tag change hook (shell command run when a display's visible tags change)
+    SetTagChangeHook {
+        command: Option<String>,
+    },
+    GetTagChangeHook,
+
+    // Float visibility
+    SetFloatVisibility {
+        mode: FloatVisibility,
+    },
+    GetFloatVisibility,
+
+    // Retile hooks (shell command run with a JSON context on stdin)
+    SetHook {
+        event: HookEvent,
+        command: Option<String>,
+    },
+    GetHook {
+        event: HookEvent,
+    },
+
+    // Arrangements (window tags, displays, layout order and floating frames, by name)
+    ArrangementSave {
+        name: String,
+    },
+    ArrangementRestore {
+        name: String,
+    },
+
+    // Pause (suspend retiling and rule-driven window moves until resumed)
+    Pause,
+    Resume,
+
     // Control
+    /// Reply once the window animations started by earlier commands have finished.
+    /// Sent after another command by `--wait`; effects themselves run before each reply.
+    Wait,
     Quit,
 }
 
@@ -745,28 +1303,155 @@ pub enum OutputSpecifier {
     Name(String),
 }
 
+impl Command {
+    /// Serialized type name, e.g. `tag_view`
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Response {
     Ok,
-    Error { message: String },
-    Windows { windows: Vec<WindowInfo> },
-    Outputs { outputs: Vec<OutputInfo> },
-    State { state: StateInfo },
-    Bindings { bindings: Vec<BindingInfo> },
-    Rules { rules: Vec<RuleInfo> },
-    WindowId { id: Option<u32> },
-    Layout { layout: String },
-    ExecPath { path: String },
-    CursorWarp { mode: CursorWarpMode },
-    AutoRaise { mode: AutoRaiseMode, delay_ms: u64 },
-    OuterGap { outer_gap: OuterGap },
+    Error {
+        message: String,
+    },
+    Windows {
+        windows: Vec<WindowInfo>,
+    },
+    Outputs {
+        outputs: Vec<OutputInfo>,
+    },
+    State {
+        state: StateInfo,
+    },
+    StateDump {
+        dump: StateDump,
+    },
+    Stats {
+        commands: Vec<CommandLatency>,
+    },
+    LastErrors {
+        errors: Vec<ErrorInfo>,
+    },
+    Bench {
+        report: BenchReport,
+    },
+    Bindings {
+        bindings: Vec<BindingInfo>,
+    },
+    Execs {
+        execs: Vec<ExecInfo>,
+    },
+    Rules {
+        rules: Vec<RuleInfo>,
+    },
+    RuleSuggestions {
+        suggestions: Vec<RuleSuggestion>,
+    },
+    WindowId {
+        id: Option<u32>,
+    },
+    Output {
+        output: OutputInfo,
+    },
+    WindowDetails {
+        window: WindowDetails,
+    },
+    Layout {
+        layout: String,
+    },
+    ExecPath {
+        path: String,
+    },
+    CursorWarp {
+        mode: CursorWarpMode,
+    },
+    AutoRaise {
+        mode: AutoRaiseMode,
+        delay_ms: u64,
+    },
+    NewWindowTag {
+        policy: NewWindowTagPolicy,
+    },
+    OrphanPolicy {
+        policy: OrphanPolicy,
+    },
+    TagMode {
+        mode: TagMode,
+    },
+    Animation {
+        duration_ms: u32,
+        easing: AnimationEasing,
+    },
+    MenubarIndicator {
+        enabled: bool,
+    },
+    HideApps {
+        enabled: bool,
+    },
+    WindowShadows {
+        enabled: bool,
+    },
+    CornerCompensation {
+        px: u32,
+    },
+    ObserverExcludes {
+        app_ids: Vec<String>,
+    },
+    GapScale {
+        mode: GapScaleMode,
+    },
+    RaisePolicy {
+        policy: RaisePolicy,
+    },
+    FocusIncludeFloating {
+        mode: FocusFloatingMode,
+    },
+    Retile {
+        displays: Vec<RetileInfo>,
+    },
+    OuterGap {
+        outer_gap: OuterGap,
+    },
+    TagChangeHook {
+        command: Option<String>,
+    },
+    Hook {
+        command: Option<String>,
+    },
+    FloatVisibility {
+        mode: FloatVisibility,
+    },
+    InputSource {
+        input_source: Option<String>,
+    },
+    HotkeyBackend {
+        backend: HotkeyBackend,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BindingInfo {
     pub key: String,
     pub action: String,
+    #[serde(default)]
+    pub double: bool,
+    #[serde(default)]
+    pub passthrough: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecInfo {
+    pub pid: u32,
+    pub command: String,
+    pub name: Option<String>,
+    /// Terminated when yashiki quits
+    pub track: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -780,6 +1465,20 @@ pub struct OutputInfo {
     pub is_main: bool,
     pub visible_tags: u32,
     pub is_focused: bool,
+    /// Backing scale factor (2.0 for Retina)
+    pub scale_factor: f64,
+    /// Rotation in degrees (0, 90, 180 or 270)
+    #[serde(default)]
+    pub rotation: u32,
+    /// Refresh rate in Hz (0 when the display doesn't report one)
+    #[serde(default)]
+    pub refresh_rate: f64,
+    /// Hardware UUID, distinguishes identical-model monitors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    /// Tiled window IDs in layout order (as of the last retile)
+    #[serde(default)]
+    pub window_order: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -798,6 +1497,11 @@ pub struct WindowInfo {
     pub is_focused: bool,
     pub is_floating: bool,
     pub is_fullscreen: bool,
+    /// In native macOS fullscreen on its own Space
+    #[serde(default)]
+    pub is_native_fullscreen: bool,
+    #[serde(default)]
+    pub is_manual: bool,
     pub output_id: u32,
     // Optional status field (present when --all is used)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -817,38 +1521,250 @@ pub struct WindowInfo {
     pub minimize_button: Option<ButtonInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub zoom_button: Option<ButtonInfo>,
+    // Applied rule indices in list-rules order (present when --why is used)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_rules: Option<Vec<usize>>,
 }
 
+/// Full daemon state (state-dump output), replayable with `yashiki simulate --state`
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StateInfo {
-    pub visible_tags: u32,
+pub struct StateDump {
+    /// Managed windows, by ID
+    pub windows: Vec<WindowDetails>,
+    /// Displays with their visible tags and layout order, by ID
+    pub outputs: Vec<OutputInfo>,
+    /// (output ID, layout) of each output that has a layout set
+    #[serde(default)]
+    pub output_layouts: Vec<(u32, String)>,
     pub focused_window_id: Option<u32>,
-    pub window_count: usize,
+    pub focused_output_id: u32,
     pub default_layout: String,
-    pub current_layout: Option<String>,
+    #[serde(default)]
+    pub float_tags: u32,
+    /// Stashed window IDs, most recent last
+    #[serde(default)]
+    pub stash: Vec<u32>,
+    #[serde(default)]
+    pub rules: Vec<WindowRule>,
+    #[serde(default)]
+    pub outer_gap: OuterGap,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_command_tag_view_serialization() {
-        let cmd = Command::TagView {
-            tags: 1,
-            output: None,
-        };
-        let json = serde_json::to_string(&cmd).unwrap();
-        assert!(json.contains("\"type\":\"tag_view\""));
-        assert!(json.contains("\"tags\":1"));
+/// Window frame in screen coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameInfo {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
 
-        let deserialized: Command = serde_json::from_str(&json).unwrap();
-        match deserialized {
+/// Every tracked attribute of a single window (window-info output)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowDetails {
+    pub id: u32,
+    pub pid: i32,
+    pub title: String,
+    pub app_name: String,
+    pub app_id: Option<String>,
+    pub ax_id: Option<String>,
+    pub subrole: Option<String>,
+    pub window_level: i32,
+    pub tags: u32,
+    pub output_id: u32,
+    pub frame: FrameInfo,
+    /// Frame before the window was hidden offscreen
+    pub saved_frame: Option<FrameInfo>,
+    pub is_focused: bool,
+    pub is_floating: bool,
+    pub is_fullscreen: bool,
+    #[serde(default)]
+    pub is_native_fullscreen: bool,
+    #[serde(default)]
+    pub is_manual: bool,
+    pub is_hidden: bool,
+    pub orphaned_from: Option<u32>,
+    pub close_button: ButtonInfo,
+    pub fullscreen_button: ButtonInfo,
+    pub minimize_button: ButtonInfo,
+    pub zoom_button: ButtonInfo,
+    /// Rules whose matcher currently matches this window (in precedence order)
+    pub matched_rules: Vec<RuleInfo>,
+    /// Indices (in list-rules order) of the rules that were applied to this window
+    pub applied_rules: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateInfo {
+    pub visible_tags: u32,
+    pub focused_window_id: Option<u32>,
+    pub window_count: usize,
+    pub default_layout: String,
+    pub current_layout: Option<String>,
+    /// Tiled window IDs of the focused display in layout order
+    #[serde(default)]
+    pub window_order: Vec<u32>,
+    #[serde(default)]
+    pub paused: bool,
+    /// Main run loop wakeups per second, averaged over the last 10 seconds
+    #[serde(default)]
+    pub wakeups_per_second: f64,
+    /// Float-only tags bitmask
+    #[serde(default)]
+    pub float_tags: u32,
+    /// macOS Stage Manager is enabled (conflicts with tiling)
+    #[serde(default)]
+    pub stage_manager: bool,
+    /// Managed windows per tag across all displays, occupied tags only
+    #[serde(default)]
+    pub tag_occupancy: Vec<TagOccupancy>,
+    /// Hotkeys are suspended by bindings-suspend
+    #[serde(default)]
+    pub bindings_suspended: bool,
+    /// The screen is locked and window events are ignored until unlock
+    #[serde(default)]
+    pub screen_locked: bool,
+}
+
+/// Number of managed windows on one tag (get-state and tags_state events)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagOccupancy {
+    /// Tag number (1-32)
+    pub tag: u32,
+    pub windows: usize,
+    /// Windows marked urgent with window-set-urgent
+    #[serde(default)]
+    pub urgent: usize,
+}
+
+/// What a retile did on one display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetileInfo {
+    pub display_id: u32,
+    /// Windows laid out by the layout engine
+    pub windows: usize,
+    /// Windows whose frame changed
+    pub moved: usize,
+    pub duration_ms: f64,
+}
+
+/// Context written to a retile hook's stdin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookContext {
+    pub event: HookEvent,
+    pub display_ids: Vec<u32>,
+    /// What the retile did on each display (post-retile only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub displays: Vec<RetileInfo>,
+}
+
+/// Latency of one command type over its recent runs (stats output)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLatency {
+    /// Command type as serialized, e.g. `tag_view`
+    pub command: String,
+    pub count: u64,
+    /// Full handling time, including effects
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    /// Time spent applying effects (window moves, retiles, focus)
+    pub effects_p50_ms: f64,
+    pub effects_p95_ms: f64,
+}
+
+/// An asynchronous failure kept for `last-errors`, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    /// What failed, e.g. `layout` or `apply_layout`
+    pub source: String,
+    pub message: String,
+    pub seconds_ago: u64,
+}
+
+fn default_bench_iterations() -> u32 {
+    5
+}
+
+/// AX throughput measured by `bench ax`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub iterations: u32,
+    /// Listing the on-screen windows (CGWindowList)
+    pub on_screen_p50_ms: f64,
+    pub on_screen_max_ms: f64,
+    pub apps: Vec<AppBench>,
+}
+
+/// Per-app AX timings (bench output)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppBench {
+    pub pid: i32,
+    pub app_name: String,
+    pub windows: usize,
+    /// Fetching extended attributes (subrole, buttons, level) of one window
+    pub attributes_p50_ms: f64,
+    pub attributes_max_ms: f64,
+    /// Moving and resizing one managed window in place; None without visible managed windows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_resize_p50_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub move_resize_max_ms: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_name() {
+        let cmd = Command::TagView {
+            tags: 1,
+            output: None,
+        };
+        assert_eq!(cmd.name(), "tag_view");
+        assert_eq!(Command::Stats.name(), "stats");
+    }
+
+    #[test]
+    fn test_command_tag_view_serialization() {
+        let cmd = Command::TagView {
+            tags: 1,
+            output: None,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"tag_view\""));
+        assert!(json.contains("\"tags\":1"));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
             Command::TagView { tags, .. } => assert_eq!(tags, 1),
             _ => panic!("Wrong variant"),
         }
     }
 
+    #[test]
+    fn test_command_from_hand_written_json() {
+        let cmd: Command = serde_json::from_str(r#"{"type":"tag_view","tags":2}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::TagView {
+                tags: 2,
+                output: None
+            }
+        ));
+
+        let json = r#"{"type":"layout_command","cmd":"set-main-ratio","args":["0.6"]}"#;
+        let cmd: Command = serde_json::from_str(json).unwrap();
+        match cmd {
+            Command::LayoutCommand { layout, cmd, args } => {
+                assert_eq!(layout, None);
+                assert_eq!(cmd, "set-main-ratio");
+                assert_eq!(args, vec!["0.6"]);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
     #[test]
     fn test_command_window_focus_serialization() {
         let cmd = Command::WindowFocus {
@@ -859,6 +1775,78 @@ mod tests {
         assert!(json.contains("\"direction\":\"next\""));
     }
 
+    #[test]
+    fn test_command_window_zoom_serialization() {
+        let json = serde_json::to_string(&Command::WindowZoom).unwrap();
+        assert_eq!(json, "{\"type\":\"window_zoom\"}");
+
+        let json = serde_json::to_string(&Command::WindowSwapFloat).unwrap();
+        assert_eq!(json, "{\"type\":\"window_swap_float\"}");
+
+        let json = serde_json::to_string(&Command::WindowFocusParent).unwrap();
+        assert_eq!(json, "{\"type\":\"window_focus_parent\"}");
+
+        let json = serde_json::to_string(&Command::WindowUnstash).unwrap();
+        assert_eq!(json, "{\"type\":\"window_unstash\"}");
+    }
+
+    #[test]
+    fn test_command_window_hide_app_serialization() {
+        let json = serde_json::to_string(&Command::WindowHideApp).unwrap();
+        assert_eq!(json, r#"{"type":"window_hide_app"}"#);
+
+        let cmd: Command = serde_json::from_str(r#"{"type":"window_unhide_app"}"#).unwrap();
+        assert!(matches!(cmd, Command::WindowUnhideApp { all: false }));
+        let json = serde_json::to_string(&Command::WindowUnhideApp { all: true }).unwrap();
+        assert_eq!(json, r#"{"type":"window_unhide_app","all":true}"#);
+    }
+
+    #[test]
+    fn test_last_errors_serialization() {
+        let json = serde_json::to_string(&Command::LastErrors).unwrap();
+        assert_eq!(json, r#"{"type":"last_errors"}"#);
+
+        let response = Response::LastErrors {
+            errors: vec![ErrorInfo {
+                source: "layout".to_string(),
+                message: "engine exited".to_string(),
+                seconds_ago: 3,
+            }],
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"last_errors","errors":[{"source":"layout","message":"engine exited","seconds_ago":3}]}"#
+        );
+    }
+
+    #[test]
+    fn test_command_wait_serialization() {
+        let json = serde_json::to_string(&Command::Wait).unwrap();
+        assert_eq!(json, r#"{"type":"wait"}"#);
+        let cmd: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(cmd, Command::Wait));
+    }
+
+    #[test]
+    fn test_command_window_close_serialization() {
+        let cmd = Command::WindowClose {
+            force_quit_app: true,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"window_close\""));
+        assert!(json.contains("\"force_quit_app\":true"));
+
+        // force_quit_app defaults to false for older clients
+        let cmd: Command = serde_json::from_str(r#"{"type":"window_close"}"#).unwrap();
+        assert!(matches!(
+            cmd,
+            Command::WindowClose {
+                force_quit_app: false
+            }
+        ));
+    }
+
     #[test]
     fn test_command_bind_serialization() {
         let cmd = Command::Bind {
@@ -867,13 +1855,22 @@ mod tests {
                 tags: 1,
                 output: None,
             }),
+            double: false,
+            passthrough: true,
         };
         let json = serde_json::to_string(&cmd).unwrap();
 
         let deserialized: Command = serde_json::from_str(&json).unwrap();
         match deserialized {
-            Command::Bind { key, action } => {
+            Command::Bind {
+                key,
+                action,
+                double,
+                passthrough,
+            } => {
                 assert_eq!(key, "alt-1");
+                assert!(!double);
+                assert!(passthrough);
                 match *action {
                     Command::TagView { tags, .. } => assert_eq!(tags, 1),
                     _ => panic!("Wrong inner variant"),
@@ -883,6 +1880,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_command_bind_double() {
+        let json =
+            r#"{"type":"bind","key":"alt-1","action":{"type":"tag_view_last"},"double":true}"#;
+        match serde_json::from_str(json).unwrap() {
+            Command::Bind { action, double, .. } => {
+                assert!(matches!(*action, Command::TagViewLast));
+                assert!(double);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_response_retile_serialization() {
+        // Older clients send retile without `all`
+        let cmd: Command = serde_json::from_str(r#"{"type":"retile","output":null}"#).unwrap();
+        assert!(matches!(cmd, Command::Retile { all: false, .. }));
+
+        let resp = Response::Retile {
+            displays: vec![RetileInfo {
+                display_id: 1,
+                windows: 3,
+                moved: 2,
+                duration_ms: 4.5,
+            }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"moved\":2"));
+        match serde_json::from_str(&json).unwrap() {
+            Response::Retile { displays } => assert_eq!(displays[0].windows, 3),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_tag_distribute_serialization() {
+        let json = r#"{"type":"tag_distribute","from":1,"to":2,"count":3,"output":null}"#;
+        match serde_json::from_str(json).unwrap() {
+            Command::TagDistribute {
+                from,
+                to,
+                count,
+                every_other,
+                output,
+            } => {
+                assert_eq!((from, to, count), (1, 2, Some(3)));
+                assert!(!every_other);
+                assert!(output.is_none());
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
     #[test]
     fn test_command_layout_command_serialization() {
         let cmd = Command::LayoutCommand {
@@ -992,6 +2043,8 @@ mod tests {
                 is_focused: true,
                 is_floating: false,
                 is_fullscreen: false,
+                is_native_fullscreen: false,
+                is_manual: false,
                 output_id: 1,
                 status: None,
                 ax_id: None,
@@ -1001,6 +2054,7 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                applied_rules: None,
             }],
         };
         let json = serde_json::to_string(&resp).unwrap();
@@ -1028,6 +2082,18 @@ mod tests {
                 window_count: 5,
                 default_layout: "tatami".to_string(),
                 current_layout: Some("byobu".to_string()),
+                window_order: vec![3, 1],
+                paused: true,
+                wakeups_per_second: 1.5,
+                float_tags: 0,
+                stage_manager: false,
+                tag_occupancy: vec![TagOccupancy {
+                    tag: 2,
+                    windows: 3,
+                    urgent: 1,
+                }],
+                bindings_suspended: false,
+                screen_locked: true,
             },
         };
         let json = serde_json::to_string(&resp).unwrap();
@@ -1038,8 +2104,20 @@ mod tests {
                 assert_eq!(state.visible_tags, 0b0011);
                 assert_eq!(state.focused_window_id, Some(42));
                 assert_eq!(state.window_count, 5);
+                assert!(state.paused);
+                assert!(state.screen_locked);
+                assert_eq!(
+                    state.tag_occupancy,
+                    vec![TagOccupancy {
+                        tag: 2,
+                        windows: 3,
+                        urgent: 1,
+                    }]
+                );
+                assert_eq!(state.wakeups_per_second, 1.5);
                 assert_eq!(state.default_layout, "tatami");
                 assert_eq!(state.current_layout, Some("byobu".to_string()));
+                assert_eq!(state.window_order, vec![3, 1]);
             }
             _ => panic!("Wrong variant"),
         }
@@ -1148,12 +2226,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_response_output_serialization() {
+        let resp = Response::Output {
+            output: OutputInfo {
+                id: 2,
+                name: "DELL U2723QE".to_string(),
+                x: 1512,
+                y: 0,
+                width: 2560,
+                height: 1440,
+                is_main: false,
+                visible_tags: 4,
+                is_focused: true,
+                scale_factor: 1.0,
+                rotation: 0,
+                refresh_rate: 60.0,
+                uuid: None,
+                window_order: vec![10, 11],
+            },
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"type\":\"output\""));
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Response::Output { output } => {
+                assert_eq!(output.id, 2);
+                assert_eq!(output.name, "DELL U2723QE");
+                assert_eq!(output.visible_tags, 4);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_response_state_dump_serialization() {
+        let resp = Response::StateDump {
+            dump: StateDump {
+                windows: vec![],
+                outputs: vec![],
+                output_layouts: vec![(2, "byobu".to_string())],
+                focused_window_id: Some(10),
+                focused_output_id: 2,
+                default_layout: "tatami".to_string(),
+                float_tags: 0b100,
+                stash: vec![11],
+                rules: vec![],
+                outer_gap: OuterGap::all(8),
+            },
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"type\":\"state_dump\""));
+        assert!(json.contains("\"output_layouts\":[[2,\"byobu\"]]"));
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Response::StateDump { dump } => {
+                assert_eq!(dump.output_layouts, vec![(2, "byobu".to_string())]);
+                assert_eq!(dump.focused_window_id, Some(10));
+                assert_eq!(dump.stash, vec![11]);
+                assert_eq!(dump.outer_gap, OuterGap::all(8));
+            }
+            _ => panic!("Wrong variant"),
+        }
+
+        // Fields added later default when missing
+        let dump: StateDump = serde_json::from_str(
+            r#"{"windows":[],"outputs":[],"focused_window_id":null,"focused_output_id":1,"default_layout":"tatami"}"#,
+        )
+        .unwrap();
+        assert!(dump.rules.is_empty());
+    }
+
     #[test]
     fn test_response_bindings_serialization() {
         let resp = Response::Bindings {
             bindings: vec![BindingInfo {
                 key: "alt-1".to_string(),
                 action: "tag-view 1".to_string(),
+                double: false,
+                passthrough: false,
             }],
         };
         let json = serde_json::to_string(&resp).unwrap();
@@ -1476,15 +2629,52 @@ mod tests {
                 "\"action\":\"output\"",
             ),
             (
-                RuleAction::Position { x: 100, y: 200 },
-                "\"action\":\"position\"",
+                RuleAction::Position {
+                    x: 100,
+                    y: 200,
+                    anchor: None,
+                },
+                "\"action\":\"position\",\"x\":100,\"y\":200}",
+            ),
+            (
+                RuleAction::Position {
+                    x: 20,
+                    y: 20,
+                    anchor: Some(Anchor::TopRight),
+                },
+                "\"anchor\":\"top_right\"",
             ),
             (
                 RuleAction::Dimensions {
-                    width: 800,
-                    height: 600,
+                    width: RuleSize::Points(800),
+                    height: RuleSize::Points(600),
                 },
-                "\"action\":\"dimensions\"",
+                "\"action\":\"dimensions\",\"width\":800,\"height\":600",
+            ),
+            (
+                RuleAction::Dimensions {
+                    width: RuleSize::Percent { percent: 50 },
+                    height: RuleSize::Percent { percent: 60 },
+                },
+                "\"width\":{\"percent\":50}",
+            ),
+            (
+                RuleAction::CloseBehavior {
+                    behavior: CloseBehavior::Hide,
+                },
+                "\"action\":\"close_behavior\",\"behavior\":\"hide\"",
+            ),
+            (
+                RuleAction::NoManageResize,
+                "\"action\":\"no_manage_resize\"",
+            ),
+            (
+                RuleAction::Throttle { ms: 500 },
+                "\"action\":\"throttle\",\"ms\":500",
+            ),
+            (
+                RuleAction::Opacity { percent: 90 },
+                "\"action\":\"opacity\",\"percent\":90",
             ),
         ];
 
@@ -1533,11 +2723,27 @@ mod tests {
             Command::RuleAdd { rule } => {
                 assert!(rule.matcher.app_name.is_some());
                 assert!(matches!(rule.action, RuleAction::Float));
+                assert!(!rule.dynamic);
             }
             _ => panic!("Wrong variant"),
         }
     }
 
+    #[test]
+    fn test_dynamic_rule_serialization() {
+        let rule = WindowRule::new(
+            RuleMatcher::new(None, Some(GlobPattern::new("*Meet*"))),
+            RuleAction::Float,
+        );
+        let json = serde_json::to_string(&rule).unwrap();
+        assert!(!json.contains("dynamic"));
+
+        let json = serde_json::to_string(&rule.with_dynamic(true)).unwrap();
+        assert!(json.contains("\"dynamic\":true"));
+        let deserialized: WindowRule = serde_json::from_str(&json).unwrap();
+        assert!(deserialized.dynamic);
+    }
+
     #[test]
     fn test_command_rule_del_serialization() {
         let cmd = Command::RuleDel {
@@ -1581,6 +2787,10 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
+                dynamic: false,
                 action: "float".to_string(),
             }],
         };
@@ -1611,6 +2821,10 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
+                dynamic: false,
                 action: "float".to_string(),
             }],
         };
@@ -1641,6 +2855,10 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
+                dynamic: false,
                 action: "float".to_string(),
             }],
         };
@@ -1679,6 +2897,8 @@ mod tests {
                 is_focused: true,
                 is_floating: false,
                 is_fullscreen: false,
+                is_native_fullscreen: false,
+                is_manual: false,
                 output_id: 1,
                 status: None,
                 ax_id: None,
@@ -1688,6 +2908,7 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                applied_rules: None,
             }],
         };
         let json = serde_json::to_string(&resp).unwrap();
@@ -1765,6 +2986,204 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_command_set_new_window_tag_serialization() {
+        let cmd = Command::SetNewWindowTag {
+            policy: NewWindowTagPolicy::AppSticky,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_new_window_tag\""));
+        assert!(json.contains("\"policy\":\"app_sticky\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetNewWindowTag { policy } => {
+                assert_eq!(policy, NewWindowTagPolicy::AppSticky)
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_orphan_policy_serialization() {
+        let cmd = Command::SetOrphanPolicy {
+            policy: OrphanPolicy::ByTag,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_orphan_policy\""));
+        assert!(json.contains("\"policy\":\"by_tag\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetOrphanPolicy { policy } => assert_eq!(policy, OrphanPolicy::ByTag),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_tag_mode_serialization() {
+        let cmd = Command::SetTagMode {
+            mode: TagMode::Global,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_tag_mode\""));
+        assert!(json.contains("\"mode\":\"global\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetTagMode { mode } => assert_eq!(mode, TagMode::Global),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_animation_serialization() {
+        let cmd = Command::SetAnimation {
+            duration_ms: 120,
+            easing: AnimationEasing::EaseInOut,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_animation\""));
+        assert!(json.contains("\"duration_ms\":120"));
+        assert!(json.contains("\"easing\":\"ease_in_out\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetAnimation {
+                duration_ms,
+                easing,
+            } => {
+                assert_eq!(duration_ms, 120);
+                assert_eq!(easing, AnimationEasing::EaseInOut);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_menubar_indicator_serialization() {
+        let cmd = Command::SetMenubarIndicator { enabled: true };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_menubar_indicator\""));
+        assert!(json.contains("\"enabled\":true"));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetMenubarIndicator { enabled } => assert!(enabled),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_gap_scale_serialization() {
+        let cmd = Command::SetGapScale {
+            mode: GapScaleMode::Auto,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_gap_scale\""));
+        assert!(json.contains("\"mode\":\"auto\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetGapScale { mode } => assert_eq!(mode, GapScaleMode::Auto),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_focus_include_floating_serialization() {
+        let cmd = Command::SetFocusIncludeFloating {
+            mode: FocusFloatingMode::TiledFirst,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_focus_include_floating\""));
+        assert!(json.contains("\"mode\":\"tiled_first\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetFocusIncludeFloating { mode } => {
+                assert_eq!(mode, FocusFloatingMode::TiledFirst)
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_set_raise_policy_serialization() {
+        let cmd = Command::SetRaisePolicy {
+            policy: RaisePolicy::FocusedTop,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_raise_policy\""));
+        assert!(json.contains("\"policy\":\"focused_top\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetRaisePolicy { policy } => assert_eq!(policy, RaisePolicy::FocusedTop),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_hotkey_backend_serialization() {
+        let cmd = Command::SetHotkeyBackend {
+            backend: HotkeyBackend::External,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_hotkey_backend\""));
+        assert!(json.contains("\"backend\":\"external\""));
+
+        let json = r#"{"type":"trigger_binding","key":"alt-1"}"#;
+        let deserialized: Command = serde_json::from_str(json).unwrap();
+        match deserialized {
+            Command::TriggerBinding { key } => assert_eq!(key, "alt-1"),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_window_info_serialization() {
+        let cmd = Command::WindowInfo {
+            window_id: Some(42),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"window_info\""));
+        assert!(json.contains("\"window_id\":42"));
+
+        // window_id defaults to None (focused window)
+        let deserialized: Command = serde_json::from_str(r#"{"type":"window_info"}"#).unwrap();
+        match deserialized {
+            Command::WindowInfo { window_id } => assert_eq!(window_id, None),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_animation_easing_apply() {
+        for easing in [
+            AnimationEasing::Linear,
+            AnimationEasing::EaseOut,
+            AnimationEasing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert_eq!(AnimationEasing::Linear.apply(0.5), 0.5);
+        assert!(AnimationEasing::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(AnimationEasing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_response_new_window_tag_serialization() {
+        let resp = Response::NewWindowTag {
+            policy: NewWindowTagPolicy::Emptiest,
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"type\":\"new_window_tag\""));
+        assert!(json.contains("\"policy\":\"emptiest\""));
+    }
+
     #[test]
     fn test_glob_pattern_matches_optional_none() {
         let pattern = GlobPattern::new("none");
@@ -1914,13 +3333,118 @@ mod tests {
         assert!(exact_rule.specificity() > none_rule.specificity());
     }
 
+    #[test]
+    fn test_rule_matcher_when_tag() {
+        let matcher = RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+            .with_conditions(Some(0b1000), None);
+        let on_tag = ExtendedWindowAttributes {
+            visible_tags: 0b1001,
+            ..Default::default()
+        };
+        let off_tag = ExtendedWindowAttributes {
+            visible_tags: 0b0001,
+            ..Default::default()
+        };
+        assert!(matcher.matches_extended("Safari", None, "Window", &on_tag));
+        assert!(!matcher.matches_extended("Safari", None, "Window", &off_tag));
+    }
+
+    #[test]
+    fn test_rule_matcher_when_output() {
+        let matcher = RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+            .with_conditions(None, Some(GlobPattern::new("DELL*")));
+        let dell = ExtendedWindowAttributes {
+            output_name: Some("DELL U2720Q".to_string()),
+            ..Default::default()
+        };
+        let builtin = ExtendedWindowAttributes {
+            output_name: Some("Built-in Retina Display".to_string()),
+            ..Default::default()
+        };
+        assert!(matcher.matches_extended("Safari", None, "Window", &dell));
+        assert!(!matcher.matches_extended("Safari", None, "Window", &builtin));
+        assert!(!matcher.matches_extended(
+            "Safari",
+            None,
+            "Window",
+            &ExtendedWindowAttributes::default()
+        ));
+    }
+
+    #[test]
+    fn test_rule_matcher_when_output_id() {
+        let laptop = ExtendedWindowAttributes {
+            output_id: Some(1),
+            output_name: Some("Built-in Retina Display".to_string()),
+            ..Default::default()
+        };
+        let external = ExtendedWindowAttributes {
+            output_id: Some(2),
+            output_name: Some("DELL U2720Q".to_string()),
+            ..Default::default()
+        };
+        let by_id = RuleMatcher::new(Some(GlobPattern::new("Calculator")), None)
+            .with_conditions(None, Some(GlobPattern::new("1")));
+        assert!(by_id.matches_extended("Calculator", None, "Window", &laptop));
+        assert!(!by_id.matches_extended("Calculator", None, "Window", &external));
+        assert!(!by_id.matches_extended(
+            "Calculator",
+            None,
+            "Window",
+            &ExtendedWindowAttributes::default()
+        ));
+
+        // Same specificity as a name pattern of the same length
+        let by_name = RuleMatcher::new(Some(GlobPattern::new("Calculator")), None)
+            .with_conditions(None, Some(GlobPattern::new("X")));
+        assert_eq!(by_id.specificity(), by_name.specificity());
+    }
+
+    #[test]
+    fn test_rule_matcher_parent_exec() {
+        let matcher =
+            RuleMatcher::new(None, None).with_parent_exec(Some(GlobPattern::new("kitty")));
+        let from_kitty = ExtendedWindowAttributes {
+            parent_execs: vec!["zsh".to_string(), "kitty".to_string()],
+            ..Default::default()
+        };
+        let from_launchd = ExtendedWindowAttributes::default();
+        assert!(matcher.matches_extended("mpv", None, "Window", &from_kitty));
+        assert!(!matcher.matches_extended("mpv", None, "Window", &from_launchd));
+        assert!(matcher.specificity() > RuleMatcher::new(None, None).specificity());
+    }
+
+    #[test]
+    fn test_rule_matcher_condition_specificity() {
+        let plain = RuleMatcher::new(Some(GlobPattern::new("Safari")), None);
+        let conditional = plain.clone().with_conditions(Some(0b1000), None);
+        assert!(conditional.specificity() > plain.specificity());
+    }
+
+    #[test]
+    fn test_rule_matcher_conditions_serialization() {
+        let matcher = RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+            .with_conditions(Some(4), Some(GlobPattern::new("DELL*")));
+        let json = serde_json::to_string(&matcher).unwrap();
+        assert!(json.contains("\"when_tag\":4"));
+        assert!(json.contains("\"when_output\":\"DELL*\""));
+
+        let plain = RuleMatcher::new(Some(GlobPattern::new("Safari")), None);
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("when_tag"));
+
+        let deserialized: RuleMatcher = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.when_tag, None);
+        assert_eq!(deserialized.when_output, None);
+    }
+
     #[test]
     fn test_command_exec_backward_compatibility() {
         // Test backward compatibility: old JSON without "track" field
         let old_json = r#"{"type":"exec","command":"echo hello"}"#;
         let cmd: Command = serde_json::from_str(old_json).unwrap();
         match cmd {
-            Command::Exec { command, track } => {
+            Command::Exec { command, track, .. } => {
                 assert_eq!(command, "echo hello");
                 assert!(
                     !track,
@@ -1931,12 +3455,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_command_window_focus_id_serialization() {
+        let cmd = Command::WindowFocusId { window_id: 42 };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"type":"window_focus_id","window_id":42}"#);
+        let parsed: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, Command::WindowFocusId { window_id: 42 }));
+    }
+
+    #[test]
+    fn test_command_exec_or_focus_serialization() {
+        // Older clients send app_name only
+        let old_json = r#"{"type":"exec_or_focus","app_name":"Safari","command":"open -a Safari"}"#;
+        let cmd: Command = serde_json::from_str(old_json).unwrap();
+        match cmd {
+            Command::ExecOrFocus {
+                app_name,
+                app_id,
+                title,
+                command,
+                bring,
+            } => {
+                assert_eq!(app_name.as_deref(), Some("Safari"));
+                assert!(app_id.is_none());
+                assert!(title.is_none());
+                assert_eq!(command, "open -a Safari");
+                assert!(!bring);
+            }
+            _ => panic!("Wrong variant"),
+        }
+
+        let cmd = Command::ExecOrFocus {
+            app_name: None,
+            app_id: Some(GlobPattern::new("com.mitchellh.ghostty")),
+            title: None,
+            command: "open -a Ghostty".to_string(),
+            bring: false,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"app_id\":\"com.mitchellh.ghostty\""));
+        assert!(!json.contains("app_name"));
+        assert!(!json.contains("title"));
+    }
+
     #[test]
     fn test_command_exec_with_track_serialization() {
         // Test with track=true
         let cmd = Command::Exec {
             command: "sleep 1000".to_string(),
             track: true,
+            args: None,
+            unique: None,
         };
         let json = serde_json::to_string(&cmd).unwrap();
         assert!(json.contains("\"type\":\"exec\""));
@@ -1945,7 +3515,7 @@ mod tests {
 
         let deserialized: Command = serde_json::from_str(&json).unwrap();
         match deserialized {
-            Command::Exec { command, track } => {
+            Command::Exec { command, track, .. } => {
                 assert_eq!(command, "sleep 1000");
                 assert!(track);
             }
@@ -1956,16 +3526,311 @@ mod tests {
         let cmd = Command::Exec {
             command: "echo hello".to_string(),
             track: false,
+            args: None,
+            unique: None,
         };
         let json = serde_json::to_string(&cmd).unwrap();
 
         let deserialized: Command = serde_json::from_str(&json).unwrap();
         match deserialized {
-            Command::Exec { command, track } => {
+            Command::Exec { command, track, .. } => {
                 assert_eq!(command, "echo hello");
                 assert!(!track);
             }
             _ => panic!("Wrong variant"),
         }
     }
+
+    #[test]
+    fn test_command_set_tag_change_hook_serialization() {
+        let cmd = Command::SetTagChangeHook {
+            command: Some("desktoppr ~/Pictures/tag$YASHIKI_TAGS.jpg".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_tag_change_hook\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::SetTagChangeHook { command } => {
+                assert_eq!(
+                    command.as_deref(),
+                    Some("desktoppr ~/Pictures/tag$YASHIKI_TAGS.jpg")
+                );
+            }
+            _ => panic!("Wrong variant"),
+        }
+
+        let resp = Response::TagChangeHook { command: None };
+        let json = serde_json::to_string(&resp).unwrap();
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            deserialized,
+            Response::TagChangeHook { command: None }
+        ));
+    }
+
+    #[test]
+    fn test_command_tag_set_input_source_serialization() {
+        let cmd = Command::TagSetInputSource {
+            tags: 4,
+            input_source: Some("com.apple.keylayout.US".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"tag_set_input_source","tags":4,"input_source":"com.apple.keylayout.US"}"#
+        );
+
+        let resp: Response =
+            serde_json::from_str(r#"{"type":"input_source","input_source":null}"#).unwrap();
+        assert!(matches!(resp, Response::InputSource { input_source: None }));
+    }
+
+    #[test]
+    fn test_command_set_float_visibility_serialization() {
+        let cmd = Command::SetFloatVisibility {
+            mode: FloatVisibility::Always,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, r#"{"type":"set_float_visibility","mode":"always"}"#);
+
+        let resp: Response =
+            serde_json::from_str(r#"{"type":"float_visibility","mode":"tagged"}"#).unwrap();
+        assert!(matches!(
+            resp,
+            Response::FloatVisibility {
+                mode: FloatVisibility::Tagged
+            }
+        ));
+    }
+
+    #[test]
+    fn test_command_set_hook_serialization() {
+        let cmd = Command::SetHook {
+            event: HookEvent::PreRetile,
+            command: Some("sketchybar --trigger retile".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"type\":\"set_hook\""));
+        assert!(json.contains("\"event\":\"pre_retile\""));
+
+        let context = HookContext {
+            event: HookEvent::PostRetile,
+            display_ids: vec![1],
+            displays: vec![],
+        };
+        assert_eq!(
+            serde_json::to_string(&context).unwrap(),
+            "{\"event\":\"post_retile\",\"display_ids\":[1]}"
+        );
+    }
+
+    #[test]
+    fn test_command_bindings_suspend_serialization() {
+        let cmd = Command::BindingsSuspend {
+            except: vec!["cmd-alt-escape".to_string()],
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"bindings_suspend\",\"except\":[\"cmd-alt-escape\"]}"
+        );
+
+        let deserialized: Command = serde_json::from_str("{\"type\":\"bindings_toggle\"}").unwrap();
+        match deserialized {
+            Command::BindingsToggle { except } => assert!(except.is_empty()),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_arrangement_serialization() {
+        let cmd = Command::ArrangementSave {
+            name: "demo".to_string(),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(json, "{\"type\":\"arrangement_save\",\"name\":\"demo\"}");
+
+        let deserialized: Command =
+            serde_json::from_str("{\"type\":\"arrangement_restore\",\"name\":\"demo\"}").unwrap();
+        match deserialized {
+            Command::ArrangementRestore { name } => assert_eq!(name, "demo"),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_bench_ax_serialization() {
+        let deserialized: Command = serde_json::from_str("{\"type\":\"bench_ax\"}").unwrap();
+        match deserialized {
+            Command::BenchAx { iterations } => assert_eq!(iterations, 5),
+            _ => panic!("Wrong variant"),
+        }
+
+        let response = Response::Bench {
+            report: BenchReport {
+                iterations: 5,
+                on_screen_p50_ms: 1.5,
+                on_screen_max_ms: 2.0,
+                apps: vec![AppBench {
+                    pid: 42,
+                    app_name: "Finder".to_string(),
+                    windows: 0,
+                    attributes_p50_ms: 0.0,
+                    attributes_max_ms: 0.0,
+                    move_resize_p50_ms: None,
+                    move_resize_max_ms: None,
+                }],
+            },
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("move_resize"));
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Response::Bench { report } => {
+                assert_eq!(report.apps.len(), 1);
+                assert_eq!(report.apps[0].move_resize_p50_ms, None);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_pause_resume_serialization() {
+        let json = serde_json::to_string(&Command::Pause).unwrap();
+        assert_eq!(json, "{\"type\":\"pause\"}");
+        let json = serde_json::to_string(&Command::Resume).unwrap();
+        assert_eq!(json, "{\"type\":\"resume\"}");
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(deserialized, Command::Resume));
+    }
+
+    #[test]
+    fn test_command_set_hide_apps_serialization() {
+        let json = serde_json::to_string(&Command::SetHideApps { enabled: true }).unwrap();
+        assert_eq!(json, "{\"type\":\"set_hide_apps\",\"enabled\":true}");
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            deserialized,
+            Command::SetHideApps { enabled: true }
+        ));
+    }
+
+    #[test]
+    fn test_command_set_window_shadows_serialization() {
+        let json = serde_json::to_string(&Command::SetWindowShadows { enabled: false }).unwrap();
+        assert_eq!(json, "{\"type\":\"set_window_shadows\",\"enabled\":false}");
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            deserialized,
+            Command::SetWindowShadows { enabled: false }
+        ));
+    }
+
+    #[test]
+    fn test_command_set_corner_compensation_serialization() {
+        let json = serde_json::to_string(&Command::SetCornerCompensation { px: 4 }).unwrap();
+        assert_eq!(json, "{\"type\":\"set_corner_compensation\",\"px\":4}");
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        assert!(matches!(
+            deserialized,
+            Command::SetCornerCompensation { px: 4 }
+        ));
+    }
+
+    #[test]
+    fn test_command_observer_exclude_serialization() {
+        let cmd = Command::ObserverExclude {
+            app_id: GlobPattern::new("com.apple.Safari.WebContent"),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"observer_exclude\",\"app_id\":\"com.apple.Safari.WebContent\"}"
+        );
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::ObserverExclude { app_id } => {
+                assert!(app_id.matches("com.apple.Safari.WebContent"))
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_exec_unique_serialization() {
+        let cmd = Command::Exec {
+            command: "~/bin/menu.sh".to_string(),
+            track: false,
+            args: None,
+            unique: Some("menu".to_string()),
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"unique\":\"menu\""));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::Exec { unique, .. } => assert_eq!(unique, Some("menu".to_string())),
+            _ => panic!("Wrong variant"),
+        }
+
+        let old_json = r#"{"type":"exec","command":"echo hello"}"#;
+        match serde_json::from_str::<Command>(old_json).unwrap() {
+            Command::Exec { unique, .. } => assert_eq!(unique, None),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_response_execs_serialization() {
+        let resp = Response::Execs {
+            execs: vec![ExecInfo {
+                pid: 4242,
+                command: "borders".to_string(),
+                name: Some("borders".to_string()),
+                track: true,
+            }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"pid\":4242"));
+
+        let deserialized: Response = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Response::Execs { execs } => {
+                assert_eq!(execs.len(), 1);
+                assert_eq!(execs[0].command, "borders");
+                assert!(execs[0].track);
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_command_exec_with_args_serialization() {
+        let cmd = Command::Exec {
+            command: "open".to_string(),
+            track: false,
+            args: Some(vec!["-a".to_string(), "Visual Studio Code".to_string()]),
+            unique: None,
+        };
+        let json = serde_json::to_string(&cmd).unwrap();
+        assert!(json.contains("\"args\":[\"-a\",\"Visual Studio Code\"]"));
+
+        let deserialized: Command = serde_json::from_str(&json).unwrap();
+        match deserialized {
+            Command::Exec { command, args, .. } => {
+                assert_eq!(command, "open");
+                assert_eq!(
+                    args,
+                    Some(vec!["-a".to_string(), "Visual Studio Code".to_string()])
+                );
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
 }
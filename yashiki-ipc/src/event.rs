@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{OutputInfo, WindowInfo};
+use crate::{GlobPattern, OutputInfo, TagOccupancy, WindowInfo};
 
 /// Event filter for subscribing to specific event types
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -20,6 +20,15 @@ pub struct EventFilter {
     /// Subscribe to layout change events
     #[serde(default)]
     pub layout: bool,
+    /// Subscribe to asynchronous failure events
+    #[serde(default)]
+    pub error: bool,
+    /// Only events on this output (display ID); events not tied to an output still pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<u32>,
+    /// Only window and focus events of apps with a matching bundle identifier
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<GlobPattern>,
 }
 
 impl EventFilter {
@@ -31,28 +40,52 @@ impl EventFilter {
             display: true,
             tags: true,
             layout: true,
+            error: true,
+            output: None,
+            app_id: None,
         }
     }
 
     /// Check if the filter matches a given event
     pub fn matches(&self, event: &StateEvent) -> bool {
+        self.matches_type(event) && self.matches_source(event)
+    }
+
+    fn matches_type(&self, event: &StateEvent) -> bool {
         match event {
             StateEvent::WindowCreated { .. }
             | StateEvent::WindowDestroyed { .. }
-            | StateEvent::WindowUpdated { .. } => self.window,
+            | StateEvent::WindowUpdated { .. }
+            | StateEvent::ObserverRecreated { .. } => self.window,
             StateEvent::WindowFocused { .. } | StateEvent::DisplayFocused { .. } => self.focus,
             StateEvent::DisplayAdded { .. }
             | StateEvent::DisplayRemoved { .. }
             | StateEvent::DisplayUpdated { .. } => self.display,
-            StateEvent::TagsChanged { .. } => self.tags,
-            StateEvent::LayoutChanged { .. } => self.layout,
+            StateEvent::TagsChanged { .. }
+            | StateEvent::WindowTagsChanged { .. }
+            | StateEvent::TagsState { .. } => self.tags,
+            StateEvent::LayoutChanged { .. } | StateEvent::LayoutOrderChanged { .. } => self.layout,
+            StateEvent::Error { .. } => self.error,
             StateEvent::Snapshot { .. } => true, // Snapshots always pass filter
         }
     }
 
+    /// Check the output and app_id filters against where the event happened
+    fn matches_source(&self, event: &StateEvent) -> bool {
+        let output_matches = match (self.output, event.output_id()) {
+            (Some(output), Some(id)) => output == id,
+            _ => true,
+        };
+        let app_matches = match (&self.app_id, event.app_id()) {
+            (Some(pattern), Some(app_id)) => app_id.is_some_and(|id| pattern.matches(id)),
+            _ => true,
+        };
+        output_matches && app_matches
+    }
+
     /// Check if any filter is set
     pub fn any(&self) -> bool {
-        self.window || self.focus || self.display || self.tags || self.layout
+        self.window || self.focus || self.display || self.tags || self.layout || self.error
     }
 }
 
@@ -65,6 +98,10 @@ pub struct SubscribeRequest {
     /// Event filter (if not set or all false, subscribes to all events)
     #[serde(default)]
     pub filter: EventFilter,
+    /// Batch events arriving within a few milliseconds, keeping only the latest
+    /// of repeated updates (e.g. during a retile)
+    #[serde(default)]
+    pub coalesce: bool,
 }
 
 impl SubscribeRequest {
@@ -73,15 +110,20 @@ impl SubscribeRequest {
         Self {
             snapshot: true,
             filter: EventFilter::default(),
+            coalesce: false,
         }
     }
 
-    /// Get the effective filter (all if none specified)
+    /// Get the effective filter (all event types if none specified)
     pub fn effective_filter(&self) -> EventFilter {
         if self.filter.any() {
             self.filter.clone()
         } else {
-            EventFilter::all()
+            EventFilter {
+                output: self.filter.output,
+                app_id: self.filter.app_id.clone(),
+                ..EventFilter::all()
+            }
         }
     }
 }
@@ -96,14 +138,29 @@ pub enum StateEvent {
     },
     WindowDestroyed {
         window_id: u32,
+        /// Output the window was on
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output_id: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_id: Option<String>,
     },
     WindowUpdated {
         window: WindowInfo,
     },
+    /// An app's accessibility observer stopped delivering events and was recreated
+    ObserverRecreated {
+        pid: i32,
+        app_name: String,
+    },
 
     // Focus events
     WindowFocused {
         window_id: Option<u32>,
+        /// Output of the focused window
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output_id: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_id: Option<String>,
     },
     DisplayFocused {
         display_id: u32,
@@ -126,12 +183,36 @@ pub enum StateEvent {
         visible_tags: u32,
         previous_tags: u32,
     },
+    /// A window was moved to or toggled onto other tags
+    WindowTagsChanged {
+        window_id: u32,
+        tags: u32,
+        previous_tags: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output_id: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_id: Option<String>,
+    },
+    /// Window and urgent counts of occupied tags (all displays), sent when they change
+    TagsState {
+        tags: Vec<TagOccupancy>,
+    },
 
     // Layout events
     LayoutChanged {
         display_id: u32,
         layout: String,
     },
+    LayoutOrderChanged {
+        display_id: u32,
+        window_order: Vec<u32>,
+    },
+
+    /// A failure that happened outside of a command's reply, e.g. during a retile
+    Error {
+        source: String,
+        message: String,
+    },
 
     // Full snapshot
     Snapshot {
@@ -143,6 +224,76 @@ pub enum StateEvent {
     },
 }
 
+impl StateEvent {
+    /// Add an event to a batch, dropping an earlier event it supersedes. Events that
+    /// report a state (focus, window properties, layout order, tag counts) supersede
+    /// older ones about the same thing; changes with a previous value are all kept.
+    pub fn push_coalesced(batch: &mut Vec<StateEvent>, event: StateEvent) {
+        if let Some(key) = event.coalesce_key() {
+            batch.retain(|e| e.coalesce_key() != Some(key));
+        }
+        batch.push(event);
+    }
+
+    fn coalesce_key(&self) -> Option<(&'static str, u32)> {
+        match self {
+            StateEvent::WindowUpdated { window } => Some(("window_updated", window.id)),
+            StateEvent::WindowFocused { .. } => Some(("window_focused", 0)),
+            StateEvent::DisplayFocused { .. } => Some(("display_focused", 0)),
+            StateEvent::DisplayUpdated { display } => Some(("display_updated", display.id)),
+            StateEvent::TagsState { .. } => Some(("tags_state", 0)),
+            StateEvent::LayoutChanged { display_id, .. } => Some(("layout_changed", *display_id)),
+            StateEvent::LayoutOrderChanged { display_id, .. } => {
+                Some(("layout_order_changed", *display_id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Output the event happened on, None for events not tied to one. Display focus
+    /// changes count as global, so a bar notices when its output loses focus.
+    fn output_id(&self) -> Option<u32> {
+        match self {
+            StateEvent::WindowCreated { window } | StateEvent::WindowUpdated { window } => {
+                Some(window.output_id)
+            }
+            StateEvent::WindowDestroyed { output_id, .. }
+            | StateEvent::WindowFocused { output_id, .. }
+            | StateEvent::WindowTagsChanged { output_id, .. } => *output_id,
+            StateEvent::DisplayAdded { display } | StateEvent::DisplayUpdated { display } => {
+                Some(display.id)
+            }
+            StateEvent::DisplayRemoved { display_id }
+            | StateEvent::TagsChanged { display_id, .. }
+            | StateEvent::LayoutChanged { display_id, .. }
+            | StateEvent::LayoutOrderChanged { display_id, .. } => Some(*display_id),
+            StateEvent::ObserverRecreated { .. }
+            | StateEvent::DisplayFocused { .. }
+            | StateEvent::TagsState { .. }
+            | StateEvent::Error { .. }
+            | StateEvent::Snapshot { .. } => None,
+        }
+    }
+
+    /// App of the window the event is about: None if it is not about a window,
+    /// Some(None) if the window has no bundle identifier
+    fn app_id(&self) -> Option<Option<&str>> {
+        match self {
+            StateEvent::WindowCreated { window } | StateEvent::WindowUpdated { window } => {
+                Some(window.app_id.as_deref())
+            }
+            StateEvent::WindowDestroyed { app_id, .. }
+            | StateEvent::WindowTagsChanged { app_id, .. } => Some(app_id.as_deref()),
+            StateEvent::WindowFocused {
+                window_id: Some(_),
+                app_id,
+                ..
+            } => Some(app_id.as_deref()),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +306,7 @@ mod tests {
         assert!(filter.display);
         assert!(filter.tags);
         assert!(filter.layout);
+        assert!(filter.error);
     }
 
     #[test]
@@ -179,6 +331,8 @@ mod tests {
                 is_focused: false,
                 is_floating: false,
                 is_fullscreen: false,
+                is_native_fullscreen: false,
+                is_manual: false,
                 output_id: 1,
                 status: None,
                 ax_id: None,
@@ -188,10 +342,19 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                applied_rules: None,
             }
         }));
-        assert!(window_filter.matches(&StateEvent::WindowDestroyed { window_id: 1 }));
-        assert!(!window_filter.matches(&StateEvent::WindowFocused { window_id: Some(1) }));
+        assert!(window_filter.matches(&StateEvent::WindowDestroyed {
+            window_id: 1,
+            output_id: None,
+            app_id: None,
+        }));
+        assert!(!window_filter.matches(&StateEvent::WindowFocused {
+            window_id: Some(1),
+            output_id: None,
+            app_id: None,
+        }));
         assert!(!window_filter.matches(&StateEvent::TagsChanged {
             display_id: 1,
             visible_tags: 1,
@@ -214,16 +377,32 @@ mod tests {
                 focus: true,
                 ..Default::default()
             },
+            coalesce: false,
         };
         let effective = req.effective_filter();
         assert!(!effective.window);
         assert!(effective.focus);
+
+        // Output and app filters are kept when no event type is selected
+        let req = SubscribeRequest {
+            snapshot: false,
+            filter: EventFilter {
+                output: Some(2),
+                ..Default::default()
+            },
+            coalesce: false,
+        };
+        let effective = req.effective_filter();
+        assert!(effective.window);
+        assert_eq!(effective.output, Some(2));
     }
 
     #[test]
     fn test_state_event_serialization() {
         let event = StateEvent::WindowFocused {
             window_id: Some(123),
+            output_id: None,
+            app_id: None,
         };
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("\"type\":\"window_focused\""));
@@ -231,11 +410,165 @@ mod tests {
 
         let deserialized: StateEvent = serde_json::from_str(&json).unwrap();
         match deserialized {
-            StateEvent::WindowFocused { window_id } => assert_eq!(window_id, Some(123)),
+            StateEvent::WindowFocused { window_id, .. } => assert_eq!(window_id, Some(123)),
             _ => panic!("Wrong variant"),
         }
     }
 
+    #[test]
+    fn test_event_filter_output_and_app_id() {
+        let filter = EventFilter {
+            output: Some(2),
+            app_id: Some(GlobPattern::new("com.apple.*")),
+            ..EventFilter::all()
+        };
+        let focused = |output_id, app_id: &str| StateEvent::WindowFocused {
+            window_id: Some(1),
+            output_id: Some(output_id),
+            app_id: Some(app_id.to_string()),
+        };
+        assert!(filter.matches(&focused(2, "com.apple.Safari")));
+        assert!(!filter.matches(&focused(1, "com.apple.Safari")));
+        assert!(!filter.matches(&focused(2, "com.google.Chrome")));
+
+        // Events on other outputs are dropped, events not tied to a window keep passing
+        assert!(!filter.matches(&StateEvent::TagsChanged {
+            display_id: 1,
+            visible_tags: 1,
+            previous_tags: 2,
+        }));
+        assert!(filter.matches(&StateEvent::TagsChanged {
+            display_id: 2,
+            visible_tags: 1,
+            previous_tags: 2,
+        }));
+        assert!(filter.matches(&StateEvent::DisplayFocused { display_id: 1 }));
+        assert!(filter.matches(&StateEvent::WindowFocused {
+            window_id: None,
+            output_id: None,
+            app_id: None,
+        }));
+
+        let json = serde_json::to_string(&filter).unwrap();
+        assert!(json.contains("\"output\":2"));
+        assert!(json.contains("\"app_id\":\"com.apple.*\""));
+        let json = serde_json::to_string(&EventFilter::all()).unwrap();
+        assert!(!json.contains("output"));
+    }
+
+    #[test]
+    fn test_push_coalesced() {
+        let order = |display_id, window_order: Vec<u32>| StateEvent::LayoutOrderChanged {
+            display_id,
+            window_order,
+        };
+        let mut batch = Vec::new();
+        StateEvent::push_coalesced(&mut batch, order(1, vec![1, 2]));
+        StateEvent::push_coalesced(
+            &mut batch,
+            StateEvent::WindowDestroyed {
+                window_id: 3,
+                output_id: Some(1),
+                app_id: None,
+            },
+        );
+        StateEvent::push_coalesced(&mut batch, order(2, vec![4]));
+        StateEvent::push_coalesced(&mut batch, order(1, vec![2]));
+        StateEvent::push_coalesced(&mut batch, StateEvent::DisplayFocused { display_id: 1 });
+        StateEvent::push_coalesced(&mut batch, StateEvent::DisplayFocused { display_id: 2 });
+
+        assert_eq!(batch.len(), 4);
+        assert!(matches!(
+            batch[0],
+            StateEvent::WindowDestroyed { window_id: 3, .. }
+        ));
+        assert!(matches!(
+            batch[1],
+            StateEvent::LayoutOrderChanged { display_id: 2, .. }
+        ));
+        match &batch[2] {
+            StateEvent::LayoutOrderChanged {
+                display_id,
+                window_order,
+            } => {
+                assert_eq!(*display_id, 1);
+                assert_eq!(window_order, &vec![2]);
+            }
+            e => panic!("Unexpected event {:?}", e),
+        }
+        assert!(matches!(
+            batch[3],
+            StateEvent::DisplayFocused { display_id: 2 }
+        ));
+
+        let request: SubscribeRequest = serde_json::from_str("{\"coalesce\":true}").unwrap();
+        assert!(request.coalesce);
+        assert!(!SubscribeRequest::default().coalesce);
+    }
+
+    #[test]
+    fn test_observer_recreated_serialization() {
+        let event = StateEvent::ObserverRecreated {
+            pid: 1000,
+            app_name: "Safari".to_string(),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"observer_recreated\",\"pid\":1000,\"app_name\":\"Safari\"}"
+        );
+        assert!(EventFilter {
+            window: true,
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
+    #[test]
+    fn test_error_serialization() {
+        let event = StateEvent::Error {
+            source: "layout".to_string(),
+            message: "engine exited".to_string(),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"error","source":"layout","message":"engine exited"}"#
+        );
+        assert!(EventFilter {
+            error: true,
+            output: Some(2),
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!EventFilter {
+            window: true,
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
+    #[test]
+    fn test_tags_state_serialization() {
+        let event = StateEvent::TagsState {
+            tags: vec![TagOccupancy {
+                tag: 1,
+                windows: 2,
+                urgent: 1,
+            }],
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            "{\"type\":\"tags_state\",\"tags\":[{\"tag\":1,\"windows\":2,\"urgent\":1}]}"
+        );
+        assert!(EventFilter {
+            tags: true,
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
     #[test]
     fn test_tags_changed_serialization() {
         let event = StateEvent::TagsChanged {
@@ -264,6 +597,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_window_tags_changed_serialization() {
+        let event = StateEvent::WindowTagsChanged {
+            window_id: 100,
+            tags: 0b0110,
+            previous_tags: 0b0001,
+            output_id: None,
+            app_id: None,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"window_tags_changed","window_id":100,"tags":6,"previous_tags":1}"#
+        );
+
+        let tags_filter = EventFilter {
+            tags: true,
+            ..Default::default()
+        };
+        assert!(tags_filter.matches(&event));
+        assert!(!EventFilter {
+            window: true,
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
     #[test]
     fn test_snapshot_serialization() {
         let event = StateEvent::Snapshot {
@@ -288,6 +648,7 @@ mod tests {
                 tags: true,
                 ..Default::default()
             },
+            coalesce: false,
         };
         let json = serde_json::to_string(&req).unwrap();
         assert!(json.contains("\"snapshot\":true"));
@@ -321,6 +682,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_layout_order_changed_serialization() {
+        let event = StateEvent::LayoutOrderChanged {
+            display_id: 1,
+            window_order: vec![3, 1, 2],
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"type\":\"layout_order_changed\""));
+        assert!(json.contains("\"window_order\":[3,1,2]"));
+
+        let layout_filter = EventFilter {
+            layout: true,
+            ..Default::default()
+        };
+        assert!(layout_filter.matches(&event));
+        assert!(!EventFilter::default().matches(&event));
+    }
+
     #[test]
     fn test_display_events_serialization() {
         let added = StateEvent::DisplayAdded {
@@ -334,6 +713,11 @@ mod tests {
                 is_main: true,
                 visible_tags: 1,
                 is_focused: true,
+                scale_factor: 2.0,
+                rotation: 0,
+                refresh_rate: 60.0,
+                uuid: None,
+                window_order: vec![],
             },
         };
         let json = serde_json::to_string(&added).unwrap();
@@ -24,10 +24,67 @@ pub enum LayoutResult {
     Ok,
     /// Command succeeded and requests retile
     NeedsRetile,
+    /// Commands the engine supports (reply to `capabilities`)
+    Capabilities { commands: Vec<String> },
     /// Error occurred
     Error { message: String },
 }
 
+/// Engine-independent layout adjustments, mapped to whichever command the active engine supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutAction {
+    MainRatioInc,
+    MainRatioDec,
+    MainCountInc,
+    MainCountDec,
+    GapInc,
+    GapDec,
+    OrientationToggle,
+}
+
+impl LayoutAction {
+    pub const ALL: [LayoutAction; 7] = [
+        LayoutAction::MainRatioInc,
+        LayoutAction::MainRatioDec,
+        LayoutAction::MainCountInc,
+        LayoutAction::MainCountDec,
+        LayoutAction::GapInc,
+        LayoutAction::GapDec,
+        LayoutAction::OrientationToggle,
+    ];
+
+    /// CLI name, e.g. `main-ratio-inc`
+    pub fn name(&self) -> &'static str {
+        match self {
+            LayoutAction::MainRatioInc => "main-ratio-inc",
+            LayoutAction::MainRatioDec => "main-ratio-dec",
+            LayoutAction::MainCountInc => "main-count-inc",
+            LayoutAction::MainCountDec => "main-count-dec",
+            LayoutAction::GapInc => "gap-inc",
+            LayoutAction::GapDec => "gap-dec",
+            LayoutAction::OrientationToggle => "orientation-toggle",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Engine commands that implement this action, in order of preference
+    pub fn engine_commands(&self) -> &'static [&'static str] {
+        match self {
+            LayoutAction::MainRatioInc => &["inc-main-ratio"],
+            LayoutAction::MainRatioDec => &["dec-main-ratio"],
+            LayoutAction::MainCountInc => &["inc-main-count"],
+            LayoutAction::MainCountDec => &["dec-main-count"],
+            LayoutAction::GapInc => &["inc-inner-gap", "inc-padding"],
+            LayoutAction::GapDec => &["dec-inner-gap", "dec-padding"],
+            LayoutAction::OrientationToggle => &["toggle-orientation"],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct WindowGeometry {
     pub id: u32,
@@ -35,6 +92,14 @@ pub struct WindowGeometry {
     pub y: i32,
     pub width: u32,
     pub height: u32,
+    /// Stacking order: windows are raised in ascending order, so the highest value ends up on top.
+    /// Windows without a value keep their current stacking.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raise_order: Option<u32>,
+    /// Park the window off-screen (e.g. non-focused windows in a monocle layout).
+    /// The window is restored as soon as the engine lays it out without this flag.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
 }
 
 #[cfg(test)]
@@ -98,6 +163,8 @@ mod tests {
                     y: 0,
                     width: 960,
                     height: 1080,
+                    raise_order: None,
+                    hidden: false,
                 },
                 WindowGeometry {
                     id: 2,
@@ -105,6 +172,8 @@ mod tests {
                     y: 0,
                     width: 960,
                     height: 540,
+                    raise_order: None,
+                    hidden: false,
                 },
             ],
         };
@@ -122,6 +191,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_window_geometry_raise_order_serialization() {
+        let geom = WindowGeometry {
+            id: 1,
+            x: 0,
+            y: 0,
+            width: 960,
+            height: 1080,
+            raise_order: None,
+            hidden: false,
+        };
+        let json = serde_json::to_string(&geom).unwrap();
+        assert!(!json.contains("raise_order"));
+
+        let json = r#"{"id":1,"x":0,"y":0,"width":960,"height":1080,"raise_order":2}"#;
+        let geom: WindowGeometry = serde_json::from_str(json).unwrap();
+        assert_eq!(geom.raise_order, Some(2));
+    }
+
+    #[test]
+    fn test_window_geometry_hidden_serialization() {
+        let json = r#"{"id":1,"x":0,"y":0,"width":960,"height":1080}"#;
+        let mut geom: WindowGeometry = serde_json::from_str(json).unwrap();
+        assert!(!geom.hidden);
+        assert!(!serde_json::to_string(&geom).unwrap().contains("hidden"));
+
+        geom.hidden = true;
+        assert!(serde_json::to_string(&geom)
+            .unwrap()
+            .contains("\"hidden\":true"));
+    }
+
     #[test]
     fn test_layout_result_ok_serialization() {
         let result = LayoutResult::Ok;
@@ -132,6 +233,30 @@ mod tests {
         matches!(deserialized, LayoutResult::Ok);
     }
 
+    #[test]
+    fn test_layout_result_capabilities_serialization() {
+        let json = r#"{"type":"capabilities","commands":["inc-main-ratio","zoom"]}"#;
+        match serde_json::from_str::<LayoutResult>(json).unwrap() {
+            LayoutResult::Capabilities { commands } => {
+                assert_eq!(commands, vec!["inc-main-ratio", "zoom"])
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_layout_action_names() {
+        for action in LayoutAction::ALL {
+            assert_eq!(LayoutAction::from_name(action.name()), Some(action));
+            assert!(!action.engine_commands().is_empty());
+        }
+        assert_eq!(LayoutAction::from_name("zoom"), None);
+        assert_eq!(
+            serde_json::to_string(&LayoutAction::GapInc).unwrap(),
+            "\"gap_inc\""
+        );
+    }
+
     #[test]
     fn test_layout_result_error_serialization() {
         let result = LayoutResult::Error {
@@ -154,6 +279,8 @@ mod tests {
             y: 0,
             width: 100,
             height: 100,
+            raise_order: None,
+            hidden: false,
         };
         let g2 = WindowGeometry {
             id: 1,
@@ -161,6 +288,8 @@ mod tests {
             y: 0,
             width: 100,
             height: 100,
+            raise_order: None,
+            hidden: false,
         };
         let g3 = WindowGeometry {
             id: 2,
@@ -168,6 +297,8 @@ mod tests {
             y: 0,
             width: 100,
             height: 100,
+            raise_order: None,
+            hidden: false,
         };
 
         assert_eq!(g1, g2);
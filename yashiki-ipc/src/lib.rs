@@ -2,13 +2,19 @@ pub mod command;
 pub mod event;
 pub mod layout;
 pub mod outer_gap;
+pub mod rule_geometry;
 
 pub use command::{
-    AutoRaiseMode, BindingInfo, ButtonInfo, ButtonState, Command, CursorWarpMode, Direction,
-    ExtendedWindowAttributes, GlobPattern, OutputDirection, OutputInfo, OutputSpecifier, Response,
-    RuleAction, RuleInfo, RuleMatcher, StateInfo, WindowInfo, WindowLevel, WindowLevelName,
-    WindowLevelOther, WindowRule, WindowStatus,
+    AnimationEasing, AppBench, AutoRaiseMode, BenchReport, BindingInfo, ButtonInfo, ButtonState,
+    CloseBehavior, Command, CommandLatency, CursorWarpMode, Direction, ErrorInfo, ExecInfo,
+    ExtendedWindowAttributes, FloatVisibility, FocusFloatingMode, FrameInfo, GapScaleMode,
+    GlobPattern, HookContext, HookEvent, HotkeyBackend, NewWindowTagPolicy, OrphanPolicy,
+    OutputDirection, OutputInfo, OutputSpecifier, RaisePolicy, Response, RetileInfo, RuleAction,
+    RuleInfo, RuleMatcher, RuleSuggestion, StateDump, StateInfo, TagMode, TagOccupancy,
+    WindowDetails, WindowInfo, WindowLevel, WindowLevelName, WindowLevelOther, WindowRule,
+    WindowStatus,
 };
 pub use event::{EventFilter, StateEvent, SubscribeRequest};
-pub use layout::{LayoutMessage, LayoutResult, WindowGeometry};
+pub use layout::{LayoutAction, LayoutMessage, LayoutResult, WindowGeometry};
 pub use outer_gap::OuterGap;
+pub use rule_geometry::{format_anchored_position, Anchor, RuleSize};
@@ -58,6 +58,17 @@ impl OuterGap {
     pub fn vertical(&self) -> u32 {
         self.top + self.bottom
     }
+
+    /// Multiply each side by `factor`, rounding to the nearest point
+    pub fn scaled(&self, factor: f64) -> Self {
+        let scale = |v: u32| (v as f64 * factor).round() as u32;
+        Self {
+            top: scale(self.top),
+            right: scale(self.right),
+            bottom: scale(self.bottom),
+            left: scale(self.left),
+        }
+    }
 }
 
 impl std::fmt::Display for OuterGap {
@@ -146,6 +157,26 @@ mod tests {
         assert_eq!(gap.vertical(), 40);
     }
 
+    #[test]
+    fn test_outer_gap_scaled() {
+        let gap = OuterGap {
+            top: 10,
+            right: 20,
+            bottom: 5,
+            left: 0,
+        };
+        assert_eq!(gap.scaled(1.0), gap);
+        assert_eq!(
+            gap.scaled(0.5),
+            OuterGap {
+                top: 5,
+                right: 10,
+                bottom: 3,
+                left: 0,
+            }
+        );
+    }
+
     #[test]
     fn test_outer_gap_display() {
         let gap = OuterGap {
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+/// Window size along one axis: points, or a percentage of the display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RuleSize {
+    Points(u32),
+    Percent { percent: u32 },
+}
+
+impl RuleSize {
+    /// Parse `800` or `50%`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.strip_suffix('%') {
+            Some(percent) => percent
+                .parse()
+                .ok()
+                .map(|percent| Self::Percent { percent }),
+            None => s.parse().ok().map(Self::Points),
+        }
+    }
+
+    pub fn resolve(&self, display_size: u32) -> u32 {
+        match self {
+            Self::Points(points) => *points,
+            Self::Percent { percent } => (display_size as u64 * *percent as u64 / 100) as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for RuleSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Points(points) => write!(f, "{}", points),
+            Self::Percent { percent } => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// Display edge or corner a rule position is relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Alignment of a window along one axis of the display
+enum Align {
+    Start,
+    Middle,
+    End,
+}
+
+impl Align {
+    fn place(&self, origin: i32, display_size: u32, window_size: u32, offset: i32) -> i32 {
+        let free = display_size as i32 - window_size as i32;
+        match self {
+            Self::Start => origin + offset,
+            Self::Middle => origin + free / 2 + offset,
+            Self::End => origin + free - offset,
+        }
+    }
+}
+
+impl Anchor {
+    const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::TopRight,
+        Anchor::BottomLeft,
+        Anchor::BottomRight,
+        Anchor::Top,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::Bottom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TopLeft => "top-left",
+            Self::Top => "top",
+            Self::TopRight => "top-right",
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+            Self::BottomLeft => "bottom-left",
+            Self::Bottom => "bottom",
+            Self::BottomRight => "bottom-right",
+        }
+    }
+
+    /// Parse `center` or `top-right+20+20` into an anchor and (x, y) offsets
+    pub fn parse_position(s: &str) -> Option<(Self, i32, i32)> {
+        let anchor = Self::ALL.into_iter().find(|a| s.starts_with(a.name()))?;
+        let rest = &s[anchor.name().len()..];
+        if rest.is_empty() {
+            return Some((anchor, 0, 0));
+        }
+
+        // Offsets: <sign><digits><sign><digits>
+        let split = rest.strip_prefix(['+', '-'])?.find(['+', '-'])? + 1;
+        let x = rest[..split].parse().ok()?;
+        let y = rest[split..].parse().ok()?;
+        Some((anchor, x, y))
+    }
+
+    fn align(&self) -> (Align, Align) {
+        match self {
+            Self::TopLeft => (Align::Start, Align::Start),
+            Self::Top => (Align::Middle, Align::Start),
+            Self::TopRight => (Align::End, Align::Start),
+            Self::Left => (Align::Start, Align::Middle),
+            Self::Center => (Align::Middle, Align::Middle),
+            Self::Right => (Align::End, Align::Middle),
+            Self::BottomLeft => (Align::Start, Align::End),
+            Self::Bottom => (Align::Middle, Align::End),
+            Self::BottomRight => (Align::End, Align::End),
+        }
+    }
+
+    /// Top-left position of a window placed at this anchor.
+    /// Offsets move the window inward from anchored edges and right/down when centered.
+    pub fn resolve(
+        &self,
+        offset: (i32, i32),
+        display: (i32, i32, u32, u32),
+        window_size: (u32, u32),
+    ) -> (i32, i32) {
+        let (dx, dy, dw, dh) = display;
+        let (horizontal, vertical) = self.align();
+        (
+            horizontal.place(dx, dw, window_size.0, offset.0),
+            vertical.place(dy, dh, window_size.1, offset.1),
+        )
+    }
+}
+
+/// Format an anchored position as accepted by `Anchor::parse_position`
+pub fn format_anchored_position(anchor: Anchor, x: i32, y: i32) -> String {
+    if x == 0 && y == 0 {
+        anchor.name().to_string()
+    } else {
+        format!("{}{:+}{:+}", anchor.name(), x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_size_parse() {
+        assert_eq!(RuleSize::parse("800"), Some(RuleSize::Points(800)));
+        assert_eq!(
+            RuleSize::parse("50%"),
+            Some(RuleSize::Percent { percent: 50 })
+        );
+        assert_eq!(RuleSize::parse("abc"), None);
+        assert_eq!(RuleSize::parse("%"), None);
+    }
+
+    #[test]
+    fn test_rule_size_resolve_and_display() {
+        assert_eq!(RuleSize::Points(800).resolve(1920), 800);
+        assert_eq!(RuleSize::Percent { percent: 50 }.resolve(1920), 960);
+        assert_eq!(RuleSize::Points(800).to_string(), "800");
+        assert_eq!(RuleSize::Percent { percent: 60 }.to_string(), "60%");
+    }
+
+    #[test]
+    fn test_rule_size_serialization() {
+        assert_eq!(
+            serde_json::to_string(&RuleSize::Points(800)).unwrap(),
+            "800"
+        );
+        let json = serde_json::to_string(&RuleSize::Percent { percent: 50 }).unwrap();
+        assert_eq!(json, "{\"percent\":50}");
+        let deserialized: RuleSize = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, RuleSize::Percent { percent: 50 });
+    }
+
+    #[test]
+    fn test_anchor_parse_position() {
+        assert_eq!(
+            Anchor::parse_position("center"),
+            Some((Anchor::Center, 0, 0))
+        );
+        assert_eq!(
+            Anchor::parse_position("top-right+20+20"),
+            Some((Anchor::TopRight, 20, 20))
+        );
+        assert_eq!(
+            Anchor::parse_position("bottom-10+5"),
+            Some((Anchor::Bottom, -10, 5))
+        );
+        assert_eq!(Anchor::parse_position("top-right+20"), None);
+        assert_eq!(Anchor::parse_position("middle"), None);
+        assert_eq!(Anchor::parse_position("100"), None);
+    }
+
+    #[test]
+    fn test_anchor_resolve() {
+        let display = (0, 25, 1920, 1055);
+        assert_eq!(
+            Anchor::Center.resolve((0, 0), display, (800, 600)),
+            (560, 252)
+        );
+        assert_eq!(
+            Anchor::TopRight.resolve((20, 20), display, (800, 600)),
+            (1100, 45)
+        );
+        assert_eq!(
+            Anchor::BottomLeft.resolve((10, 10), display, (800, 600)),
+            (10, 470)
+        );
+    }
+
+    #[test]
+    fn test_format_anchored_position() {
+        assert_eq!(format_anchored_position(Anchor::Center, 0, 0), "center");
+        assert_eq!(
+            format_anchored_position(Anchor::TopRight, 20, -5),
+            "top-right+20-5"
+        );
+    }
+}
@@ -59,8 +59,22 @@ fn handle_message(state: &mut LayoutState, msg: LayoutMessage) -> LayoutResult {
     }
 }
 
+/// Commands reported by `capabilities`
+const COMMANDS: &[&str] = &[
+    "set-padding",
+    "inc-padding",
+    "dec-padding",
+    "set-orientation",
+    "toggle-orientation",
+    "focus-changed",
+    "promote",
+];
+
 fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> LayoutResult {
     match cmd {
+        "capabilities" => LayoutResult::Capabilities {
+            commands: COMMANDS.iter().map(|c| c.to_string()).collect(),
+        },
         "set-padding" => {
             if let Some(padding) = args.first().and_then(|s| s.parse::<u32>().ok()) {
                 state.padding = padding;
@@ -121,6 +135,8 @@ fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> Layout
                 }
             }
         }
+        // No main slot in an accordion
+        "promote" => LayoutResult::Ok,
         _ => LayoutResult::Error {
             message: format!("unknown command: {}", cmd),
         },
@@ -145,6 +161,8 @@ fn generate_layout(
             y: 0,
             width,
             height,
+            raise_order: None,
+            hidden: false,
         }];
     }
 
@@ -187,6 +205,8 @@ fn generate_layout(
                     y: 0,
                     width: width.saturating_sub(total_offset),
                     height,
+                    raise_order: Some(index as u32),
+                    hidden: false,
                 },
                 Orientation::Vertical => WindowGeometry {
                     id,
@@ -194,6 +214,8 @@ fn generate_layout(
                     y: offset as i32,
                     width,
                     height: height.saturating_sub(total_offset),
+                    raise_order: Some(index as u32),
+                    hidden: false,
                 },
             }
         })
@@ -279,6 +301,24 @@ mod tests {
         assert_eq!(windows[2].width, 1920 - 60);
     }
 
+    #[test]
+    fn test_raise_order_puts_focused_on_top() {
+        let mut state = LayoutState::default();
+        state.focused_window_id = Some(2);
+
+        let windows = generate_layout(&state, 1920, 1080, &[1, 2, 3]);
+        let top = windows.iter().max_by_key(|w| w.raise_order).unwrap();
+        assert_eq!(top.id, 2);
+        assert_eq!(
+            windows.iter().map(|w| w.raise_order).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(2)]
+        );
+
+        // A single window leaves stacking untouched
+        let windows = generate_layout(&state, 1920, 1080, &[1]);
+        assert_eq!(windows[0].raise_order, None);
+    }
+
     #[test]
     fn test_vertical_orientation() {
         let mut state = LayoutState::default();
@@ -338,6 +378,30 @@ mod tests {
         assert_eq!(state.focused_window_id, Some(42));
     }
 
+    #[test]
+    fn test_capabilities_lists_known_commands() {
+        let mut state = LayoutState::default();
+        let LayoutResult::Capabilities { commands } =
+            handle_command(&mut state, "capabilities", &[])
+        else {
+            panic!("Expected Capabilities");
+        };
+        for cmd in &commands {
+            assert!(!matches!(
+                handle_command(&mut state, cmd, &["1".to_string()]),
+                LayoutResult::Error { ref message } if message.starts_with("unknown command")
+            ));
+        }
+        assert!(commands.contains(&"toggle-orientation".to_string()));
+    }
+
+    #[test]
+    fn test_promote_is_accepted() {
+        let mut state = LayoutState::default();
+        let result = handle_command(&mut state, "promote", &["42".to_string()]);
+        assert!(matches!(result, LayoutResult::Ok));
+    }
+
     #[test]
     fn test_set_padding_command() {
         let mut state = LayoutState::default();
@@ -10,6 +10,8 @@ struct LayoutState {
     inner_gap: u32,
     main_window_id: Option<u32>,
     focused_window_id: Option<u32>,
+    /// Put main on top with the stack below on portrait (rotated) displays
+    auto_rotate: bool,
 }
 
 impl Default for LayoutState {
@@ -20,6 +22,7 @@ impl Default for LayoutState {
             inner_gap: 0,
             main_window_id: None,
             focused_window_id: None,
+            auto_rotate: false,
         }
     }
 }
@@ -48,7 +51,14 @@ fn handle_message(state: &mut LayoutState, msg: LayoutMessage) -> LayoutResult {
             height,
             windows,
         } => {
-            let geometries = generate_layout(state, width, height, &windows);
+            let geometries = if state.auto_rotate && height > width {
+                generate_layout(state, height, width, &windows)
+                    .into_iter()
+                    .map(transpose)
+                    .collect()
+            } else {
+                generate_layout(state, width, height, &windows)
+            };
             LayoutResult::Layout {
                 windows: geometries,
             }
@@ -57,8 +67,28 @@ fn handle_message(state: &mut LayoutState, msg: LayoutMessage) -> LayoutResult {
     }
 }
 
+/// Commands reported by `capabilities`
+const COMMANDS: &[&str] = &[
+    "set-main-ratio",
+    "inc-main-ratio",
+    "dec-main-ratio",
+    "inc-main-count",
+    "dec-main-count",
+    "set-main-count",
+    "set-inner-gap",
+    "inc-inner-gap",
+    "dec-inner-gap",
+    "set-auto-rotate",
+    "focus-changed",
+    "promote",
+    "zoom",
+];
+
 fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> LayoutResult {
     match cmd {
+        "capabilities" => LayoutResult::Capabilities {
+            commands: COMMANDS.iter().map(|c| c.to_string()).collect(),
+        },
         "set-main-ratio" => {
             if let Some(ratio) = args.first().and_then(|s| s.parse::<f64>().ok()) {
                 if (0.1..=0.9).contains(&ratio) {
@@ -132,6 +162,19 @@ fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> Layout
             state.inner_gap = state.inner_gap.saturating_sub(delta);
             LayoutResult::Ok
         }
+        "set-auto-rotate" => match args.first().map(String::as_str) {
+            Some("on") => {
+                state.auto_rotate = true;
+                LayoutResult::Ok
+            }
+            Some("off") => {
+                state.auto_rotate = false;
+                LayoutResult::Ok
+            }
+            _ => LayoutResult::Error {
+                message: "usage: set-auto-rotate <on|off>".to_string(),
+            },
+        },
         "focus-changed" => {
             if let Some(id) = args.first().and_then(|s| s.parse::<u32>().ok()) {
                 state.focused_window_id = Some(id);
@@ -142,6 +185,16 @@ fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> Layout
                 }
             }
         }
+        "promote" => {
+            if let Some(id) = args.first().and_then(|s| s.parse::<u32>().ok()) {
+                state.main_window_id = Some(id);
+                LayoutResult::Ok
+            } else {
+                LayoutResult::Error {
+                    message: "usage: promote <window_id>".to_string(),
+                }
+            }
+        }
         "zoom" => {
             let id = args
                 .first()
@@ -163,6 +216,17 @@ fn handle_command(state: &mut LayoutState, cmd: &str, args: &[String]) -> Layout
     }
 }
 
+/// Swap axes of a geometry computed for the transposed display
+fn transpose(geometry: WindowGeometry) -> WindowGeometry {
+    WindowGeometry {
+        x: geometry.y,
+        y: geometry.x,
+        width: geometry.height,
+        height: geometry.width,
+        ..geometry
+    }
+}
+
 fn generate_layout(
     state: &LayoutState,
     width: u32,
@@ -225,6 +289,8 @@ fn generate_layout(
             y: y as i32,
             width: main_width,
             height: h,
+            raise_order: None,
+            hidden: false,
         });
     }
 
@@ -249,9 +315,90 @@ fn generate_layout(
                 y: y as i32,
                 width: stack_width,
                 height: h,
+                raise_order: None,
+                hidden: false,
             });
         }
     }
 
     windows
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(
+        state: &mut LayoutState,
+        width: u32,
+        height: u32,
+        windows: &[u32],
+    ) -> Vec<WindowGeometry> {
+        let msg = LayoutMessage::Layout {
+            width,
+            height,
+            windows: windows.to_vec(),
+        };
+        let LayoutResult::Layout { windows } = handle_message(state, msg) else {
+            panic!("Expected Layout");
+        };
+        windows
+    }
+
+    #[test]
+    fn test_set_auto_rotate_stacks_on_portrait_displays() {
+        let mut state = LayoutState::default();
+
+        // Off: main and stack side by side even on a portrait display
+        let windows = layout(&mut state, 1080, 1920, &[1, 2]);
+        assert_eq!((windows[0].x, windows[0].y), (0, 0));
+        assert_eq!((windows[0].width, windows[0].height), (540, 1920));
+        assert_eq!((windows[1].x, windows[1].y), (540, 0));
+
+        // On: main on top with the stack below
+        let result = handle_command(&mut state, "set-auto-rotate", &["on".to_string()]);
+        assert!(matches!(result, LayoutResult::Ok));
+        let windows = layout(&mut state, 1080, 1920, &[1, 2]);
+        assert_eq!((windows[0].x, windows[0].y), (0, 0));
+        assert_eq!((windows[0].width, windows[0].height), (1080, 960));
+        assert_eq!((windows[1].x, windows[1].y), (0, 960));
+        assert_eq!((windows[1].width, windows[1].height), (1080, 960));
+
+        // Landscape displays are unaffected
+        let windows = layout(&mut state, 1920, 1080, &[1, 2]);
+        assert_eq!((windows[1].x, windows[1].y), (960, 0));
+
+        let result = handle_command(&mut state, "set-auto-rotate", &["off".to_string()]);
+        assert!(matches!(result, LayoutResult::Ok));
+        let windows = layout(&mut state, 1080, 1920, &[1, 2]);
+        assert_eq!((windows[1].x, windows[1].y), (540, 0));
+
+        let result = handle_command(&mut state, "set-auto-rotate", &[]);
+        assert!(matches!(result, LayoutResult::Error { .. }));
+    }
+
+    #[test]
+    fn test_promote_moves_window_to_main() {
+        let mut state = LayoutState::default();
+        let ids = |windows: &[WindowGeometry]| windows.iter().map(|w| w.id).collect::<Vec<_>>();
+
+        // The focused window takes the main slot
+        handle_command(&mut state, "focus-changed", &["3".to_string()]);
+        let result = handle_command(&mut state, "promote", &["3".to_string()]);
+        assert!(matches!(result, LayoutResult::Ok));
+        let windows = layout(&mut state, 1920, 1080, &[1, 2, 3]);
+        assert_eq!(ids(&windows), vec![3, 1, 2]);
+        assert_eq!((windows[0].x, windows[0].width), (0, 960));
+
+        // window-swap of the main window with the next one reorders the windows and promotes
+        // the next one, which would otherwise be pushed back behind the remembered main
+        handle_command(&mut state, "promote", &["1".to_string()]);
+        assert_eq!(
+            ids(&layout(&mut state, 1920, 1080, &[1, 3, 2])),
+            vec![1, 3, 2]
+        );
+
+        let result = handle_command(&mut state, "promote", &[]);
+        assert!(matches!(result, LayoutResult::Error { .. }));
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::core::Rect;
+use yashiki_ipc::AnimationEasing;
+
+/// Interval between animation frames (~60fps)
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameUpdate {
+    pub window_id: u32,
+    pub pid: i32,
+    pub frame: Rect,
+    pub resize: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Animation {
+    pid: i32,
+    from: Rect,
+    to: Rect,
+    start: Instant,
+}
+
+/// Interpolates window frames over time.
+/// Retargeting a window that is still animating continues from its current frame,
+/// so consecutive layouts coalesce instead of jumping.
+#[derive(Debug, Default)]
+pub struct Animator {
+    duration: Duration,
+    easing: AnimationEasing,
+    animations: HashMap<u32, Animation>,
+}
+
+impl Animator {
+    pub fn configure(&mut self, duration_ms: u32, easing: AnimationEasing) {
+        self.duration = Duration::from_millis(duration_ms as u64);
+        self.easing = easing;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.duration.is_zero()
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    pub fn animate(&mut self, window_id: u32, pid: i32, from: Rect, to: Rect, now: Instant) {
+        let from = self
+            .animations
+            .get(&window_id)
+            .map(|a| self.frame_at(a, now))
+            .unwrap_or(from);
+        if from == to {
+            self.animations.remove(&window_id);
+            return;
+        }
+        self.animations.insert(
+            window_id,
+            Animation {
+                pid,
+                from,
+                to,
+                start: now,
+            },
+        );
+    }
+
+    /// Stop animating a window (e.g. when its frame is set directly)
+    pub fn cancel(&mut self, window_id: u32) {
+        self.animations.remove(&window_id);
+    }
+
+    /// Compute frames for all running animations, dropping the ones that finished
+    pub fn step(&mut self, now: Instant) -> Vec<FrameUpdate> {
+        let updates: Vec<_> = self
+            .animations
+            .iter()
+            .map(|(&window_id, a)| FrameUpdate {
+                window_id,
+                pid: a.pid,
+                frame: self.frame_at(a, now),
+                resize: a.from.width != a.to.width || a.from.height != a.to.height,
+            })
+            .collect();
+        let duration = self.duration;
+        self.animations
+            .retain(|_, a| now.saturating_duration_since(a.start) < duration);
+        updates
+    }
+
+    fn frame_at(&self, animation: &Animation, now: Instant) -> Rect {
+        let elapsed = now.saturating_duration_since(animation.start);
+        if elapsed >= self.duration {
+            return animation.to;
+        }
+        let progress = self
+            .easing
+            .apply(elapsed.as_secs_f64() / self.duration.as_secs_f64());
+        let (from, to) = (animation.from, animation.to);
+        Rect {
+            x: lerp(from.x as i64, to.x as i64, progress) as i32,
+            y: lerp(from.y as i64, to.y as i64, progress) as i32,
+            width: lerp(from.width as i64, to.width as i64, progress) as u32,
+            height: lerp(from.height as i64, to.height as i64, progress) as u32,
+        }
+    }
+}
+
+fn lerp(from: i64, to: i64, progress: f64) -> i64 {
+    from + ((to - from) as f64 * progress).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn linear_animator() -> Animator {
+        let mut animator = Animator::default();
+        animator.configure(100, AnimationEasing::Linear);
+        animator
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(!Animator::default().is_enabled());
+    }
+
+    #[test]
+    fn test_step_interpolates_and_finishes() {
+        let mut animator = linear_animator();
+        let start = Instant::now();
+        animator.animate(1, 100, rect(0, 0, 100, 100), rect(100, 0, 100, 100), start);
+
+        let updates = animator.step(start + Duration::from_millis(50));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].frame, rect(50, 0, 100, 100));
+        assert!(!updates[0].resize);
+        assert!(animator.is_active());
+
+        let updates = animator.step(start + Duration::from_millis(100));
+        assert_eq!(updates[0].frame, rect(100, 0, 100, 100));
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_retarget_continues_from_current_frame() {
+        let mut animator = linear_animator();
+        let start = Instant::now();
+        animator.animate(1, 100, rect(0, 0, 100, 100), rect(100, 0, 100, 100), start);
+
+        // A new layout arrives halfway; the stale "from" is ignored
+        let mid = start + Duration::from_millis(50);
+        animator.animate(1, 100, rect(0, 0, 100, 100), rect(50, 0, 200, 100), mid);
+
+        let updates = animator.step(mid);
+        assert_eq!(updates[0].frame, rect(50, 0, 100, 100));
+        assert!(updates[0].resize);
+
+        let updates = animator.step(mid + Duration::from_millis(100));
+        assert_eq!(updates[0].frame, rect(50, 0, 200, 100));
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_animate_to_same_frame_is_noop() {
+        let mut animator = linear_animator();
+        let frame = rect(10, 10, 100, 100);
+        animator.animate(1, 100, frame, frame, Instant::now());
+        assert!(!animator.is_active());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut animator = linear_animator();
+        animator.animate(
+            1,
+            100,
+            rect(0, 0, 100, 100),
+            rect(100, 0, 100, 100),
+            Instant::now(),
+        );
+        animator.cancel(1);
+        assert!(!animator.is_active());
+    }
+}
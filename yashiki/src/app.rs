@@ -3,54 +3,92 @@ use std::ptr;
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::mpsc as std_mpsc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use core_foundation::base::TCFType;
 use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop};
+use core_foundation_sys::date::CFAbsoluteTimeGetCurrent;
 use core_foundation_sys::runloop::{
-    CFRunLoopAddSource, CFRunLoopGetMain, CFRunLoopSourceContext, CFRunLoopSourceCreate,
+    CFRunLoopAddSource, CFRunLoopAddTimer, CFRunLoopGetMain, CFRunLoopSourceContext,
+    CFRunLoopSourceCreate, CFRunLoopSourceSignal, CFRunLoopTimerCallBack, CFRunLoopTimerContext,
+    CFRunLoopTimerCreate, CFRunLoopTimerRef, CFRunLoopTimerSetNextFireDate,
 };
 use objc2::rc::Retained;
 use objc2_app_kit::{NSApplication, NSApplicationActivationPolicy, NSEvent, NSEventType};
 use objc2_foundation::MainThreadMarker;
 use tokio::sync::mpsc;
 
+mod bench;
 mod channels;
 mod command;
 mod dispatch;
 mod effects;
 mod focus;
+mod menubar;
 mod retile;
+#[cfg(any(test, feature = "simulate"))]
+mod simulate;
 mod state_events;
 mod sync_helper;
 
+pub use bench::MAX_BENCH_ITERATIONS;
+pub use command::runs_programs;
+
 use channels::{create_channels, run_async, IpcCommandWithResponse, MainChannels, SnapshotRequest};
-use dispatch::dispatch_command;
-use focus::{notify_layout_focus, switch_tag_for_focused_window};
-use retile::{do_retile, do_retile_display};
-use sync_helper::{process_new_windows, sync_and_process_new_windows, sync_focused_and_process};
+use dispatch::{
+    dispatch_command, prune_exited_processes, update_hidden_apps, update_window_opacity,
+};
+use focus::{
+    apply_deferred_warp, focus_visible_window_if_needed, notify_layout_focus,
+    switch_tag_for_focused_window,
+};
+use menubar::update_menubar_indicator;
+use retile::{do_retile, do_retile_display, flush_retile_hooks};
+#[cfg(feature = "simulate")]
+pub use simulate::{run_scenario, Scenario};
+use state_events::{
+    capture_event_state, emit_state_change_events, run_tag_change_hook, switch_tag_input_source,
+};
+use sync_helper::{
+    coalesce_observer_events, process_new_windows, stale_observer_pids,
+    sync_and_process_new_windows, sync_focused_and_process, take_due_throttled_pids,
+    throttle_observer_events,
+};
 
-use crate::core::State;
+use crate::animation::FRAME_INTERVAL;
+use crate::core::{default_excluded_apps, State};
 use crate::event::Event;
 use crate::event_emitter::{create_snapshot, EventEmitter};
 use crate::layout::LayoutEngineManager;
 use crate::macos;
 use crate::macos::{
-    DisplayReconfigEvent, HotkeyManager, MousePosition, MouseTracker, ObserverManager,
-    WorkspaceEvent, WorkspaceWatcher,
+    AXUIElement, CommandParser, DisplayReconfigEvent, HotkeyManager, MouseEvent, MouseTracker,
+    ObserverManager, StatusItem, UrlHandler, WorkspaceEvent, WorkspaceWatcher,
 };
 use crate::pid;
-use crate::platform::{MacOSWindowManipulator, MacOSWindowSystem, WindowManipulator};
-use yashiki_ipc::Command;
+use crate::platform::{MacOSWindowManipulator, MacOSWindowSystem, WindowManipulator, TIMER_PARKED};
+use yashiki_ipc::{Command, Response};
+
+/// Upper bound for a single AX call to an app, so a hung app cannot freeze the main thread
+const AX_MESSAGING_TIMEOUT_SECS: f32 = 1.0;
+
+/// How often observers are checked for having stopped delivering notifications
+const OBSERVER_VERIFY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Quiet period after the last display reconfiguration before the arrangement is handled
+const DISPLAY_SETTLE_DELAY: Duration = Duration::from_millis(500);
 
 struct RunLoopContext {
     ipc_cmd_rx: std_mpsc::Receiver<IpcCommandWithResponse>,
     hotkey_cmd_rx: std_mpsc::Receiver<Command>,
-    mouse_event_rx: std_mpsc::Receiver<MousePosition>,
+    mouse_event_rx: std_mpsc::Receiver<MouseEvent>,
     observer_event_rx: std_mpsc::Receiver<Event>,
     workspace_event_rx: std_mpsc::Receiver<WorkspaceEvent>,
     snapshot_request_rx: std_mpsc::Receiver<SnapshotRequest>,
     display_reconfig_rx: std_mpsc::Receiver<DisplayReconfigEvent>,
+    display_settle_timer: Arc<AtomicPtr<std::ffi::c_void>>,
+    throttle_timer: Arc<AtomicPtr<std::ffi::c_void>>,
     event_tx: mpsc::Sender<Event>,
     event_emitter: EventEmitter,
     observer_manager: RefCell<ObserverManager>,
@@ -58,15 +96,18 @@ struct RunLoopContext {
     layout_engine_manager: RefCell<LayoutEngineManager>,
     hotkey_manager: RefCell<HotkeyManager>,
     mouse_tracker: RefCell<MouseTracker>,
+    status_item: RefCell<StatusItem>,
     window_system: MacOSWindowSystem,
     window_manipulator: MacOSWindowManipulator,
+    /// Replies to `wait` commands, sent once animations have finished
+    pending_waits: RefCell<Vec<mpsc::Sender<Response>>>,
     ns_app: Retained<NSApplication>,
 }
 
 pub struct App {}
 
 impl App {
-    pub fn run() -> Result<()> {
+    pub fn run(url_parser: CommandParser, disable_exec: bool) -> Result<()> {
         // Check if already running
         if let Some(existing_pid) = pid::check_already_running() {
             anyhow::bail!("yashiki is already running (pid: {})", existing_pid);
@@ -84,6 +125,11 @@ impl App {
             anyhow::bail!("Please grant Accessibility permission and restart");
         }
 
+        if let Err(e) = AXUIElement::system_wide().set_messaging_timeout(AX_MESSAGING_TIMEOUT_SECS)
+        {
+            tracing::warn!("Failed to set AX messaging timeout: {}", e);
+        }
+
         let (tokio_channels, main_channels) = create_channels();
 
         // Spawn tokio runtime in separate thread
@@ -95,14 +141,14 @@ impl App {
         });
 
         let app = App {};
-        app.run_main_loop(main_channels);
+        app.run_main_loop(main_channels, url_parser, disable_exec);
 
         // Clean up PID file on exit
         pid::remove_pid();
         Ok(())
     }
 
-    fn run_main_loop(self, channels: MainChannels) {
+    fn run_main_loop(self, channels: MainChannels, url_parser: CommandParser, disable_exec: bool) {
         // Destructure channels
         let MainChannels {
             ipc_cmd_rx,
@@ -129,10 +175,15 @@ impl App {
         let observer_source_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
         let workspace_source_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
         let display_source_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let animation_timer_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let display_settle_timer_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let throttle_timer_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let hook_timer_ptr = Arc::new(AtomicPtr::new(ptr::null_mut()));
 
         // Start observer manager (with source_ptr for event-driven signaling)
         let mut observer_manager =
             ObserverManager::new(observer_event_tx, observer_source_ptr.clone());
+        observer_manager.set_excluded_apps(default_excluded_apps());
         observer_manager.start();
 
         // Start workspace watcher for app launch/terminate notifications
@@ -144,6 +195,13 @@ impl App {
         let window_system = MacOSWindowSystem;
         let mut state = State::new();
         state.config.exec_path = build_initial_exec_path();
+        state.exec_disabled = disable_exec;
+        state.internal_pids.insert(std::process::id() as i32);
+        // Started behind the lock screen: no lock notification will arrive
+        state.screen_locked = macos::is_session_locked();
+        if state.screen_locked {
+            tracing::info!("Screen locked at startup, ignoring window events until unlock");
+        }
         // Initial sync has no hidden windows, so rehide_moves is always empty
         // Note: new_window_ids are not processed here - rules aren't loaded yet,
         // ApplyRules command is sent after init script runs
@@ -155,9 +213,11 @@ impl App {
         let layout_engine_manager = RefCell::new(layout_engine_manager);
 
         let state = RefCell::new(state);
+        sync_stage_manager(&state);
 
         // Create window manipulator
-        let window_manipulator = MacOSWindowManipulator;
+        let window_manipulator =
+            MacOSWindowManipulator::new(animation_timer_ptr.clone(), hook_timer_ptr.clone());
 
         // Create event emitter
         let event_emitter = EventEmitter::new(state_event_tx);
@@ -171,6 +231,15 @@ impl App {
 
         // Create hotkey manager
         let (hotkey_cmd_tx, hotkey_cmd_rx) = std_mpsc::channel::<Command>();
+
+        // yashiki:// URLs are dispatched like hotkey commands
+        let _url_handler = UrlHandler::new(
+            hotkey_cmd_tx.clone(),
+            Arc::clone(&hotkey_source_ptr),
+            url_parser,
+            mtm,
+        );
+
         let mut hotkey_manager = HotkeyManager::new(hotkey_cmd_tx, hotkey_source_clone);
 
         // Start hotkey tap (initially with no bindings, will be updated via IPC)
@@ -183,7 +252,7 @@ impl App {
         let mouse_source_clone = Arc::clone(&mouse_source_ptr);
 
         // Create mouse tracker (initially stopped, will be started via IPC)
-        let (mouse_event_tx, mouse_event_rx) = std_mpsc::channel::<MousePosition>();
+        let (mouse_event_tx, mouse_event_rx) = std_mpsc::channel::<MouseEvent>();
         let mouse_tracker = MouseTracker::new(mouse_event_tx, mouse_source_clone);
 
         // Create shared context for IPC/hotkey/display sources
@@ -195,6 +264,8 @@ impl App {
             workspace_event_rx,
             snapshot_request_rx,
             display_reconfig_rx,
+            display_settle_timer: display_settle_timer_ptr.clone(),
+            throttle_timer: throttle_timer_ptr.clone(),
             event_tx,
             event_emitter,
             observer_manager: RefCell::new(observer_manager),
@@ -202,8 +273,10 @@ impl App {
             layout_engine_manager,
             hotkey_manager: RefCell::new(hotkey_manager),
             mouse_tracker: RefCell::new(mouse_tracker),
+            status_item: RefCell::new(StatusItem::new(mtm)),
             window_system,
             window_manipulator,
+            pending_waits: RefCell::new(Vec::new()),
             ns_app: ns_app.clone(),
         });
         let context_ptr = Box::into_raw(context) as *mut std::ffi::c_void;
@@ -211,6 +284,7 @@ impl App {
         // Create CFRunLoopSource for IPC commands (immediate processing)
         extern "C" fn ipc_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
 
             // Process snapshot requests
             while let Ok(resp_tx) = ctx.snapshot_request_rx.try_recv() {
@@ -221,6 +295,12 @@ impl App {
             // Process all pending IPC commands
             while let Ok((cmd, resp_tx)) = ctx.ipc_cmd_rx.try_recv() {
                 tracing::debug!("Received IPC command: {:?}", cmd);
+                unlock_if_session_unlocked(ctx);
+
+                if matches!(cmd, Command::Wait) && ctx.window_manipulator.is_animating() {
+                    ctx.pending_waits.borrow_mut().push(resp_tx);
+                    continue;
+                }
 
                 let response = dispatch_command(
                     &cmd,
@@ -236,9 +316,24 @@ impl App {
 
                 // Handle Quit command after sending response
                 if matches!(cmd, Command::Quit) {
-                    // Terminate all tracked processes
-                    for process in ctx.state.borrow().tracked_processes.iter() {
-                        ctx.window_manipulator.terminate_process(process.pid);
+                    // Terminate all tracked processes that are still running
+                    prune_exited_processes(&ctx.state, &ctx.window_manipulator);
+                    for process in ctx.state.borrow().spawned_processes.iter() {
+                        if process.track && ctx.window_manipulator.is_process_alive(process.pid) {
+                            ctx.window_manipulator.terminate_process(process.pid);
+                        }
+                    }
+                    // Bring back apps hidden for having no visible windows
+                    for &pid in ctx.state.borrow().hidden_apps.iter() {
+                        ctx.window_manipulator.unhide_app(pid);
+                    }
+                    // Give tiled windows their shadows back
+                    for &window_id in ctx.state.borrow().shadowless_windows.iter() {
+                        let _ = ctx.window_manipulator.set_window_shadow(window_id, true);
+                    }
+                    // Make translucent windows opaque again
+                    for &window_id in ctx.state.borrow().window_alpha.keys() {
+                        let _ = ctx.window_manipulator.set_window_alpha(window_id, 1.0);
                     }
                     // Stop NSApplication and post a dummy event to exit run() immediately
                     ctx.ns_app.stop(None);
@@ -264,26 +359,12 @@ impl App {
                 tracing::error!("Failed to update hotkey tap: {}", e);
             }
 
-            // Sync mouse tracker state with auto-raise config
-            {
-                use yashiki_ipc::AutoRaiseMode;
-                let mode = ctx.state.borrow().config.auto_raise_mode;
-                let mut tracker = ctx.mouse_tracker.borrow_mut();
-                match mode {
-                    AutoRaiseMode::Enabled => {
-                        if !tracker.is_running() {
-                            if let Err(e) = tracker.start() {
-                                tracing::error!("Failed to start mouse tracker: {}", e);
-                            }
-                        }
-                    }
-                    AutoRaiseMode::Disabled => {
-                        if tracker.is_running() {
-                            tracker.stop();
-                        }
-                    }
-                }
-            }
+            sync_mouse_tracker(ctx);
+
+            ctx.event_emitter
+                .emit_tags_state_if_changed(&ctx.state.borrow());
+            ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+            update_menubar_indicator(&ctx.state, &ctx.status_item);
         }
 
         let mut source_context = CFRunLoopSourceContext {
@@ -314,12 +395,16 @@ impl App {
             }
             // Share source pointer with tokio thread
             ipc_source_ptr.store(ipc_source as *mut std::ffi::c_void, Ordering::Release);
+            // Commands that arrived during startup were queued without a signal;
+            // process them once the run loop starts, against the fully synced state
+            unsafe { CFRunLoopSourceSignal(ipc_source) };
             tracing::info!("IPC CFRunLoopSource created and registered");
         }
 
         // Create CFRunLoopSource for hotkey commands (immediate processing)
         extern "C" fn hotkey_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
 
             // Process all pending hotkey commands
             while let Ok(cmd) = ctx.hotkey_cmd_rx.try_recv() {
@@ -336,6 +421,18 @@ impl App {
                     &ctx.observer_manager,
                 );
             }
+
+            // Apply binding changes made from a hotkey (e.g. bindings-suspend)
+            if let Err(e) = ctx.hotkey_manager.borrow_mut().ensure_tap() {
+                tracing::error!("Failed to update hotkey tap: {}", e);
+            }
+
+            sync_mouse_tracker(ctx);
+
+            ctx.event_emitter
+                .emit_tags_state_if_changed(&ctx.state.borrow());
+            ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+            update_menubar_indicator(&ctx.state, &ctx.status_item);
         }
 
         let mut hotkey_source_context = CFRunLoopSourceContext {
@@ -373,12 +470,27 @@ impl App {
         // Create CFRunLoopSource for mouse events (auto-raise)
         extern "C" fn mouse_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
 
             use std::time::Instant;
             use yashiki_ipc::AutoRaiseMode;
 
+            if ctx.state.borrow().screen_locked {
+                while ctx.mouse_event_rx.try_recv().is_ok() {}
+                return;
+            }
+
             // Process all pending mouse events
-            while let Ok(pos) = ctx.mouse_event_rx.try_recv() {
+            while let Ok(event) = ctx.mouse_event_rx.try_recv() {
+                let pos = match event {
+                    MouseEvent::Moved(pos) => pos,
+                    MouseEvent::LeftUp => {
+                        // Also performs a cursor warp deferred during the drag
+                        sync_drag_deferred_pids(ctx);
+                        continue;
+                    }
+                };
+
                 // Check if auto-raise is enabled
                 let mode = ctx.state.borrow().config.auto_raise_mode;
                 if mode == AutoRaiseMode::Disabled {
@@ -412,7 +524,10 @@ impl App {
                                         );
                                         ctx.window_manipulator.focus_window(window_id, pid);
                                         ctx.state.borrow_mut().set_focused(Some(window_id));
-                                        ctx.event_emitter.emit_window_focused(Some(window_id));
+                                        ctx.event_emitter.emit_window_focused(
+                                            Some(window_id),
+                                            &ctx.state.borrow(),
+                                        );
                                         // Clear hover state after focusing
                                         ctx.state.borrow_mut().auto_raise_state.hover_start = None;
                                     }
@@ -432,6 +547,11 @@ impl App {
                     }
                 }
             }
+
+            ctx.event_emitter
+                .emit_tags_state_if_changed(&ctx.state.borrow());
+            ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+            update_menubar_indicator(&ctx.state, &ctx.status_item);
         }
 
         let mut mouse_source_context = CFRunLoopSourceContext {
@@ -466,75 +586,90 @@ impl App {
             tracing::info!("Mouse CFRunLoopSource created and registered");
         }
 
+        // Create CFRunLoopTimer that handles display changes once they settle
+        // (parked until a reconfiguration arms it)
+        extern "C" fn display_settle_timer_callback(
+            _timer: CFRunLoopTimerRef,
+            info: *mut std::ffi::c_void,
+        ) {
+            let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+            handle_display_settled(ctx);
+        }
+
+        let display_settle_timer =
+            add_repeating_timer(display_settle_timer_callback, context_ptr, TIMER_PARKED);
+        if display_settle_timer.is_null() {
+            tracing::error!("Failed to create CFRunLoopTimer for display changes");
+        } else {
+            display_settle_timer_ptr.store(
+                display_settle_timer as *mut std::ffi::c_void,
+                Ordering::Release,
+            );
+        }
+
+        // Create CFRunLoopTimer that syncs apps held back by throttle rules
+        // (parked until a throttled event arms it)
+        extern "C" fn throttle_timer_callback(
+            _timer: CFRunLoopTimerRef,
+            info: *mut std::ffi::c_void,
+        ) {
+            let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+            // Re-armed on unlock
+            if ctx.state.borrow().screen_locked {
+                return;
+            }
+            let pids = take_due_throttled_pids(&mut ctx.state.borrow_mut(), Instant::now());
+            sync_pids(ctx, pids);
+            arm_throttle_timer(ctx);
+        }
+
+        let throttle_timer =
+            add_repeating_timer(throttle_timer_callback, context_ptr, TIMER_PARKED);
+        if throttle_timer.is_null() {
+            tracing::error!("Failed to create CFRunLoopTimer for sync throttling");
+        } else {
+            throttle_timer_ptr.store(throttle_timer as *mut std::ffi::c_void, Ordering::Release);
+        }
+
+        // Create CFRunLoopTimer that runs post-retile hooks once retiles settle
+        // (parked until a retile schedules it)
+        extern "C" fn hook_timer_callback(_timer: CFRunLoopTimerRef, info: *mut std::ffi::c_void) {
+            let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+            flush_retile_hooks(&ctx.state, &ctx.window_manipulator, Instant::now());
+        }
+
+        let hook_timer = add_repeating_timer(hook_timer_callback, context_ptr, TIMER_PARKED);
+        if hook_timer.is_null() {
+            tracing::error!("Failed to create CFRunLoopTimer for retile hooks");
+        } else {
+            hook_timer_ptr.store(hook_timer as *mut std::ffi::c_void, Ordering::Release);
+        }
+
         // Create CFRunLoopSource for display reconfiguration events
         extern "C" fn display_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
 
-            // Process all pending display reconfig events
+            // Reconfiguration arrives in bursts (sleep/wake, arrangement changes), so wait for
+            // the displays to settle and handle the final arrangement once
+            let mut received = false;
             while let Ok(event) = ctx.display_reconfig_rx.try_recv() {
                 tracing::info!(
                     "Display reconfiguration: display_id={}, flags={:#x}",
                     event.display_id,
                     event.flags
                 );
+                received = true;
+            }
+            if !received {
+                return;
+            }
 
-                // Handle display change
-                let result = ctx
-                    .state
-                    .borrow_mut()
-                    .handle_display_change(&ctx.window_system);
-
-                // Emit display events
-                let focused_display = ctx.state.borrow().focused_display;
-                for display in &result.added {
-                    ctx.event_emitter
-                        .emit_display_added(display, focused_display);
-                }
-                for display_id in &result.removed {
-                    ctx.event_emitter.emit_display_removed(*display_id);
-                }
-
-                // Emit DisplayUpdated events for frame changes
-                {
-                    let state = ctx.state.borrow();
-                    for disp in state.displays.values() {
-                        ctx.event_emitter
-                            .emit_display_updated(disp, focused_display);
-                    }
-                }
-
-                // Apply window moves for orphaned windows
-                if !result.window_moves.is_empty() {
-                    ctx.window_manipulator
-                        .apply_window_moves(&result.window_moves);
-                }
-
-                // Apply rules to newly discovered windows
-                process_new_windows(
-                    result.new_window_ids,
-                    &ctx.state,
-                    &ctx.layout_engine_manager,
-                    &ctx.window_manipulator,
-                    &ctx.event_emitter,
-                );
-
-                // Retile affected displays
-                if !result.displays_to_retile.is_empty() {
-                    for display_id in result.displays_to_retile {
-                        do_retile_display(
-                            &ctx.state,
-                            &ctx.layout_engine_manager,
-                            &ctx.window_manipulator,
-                            display_id,
-                        );
-                    }
-                } else {
-                    do_retile(
-                        &ctx.state,
-                        &ctx.layout_engine_manager,
-                        &ctx.window_manipulator,
-                    );
-                }
+            if !arm_timer(&ctx.display_settle_timer, DISPLAY_SETTLE_DELAY) {
+                handle_display_settled(ctx);
             }
         }
 
@@ -581,9 +716,16 @@ impl App {
         // Create CFRunLoopSource for workspace events (app launch/terminate)
         extern "C" fn workspace_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+
+            sync_stage_manager(&ctx.state);
 
             // Process workspace events (app launch/terminate)
             while let Ok(event) = ctx.workspace_event_rx.try_recv() {
+                if ctx.state.borrow().screen_locked {
+                    handle_workspace_event_while_locked(ctx, event);
+                    continue;
+                }
                 match event {
                     WorkspaceEvent::AppLaunched { pid } => {
                         tracing::info!("App launched, adding observer for pid {}", pid);
@@ -631,7 +773,7 @@ impl App {
                         }
 
                         ctx.event_emitter
-                            .emit_window_focused(ctx.state.borrow().focused);
+                            .emit_window_focused(ctx.state.borrow().focused, &ctx.state.borrow());
                     }
                     WorkspaceEvent::AppTerminated { pid } => {
                         tracing::info!("App terminated, removing observer for pid {}", pid);
@@ -642,7 +784,11 @@ impl App {
                             let state = ctx.state.borrow();
                             for window in state.windows.values() {
                                 if window.pid == pid {
-                                    ctx.event_emitter.emit_window_destroyed(window.id);
+                                    ctx.event_emitter.emit_window_destroyed(
+                                        window.id,
+                                        window.display_id,
+                                        window.app_id.as_deref(),
+                                    );
                                 }
                             }
                         }
@@ -705,20 +851,47 @@ impl App {
                                 );
                             }
 
-                            ctx.event_emitter
-                                .emit_window_focused(ctx.state.borrow().focused);
+                            ctx.event_emitter.emit_window_focused(
+                                ctx.state.borrow().focused,
+                                &ctx.state.borrow(),
+                            );
                         } else {
                             tracing::debug!("App activated (already tracked), pid {}", pid);
                         }
                     }
+                    WorkspaceEvent::AppUnhidden { pid } => {
+                        // Apps hidden with window-hide-app may come back from the Dock
+                        // or app switcher, not only through window-unhide-app
+                        let returned = ctx.state.borrow_mut().app_unhidden(pid);
+                        if returned {
+                            do_retile(
+                                &ctx.state,
+                                &ctx.layout_engine_manager,
+                                &ctx.window_manipulator,
+                            );
+                        }
+                    }
                     WorkspaceEvent::DisplaysChanged => {
                         // Handled by display_source_callback via CGDisplayRegisterReconfigurationCallback
                         tracing::debug!(
                             "DisplaysChanged event received (handled by display callback)"
                         );
                     }
+                    WorkspaceEvent::ScreenLocked => {
+                        // Displays and windows are in flux behind loginwindow, so anything
+                        // synced now could move windows to bogus positions
+                        tracing::info!("Screen locked, ignoring window events until unlock");
+                        ctx.state.borrow_mut().screen_locked = true;
+                    }
+                    WorkspaceEvent::ScreenUnlocked | WorkspaceEvent::DidWake => {}
                 }
             }
+
+            update_window_opacity(&ctx.state, &ctx.window_manipulator);
+            ctx.event_emitter
+                .emit_tags_state_if_changed(&ctx.state.borrow());
+            ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+            update_menubar_indicator(&ctx.state, &ctx.status_item);
         }
 
         let mut workspace_source_context = CFRunLoopSourceContext {
@@ -757,10 +930,27 @@ impl App {
         // Create CFRunLoopSource for observer events
         extern "C" fn observer_source_callback(info: *const std::ffi::c_void) {
             let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+
+            // Coalesce move/resize floods (e.g. during window drags)
+            let events: Vec<Event> = ctx.observer_event_rx.try_iter().collect();
+            if ctx.state.borrow().screen_locked {
+                tracing::debug!("Ignoring {} observer events while locked", events.len());
+                return;
+            }
+            let events = coalesce_observer_events(
+                events,
+                macos::is_left_mouse_down(),
+                &mut ctx.state.borrow_mut().drag_deferred_pids,
+            );
+            let events =
+                throttle_observer_events(events, &mut ctx.state.borrow_mut(), Instant::now());
+            arm_throttle_timer(ctx);
+            sync_mouse_tracker(ctx);
 
             // Process observer events and forward to tokio
             let mut needs_retile = false;
-            while let Ok(event) = ctx.observer_event_rx.try_recv() {
+            for event in events {
                 let is_focus_event = matches!(
                     event,
                     Event::FocusedWindowChanged | Event::ApplicationActivated { .. }
@@ -804,6 +994,13 @@ impl App {
                     }
                 }
 
+                // Capture the focused window in case this event closed it
+                let closed_focus = if matches!(event, Event::WindowDestroyed { .. }) {
+                    ctx.state.borrow().focused
+                } else {
+                    None
+                };
+
                 let (changed, new_window_ids, rehide_moves) = ctx
                     .state
                     .borrow_mut()
@@ -827,6 +1024,26 @@ impl App {
                     &ctx.event_emitter,
                 );
 
+                // The focused window closed: focus the previous one instead of
+                // leaving it to whatever app macOS activates
+                if let Some(closed_id) = closed_focus {
+                    if !ctx.state.borrow().windows.contains_key(&closed_id) {
+                        if let Some(window_id) =
+                            focus_visible_window_if_needed(&ctx.state, &ctx.window_manipulator)
+                        {
+                            ctx.event_emitter
+                                .emit_window_focused(Some(window_id), &ctx.state.borrow());
+                            if notify_layout_focus(
+                                &ctx.state,
+                                &ctx.layout_engine_manager,
+                                window_id,
+                            ) {
+                                needs_retile = true;
+                            }
+                        }
+                    }
+                }
+
                 // On external focus change, notify layout engine and switch tag if focused window is hidden
                 if is_focus_event {
                     let focused_id = ctx.state.borrow().focused;
@@ -860,7 +1077,8 @@ impl App {
                     }
 
                     // Emit focus change event
-                    ctx.event_emitter.emit_window_focused(focused_id);
+                    ctx.event_emitter
+                        .emit_window_focused(focused_id, &ctx.state.borrow());
 
                     if let Some(focused_id) = focused_id {
                         if notify_layout_focus(&ctx.state, &ctx.layout_engine_manager, focused_id) {
@@ -876,12 +1094,17 @@ impl App {
                         Some(Some(prev_id)) => Some(prev_id) != focused_id,
                         _ => false,
                     };
-                    if focus_changed {
+                    if focus_changed && !ctx.state.borrow().paused {
+                        let pre = capture_event_state(&ctx.state);
                         let moves = switch_tag_for_focused_window(&ctx.state);
                         if let Some(moves) = moves {
                             ctx.window_manipulator.apply_window_moves(&moves);
                             needs_retile = true;
                         }
+                        emit_state_change_events(&ctx.event_emitter, &ctx.state, &pre);
+                        run_tag_change_hook(&ctx.state, &pre, &ctx.window_manipulator);
+                        switch_tag_input_source(&ctx.state, &pre, &ctx.window_manipulator);
+                        update_hidden_apps(&ctx.state, &ctx.window_manipulator);
                     }
                 }
 
@@ -896,6 +1119,12 @@ impl App {
                     &ctx.window_manipulator,
                 );
             }
+
+            update_window_opacity(&ctx.state, &ctx.window_manipulator);
+            ctx.event_emitter
+                .emit_tags_state_if_changed(&ctx.state.borrow());
+            ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+            update_menubar_indicator(&ctx.state, &ctx.status_item);
         }
 
         let mut observer_source_context = CFRunLoopSourceContext {
@@ -930,6 +1159,78 @@ impl App {
             tracing::info!("Observer CFRunLoopSource created and registered");
         }
 
+        // Create CFRunLoopTimer that recreates observers which stopped delivering notifications
+        extern "C" fn observer_verify_timer_callback(
+            _timer: CFRunLoopTimerRef,
+            info: *mut std::ffi::c_void,
+        ) {
+            let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+            // Apps deliver no notifications while locked, which would look stale
+            if !ctx.state.borrow().screen_locked {
+                recreate_stale_observers(ctx);
+            }
+        }
+
+        if add_repeating_timer(
+            observer_verify_timer_callback,
+            context_ptr,
+            OBSERVER_VERIFY_INTERVAL.as_secs_f64(),
+        )
+        .is_null()
+        {
+            tracing::error!("Failed to create CFRunLoopTimer for observer verification");
+        }
+
+        // Create CFRunLoopTimer for window animations (parks itself while no animation runs)
+        extern "C" fn animation_timer_callback(
+            _timer: CFRunLoopTimerRef,
+            info: *mut std::ffi::c_void,
+        ) {
+            let ctx = unsafe { &*(info as *const RunLoopContext) };
+            record_wakeup(ctx);
+            ctx.window_manipulator.step_animations();
+            if !ctx.window_manipulator.is_animating() {
+                for resp_tx in ctx.pending_waits.borrow_mut().drain(..) {
+                    let _ = resp_tx.blocking_send(Response::Ok);
+                }
+            }
+        }
+
+        let mut animation_timer_context = CFRunLoopTimerContext {
+            version: 0,
+            info: context_ptr,
+            retain: None,
+            release: None,
+            copyDescription: None,
+        };
+
+        let animation_timer = unsafe {
+            CFRunLoopTimerCreate(
+                ptr::null(),
+                CFAbsoluteTimeGetCurrent(),
+                FRAME_INTERVAL.as_secs_f64(),
+                0,
+                0,
+                animation_timer_callback,
+                &mut animation_timer_context,
+            )
+        };
+        if animation_timer.is_null() {
+            tracing::error!("Failed to create CFRunLoopTimer for animations");
+        } else {
+            let run_loop = CFRunLoop::get_current();
+            unsafe {
+                CFRunLoopAddTimer(
+                    run_loop.as_concrete_TypeRef(),
+                    animation_timer,
+                    kCFRunLoopDefaultMode,
+                );
+            }
+            animation_timer_ptr.store(animation_timer as *mut std::ffi::c_void, Ordering::Release);
+            tracing::info!("Animation CFRunLoopTimer created and registered");
+        }
+
         // Run init script in background thread
         std::thread::spawn(|| {
             run_init_script();
@@ -941,6 +1242,294 @@ impl App {
     }
 }
 
+/// Run the mouse tracker while auto-raise is enabled or a drag has deferred syncs or
+/// a deferred cursor warp (it reports the mouse-up that ends the drag)
+fn sync_mouse_tracker(ctx: &RunLoopContext) {
+    // The button may have been released before the tracker was running
+    apply_deferred_warp(&ctx.state, &ctx.window_manipulator);
+    let needed = {
+        let state = ctx.state.borrow();
+        state.config.auto_raise_mode == yashiki_ipc::AutoRaiseMode::Enabled
+            || !state.drag_deferred_pids.is_empty()
+            || state.deferred_warp.is_some()
+    };
+    let mut tracker = ctx.mouse_tracker.borrow_mut();
+    if needed && !tracker.is_running() {
+        if let Err(e) = tracker.start() {
+            tracing::error!("Failed to start mouse tracker: {}", e);
+        }
+    } else if !needed && tracker.is_running() {
+        tracker.stop();
+    }
+}
+
+/// Create a timer on the current run loop that first fires one interval from now.
+/// With `TIMER_PARKED` as interval it stays parked until `arm_timer` fires it.
+/// Returns null if the timer could not be created.
+fn add_repeating_timer(
+    callback: CFRunLoopTimerCallBack,
+    info: *mut std::ffi::c_void,
+    interval_secs: f64,
+) -> CFRunLoopTimerRef {
+    let mut context = CFRunLoopTimerContext {
+        version: 0,
+        info,
+        retain: None,
+        release: None,
+        copyDescription: None,
+    };
+    let timer = unsafe {
+        CFRunLoopTimerCreate(
+            ptr::null(),
+            CFAbsoluteTimeGetCurrent() + interval_secs,
+            interval_secs,
+            0,
+            0,
+            callback,
+            &mut context,
+        )
+    };
+    if !timer.is_null() {
+        let run_loop = CFRunLoop::get_current();
+        unsafe { CFRunLoopAddTimer(run_loop.as_concrete_TypeRef(), timer, kCFRunLoopDefaultMode) };
+    }
+    timer
+}
+
+/// Fire a parked timer after `delay`. Returns false if the timer was never created.
+fn arm_timer(timer: &AtomicPtr<std::ffi::c_void>, delay: Duration) -> bool {
+    let timer = timer.load(Ordering::Acquire) as CFRunLoopTimerRef;
+    if timer.is_null() {
+        return false;
+    }
+    unsafe {
+        CFRunLoopTimerSetNextFireDate(timer, CFAbsoluteTimeGetCurrent() + delay.as_secs_f64())
+    };
+    true
+}
+
+/// Arm the throttle timer for the earliest held-back sync, if any.
+/// Without a timer, held-back pids are synced right away.
+fn arm_throttle_timer(ctx: &RunLoopContext) {
+    let pending: Vec<Instant> = ctx
+        .state
+        .borrow()
+        .throttle_pending
+        .values()
+        .copied()
+        .collect();
+    let (Some(&first), Some(&last)) = (pending.iter().min(), pending.iter().max()) else {
+        return;
+    };
+    if !arm_timer(
+        &ctx.throttle_timer,
+        first.saturating_duration_since(Instant::now()),
+    ) {
+        let pids = take_due_throttled_pids(&mut ctx.state.borrow_mut(), last);
+        sync_pids(ctx, pids);
+    }
+}
+
+/// Recreate observers that stopped delivering notifications and resync their apps
+fn recreate_stale_observers(ctx: &RunLoopContext) {
+    let stale = {
+        let observer_manager = ctx.observer_manager.borrow();
+        stale_observer_pids(
+            &ctx.state.borrow(),
+            &ctx.window_system,
+            |pid| observer_manager.last_event(pid),
+            Instant::now(),
+        )
+    };
+    let mut pids = Vec::new();
+    for (pid, app_name) in stale {
+        tracing::warn!(
+            "Observer for pid {} ({}) stopped delivering notifications, recreating",
+            pid,
+            app_name
+        );
+        if let Err(e) = ctx.observer_manager.borrow_mut().recreate_observer(pid) {
+            tracing::warn!("Failed to recreate observer for pid {}: {}", pid, e);
+            continue;
+        }
+        ctx.event_emitter.emit_observer_recreated(pid, &app_name);
+        pids.push(pid);
+    }
+    sync_pids(ctx, pids);
+}
+
+/// Sync windows whose move/resize events were deferred during a drag
+fn sync_drag_deferred_pids(ctx: &RunLoopContext) {
+    let pids: Vec<i32> = ctx.state.borrow_mut().drag_deferred_pids.drain().collect();
+    sync_pids(ctx, pids);
+    sync_mouse_tracker(ctx);
+}
+
+/// Sync the windows of each pid, retiling once if anything changed
+fn sync_pids(ctx: &RunLoopContext, pids: Vec<i32>) {
+    let mut needs_retile = false;
+    for pid in pids {
+        let result = sync_and_process_new_windows(
+            &ctx.state,
+            &ctx.window_system,
+            &ctx.layout_engine_manager,
+            &ctx.window_manipulator,
+            &ctx.event_emitter,
+            &ctx.observer_manager,
+            pid,
+        );
+        needs_retile |= result.changed;
+    }
+    if needs_retile {
+        do_retile(
+            &ctx.state,
+            &ctx.layout_engine_manager,
+            &ctx.window_manipulator,
+        );
+    }
+}
+
+/// While locked, only keep observers in step with running apps; their windows are
+/// picked up by the full sync on unlock
+fn handle_workspace_event_while_locked(ctx: &RunLoopContext, event: WorkspaceEvent) {
+    match event {
+        WorkspaceEvent::AppLaunched { pid } => {
+            if let Err(e) = ctx.observer_manager.borrow_mut().add_observer(pid) {
+                tracing::warn!("Failed to add observer for pid {}: {}", pid, e);
+            }
+        }
+        WorkspaceEvent::AppTerminated { pid } => {
+            ctx.observer_manager.borrow_mut().remove_observer(pid);
+        }
+        WorkspaceEvent::ScreenUnlocked => handle_screen_unlocked(ctx),
+        // The unlock notification may have been missed while asleep
+        WorkspaceEvent::DidWake => unlock_if_session_unlocked(ctx),
+        WorkspaceEvent::AppActivated { .. }
+        | WorkspaceEvent::AppUnhidden { .. }
+        | WorkspaceEvent::DisplaysChanged
+        | WorkspaceEvent::ScreenLocked => {}
+    }
+}
+
+fn handle_screen_unlocked(ctx: &RunLoopContext) {
+    tracing::info!("Screen unlocked, resyncing windows and displays");
+    ctx.state.borrow_mut().screen_locked = false;
+    handle_display_change(ctx);
+    arm_throttle_timer(ctx);
+}
+
+/// Fallback for a missed unlock notification: unlock when the session no longer reports
+/// a locked screen
+fn unlock_if_session_unlocked(ctx: &RunLoopContext) {
+    if !ctx.state.borrow().screen_locked || macos::is_session_locked() {
+        return;
+    }
+    tracing::info!("Session reports the screen unlocked without an unlock notification");
+    handle_screen_unlocked(ctx);
+}
+
+/// Displays stopped reconfiguring. While locked the full sync on unlock picks up the final
+/// arrangement, unless the session turns out to be unlocked already.
+fn handle_display_settled(ctx: &RunLoopContext) {
+    if ctx.state.borrow().screen_locked {
+        unlock_if_session_unlocked(ctx);
+    } else {
+        handle_display_change(ctx);
+    }
+}
+
+/// Bring state in line with the settled display arrangement
+fn handle_display_change(ctx: &RunLoopContext) {
+    sync_stage_manager(&ctx.state);
+
+    let result = ctx
+        .state
+        .borrow_mut()
+        .handle_display_change(&ctx.window_system);
+
+    // Emit display events
+    let focused_display = ctx.state.borrow().focused_display;
+    for display in &result.added {
+        ctx.event_emitter
+            .emit_display_added(display, focused_display);
+    }
+    for display_id in &result.removed {
+        ctx.event_emitter.emit_display_removed(*display_id);
+    }
+
+    // Emit DisplayUpdated events for frame changes
+    {
+        let state = ctx.state.borrow();
+        for disp in state.displays.values() {
+            ctx.event_emitter
+                .emit_display_updated(disp, focused_display);
+        }
+    }
+
+    // Apply window moves for orphaned windows
+    if !result.window_moves.is_empty() {
+        ctx.window_manipulator
+            .apply_window_moves(&result.window_moves);
+    }
+
+    // Apply rules to newly discovered windows
+    process_new_windows(
+        result.new_window_ids,
+        &ctx.state,
+        &ctx.layout_engine_manager,
+        &ctx.window_manipulator,
+        &ctx.event_emitter,
+    );
+
+    // Retile affected displays
+    if !result.displays_to_retile.is_empty() {
+        for display_id in result.displays_to_retile {
+            do_retile_display(
+                &ctx.state,
+                &ctx.layout_engine_manager,
+                &ctx.window_manipulator,
+                display_id,
+            );
+        }
+    } else {
+        do_retile(
+            &ctx.state,
+            &ctx.layout_engine_manager,
+            &ctx.window_manipulator,
+        );
+    }
+
+    ctx.event_emitter
+        .emit_tags_state_if_changed(&ctx.state.borrow());
+    ctx.event_emitter.emit_new_errors(&ctx.state.borrow());
+    update_menubar_indicator(&ctx.state, &ctx.status_item);
+}
+
+/// Track Stage Manager and warn when it gets turned on
+fn sync_stage_manager(state: &RefCell<State>) {
+    let enabled = macos::is_stage_manager_enabled();
+    let mut state = state.borrow_mut();
+    if enabled == state.stage_manager {
+        return;
+    }
+    if enabled {
+        tracing::warn!(
+            "Stage Manager is enabled. It moves and resizes windows on its own, \
+             so hiding and tiling will misbehave. Turn it off in System Settings > Desktop & Dock."
+        );
+    } else {
+        tracing::info!("Stage Manager is disabled");
+    }
+    state.stage_manager = enabled;
+}
+
+fn record_wakeup(ctx: &RunLoopContext) {
+    ctx.state
+        .borrow_mut()
+        .wakeups
+        .record(std::time::Instant::now());
+}
+
 fn build_initial_exec_path() -> String {
     let mut paths = Vec::new();
 
@@ -1033,10 +1622,12 @@ fn send_apply_rules(needs_delay: bool) {
 mod tests {
     use super::*;
     use crate::app::command::process_command;
+    use crate::app::retile::HOOK_SETTLE_DELAY;
     use crate::app::state_events::{capture_event_state, emit_state_change_events};
+    use crate::core::Tag;
     use crate::effect::Effect;
     use crate::platform::mock::{create_test_display, create_test_window, MockWindowSystem};
-    use yashiki_ipc::{Command, Direction, Response};
+    use yashiki_ipc::{Command, Direction, GlobPattern, Response};
 
     fn setup_state() -> (State, HotkeyManager) {
         let ws = MockWindowSystem::new()
@@ -1069,6 +1660,7 @@ mod tests {
             &Command::ListWindows {
                 all: false,
                 debug: false,
+                why: false,
             },
         );
         assert!(result.effects.is_empty());
@@ -1079,11 +1671,21 @@ mod tests {
         assert!(result.effects.is_empty());
         assert!(matches!(result.response, Response::State { .. }));
 
+        // StateDump
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::StateDump);
+        assert!(result.effects.is_empty());
+        assert!(matches!(result.response, Response::StateDump { .. }));
+
         // FocusedWindow
         let result = process_command(&mut state, &mut hotkey_manager, &Command::FocusedWindow);
         assert!(result.effects.is_empty());
         assert!(matches!(result.response, Response::WindowId { .. }));
 
+        // FocusedOutput
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::FocusedOutput);
+        assert!(result.effects.is_empty());
+        assert!(matches!(result.response, Response::Output { .. }));
+
         // ListBindings
         let result = process_command(&mut state, &mut hotkey_manager, &Command::ListBindings);
         assert!(result.effects.is_empty());
@@ -1091,37 +1693,309 @@ mod tests {
     }
 
     #[test]
-    fn test_list_windows_with_debug_includes_debug_fields() {
+    fn test_trigger_binding_runs_bound_command() {
         let (mut state, mut hotkey_manager) = setup_state();
+        hotkey_manager
+            .bind(
+                "alt-2",
+                Command::TagView {
+                    tags: 2,
+                    output: None,
+                },
+                false,
+                false,
+            )
+            .unwrap();
+        hotkey_manager.set_backend(yashiki_ipc::HotkeyBackend::External);
 
         let result = process_command(
             &mut state,
             &mut hotkey_manager,
-            &Command::ListWindows {
-                all: false,
-                debug: true,
+            &Command::TriggerBinding {
+                key: "alt-2".to_string(),
             },
         );
+        assert!(matches!(result.response, Response::Ok));
+        assert_eq!(state.visible_tags().mask(), 2);
 
-        assert!(result.effects.is_empty());
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::TriggerBinding {
+                key: "alt-3".to_string(),
+            },
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
 
-        if let Response::Windows { windows } = result.response {
-            assert!(!windows.is_empty());
-            // Debug fields should be populated when debug=true
-            let first_window = &windows[0];
-            // window_level should be Some when debug=true
-            assert!(first_window.window_level.is_some());
-            // close_button should be Some when debug=true
-            assert!(first_window.close_button.is_some());
-            // Status should be None for non-all mode
-            assert!(first_window.status.is_none());
-        } else {
-            panic!("Expected Response::Windows");
-        }
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::GetHotkeyBackend);
+        assert!(matches!(
+            result.response,
+            Response::HotkeyBackend {
+                backend: yashiki_ipc::HotkeyBackend::External
+            }
+        ));
     }
 
     #[test]
-    fn test_list_windows_all_true_returns_empty_marker() {
+    fn test_observer_exclude_restores_hidden_windows() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let saved = {
+            let window = state.windows.get_mut(&101).unwrap();
+            window.app_id = Some("com.apple.Terminal".to_string());
+            let saved = window.frame;
+            window.saved_frame = Some(saved);
+            window.frame.x = -10000;
+            saved
+        };
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::ObserverExclude {
+                app_id: GlobPattern::new("com.apple.Terminal"),
+            },
+        );
+        assert!(matches!(result.response, Response::Ok));
+        assert!(!state.windows.contains_key(&101));
+        match &result.effects[..] {
+            [Effect::ApplyWindowMoves(moves), Effect::Retile] => {
+                assert_eq!(moves.len(), 1);
+                assert_eq!(moves[0].window_id, 101);
+                assert_eq!((moves[0].new_x, moves[0].new_y), (saved.x, saved.y));
+            }
+            effects => panic!("Unexpected effects: {:?}", effects),
+        }
+    }
+
+    #[test]
+    fn test_bind_rejects_trigger_action() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::Bind {
+                key: "alt-a".to_string(),
+                action: Box::new(Command::TriggerBinding {
+                    key: "alt-a".to_string(),
+                }),
+                double: false,
+                passthrough: false,
+            },
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+        assert!(hotkey_manager.list_bindings().is_empty());
+    }
+
+    #[test]
+    fn test_trigger_binding_double_press() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let tag_view = Command::TagView {
+            tags: 2,
+            output: None,
+        };
+        hotkey_manager
+            .bind("alt-2", tag_view, false, false)
+            .unwrap();
+        hotkey_manager
+            .bind("alt-2", Command::TagViewLast, true, false)
+            .unwrap();
+        assert_eq!(hotkey_manager.list_bindings().len(), 2);
+
+        let trigger = Command::TriggerBinding {
+            key: "alt-2".to_string(),
+        };
+        process_command(&mut state, &mut hotkey_manager, &trigger);
+        assert_eq!(state.visible_tags().mask(), 2);
+        // Pressed again right away: back to the previous tags
+        process_command(&mut state, &mut hotkey_manager, &trigger);
+        assert_eq!(state.visible_tags().mask(), 1);
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::Unbind {
+                key: "alt-2".to_string(),
+            },
+        );
+        assert!(matches!(result.response, Response::Ok));
+        assert!(hotkey_manager.list_bindings().is_empty());
+    }
+
+    #[test]
+    fn test_exec_disabled_rejects_commands_that_run_programs() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        state.exec_disabled = true;
+        let exec = Command::Exec {
+            command: "open -a Safari".to_string(),
+            args: None,
+            track: false,
+            unique: None,
+        };
+
+        let result = process_command(&mut state, &mut hotkey_manager, &exec);
+        assert!(matches!(result.response, Response::Error { .. }));
+        assert!(result.effects.is_empty());
+
+        let bind = Command::Bind {
+            key: "alt-return".to_string(),
+            action: Box::new(exec),
+            double: false,
+            passthrough: false,
+        };
+        let result = process_command(&mut state, &mut hotkey_manager, &bind);
+        assert!(matches!(result.response, Response::Error { .. }));
+        assert!(hotkey_manager.list_bindings().is_empty());
+
+        // Clearing the hook and other commands still work
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::SetTagChangeHook { command: None },
+        );
+        assert!(matches!(result.response, Response::Ok));
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::GetState);
+        assert!(matches!(result.response, Response::State { .. }));
+    }
+
+    #[test]
+    fn test_rule_add_rejects_opacity_out_of_range() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let rule_add = |percent| Command::RuleAdd {
+            rule: yashiki_ipc::WindowRule::new(
+                yashiki_ipc::RuleMatcher::new(Some(GlobPattern::new("Safari")), None),
+                yashiki_ipc::RuleAction::Opacity { percent },
+            ),
+        };
+
+        for percent in [0, 101] {
+            let result = process_command(&mut state, &mut hotkey_manager, &rule_add(percent));
+            assert!(matches!(result.response, Response::Error { .. }));
+        }
+        assert!(state.rules_engine.rules().is_empty());
+
+        let result = process_command(&mut state, &mut hotkey_manager, &rule_add(80));
+        assert!(matches!(result.response, Response::Ok));
+        assert_eq!(state.rules_engine.rules().len(), 1);
+    }
+
+    #[test]
+    fn test_rule_add_rejects_invalid_dynamic_rules() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let rule_add = |title: Option<&str>, action| Command::RuleAdd {
+            rule: yashiki_ipc::WindowRule::new(
+                yashiki_ipc::RuleMatcher::new(None, title.map(GlobPattern::new)),
+                action,
+            )
+            .with_dynamic(true),
+        };
+
+        // Needs a title matcher
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &rule_add(None, yashiki_ipc::RuleAction::Float),
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+        // Only float, no-float, tags and output can be re-applied
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &rule_add(Some("*Meet*"), yashiki_ipc::RuleAction::Ignore),
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+        assert!(state.rules_engine.rules().is_empty());
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &rule_add(Some("*Meet*"), yashiki_ipc::RuleAction::Tags { tags: 8 }),
+        );
+        assert!(matches!(result.response, Response::Ok));
+    }
+
+    #[test]
+    fn test_window_info_returns_details_and_matched_rules() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        state.add_rule(yashiki_ipc::WindowRule::new(
+            yashiki_ipc::RuleMatcher::new(Some(yashiki_ipc::GlobPattern::new("Terminal")), None),
+            yashiki_ipc::RuleAction::Float,
+        ));
+
+        // Defaults to focused window
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::WindowInfo { window_id: None },
+        );
+        assert!(result.effects.is_empty());
+        match result.response {
+            Response::WindowDetails { window } => {
+                assert_eq!(window.id, 100);
+                assert!(window.is_focused);
+                assert!(window.matched_rules.is_empty());
+            }
+            _ => panic!("Expected WindowDetails response"),
+        }
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::WindowInfo {
+                window_id: Some(101),
+            },
+        );
+        match result.response {
+            Response::WindowDetails { window } => {
+                assert_eq!(window.app_name, "Terminal");
+                assert_eq!(window.matched_rules.len(), 1);
+                assert_eq!(window.matched_rules[0].action, "float");
+            }
+            _ => panic!("Expected WindowDetails response"),
+        }
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::WindowInfo {
+                window_id: Some(999),
+            },
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+    }
+
+    #[test]
+    fn test_list_windows_with_debug_includes_debug_fields() {
+        let (mut state, mut hotkey_manager) = setup_state();
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::ListWindows {
+                all: false,
+                debug: true,
+                why: false,
+            },
+        );
+
+        assert!(result.effects.is_empty());
+
+        if let Response::Windows { windows } = result.response {
+            assert!(!windows.is_empty());
+            // Debug fields should be populated when debug=true
+            let first_window = &windows[0];
+            // window_level should be Some when debug=true
+            assert!(first_window.window_level.is_some());
+            // close_button should be Some when debug=true
+            assert!(first_window.close_button.is_some());
+            // Status should be None for non-all mode
+            assert!(first_window.status.is_none());
+        } else {
+            panic!("Expected Response::Windows");
+        }
+    }
+
+    #[test]
+    fn test_list_windows_all_true_returns_empty_marker() {
         let (mut state, mut hotkey_manager) = setup_state();
 
         // When all=true, process_command returns an empty marker
@@ -1132,6 +2006,7 @@ mod tests {
             &Command::ListWindows {
                 all: true,
                 debug: false,
+                why: false,
             },
         );
 
@@ -1199,6 +2074,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_window_swap_promotes_new_main_window() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let swap = |state: &mut State, hotkey_manager: &mut HotkeyManager| {
+            process_command(
+                state,
+                hotkey_manager,
+                &Command::WindowSwap {
+                    direction: Direction::Next,
+                },
+            )
+            .effects
+        };
+
+        // 100 is main; swapping it with 101 makes 101 main
+        assert_eq!(
+            swap(&mut state, &mut hotkey_manager),
+            vec![
+                Effect::PromoteWindow {
+                    display_id: 1,
+                    window_id: 101,
+                },
+                Effect::RetileDisplays(vec![1]),
+            ]
+        );
+
+        // 100 is now second; swapping it with 102 leaves the main slot alone
+        assert_eq!(
+            swap(&mut state, &mut hotkey_manager),
+            vec![Effect::RetileDisplays(vec![1])]
+        );
+    }
+
+    #[test]
+    fn test_window_close_respects_close_behavior() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let close = |state: &mut State, hotkey_manager: &mut HotkeyManager, force_quit_app| {
+            process_command(
+                state,
+                hotkey_manager,
+                &Command::WindowClose { force_quit_app },
+            )
+            .effects
+        };
+
+        assert_eq!(
+            close(&mut state, &mut hotkey_manager, false),
+            vec![Effect::CloseWindow {
+                window_id: 100,
+                pid: 1000
+            }]
+        );
+
+        state.add_rule(yashiki_ipc::WindowRule::new(
+            yashiki_ipc::RuleMatcher::new(Some(yashiki_ipc::GlobPattern::new("Safari")), None),
+            yashiki_ipc::RuleAction::CloseBehavior {
+                behavior: yashiki_ipc::CloseBehavior::Hide,
+            },
+        ));
+        assert_eq!(
+            close(&mut state, &mut hotkey_manager, false),
+            vec![Effect::HideApp { pid: 1000 }]
+        );
+
+        // --force-quit-app overrides the rule
+        assert_eq!(
+            close(&mut state, &mut hotkey_manager, true),
+            vec![Effect::QuitApp {
+                pid: 1000,
+                force: true
+            }]
+        );
+    }
+
     #[test]
     fn test_exec_produces_exec_effect() {
         let (mut state, mut hotkey_manager) = setup_state();
@@ -1209,6 +2158,8 @@ mod tests {
             &Command::Exec {
                 command: "open -a Safari".to_string(),
                 track: false,
+                args: None,
+                unique: None,
             },
         );
 
@@ -1216,15 +2167,22 @@ mod tests {
         assert_eq!(result.effects.len(), 1);
 
         match &result.effects[0] {
-            Effect::ExecCommand { command, .. } => {
+            Effect::ExecCommand {
+                command, args, env, ..
+            } => {
                 assert_eq!(command, "open -a Safari");
+                assert_eq!(args, &None);
+                let var = |name: &str| env.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+                assert_eq!(var("YASHIKI_FOCUSED_ID"), Some("100"));
+                assert_eq!(var("YASHIKI_FOCUSED_APP"), Some("Safari"));
+                assert_eq!(var("YASHIKI_VISIBLE_TAGS"), Some("1"));
             }
             _ => panic!("Expected ExecCommand effect"),
         }
     }
 
     #[test]
-    fn test_exec_tracked_produces_exec_tracked_effect() {
+    fn test_exec_tracked_produces_tracked_exec_effect() {
         let (mut state, mut hotkey_manager) = setup_state();
 
         let result = process_command(
@@ -1233,6 +2191,8 @@ mod tests {
             &Command::Exec {
                 command: "sleep 1000".to_string(),
                 track: true,
+                args: None,
+                unique: None,
             },
         );
 
@@ -1240,10 +2200,103 @@ mod tests {
         assert_eq!(result.effects.len(), 1);
 
         match &result.effects[0] {
-            Effect::ExecCommandTracked { command, .. } => {
+            Effect::ExecCommand { command, track, .. } => {
                 assert_eq!(command, "sleep 1000");
+                assert!(track);
+            }
+            _ => panic!("Expected ExecCommand effect"),
+        }
+    }
+
+    #[test]
+    fn test_exec_no_shell_passes_args() {
+        let (mut state, mut hotkey_manager) = setup_state();
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::Exec {
+                command: "sleep".to_string(),
+                track: true,
+                args: Some(vec!["1000".to_string()]),
+                unique: None,
+            },
+        );
+
+        assert!(matches!(result.response, Response::Ok));
+        assert_eq!(result.effects.len(), 1);
+
+        match &result.effects[0] {
+            Effect::ExecCommand {
+                command,
+                args,
+                track,
+                ..
+            } => {
+                assert_eq!(command, "sleep");
+                assert_eq!(args, &Some(vec!["1000".to_string()]));
+                assert!(track);
+            }
+            _ => panic!("Expected ExecCommand effect"),
+        }
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let (mut state, mut hotkey_manager) = setup_state();
+
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::Pause);
+        assert!(matches!(result.response, Response::Ok));
+        assert!(state.paused);
+
+        state.deferred_new_windows.push(101);
+
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::Resume);
+        assert!(matches!(result.response, Response::Ok));
+        assert!(!state.paused);
+        assert!(state.deferred_new_windows.is_empty());
+        assert!(matches!(result.effects.last(), Some(Effect::Retile)));
+
+        // Resuming when not paused is a no-op
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::Resume);
+        assert!(result.effects.is_empty());
+    }
+
+    #[test]
+    fn test_exec_unique_skips_running_process() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let exec = Command::Exec {
+            command: "~/bin/menu.sh".to_string(),
+            track: false,
+            args: None,
+            unique: Some("menu".to_string()),
+        };
+
+        let result = process_command(&mut state, &mut hotkey_manager, &exec);
+        match &result.effects[..] {
+            [Effect::ExecCommand { name, .. }] => assert_eq!(name.as_deref(), Some("menu")),
+            _ => panic!("Expected ExecCommand effect"),
+        }
+
+        state.spawned_processes.push(crate::core::SpawnedProcess {
+            pid: 4242,
+            command: "~/bin/menu.sh".to_string(),
+            name: Some("menu".to_string()),
+            track: false,
+        });
+
+        let result = process_command(&mut state, &mut hotkey_manager, &exec);
+        assert!(matches!(result.response, Response::Ok));
+        assert!(result.effects.is_empty());
+
+        let result = process_command(&mut state, &mut hotkey_manager, &Command::ListExecs);
+        match result.response {
+            Response::Execs { execs } => {
+                assert_eq!(execs.len(), 1);
+                assert_eq!(execs[0].pid, 4242);
+                assert_eq!(execs[0].name.as_deref(), Some("menu"));
             }
-            _ => panic!("Expected ExecCommandTracked effect"),
+            _ => panic!("Expected Execs response"),
         }
     }
 
@@ -1255,8 +2308,11 @@ mod tests {
             &mut state,
             &mut hotkey_manager,
             &Command::ExecOrFocus {
-                app_name: "Safari".to_string(),
+                app_name: Some("Safari".to_string()),
+                app_id: None,
+                title: None,
                 command: "open -a Safari".to_string(),
+                bring: false,
             },
         );
 
@@ -1286,8 +2342,11 @@ mod tests {
             &mut state,
             &mut hotkey_manager,
             &Command::ExecOrFocus {
-                app_name: "Slack".to_string(), // App not in our mock windows
+                app_name: Some("Slack".to_string()), // App not in our mock windows
+                app_id: None,
+                title: None,
                 command: "open -a Slack".to_string(),
+                bring: false,
             },
         );
 
@@ -1303,6 +2362,98 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_exec_or_focus_matches_app_id_and_title() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        state.windows.get_mut(&101).unwrap().app_id = Some("com.apple.Terminal".to_string());
+
+        let mut run = |app_name: Option<&str>, app_id: Option<&str>, title: Option<&str>| {
+            let result = process_command(
+                &mut state,
+                &mut hotkey_manager,
+                &Command::ExecOrFocus {
+                    app_name: app_name.map(String::from),
+                    app_id: app_id.map(GlobPattern::new),
+                    title: title.map(GlobPattern::new),
+                    command: "open -a App".to_string(),
+                    bring: false,
+                },
+            );
+            match &result.effects[..] {
+                [Effect::FocusWindow { window_id, .. }] => Some(*window_id),
+                [Effect::ExecCommand { .. }] => None,
+                effects => panic!("Unexpected effects: {:?}", effects),
+            }
+        };
+
+        assert_eq!(run(None, Some("com.apple.term*"), None), Some(101));
+        assert_eq!(run(None, None, Some("vscode*")), Some(102));
+        // Every given matcher must match
+        assert_eq!(run(Some("Safari"), None, Some("Terminal*")), None);
+        assert_eq!(run(Some("Safari"), Some("com.apple.Terminal"), None), None);
+    }
+
+    #[test]
+    fn test_exec_or_focus_hidden_window_views_tag_or_brings() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        let exec_or_focus = |bring| Command::ExecOrFocus {
+            app_name: Some("Terminal".to_string()),
+            app_id: None,
+            title: None,
+            command: "open -a Terminal".to_string(),
+            bring,
+        };
+        // Terminal is on tag 2, display 1 shows tag 1
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        state.compute_layout_changes(1);
+        assert!(state.windows.get(&101).unwrap().is_hidden());
+
+        // Default: the window's display switches to its tag
+        let result = process_command(&mut state, &mut hotkey_manager, &exec_or_focus(false));
+        assert_eq!(state.displays.get(&1).unwrap().visible_tags, Tag::new(2));
+        assert!(!state.windows.get(&101).unwrap().is_hidden());
+        assert!(matches!(
+            result.effects.last(),
+            Some(Effect::FocusWindow { window_id: 101, .. })
+        ));
+
+        // --bring: the window joins the visible tag instead
+        state.view_tags(Tag::new(1).mask());
+        let result = process_command(&mut state, &mut hotkey_manager, &exec_or_focus(true));
+        assert_eq!(state.displays.get(&1).unwrap().visible_tags, Tag::new(1));
+        assert_eq!(state.windows.get(&101).unwrap().tags, Tag::new(1));
+        assert!(!state.windows.get(&101).unwrap().is_hidden());
+        assert!(matches!(
+            result.effects.last(),
+            Some(Effect::FocusWindow { window_id: 101, .. })
+        ));
+    }
+
+    #[test]
+    fn test_window_focus_id_shows_hidden_window() {
+        let (mut state, mut hotkey_manager) = setup_state();
+        state.windows.get_mut(&102).unwrap().tags = Tag::new(3);
+        state.compute_layout_changes(1);
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::WindowFocusId { window_id: 102 },
+        );
+        assert_eq!(state.displays.get(&1).unwrap().visible_tags, Tag::new(3));
+        assert!(matches!(
+            result.effects.last(),
+            Some(Effect::FocusWindow { window_id: 102, .. })
+        ));
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::WindowFocusId { window_id: 999 },
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+    }
+
     #[test]
     fn test_layout_command_produces_send_and_retile() {
         let (mut state, mut hotkey_manager) = setup_state();
@@ -1362,12 +2513,48 @@ mod tests {
         let result = process_command(
             &mut state,
             &mut hotkey_manager,
-            &Command::Retile { output: None },
+            &Command::Retile {
+                output: None,
+                all: false,
+            },
         );
 
         assert!(matches!(result.response, Response::Ok));
-        assert_eq!(result.effects.len(), 1);
-        assert!(matches!(result.effects[0], Effect::Retile));
+        assert_eq!(result.effects, vec![Effect::RetileDisplays(vec![1])]);
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::Retile {
+                output: Some(yashiki_ipc::OutputSpecifier::Id(1)),
+                all: true,
+            },
+        );
+        assert!(matches!(result.response, Response::Error { .. }));
+    }
+
+    #[test]
+    fn test_set_animation_produces_update_effect() {
+        let (mut state, mut hotkey_manager) = setup_state();
+
+        let result = process_command(
+            &mut state,
+            &mut hotkey_manager,
+            &Command::SetAnimation {
+                duration_ms: 120,
+                easing: yashiki_ipc::AnimationEasing::EaseOut,
+            },
+        );
+
+        assert!(matches!(result.response, Response::Ok));
+        assert_eq!(state.config.animation_duration_ms, 120);
+        assert_eq!(
+            result.effects,
+            vec![Effect::UpdateAnimation {
+                duration_ms: 120,
+                easing: yashiki_ipc::AnimationEasing::EaseOut,
+            }]
+        );
     }
 
     #[test]
@@ -1527,8 +2714,13 @@ mod tests {
         let events: Vec<_> = rx.try_iter().collect();
         assert_eq!(events.len(), 1);
         match &events[0] {
-            StateEvent::WindowFocused { window_id } => {
+            StateEvent::WindowFocused {
+                window_id,
+                output_id,
+                ..
+            } => {
                 assert_eq!(*window_id, Some(101));
+                assert_eq!(*output_id, Some(1));
             }
             _ => panic!("Expected WindowFocused event, got {:?}", events[0]),
         }
@@ -1631,6 +2823,266 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_emit_window_tags_changed_detection() {
+        use crate::event_emitter::EventEmitter;
+        use std::cell::RefCell;
+        use std::sync::mpsc as std_mpsc;
+        use yashiki_ipc::StateEvent;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 800.0, 600.0,
+            )])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let state_cell = RefCell::new(state);
+        let (tx, rx) = std_mpsc::channel::<StateEvent>();
+        let event_emitter = EventEmitter::new(tx);
+
+        let pre = capture_event_state(&state_cell);
+        state_cell.borrow_mut().move_focused_to_tags(0b100);
+        emit_state_change_events(&event_emitter, &state_cell, &pre);
+
+        let events: Vec<_> = rx.try_iter().collect();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, StateEvent::WindowUpdated { .. })));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            StateEvent::WindowTagsChanged {
+                window_id: 100,
+                tags: 0b100,
+                previous_tags: 1,
+                output_id: Some(1),
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_run_tag_change_hook() {
+        use crate::platform::mock::MockWindowManipulator;
+        use std::cell::RefCell;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 800.0, 600.0,
+            )])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.config.tag_change_hook = Some("desktoppr $YASHIKI_TAGS".to_string());
+
+        let state_cell = RefCell::new(state);
+        let manipulator = MockWindowManipulator::new();
+
+        // Unchanged tags don't run the hook
+        let pre = capture_event_state(&state_cell);
+        run_tag_change_hook(&state_cell, &pre, &manipulator);
+        assert!(manipulator.executed.borrow().is_empty());
+
+        state_cell.borrow_mut().view_tags(0b10);
+        run_tag_change_hook(&state_cell, &pre, &manipulator);
+
+        let executed = manipulator.executed.borrow();
+        assert_eq!(executed.len(), 1);
+        let (command, env) = &executed[0];
+        assert_eq!(command, "desktoppr $YASHIKI_TAGS");
+        let var = |name: &str| env.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str());
+        assert_eq!(var("YASHIKI_OUTPUT"), Some("1"));
+        assert_eq!(var("YASHIKI_OUTPUT_NAME"), Some("Display 1"));
+        assert_eq!(var("YASHIKI_TAGS"), Some("2"));
+        assert_eq!(var("YASHIKI_PREVIOUS_TAGS"), Some("1"));
+    }
+
+    #[test]
+    fn test_switch_tag_input_source() {
+        use crate::platform::mock::MockWindowManipulator;
+        use std::cell::RefCell;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_tag_input_source(0b110, Some("com.apple.keylayout.US"));
+        state.set_tag_input_source(0b100, Some("com.apple.inputmethod.Kotoeri"));
+
+        let state_cell = RefCell::new(state);
+        let manipulator = MockWindowManipulator::new();
+
+        // Unchanged tags and tags without an input source leave it alone
+        let pre = capture_event_state(&state_cell);
+        switch_tag_input_source(&state_cell, &pre, &manipulator);
+        state_cell.borrow_mut().view_tags(0b1000);
+        switch_tag_input_source(&state_cell, &pre, &manipulator);
+        assert!(manipulator.input_sources.borrow().is_empty());
+
+        state_cell.borrow_mut().view_tags(0b100);
+        switch_tag_input_source(&state_cell, &pre, &manipulator);
+        // The lowest visible tag with an input source wins
+        state_cell.borrow_mut().view_tags(0b110);
+        switch_tag_input_source(&state_cell, &pre, &manipulator);
+        assert_eq!(
+            *manipulator.input_sources.borrow(),
+            vec!["com.apple.inputmethod.Kotoeri", "com.apple.keylayout.US"]
+        );
+
+        state_cell.borrow_mut().set_tag_input_source(0b10, None);
+        assert_eq!(
+            state_cell
+                .borrow()
+                .input_source_for_tags(crate::core::Tag::from_mask(0b110)),
+            Some("com.apple.inputmethod.Kotoeri")
+        );
+    }
+
+    #[test]
+    fn test_window_shadows_recorded_only_when_applied() {
+        use crate::platform::mock::MockWindowManipulator;
+        use std::cell::RefCell;
+
+        let (mut state, _) = setup_state();
+        state.config.hide_shadows = true;
+        let state_cell = RefCell::new(state);
+        let layout_engine_manager = RefCell::new(LayoutEngineManager::new());
+        let manipulator = MockWindowManipulator::new();
+        let shadow_errors = |state: &State| {
+            state
+                .errors
+                .since(0)
+                .filter(|e| e.source == "window_shadows")
+                .count()
+        };
+
+        manipulator.refuse_private_api.set(true);
+        do_retile(&state_cell, &layout_engine_manager, &manipulator);
+        do_retile(&state_cell, &layout_engine_manager, &manipulator);
+        assert!(state_cell.borrow().shadowless_windows.is_empty());
+        // Reported once, not on every retile
+        assert_eq!(shadow_errors(&state_cell.borrow()), 1);
+
+        manipulator.refuse_private_api.set(false);
+        do_retile(&state_cell, &layout_engine_manager, &manipulator);
+        assert_eq!(state_cell.borrow().shadowless_windows.len(), 3);
+        assert_eq!(manipulator.shadows.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_retile_hooks() {
+        use crate::platform::mock::MockWindowManipulator;
+        use std::cell::RefCell;
+        use yashiki_ipc::HookEvent;
+
+        // No windows, so no layout engine is needed
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state
+            .config
+            .retile_hooks
+            .insert(HookEvent::PreRetile, "pre".to_string());
+        state
+            .config
+            .retile_hooks
+            .insert(HookEvent::PostRetile, "post".to_string());
+
+        let state_cell = RefCell::new(state);
+        let layout_engine_manager = RefCell::new(LayoutEngineManager::new());
+        let manipulator = MockWindowManipulator::new();
+        do_retile(&state_cell, &layout_engine_manager, &manipulator);
+
+        // Post-retile waits for retiles to settle
+        assert_eq!(manipulator.hooks.borrow().len(), 1);
+        let last_retile = state_cell.borrow().last_retile.unwrap();
+        flush_retile_hooks(&state_cell, &manipulator, last_retile + HOOK_SETTLE_DELAY);
+
+        let hooks = manipulator.hooks.borrow();
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].0, "pre");
+        assert_eq!(hooks[0].1, "{\"event\":\"pre_retile\",\"display_ids\":[1]}");
+        assert_eq!(hooks[1].0, "post");
+        assert!(hooks[1].1.contains("\"event\":\"post_retile\""));
+        assert!(hooks[1].1.contains("\"windows\":0"));
+    }
+
+    #[test]
+    fn test_retile_hooks_run_once_per_burst() {
+        use crate::platform::mock::MockWindowManipulator;
+        use std::cell::RefCell;
+        use yashiki_ipc::HookEvent;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        for (event, command) in [
+            (HookEvent::PreRetile, "pre"),
+            (HookEvent::PostRetile, "post"),
+        ] {
+            state.config.retile_hooks.insert(event, command.to_string());
+        }
+
+        let state_cell = RefCell::new(state);
+        let layout_engine_manager = RefCell::new(LayoutEngineManager::new());
+        let manipulator = MockWindowManipulator::new();
+        for _ in 0..5 {
+            do_retile(&state_cell, &layout_engine_manager, &manipulator);
+        }
+        assert_eq!(manipulator.hooks.borrow().len(), 1);
+
+        // Too early: the flush is pushed back instead of running the hook
+        let last_retile = state_cell.borrow().last_retile.unwrap();
+        flush_retile_hooks(&state_cell, &manipulator, last_retile);
+        assert_eq!(manipulator.hooks.borrow().len(), 1);
+        assert_eq!(
+            manipulator.hook_flushes.borrow().last(),
+            Some(&HOOK_SETTLE_DELAY)
+        );
+
+        flush_retile_hooks(&state_cell, &manipulator, last_retile + HOOK_SETTLE_DELAY);
+        let hooks: Vec<String> = manipulator
+            .hooks
+            .borrow()
+            .iter()
+            .map(|(command, _)| command.clone())
+            .collect();
+        assert_eq!(hooks, vec!["pre", "post"]);
+        assert!(state_cell.borrow().pending_post_retile.is_none());
+    }
+
+    #[test]
+    fn test_set_hook() {
+        use yashiki_ipc::HookEvent;
+
+        let (mut state, mut hotkey_manager) = setup_state();
+        let set = |command: Option<&str>| Command::SetHook {
+            event: HookEvent::PostRetile,
+            command: command.map(String::from),
+        };
+        let get = Command::GetHook {
+            event: HookEvent::PostRetile,
+        };
+
+        process_command(&mut state, &mut hotkey_manager, &set(Some("log-retile")));
+        let result = process_command(&mut state, &mut hotkey_manager, &get);
+        assert!(matches!(
+            result.response,
+            Response::Hook { command: Some(ref c) } if c == "log-retile"
+        ));
+
+        process_command(&mut state, &mut hotkey_manager, &set(Some("")));
+        let result = process_command(&mut state, &mut hotkey_manager, &get);
+        assert!(matches!(result.response, Response::Hook { command: None }));
+    }
+
     #[test]
     fn test_emit_layout_changed_detection() {
         use crate::event_emitter::EventEmitter;
@@ -1678,6 +3130,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_emit_layout_order_changed_detection() {
+        use crate::event_emitter::EventEmitter;
+        use std::cell::RefCell;
+        use std::sync::mpsc as std_mpsc;
+        use yashiki_ipc::StateEvent;
+
+        let (state, _) = setup_state();
+        let state_cell = RefCell::new(state);
+        let (tx, rx) = std_mpsc::channel::<StateEvent>();
+        let event_emitter = EventEmitter::new(tx);
+
+        let pre = capture_event_state(&state_cell);
+
+        // Retile reorders windows
+        state_cell
+            .borrow_mut()
+            .displays
+            .get_mut(&1)
+            .unwrap()
+            .window_order = vec![102, 100, 101];
+
+        emit_state_change_events(&event_emitter, &state_cell, &pre);
+
+        let events: Vec<_> = rx.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            StateEvent::LayoutOrderChanged {
+                display_id,
+                window_order,
+            } => {
+                assert_eq!(*display_id, 1);
+                assert_eq!(window_order, &vec![102, 100, 101]);
+            }
+            _ => panic!("Expected LayoutOrderChanged event, got {:?}", events[0]),
+        }
+    }
+
     #[test]
     fn test_window_swap_produces_retile_effect() {
         let (mut state, mut hotkey_manager) = setup_state();
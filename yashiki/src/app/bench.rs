@@ -0,0 +1,109 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::core::{percentile_ms, Rect, State};
+use crate::platform::{WindowManipulator, WindowSystem};
+use yashiki_ipc::{AppBench, BenchReport};
+
+#[derive(Default)]
+struct AppSamples {
+    app_name: String,
+    windows: usize,
+    attributes: Vec<Duration>,
+    move_resize: Vec<Duration>,
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+/// Every iteration runs synchronously on the main thread, so keep the window manager responsive
+pub const MAX_BENCH_ITERATIONS: u32 = 100;
+
+/// Time the AX calls yashiki relies on, per app. Managed windows are moved and
+/// resized to the frame they already have, so nothing visibly changes.
+pub fn bench_ax<S: WindowSystem, M: WindowManipulator>(
+    state: &RefCell<State>,
+    window_system: &S,
+    manipulator: &M,
+    iterations: u32,
+) -> BenchReport {
+    let iterations = iterations.clamp(1, MAX_BENCH_ITERATIONS);
+
+    let mut on_screen = Vec::new();
+    let mut windows = Vec::new();
+    for _ in 0..iterations {
+        on_screen.push(time(|| windows = window_system.get_on_screen_windows()));
+    }
+
+    let mut apps: BTreeMap<i32, AppSamples> = BTreeMap::new();
+    for window in windows.iter().filter(|w| w.layer == 0) {
+        let app = apps.entry(window.pid).or_default();
+        app.app_name.clone_from(&window.owner_name);
+        app.windows += 1;
+        for _ in 0..iterations {
+            app.attributes.push(time(|| {
+                window_system.get_extended_attributes(window.window_id, window.pid, window.layer);
+            }));
+        }
+    }
+
+    // Collect first so no borrow is held while AX calls run
+    let managed: Vec<(u32, i32, Rect)> = {
+        let state = state.borrow();
+        state
+            .windows
+            .values()
+            .filter(|w| !w.is_hidden() && !w.is_fullscreen && !w.is_native_fullscreen)
+            .map(|w| (w.id, w.pid, w.frame))
+            .collect()
+    };
+    for (window_id, pid, frame) in managed {
+        let Some(app) = apps.get_mut(&pid) else {
+            continue;
+        };
+        for _ in 0..iterations {
+            app.move_resize.push(time(|| {
+                manipulator.set_window_frame(
+                    window_id,
+                    pid,
+                    frame.x,
+                    frame.y,
+                    frame.width,
+                    frame.height,
+                );
+            }));
+        }
+    }
+
+    let max_ms = |samples: &[Duration]| percentile_ms(samples, 100);
+    let mut apps: Vec<AppBench> = apps
+        .into_iter()
+        .map(|(pid, s)| {
+            let moved = !s.move_resize.is_empty();
+            AppBench {
+                pid,
+                app_name: s.app_name,
+                windows: s.windows,
+                attributes_p50_ms: percentile_ms(&s.attributes, 50),
+                attributes_max_ms: max_ms(&s.attributes),
+                move_resize_p50_ms: moved.then(|| percentile_ms(&s.move_resize, 50)),
+                move_resize_max_ms: moved.then(|| max_ms(&s.move_resize)),
+            }
+        })
+        .collect();
+    // Slowest apps first
+    let cost = |a: &AppBench| a.attributes_p50_ms + a.move_resize_p50_ms.unwrap_or(0.0);
+    apps.sort_by(|a, b| cost(b).total_cmp(&cost(a)));
+
+    tracing::info!("Benchmarked AX calls of {} apps", apps.len());
+    BenchReport {
+        iterations,
+        on_screen_p50_ms: percentile_ms(&on_screen, 50),
+        on_screen_max_ms: max_ms(&on_screen),
+        apps,
+    }
+}
@@ -1,12 +1,16 @@
 use std::cell::RefCell;
+use std::time::Instant;
 
-use crate::core::{FocusOutputResult, State};
+use crate::core::{suggest_rule, BringWindowResult, FocusOutputResult, Rect, State, Tag, Window};
 use crate::effect::{CommandResult, Effect};
+use crate::event_emitter::display_to_info;
 use crate::macos::HotkeyManager;
 use crate::platform::WindowSystem;
 use yashiki_ipc::{
-    BindingInfo, ButtonState, Command, OuterGap, OutputInfo, Response, RuleInfo, StateInfo,
-    WindowInfo, WindowLevel, WindowLevelName, WindowLevelOther, WindowStatus,
+    format_anchored_position, BindingInfo, ButtonState, CloseBehavior, Command, ExecInfo,
+    FrameInfo, OuterGap, OutputInfo, Response, RuleAction, RuleInfo, RuleSuggestion, StateDump,
+    StateInfo, WindowDetails, WindowInfo, WindowLevel, WindowLevelName, WindowLevelOther,
+    WindowRule, WindowStatus,
 };
 
 fn apply_rules_effects(state: &mut State) -> Vec<Effect> {
@@ -29,6 +33,114 @@ fn apply_rules_effects(state: &mut State) -> Vec<Effect> {
     effects
 }
 
+/// Show the brought window on the focused display and focus it
+fn bring_window_effects(result: BringWindowResult) -> CommandResult {
+    let mut display_ids = vec![result.target_display_id];
+    if result.source_display_id != result.target_display_id {
+        display_ids.push(result.source_display_id);
+    }
+    CommandResult::ok_with_effects(vec![
+        Effect::ApplyWindowMoves(result.window_moves),
+        Effect::RetileDisplays(display_ids),
+        Effect::FocusWindow {
+            window_id: result.window_id,
+            pid: result.pid,
+            is_output_change: false,
+        },
+    ])
+}
+
+/// Focus an existing window, showing it first if it is hidden: pulled to the focused
+/// output with `bring`, otherwise by switching its display to the window's tag
+fn focus_existing_window(
+    state: &mut State,
+    window_id: u32,
+    bring: bool,
+    target: &str,
+) -> CommandResult {
+    let Some(w) = state.windows.get(&window_id) else {
+        return CommandResult::error(format!("Window {} not found", window_id));
+    };
+    // Check if window is visible on its display
+    let is_visible = state
+        .displays
+        .get(&w.display_id)
+        .is_some_and(|display| state.shows_on_tags(w, display.visible_tags) && !w.is_hidden());
+    let (pid, window_tags, display_id) = (w.pid, w.tags, w.display_id);
+
+    if is_visible {
+        tracing::info!(
+            "Focusing visible window for '{}' (window_id={}, pid={})",
+            target,
+            window_id,
+            pid
+        );
+        CommandResult::ok_with_effects(vec![Effect::FocusWindow {
+            window_id,
+            pid,
+            is_output_change: false,
+        }])
+    } else if bring {
+        tracing::info!(
+            "Bringing window for '{}' to the focused output (window_id={}, pid={})",
+            target,
+            window_id,
+            pid
+        );
+        match state.bring_window(Some(window_id)) {
+            Ok(result) => bring_window_effects(result),
+            Err(e) => CommandResult::error(e),
+        }
+    } else if let Some(tag) = window_tags.first_tag() {
+        // Window is hidden, switch its display to its tag first
+        tracing::info!(
+            "Switching display {} to tag {} and focusing window for '{}' (window_id={}, pid={})",
+            display_id,
+            tag,
+            target,
+            window_id,
+            pid
+        );
+        let display_ids = state.tag_target_displays(display_id);
+        let moves = display_ids
+            .iter()
+            .flat_map(|&id| state.view_tags_on_display(1 << (tag - 1), id))
+            .collect();
+        CommandResult::ok_with_effects(vec![
+            Effect::ApplyWindowMoves(moves),
+            Effect::RetileDisplays(display_ids),
+            Effect::FocusWindow {
+                window_id,
+                pid,
+                is_output_change: display_id != state.focused_display,
+            },
+        ])
+    } else {
+        // Stashed, there is no tag to switch to
+        CommandResult::ok_with_effects(vec![Effect::FocusWindow {
+            window_id,
+            pid,
+            is_output_change: false,
+        }])
+    }
+}
+
+/// Whether a command runs programs, directly or through a hook, binding or exec path
+pub fn runs_programs(cmd: &Command) -> bool {
+    match cmd {
+        Command::Exec { .. }
+        | Command::ExecOrFocus { .. }
+        | Command::SetExecPath { .. }
+        | Command::AddExecPath { .. }
+        | Command::SetTagChangeHook { command: Some(_) }
+        | Command::SetHook {
+            command: Some(_), ..
+        } => true,
+        Command::Bind { action, .. } => runs_programs(action),
+        _ => false,
+    }
+}
+
 /// Pure function: processes a command and returns a response with effects.
 /// This function does not perform any side effects - it only mutates state and computes effects.
 pub fn process_command(
@@ -36,9 +148,13 @@ pub fn process_command(
     hotkey_manager: &mut HotkeyManager,
     cmd: &Command,
 ) -> CommandResult {
+    if state.exec_disabled && runs_programs(cmd) {
+        return CommandResult::error("Running programs is disabled (--disable-exec)");
+    }
+
     match cmd {
         // Query commands - no effects
-        Command::ListWindows { all, debug } => {
+        Command::ListWindows { all, debug, why } => {
             // For all=true, we need system access - handled specially in handle_ipc_command
             // For all=false, we can use state data only
             if *all {
@@ -62,6 +178,8 @@ pub fn process_command(
                         is_focused: state.focused == Some(w.id),
                         is_floating: w.is_floating,
                         is_fullscreen: w.is_fullscreen,
+                        is_native_fullscreen: w.is_native_fullscreen,
+                        is_manual: w.is_manual,
                         output_id: w.display_id,
                         status: None,
                         ax_id: if *debug { w.ax_id.clone() } else { None },
@@ -87,6 +205,11 @@ pub fn process_command(
                         } else {
                             None
                         },
+                        applied_rules: if *why {
+                            Some(state.applied_rule_indices(w.id))
+                        } else {
+                            None
+                        },
                     })
                     .collect();
                 CommandResult::with_response(Response::Windows { windows })
@@ -106,6 +229,11 @@ pub fn process_command(
                     is_main: d.is_main,
                     visible_tags: d.visible_tags.mask(),
                     is_focused: state.focused_display == d.id,
+                    scale_factor: d.scale_factor,
+                    rotation: d.rotation,
+                    refresh_rate: d.refresh_rate,
+                    uuid: d.uuid.clone(),
+                    window_order: d.window_order.clone(),
                 })
                 .collect();
             CommandResult::with_response(Response::Outputs { outputs })
@@ -120,18 +248,60 @@ pub fn process_command(
                     .displays
                     .get(&state.focused_display)
                     .and_then(|d| d.current_layout.clone()),
+                window_order: state
+                    .displays
+                    .get(&state.focused_display)
+                    .map(|d| d.window_order.clone())
+                    .unwrap_or_default(),
+                paused: state.paused,
+                wakeups_per_second: state.wakeups.per_second(Instant::now()),
+                float_tags: state.float_tags.mask(),
+                stage_manager: state.stage_manager,
+                tag_occupancy: state.tag_occupancy(),
+                bindings_suspended: hotkey_manager.is_suspended(),
+                screen_locked: state.screen_locked,
             },
         }),
+        Command::StateDump => CommandResult::with_response(Response::StateDump {
+            dump: state_dump(state),
+        }),
+        Command::Stats => CommandResult::with_response(Response::Stats {
+            commands: state.latency.summary(),
+        }),
+        Command::LastErrors => CommandResult::with_response(Response::LastErrors {
+            errors: state.errors.summary(Instant::now()),
+        }),
+        // Needs AX access, so handle_ipc_command runs it
+        Command::BenchAx { .. } => CommandResult::error("bench ax must be sent to the daemon"),
         Command::FocusedWindow => {
             CommandResult::with_response(Response::WindowId { id: state.focused })
         }
+        Command::FocusedOutput => match state.displays.get(&state.focused_display) {
+            Some(d) => CommandResult::with_response(Response::Output {
+                output: display_to_info(d, state.focused_display),
+            }),
+            None => CommandResult::error("No focused output"),
+        },
+        Command::WindowInfo { window_id } => {
+            let Some(id) = window_id.or(state.focused) else {
+                return CommandResult::error("No focused window");
+            };
+            let Some(w) = state.windows.get(&id) else {
+                return CommandResult::error(format!("Window not found: {}", id));
+            };
+            CommandResult::with_response(Response::WindowDetails {
+                window: window_details(state, w),
+            })
+        }
         Command::ListBindings => {
             let bindings: Vec<BindingInfo> = hotkey_manager
                 .list_bindings()
                 .into_iter()
-                .map(|(key, cmd)| BindingInfo {
+                .map(|(key, cmd, double, passthrough)| BindingInfo {
                     key,
                     action: format!("{:?}", cmd),
+                    double,
+                    passthrough,
                 })
                 .collect();
             CommandResult::with_response(Response::Bindings { bindings })
@@ -143,10 +313,14 @@ pub fn process_command(
                 Ok(id) => id,
                 Err(e) => return CommandResult::error(e),
             };
-            let moves = state.view_tags_on_display(*tags, display_id);
+            let display_ids = state.tag_target_displays(display_id);
+            let moves = display_ids
+                .iter()
+                .flat_map(|&id| state.view_tags_on_display(*tags, id))
+                .collect();
             CommandResult::ok_with_effects(vec![
                 Effect::ApplyWindowMoves(moves),
-                Effect::RetileDisplays(vec![display_id]),
+                Effect::RetileDisplays(display_ids),
                 Effect::FocusVisibleWindowIfNeeded,
             ])
         }
@@ -155,10 +329,14 @@ pub fn process_command(
                 Ok(id) => id,
                 Err(e) => return CommandResult::error(e),
             };
-            let moves = state.toggle_tags_on_display(*tags, display_id);
+            let display_ids = state.tag_target_displays(display_id);
+            let moves = display_ids
+                .iter()
+                .flat_map(|&id| state.toggle_tags_on_display(*tags, id))
+                .collect();
             CommandResult::ok_with_effects(vec![
                 Effect::ApplyWindowMoves(moves),
-                Effect::RetileDisplays(vec![display_id]),
+                Effect::RetileDisplays(display_ids),
                 Effect::FocusVisibleWindowIfNeeded,
             ])
         }
@@ -170,6 +348,51 @@ pub fn process_command(
                 Effect::FocusVisibleWindowIfNeeded,
             ])
         }
+        Command::TagSetFloat { tags, enabled } => {
+            let moves = state.set_float_tags(*tags, *enabled);
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::Retile,
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
+        Command::TagSetInputSource { tags, input_source } => {
+            let input_source = input_source.as_deref().filter(|s| !s.is_empty());
+            tracing::info!("Set input source for tags {}: {:?}", tags, input_source);
+            state.set_tag_input_source(*tags, input_source);
+            CommandResult::ok()
+        }
+        Command::TagGetInputSource { tags } => {
+            CommandResult::with_response(Response::InputSource {
+                input_source: state
+                    .input_source_for_tags(Tag::from_mask(*tags))
+                    .map(String::from),
+            })
+        }
+        Command::TagDistribute {
+            from,
+            to,
+            count,
+            every_other,
+            output,
+        } => {
+            if count.is_none() && !every_other {
+                return CommandResult::error("tag-distribute requires --count or --every-other");
+            }
+            if *from == 0 || *to == 0 {
+                return CommandResult::error("tag-distribute requires non-empty tags");
+            }
+            let display_id = match state.get_target_display(output.as_ref()) {
+                Ok(id) => id,
+                Err(e) => return CommandResult::error(e),
+            };
+            let moves = state.distribute_tags(*from, *to, *count, *every_other, display_id);
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::RetileDisplays(vec![display_id]),
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
         Command::WindowMoveToTag { tags } => {
             let moves = state.move_focused_to_tags(*tags);
             CommandResult::ok_with_effects(vec![
@@ -178,6 +401,26 @@ pub fn process_command(
                 Effect::FocusVisibleWindowIfNeeded,
             ])
         }
+        Command::WindowStash => {
+            let moves = state.stash_focused();
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::Retile,
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
+        Command::WindowUnstash => match state.unstash() {
+            Some(result) => CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(result.window_moves),
+                Effect::RetileDisplays(vec![result.source_display_id, result.target_display_id]),
+                Effect::FocusWindow {
+                    window_id: result.window_id,
+                    pid: result.pid,
+                    is_output_change: false,
+                },
+            ]),
+            None => CommandResult::ok(),
+        },
         Command::WindowToggleTag { tags } => {
             let moves = state.toggle_focused_window_tags(*tags);
             CommandResult::ok_with_effects(vec![
@@ -188,14 +431,59 @@ pub fn process_command(
         }
 
         // Hotkey operations
-        Command::Bind { key, action } => match hotkey_manager.bind(key, *action.clone()) {
+        Command::Bind {
+            key,
+            action,
+            double,
+            passthrough,
+        } => {
+            // A bound trigger could reach itself (directly or through other bindings)
+            if matches!(**action, Command::TriggerBinding { .. }) {
+                return CommandResult::error("A binding can't trigger another binding");
+            }
+            match hotkey_manager.bind(key, *action.clone(), *double, *passthrough) {
+                Ok(()) => CommandResult::ok(),
+                Err(e) => CommandResult::error(e),
+            }
+        }
+        Command::Unbind { key } => match hotkey_manager.unbind(key) {
             Ok(()) => CommandResult::ok(),
             Err(e) => CommandResult::error(e),
         },
-        Command::Unbind { key } => match hotkey_manager.unbind(key) {
+        Command::TriggerBinding { key } => {
+            // Normally resolved by dispatch_command so the bound action gets full dispatch
+            match hotkey_manager.trigger(key) {
+                Ok(Some(action)) => process_command(state, hotkey_manager, &action),
+                Ok(None) => CommandResult::ok(),
+                Err(e) => CommandResult::error(e),
+            }
+        }
+        Command::SetHotkeyBackend { backend } => {
+            hotkey_manager.set_backend(*backend);
+            CommandResult::ok()
+        }
+        Command::BindingsSuspend { except } => match hotkey_manager.suspend(except) {
             Ok(()) => CommandResult::ok(),
             Err(e) => CommandResult::error(e),
         },
+        Command::BindingsResume => {
+            hotkey_manager.resume();
+            CommandResult::ok()
+        }
+        Command::BindingsToggle { except } => {
+            if hotkey_manager.is_suspended() {
+                hotkey_manager.resume();
+                CommandResult::ok()
+            } else {
+                match hotkey_manager.suspend(except) {
+                    Ok(()) => CommandResult::ok(),
+                    Err(e) => CommandResult::error(e),
+                }
+            }
+        }
+        Command::GetHotkeyBackend => CommandResult::with_response(Response::HotkeyBackend {
+            backend: hotkey_manager.backend(),
+        }),
 
         // Focus operations
         Command::WindowFocus { direction } => {
@@ -210,13 +498,92 @@ pub fn process_command(
                 CommandResult::ok()
             }
         }
-        Command::WindowSwap { direction } => {
-            if let Some(display_id) = state.swap_window(*direction) {
-                CommandResult::ok_with_effects(vec![Effect::RetileDisplays(vec![display_id])])
+        Command::WindowFocusParent | Command::WindowFocusChild => {
+            let target = if matches!(cmd, Command::WindowFocusParent) {
+                state.focus_parent()
+            } else {
+                state.focus_child()
+            };
+            match target {
+                Some((window_id, pid)) => {
+                    CommandResult::ok_with_effects(vec![Effect::FocusWindow {
+                        window_id,
+                        pid,
+                        is_output_change: false,
+                    }])
+                }
+                None => CommandResult::ok(),
+            }
+        }
+        Command::WindowFocusId { window_id } => {
+            focus_existing_window(state, *window_id, false, &format!("id {}", window_id))
+        }
+        Command::AppCycle { app_id } => {
+            if let Some((window_id, pid, is_output_change)) = state.cycle_app_window(app_id) {
+                tracing::info!("Cycling to window {} of app '{}'", window_id, app_id);
+                CommandResult::ok_with_effects(vec![Effect::FocusWindow {
+                    window_id,
+                    pid,
+                    is_output_change,
+                }])
             } else {
                 CommandResult::ok()
             }
         }
+        Command::WindowSwap { direction } => {
+            let main_before = state
+                .focused
+                .and_then(|id| state.windows.get(&id))
+                .and_then(|w| state.main_window_on_display(w.display_id));
+            let Some(display_id) = state.swap_window(*direction) else {
+                return CommandResult::ok();
+            };
+            let mut effects = Vec::new();
+            // Keep the engine's main window in sync so the swap survives tag switches
+            if let Some(window_id) = state
+                .main_window_on_display(display_id)
+                .filter(|&id| Some(id) != main_before)
+            {
+                effects.push(Effect::PromoteWindow {
+                    display_id,
+                    window_id,
+                });
+            }
+            effects.push(Effect::RetileDisplays(vec![display_id]));
+            CommandResult::ok_with_effects(effects)
+        }
+        Command::WindowZoom => match state.zoom_window() {
+            Some((display_id, window_id)) => CommandResult::ok_with_effects(vec![
+                Effect::PromoteWindow {
+                    display_id,
+                    window_id,
+                },
+                Effect::RetileDisplays(vec![display_id]),
+            ]),
+            None => CommandResult::ok(),
+        },
+        Command::WindowSwapFloat => match state.swap_float() {
+            Some(result) => CommandResult::ok_with_effects(vec![
+                Effect::PromoteWindow {
+                    display_id: result.display_id,
+                    window_id: result.tiled_id,
+                },
+                Effect::RetileDisplays(vec![result.display_id]),
+                Effect::MoveWindowToPosition {
+                    window_id: result.floated_id,
+                    pid: result.floated_pid,
+                    x: result.frame.x,
+                    y: result.frame.y,
+                },
+                Effect::SetWindowDimensions {
+                    window_id: result.floated_id,
+                    pid: result.floated_pid,
+                    width: result.frame.width,
+                    height: result.frame.height,
+                },
+            ]),
+            None => CommandResult::ok(),
+        },
         Command::OutputFocus { direction } => match state.focus_output(*direction) {
             Some(FocusOutputResult::Window { window_id, pid }) => {
                 tracing::info!("Focusing output - window {} (pid {})", window_id, pid);
@@ -269,14 +636,53 @@ pub fn process_command(
             }
         }
 
+        Command::WindowToggleManual { window_id } => {
+            let Some(id) = window_id.or(state.focused) else {
+                return CommandResult::error("No focused window");
+            };
+            match state.toggle_manual(id) {
+                // Let the layout reclaim the window when it's no longer manual
+                Some((display_id, false)) => {
+                    CommandResult::ok_with_effects(vec![Effect::RetileDisplays(vec![display_id])])
+                }
+                Some((_, true)) => CommandResult::ok(),
+                None => CommandResult::error(format!("Window not found: {}", id)),
+            }
+        }
+        Command::WindowSetUrgent { window_id, enabled } => {
+            let Some(id) = window_id.or(state.focused) else {
+                return CommandResult::error("No focused window");
+            };
+            if state.set_urgent(id, *enabled) {
+                CommandResult::ok()
+            } else {
+                CommandResult::error(format!("Window not found: {}", id))
+            }
+        }
+
+        Command::WindowBring { window_id } => match state.bring_window(*window_id) {
+            Ok(result) => bring_window_effects(result),
+            Err(e) => CommandResult::error(e),
+        },
+
         // Window close
-        Command::WindowClose => {
+        Command::WindowClose { force_quit_app } => {
             if let Some(focused_id) = state.focused {
                 if let Some(window) = state.windows.get(&focused_id) {
-                    CommandResult::ok_with_effects(vec![Effect::CloseWindow {
-                        window_id: focused_id,
-                        pid: window.pid,
-                    }])
+                    let pid = window.pid;
+                    let effect = if *force_quit_app {
+                        Effect::QuitApp { pid, force: true }
+                    } else {
+                        match state.close_behavior(focused_id) {
+                            CloseBehavior::Close => Effect::CloseWindow {
+                                window_id: focused_id,
+                                pid,
+                            },
+                            CloseBehavior::Hide => Effect::HideApp { pid },
+                            CloseBehavior::Quit => Effect::QuitApp { pid, force: false },
+                        }
+                    };
+                    CommandResult::ok_with_effects(vec![effect])
                 } else {
                     CommandResult::error("Focused window not found")
                 }
@@ -285,6 +691,30 @@ pub fn process_command(
             }
         }
 
+        Command::WindowHideApp => match state.hide_focused_app() {
+            Ok(pid) => {
+                CommandResult::ok_with_effects(vec![Effect::HideApp { pid }, Effect::Retile])
+            }
+            Err(e) => CommandResult::error(e),
+        },
+        Command::WindowUnhideApp { all } => {
+            let pids: Vec<i32> = if *all {
+                state.user_hidden_apps.clone()
+            } else {
+                state.user_hidden_apps.last().copied().into_iter().collect()
+            };
+            if pids.is_empty() {
+                return CommandResult::error("No app hidden with window-hide-app");
+            }
+            let mut effects = Vec::new();
+            for pid in pids {
+                state.app_unhidden(pid);
+                effects.push(Effect::UnhideApp { pid });
+            }
+            effects.push(Effect::Retile);
+            CommandResult::ok_with_effects(effects)
+        }
+
         // Send to output - returns displays that need retiling
         Command::OutputSend { direction } => {
             if let Some(result) = state.send_to_output(*direction) {
@@ -300,6 +730,20 @@ pub fn process_command(
                 CommandResult::ok()
             }
         }
+        Command::OutputIgnore { output, enabled } => {
+            let display_id = match state.get_target_display(output.as_ref()) {
+                Ok(id) => id,
+                Err(e) => return CommandResult::error(e),
+            };
+            tracing::info!("Output {} ignored: {}", display_id, enabled);
+            // Windows are picked up again by dispatch_command after command processing
+            let moves = state.set_output_ignored(display_id, *enabled);
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::RetileDisplays(vec![display_id]),
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
 
         // Layout configuration
         Command::LayoutSetDefault { layout } => {
@@ -349,16 +793,30 @@ pub fn process_command(
             }
             CommandResult::ok_with_effects(effects)
         }
-        Command::Retile { output } => {
-            if let Some(ref spec) = output {
-                let display_id = match state.get_target_display(Some(spec)) {
-                    Ok(id) => id,
+        Command::LayoutAction { action, args } => CommandResult::ok_with_effects(vec![
+            Effect::SendLayoutAction {
+                action: *action,
+                args: args.clone(),
+            },
+            Effect::Retile,
+        ]),
+        // handle_ipc_command runs the effect itself to report per-display results
+        Command::Retile { output, all } => {
+            let display_ids = match output {
+                Some(_) if *all => {
+                    return CommandResult::error("retile takes either --output or --all")
+                }
+                Some(spec) => match state.get_target_display(Some(spec)) {
+                    Ok(id) => vec![id],
                     Err(e) => return CommandResult::error(e),
-                };
-                CommandResult::ok_with_effects(vec![Effect::RetileDisplays(vec![display_id])])
-            } else {
-                CommandResult::ok_with_effects(vec![Effect::Retile])
-            }
+                },
+                None => {
+                    let mut ids: Vec<_> = state.displays.keys().copied().collect();
+                    ids.sort();
+                    ids
+                }
+            };
+            CommandResult::ok_with_effects(vec![Effect::RetileDisplays(display_ids)])
         }
 
         // Exec path commands
@@ -392,91 +850,86 @@ pub fn process_command(
         }
 
         // Exec commands
-        Command::Exec { command, track } => {
-            if *track {
-                CommandResult::ok_with_effects(vec![Effect::ExecCommandTracked {
-                    command: command.clone(),
-                    path: state.config.exec_path.clone(),
-                }])
+        Command::Exec {
+            command,
+            track,
+            args,
+            unique,
+        } => {
+            if let Some(process) = unique
+                .as_deref()
+                .and_then(|name| state.spawned_process_by_name(name))
+            {
+                tracing::info!(
+                    "Skipping exec: '{}' is already running (pid={})",
+                    command,
+                    process.pid
+                );
+                CommandResult::ok()
             } else {
                 CommandResult::ok_with_effects(vec![Effect::ExecCommand {
                     command: command.clone(),
+                    args: args.clone(),
                     path: state.config.exec_path.clone(),
+                    env: exec_env(state),
+                    track: *track,
+                    name: unique.clone(),
                 }])
             }
         }
-        Command::ExecOrFocus { app_name, command } => {
-            // Check if a window with the given app_name exists
+        Command::ExecOrFocus {
+            app_name,
+            app_id,
+            title,
+            command,
+            bring,
+        } => {
+            if app_name.is_none() && app_id.is_none() && title.is_none() {
+                return CommandResult::error("exec-or-focus needs --app-name, --app-id or --title");
+            }
+            let target = [
+                app_name.as_deref(),
+                app_id.as_ref().map(|p| p.pattern()),
+                title.as_ref().map(|p| p.pattern()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", ");
+            // Check if a window matching every given matcher exists
             let existing_window = state
                 .windows
                 .values()
-                .find(|w| w.app_name == *app_name)
-                .map(|w| (w.id, w.pid, w.tags, w.display_id, w.is_hidden()));
+                .filter(|w| app_name.as_ref().is_none_or(|name| w.app_name == *name))
+                .filter(|w| {
+                    app_id
+                        .as_ref()
+                        .is_none_or(|p| w.app_id.as_deref().is_some_and(|id| p.matches(id)))
+                })
+                .filter(|w| title.as_ref().is_none_or(|p| p.matches(&w.title)))
+                .min_by_key(|w| w.id)
+                .map(|w| w.id);
 
-            if let Some((window_id, pid, window_tags, window_display_id, is_hidden)) =
-                existing_window
-            {
-                // Check if window is visible on its display
-                let is_visible = state
-                    .displays
-                    .get(&window_display_id)
-                    .map(|display| window_tags.intersects(display.visible_tags) && !is_hidden)
-                    .unwrap_or(false);
-
-                if is_visible {
-                    tracing::info!(
-                        "Focusing visible window for app '{}' (window_id={}, pid={})",
-                        app_name,
-                        window_id,
-                        pid
-                    );
-                    CommandResult::ok_with_effects(vec![Effect::FocusWindow {
-                        window_id,
-                        pid,
-                        is_output_change: false,
-                    }])
-                } else {
-                    // Window is hidden, switch to its tag first
-                    if let Some(tag) = window_tags.first_tag() {
-                        tracing::info!(
-                            "Switching to tag {} and focusing window for app '{}' (window_id={}, pid={})",
-                            tag,
-                            app_name,
-                            window_id,
-                            pid
-                        );
-                        let moves = state.view_tags(1 << (tag - 1));
-                        CommandResult::ok_with_effects(vec![
-                            Effect::ApplyWindowMoves(moves),
-                            Effect::Retile,
-                            Effect::FocusWindow {
-                                window_id,
-                                pid,
-                                is_output_change: false,
-                            },
-                        ])
-                    } else {
-                        CommandResult::ok_with_effects(vec![Effect::FocusWindow {
-                            window_id,
-                            pid,
-                            is_output_change: false,
-                        }])
-                    }
-                }
+            if let Some(window_id) = existing_window {
+                focus_existing_window(state, window_id, *bring, &target)
             } else {
-                tracing::info!(
-                    "No existing window for app '{}', executing command",
-                    app_name
-                );
+                tracing::info!("No existing window for '{}', executing command", target);
                 CommandResult::ok_with_effects(vec![Effect::ExecCommand {
                     command: command.clone(),
+                    args: None,
                     path: state.config.exec_path.clone(),
+                    env: exec_env(state),
+                    track: false,
+                    name: None,
                 }])
             }
         }
 
         // Rules
         Command::RuleAdd { rule } => {
+            if let Err(e) = validate_rule(rule) {
+                return CommandResult::error(e);
+            }
             state.add_rule(rule.clone());
 
             if state.config.init_completed {
@@ -487,52 +940,43 @@ pub fn process_command(
         }
         Command::RuleDel { matcher, action } => {
             if state.remove_rule(matcher, action) {
+                // Opacity is the one rule effect that goes away with its rule
+                if matches!(action, RuleAction::Opacity { .. }) && state.config.init_completed {
+                    state.apply_rules_to_all_windows();
+                }
                 CommandResult::ok()
             } else {
                 CommandResult::error("Rule not found")
             }
         }
-        Command::ListRules => {
-            let rules: Vec<RuleInfo> = state
-                .rules_engine
-                .rules()
+        Command::ListExecs => {
+            let execs = state
+                .spawned_processes
                 .iter()
-                .map(|r| {
-                    let action_str = match &r.action {
-                        yashiki_ipc::RuleAction::Ignore => "ignore".to_string(),
-                        yashiki_ipc::RuleAction::Float => "float".to_string(),
-                        yashiki_ipc::RuleAction::NoFloat => "no-float".to_string(),
-                        yashiki_ipc::RuleAction::Tags { tags } => format!("tags {}", tags),
-                        yashiki_ipc::RuleAction::Output { output } => match output {
-                            yashiki_ipc::OutputSpecifier::Id(id) => format!("output {}", id),
-                            yashiki_ipc::OutputSpecifier::Name(name) => {
-                                format!("output {}", name)
-                            }
-                        },
-                        yashiki_ipc::RuleAction::Position { x, y } => {
-                            format!("position {} {}", x, y)
-                        }
-                        yashiki_ipc::RuleAction::Dimensions { width, height } => {
-                            format!("dimensions {} {}", width, height)
-                        }
-                    };
-                    RuleInfo {
-                        app_name: r.matcher.app_name.as_ref().map(|p| p.pattern().to_string()),
-                        app_id: r.matcher.app_id.as_ref().map(|p| p.pattern().to_string()),
-                        title: r.matcher.title.as_ref().map(|p| p.pattern().to_string()),
-                        ax_id: r.matcher.ax_id.as_ref().map(|p| p.pattern().to_string()),
-                        subrole: r.matcher.subrole.as_ref().map(|p| p.pattern().to_string()),
-                        window_level: r.matcher.window_level.as_ref().map(format_window_level),
-                        close_button: r.matcher.close_button.map(format_button_state),
-                        fullscreen_button: r.matcher.fullscreen_button.map(format_button_state),
-                        minimize_button: r.matcher.minimize_button.map(format_button_state),
-                        zoom_button: r.matcher.zoom_button.map(format_button_state),
-                        action: action_str,
-                    }
+                .map(|p| ExecInfo {
+                    pid: p.pid,
+                    command: p.command.clone(),
+                    name: p.name.clone(),
+                    track: p.track,
                 })
                 .collect();
+            CommandResult::with_response(Response::Execs { execs })
+        }
+        Command::ListRules => {
+            let rules: Vec<RuleInfo> = state.rules_engine.rules().iter().map(rule_info).collect();
             CommandResult::with_response(Response::Rules { rules })
         }
+        Command::RuleSuggest => {
+            let mut windows: Vec<_> = state.windows.values().collect();
+            windows.sort_by_key(|w| w.id);
+            let mut suggestions: Vec<RuleSuggestion> = Vec::new();
+            for suggestion in windows.into_iter().filter_map(suggest_rule) {
+                if !suggestions.iter().any(|s| s.command == suggestion.command) {
+                    suggestions.push(suggestion);
+                }
+            }
+            CommandResult::with_response(Response::RuleSuggestions { suggestions })
+        }
         Command::ApplyRules => {
             state.config.init_completed = true;
             tracing::info!("Applied rules to all existing windows");
@@ -567,6 +1011,201 @@ pub fn process_command(
             delay_ms: state.config.auto_raise_delay_ms,
         }),
 
+        // New window tag policy
+        Command::SetNewWindowTag { policy } => {
+            tracing::info!("Set new window tag policy: {:?}", policy);
+            state.config.new_window_tag = *policy;
+            CommandResult::ok()
+        }
+        Command::GetNewWindowTag => CommandResult::with_response(Response::NewWindowTag {
+            policy: state.config.new_window_tag,
+        }),
+
+        // Orphan policy
+        Command::SetOrphanPolicy { policy } => {
+            tracing::info!("Set orphan policy: {:?}", policy);
+            state.config.orphan_policy = *policy;
+            CommandResult::ok()
+        }
+        Command::GetOrphanPolicy => CommandResult::with_response(Response::OrphanPolicy {
+            policy: state.config.orphan_policy,
+        }),
+
+        // Tag mode
+        Command::SetTagMode { mode } => {
+            let moves = state.set_tag_mode(*mode);
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::Retile,
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
+        Command::GetTagMode => CommandResult::with_response(Response::TagMode {
+            mode: state.config.tag_mode,
+        }),
+
+        // Animation
+        Command::SetAnimation {
+            duration_ms,
+            easing,
+        } => {
+            tracing::info!("Set animation: {}ms, easing: {:?}", duration_ms, easing);
+            state.config.animation_duration_ms = *duration_ms;
+            state.config.animation_easing = *easing;
+            CommandResult::ok_with_effects(vec![Effect::UpdateAnimation {
+                duration_ms: *duration_ms,
+                easing: *easing,
+            }])
+        }
+        Command::GetAnimation => CommandResult::with_response(Response::Animation {
+            duration_ms: state.config.animation_duration_ms,
+            easing: state.config.animation_easing,
+        }),
+
+        // Menu bar indicator
+        Command::SetMenubarIndicator { enabled } => {
+            tracing::info!("Set menu bar indicator: {}", enabled);
+            state.config.menubar_indicator = *enabled;
+            // StatusItem show/hide is handled in ipc_source_callback after command processing
+            CommandResult::ok()
+        }
+        Command::GetMenubarIndicator => CommandResult::with_response(Response::MenubarIndicator {
+            enabled: state.config.menubar_indicator,
+        }),
+
+        // App hiding
+        Command::SetHideApps { enabled } => {
+            tracing::info!("Set hide apps: {}", enabled);
+            state.config.hide_apps = *enabled;
+            // Apps are hidden/unhidden by dispatch_command after command processing
+            CommandResult::ok()
+        }
+        Command::GetHideApps => CommandResult::with_response(Response::HideApps {
+            enabled: state.config.hide_apps,
+        }),
+
+        // Window shadows
+        Command::SetWindowShadows { enabled } => {
+            tracing::info!("Set window shadows: {}", enabled);
+            state.config.hide_shadows = !*enabled;
+            // Shadows are removed/restored on retile
+            CommandResult::ok_with_effects(vec![Effect::Retile])
+        }
+        Command::GetWindowShadows => CommandResult::with_response(Response::WindowShadows {
+            enabled: !state.config.hide_shadows,
+        }),
+
+        // Corner compensation
+        Command::SetCornerCompensation { px } => {
+            tracing::info!("Set corner compensation: {}px", px);
+            state.config.corner_compensation = *px;
+            CommandResult::ok_with_effects(vec![Effect::Retile])
+        }
+        Command::GetCornerCompensation => {
+            CommandResult::with_response(Response::CornerCompensation {
+                px: state.config.corner_compensation,
+            })
+        }
+
+        // Excluded apps
+        Command::ObserverExclude { app_id } => {
+            tracing::info!("Exclude app: {}", app_id.0);
+            if !state.config.excluded_apps.contains(app_id) {
+                state.config.excluded_apps.push(app_id.clone());
+            }
+            // Observers are dropped by dispatch_command after command processing
+            let pids: Vec<i32> = state
+                .windows
+                .values()
+                .filter(|w| state.config.is_app_excluded(w.app_id.as_deref()))
+                .map(|w| w.pid)
+                .collect();
+            let mut removed = false;
+            let mut moves = Vec::new();
+            for pid in pids {
+                // Windows parked off-screen on hidden tags would be stranded there
+                moves.extend(state.restore_moves_for_pid(pid));
+                removed |= state.remove_windows_for_pid(pid);
+            }
+            if removed {
+                CommandResult::ok_with_effects(vec![
+                    Effect::ApplyWindowMoves(moves),
+                    Effect::Retile,
+                ])
+            } else {
+                CommandResult::ok()
+            }
+        }
+        Command::ObserverInclude { app_id } => {
+            let len = state.config.excluded_apps.len();
+            state.config.excluded_apps.retain(|p| p != app_id);
+            if state.config.excluded_apps.len() == len {
+                return CommandResult::error(format!("App is not excluded: {}", app_id.0));
+            }
+            tracing::info!("Include app: {}", app_id.0);
+            // Windows are picked up by dispatch_command after command processing
+            CommandResult::ok_with_effects(vec![Effect::Retile])
+        }
+        Command::ListObserverExcludes => CommandResult::with_response(Response::ObserverExcludes {
+            app_ids: state
+                .config
+                .excluded_apps
+                .iter()
+                .map(|p| p.0.clone())
+                .collect(),
+        }),
+
+        // Gap scale
+        Command::SetGapScale { mode } => {
+            tracing::info!("Set gap scale: {:?}", mode);
+            state.config.gap_scale = *mode;
+            CommandResult::ok_with_effects(vec![Effect::Retile])
+        }
+        Command::GetGapScale => CommandResult::with_response(Response::GapScale {
+            mode: state.config.gap_scale,
+        }),
+
+        // Directional focus
+        Command::SetFocusIncludeFloating { mode } => {
+            tracing::info!("Set focus include floating: {:?}", mode);
+            state.config.focus_floating = *mode;
+            CommandResult::ok()
+        }
+        Command::GetFocusIncludeFloating => {
+            CommandResult::with_response(Response::FocusIncludeFloating {
+                mode: state.config.focus_floating,
+            })
+        }
+
+        // Raise policy
+        Command::SetRaisePolicy { policy } => {
+            tracing::info!("Set raise policy: {:?}", policy);
+            state.config.raise_policy = *policy;
+            CommandResult::ok_with_effects(vec![Effect::Retile])
+        }
+        Command::GetRaisePolicy => CommandResult::with_response(Response::RaisePolicy {
+            policy: state.config.raise_policy,
+        }),
+
+        // Float visibility
+        Command::SetFloatVisibility { mode } => {
+            tracing::info!("Set float visibility: {:?}", mode);
+            state.config.float_visibility = *mode;
+            let mut display_ids: Vec<_> = state.displays.keys().copied().collect();
+            display_ids.sort();
+            let moves = display_ids
+                .into_iter()
+                .flat_map(|display_id| state.compute_layout_changes(display_id))
+                .collect();
+            CommandResult::ok_with_effects(vec![
+                Effect::ApplyWindowMoves(moves),
+                Effect::FocusVisibleWindowIfNeeded,
+            ])
+        }
+        Command::GetFloatVisibility => CommandResult::with_response(Response::FloatVisibility {
+            mode: state.config.float_visibility,
+        }),
+
         // Outer gap
         Command::SetOuterGap { values } => match OuterGap::from_args(values) {
             Some(gap) => {
@@ -580,7 +1219,94 @@ pub fn process_command(
             outer_gap: state.config.outer_gap,
         }),
 
+        // Tag change hook
+        Command::SetTagChangeHook { command } => {
+            tracing::info!("Set tag change hook: {:?}", command);
+            state.config.tag_change_hook = command.clone().filter(|c| !c.is_empty());
+            CommandResult::ok()
+        }
+        Command::GetTagChangeHook => CommandResult::with_response(Response::TagChangeHook {
+            command: state.config.tag_change_hook.clone(),
+        }),
+
+        // Retile hooks
+        Command::SetHook { event, command } => {
+            tracing::info!("Set {:?} hook: {:?}", event, command);
+            match command.as_deref().filter(|c| !c.is_empty()) {
+                Some(command) => {
+                    state
+                        .config
+                        .retile_hooks
+                        .insert(*event, command.to_string());
+                }
+                None => {
+                    state.config.retile_hooks.remove(event);
+                }
+            }
+            CommandResult::ok()
+        }
+        Command::GetHook { event } => CommandResult::with_response(Response::Hook {
+            command: state.config.retile_hooks.get(event).cloned(),
+        }),
+
+        // Arrangements
+        Command::ArrangementSave { name } => {
+            let count = state.save_arrangement(name);
+            if count == 0 {
+                tracing::warn!("Arrangement {} saved with no windows", name);
+            }
+            CommandResult::ok()
+        }
+        Command::ArrangementRestore { name } => {
+            let Some(result) = state.restore_arrangement(name) else {
+                return CommandResult::error(format!("Arrangement not found: {}", name));
+            };
+            if result.restored == 0 {
+                return CommandResult::error(format!("No open windows match arrangement {}", name));
+            }
+            let mut effects = vec![Effect::ApplyWindowMoves(result.window_moves)];
+            for (window_id, pid, frame) in result.floating_frames {
+                effects.push(Effect::MoveWindowToPosition {
+                    window_id,
+                    pid,
+                    x: frame.x,
+                    y: frame.y,
+                });
+                effects.push(Effect::SetWindowDimensions {
+                    window_id,
+                    pid,
+                    width: frame.width,
+                    height: frame.height,
+                });
+            }
+            effects.push(Effect::RetileDisplays(result.display_ids));
+            effects.push(Effect::FocusVisibleWindowIfNeeded);
+            CommandResult::ok_with_effects(effects)
+        }
+
+        // Pause
+        Command::Pause => {
+            tracing::info!("Paused retiling");
+            state.paused = true;
+            CommandResult::ok()
+        }
+        Command::Resume => {
+            if !state.paused {
+                return CommandResult::ok();
+            }
+            tracing::info!("Resumed retiling");
+            state.paused = false;
+            let mut effects = Vec::new();
+            for window_id in std::mem::take(&mut state.deferred_new_windows) {
+                effects.extend(state.apply_rules_to_new_window(window_id));
+            }
+            effects.push(Effect::Retile);
+            CommandResult::ok_with_effects(effects)
+        }
+
         // Control
+        // Answered by the IPC source once animations finish; elsewhere there is nothing to wait for
+        Command::Wait => CommandResult::ok(),
         Command::Quit => {
             tracing::info!("Quit command received");
             CommandResult::ok()
@@ -593,6 +1319,7 @@ pub fn list_all_windows<S: WindowSystem>(
     state: &RefCell<State>,
     window_system: &S,
     debug: bool,
+    why: bool,
 ) -> Response {
     let state = state.borrow();
     let system_windows = window_system.get_on_screen_windows();
@@ -617,6 +1344,8 @@ pub fn list_all_windows<S: WindowSystem>(
                 is_focused: state.focused == Some(w.id),
                 is_floating: w.is_floating,
                 is_fullscreen: w.is_fullscreen,
+                is_native_fullscreen: w.is_native_fullscreen,
+                is_manual: w.is_manual,
                 output_id: w.display_id,
                 status: Some(WindowStatus::Managed),
                 ax_id: if debug { w.ax_id.clone() } else { None },
@@ -642,6 +1371,11 @@ pub fn list_all_windows<S: WindowSystem>(
                 } else {
                     None
                 },
+                applied_rules: if why {
+                    Some(state.applied_rule_indices(w.id))
+                } else {
+                    None
+                },
             });
         } else {
             // Ignored window - use system window info, query extended attrs if debug
@@ -684,8 +1418,16 @@ pub fn list_all_windows<S: WindowSystem>(
                 is_focused: false,
                 is_floating: false,
                 is_fullscreen: false,
+                is_native_fullscreen: false,
+                is_manual: false,
                 output_id,
-                status: Some(WindowStatus::Ignored),
+                status: Some(
+                    if state.is_internal_app(sys_win.pid, sys_win.bundle_id.as_deref()) {
+                        WindowStatus::Internal
+                    } else {
+                        WindowStatus::Ignored
+                    },
+                ),
                 ax_id: ext_attrs.as_ref().and_then(|a| a.ax_id.clone()),
                 subrole: ext_attrs.as_ref().and_then(|a| a.subrole.clone()),
                 window_level: ext_attrs.as_ref().map(|a| a.window_level),
@@ -693,6 +1435,7 @@ pub fn list_all_windows<S: WindowSystem>(
                 fullscreen_button: ext_attrs.as_ref().map(|a| a.fullscreen_button.clone()),
                 minimize_button: ext_attrs.as_ref().map(|a| a.minimize_button.clone()),
                 zoom_button: ext_attrs.as_ref().map(|a| a.zoom_button.clone()),
+                applied_rules: None,
             });
         }
     }
@@ -700,6 +1443,174 @@ pub fn list_all_windows<S: WindowSystem>(
     Response::Windows { windows }
 }
 
+/// Environment variables describing the current context, passed to exec'd commands
+pub fn exec_env(state: &State) -> Vec<(String, String)> {
+    let mut env = vec![
+        (
+            "YASHIKI_VISIBLE_TAGS".to_string(),
+            state.visible_tags().mask().to_string(),
+        ),
+        (
+            "YASHIKI_FOCUSED_OUTPUT".to_string(),
+            state.focused_display.to_string(),
+        ),
+    ];
+    if let Some(window) = state.focused.and_then(|id| state.windows.get(&id)) {
+        env.push(("YASHIKI_FOCUSED_ID".to_string(), window.id.to_string()));
+        env.push(("YASHIKI_FOCUSED_PID".to_string(), window.pid.to_string()));
+        env.push(("YASHIKI_FOCUSED_APP".to_string(), window.app_name.clone()));
+        if let Some(app_id) = &window.app_id {
+            env.push(("YASHIKI_FOCUSED_APP_ID".to_string(), app_id.clone()));
+        }
+        env.push(("YASHIKI_FOCUSED_TITLE".to_string(), window.title.clone()));
+    }
+    env
+}
+
+/// Every tracked attribute of a window (window-info, state-dump)
+fn window_details(state: &State, w: &Window) -> WindowDetails {
+    let frame_info = |r: &Rect| FrameInfo {
+        x: r.x,
+        y: r.y,
+        width: r.width,
+        height: r.height,
+    };
+    WindowDetails {
+        id: w.id,
+        pid: w.pid,
+        title: w.title.clone(),
+        app_name: w.app_name.clone(),
+        app_id: w.app_id.clone(),
+        ax_id: w.ax_id.clone(),
+        subrole: w.subrole.clone(),
+        window_level: w.window_level,
+        tags: w.tags.mask(),
+        output_id: w.display_id,
+        frame: frame_info(&w.frame),
+        saved_frame: w.saved_frame.as_ref().map(frame_info),
+        is_focused: state.focused == Some(w.id),
+        is_floating: w.is_floating,
+        is_fullscreen: w.is_fullscreen,
+        is_native_fullscreen: w.is_native_fullscreen,
+        is_manual: w.is_manual,
+        is_hidden: w.is_hidden(),
+        orphaned_from: w.orphaned_from,
+        close_button: w.close_button.clone(),
+        fullscreen_button: w.fullscreen_button.clone(),
+        minimize_button: w.minimize_button.clone(),
+        zoom_button: w.zoom_button.clone(),
+        matched_rules: state
+            .matching_rules(w.id)
+            .into_iter()
+            .map(rule_info)
+            .collect(),
+        applied_rules: state.applied_rule_indices(w.id),
+    }
+}
+
+/// Snapshot of the full state, sorted by ID so dumps diff cleanly
+pub fn state_dump(state: &State) -> StateDump {
+    let mut windows: Vec<_> = state.windows.values().collect();
+    windows.sort_by_key(|w| w.id);
+    let mut displays: Vec<_> = state.displays.values().collect();
+    displays.sort_by_key(|d| d.id);
+    StateDump {
+        windows: windows
+            .into_iter()
+            .map(|w| window_details(state, w))
+            .collect(),
+        outputs: displays
+            .iter()
+            .map(|d| display_to_info(d, state.focused_display))
+            .collect(),
+        output_layouts: displays
+            .iter()
+            .filter_map(|d| Some((d.id, d.current_layout.clone()?)))
+            .collect(),
+        focused_window_id: state.focused,
+        focused_output_id: state.focused_display,
+        default_layout: state.default_layout.clone(),
+        float_tags: state.float_tags.mask(),
+        stash: state.stash.clone(),
+        rules: state.rules_engine.rules().to_vec(),
+        outer_gap: state.config.outer_gap,
+    }
+}
+
+/// Reject rules the CLI would refuse, since rule-add can also arrive as raw IPC
+fn validate_rule(rule: &WindowRule) -> Result<(), String> {
+    if let RuleAction::Opacity { percent } = rule.action {
+        if !(1..=100).contains(&percent) {
+            return Err(format!("Invalid opacity: {}% (use 1 to 100)", percent));
+        }
+    }
+    if rule.dynamic {
+        if rule.matcher.title.is_none() {
+            return Err("--dynamic requires --title".to_string());
+        }
+        if !rule.action.supports_dynamic() {
+            return Err(
+                "--dynamic only works with float, no-float, tags and output rules".to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Convert a rule to its `list-rules` representation
+fn rule_info(r: &WindowRule) -> RuleInfo {
+    let action_str = match &r.action {
+        yashiki_ipc::RuleAction::Ignore => "ignore".to_string(),
+        yashiki_ipc::RuleAction::Float => "float".to_string(),
+        yashiki_ipc::RuleAction::NoFloat => "no-float".to_string(),
+        yashiki_ipc::RuleAction::Tags { tags } => format!("tags {}", tags),
+        yashiki_ipc::RuleAction::Output { output } => match output {
+            yashiki_ipc::OutputSpecifier::Id(id) => format!("output {}", id),
+            yashiki_ipc::OutputSpecifier::Name(name) => format!("output {}", name),
+        },
+        yashiki_ipc::RuleAction::Position { x, y, anchor } => match anchor {
+            Some(anchor) => format!("position {}", format_anchored_position(*anchor, *x, *y)),
+            None => format!("position {} {}", x, y),
+        },
+        yashiki_ipc::RuleAction::Dimensions { width, height } => {
+            format!("dimensions {} {}", width, height)
+        }
+        yashiki_ipc::RuleAction::CloseBehavior { behavior } => {
+            format!("close-behavior {}", format_close_behavior(*behavior))
+        }
+        yashiki_ipc::RuleAction::NoManageResize => "no-manage-resize".to_string(),
+        yashiki_ipc::RuleAction::Throttle { ms } => format!("throttle {}ms", ms),
+        yashiki_ipc::RuleAction::Opacity { percent } => {
+            format!("opacity {}", *percent as f32 / 100.0)
+        }
+    };
+    RuleInfo {
+        app_name: r.matcher.app_name.as_ref().map(|p| p.pattern().to_string()),
+        app_id: r.matcher.app_id.as_ref().map(|p| p.pattern().to_string()),
+        title: r.matcher.title.as_ref().map(|p| p.pattern().to_string()),
+        ax_id: r.matcher.ax_id.as_ref().map(|p| p.pattern().to_string()),
+        subrole: r.matcher.subrole.as_ref().map(|p| p.pattern().to_string()),
+        window_level: r.matcher.window_level.as_ref().map(format_window_level),
+        close_button: r.matcher.close_button.map(format_button_state),
+        fullscreen_button: r.matcher.fullscreen_button.map(format_button_state),
+        minimize_button: r.matcher.minimize_button.map(format_button_state),
+        zoom_button: r.matcher.zoom_button.map(format_button_state),
+        when_tag: r.matcher.when_tag,
+        when_output: r
+            .matcher
+            .when_output
+            .as_ref()
+            .map(|p| p.pattern().to_string()),
+        parent_exec: r
+            .matcher
+            .parent_exec
+            .as_ref()
+            .map(|p| p.pattern().to_string()),
+        dynamic: r.dynamic,
+        action: action_str,
+    }
+}
+
 /// Format window level for display
 fn format_window_level(level: &WindowLevel) -> String {
     match level {
@@ -715,6 +1626,15 @@ fn format_window_level(level: &WindowLevel) -> String {
     }
 }
 
+/// Format a close-behavior rule action for display
+fn format_close_behavior(behavior: CloseBehavior) -> &'static str {
+    match behavior {
+        CloseBehavior::Close => "close",
+        CloseBehavior::Hide => "hide",
+        CloseBehavior::Quit => "quit",
+    }
+}
+
 /// Format button state for display
 fn format_button_state(state: ButtonState) -> String {
     match state {
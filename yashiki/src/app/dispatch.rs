@@ -1,16 +1,22 @@
 use std::cell::RefCell;
+use std::time::Instant;
 
 use crate::core::State;
+use crate::effect::Effect;
 use crate::event_emitter::EventEmitter;
 use crate::layout::LayoutEngineManager;
 use crate::macos::{DisplayId, HotkeyManager, ObserverManager};
 use crate::platform::{WindowManipulator, WindowSystem};
 use yashiki_ipc::{Command, Response};
 
+use super::bench::bench_ax;
 use super::command::{list_all_windows, process_command};
 use super::effects::execute_effects;
-use super::state_events::{capture_event_state, emit_state_change_events};
-use super::sync_helper::sync_display_and_process_new_windows;
+use super::retile::do_retile_displays;
+use super::state_events::{
+    capture_event_state, emit_state_change_events, run_tag_change_hook, switch_tag_input_source,
+};
+use super::sync_helper::{sync_and_process_new_windows, sync_display_and_process_new_windows};
 
 /// Unified command dispatcher for IPC and hotkey commands.
 /// Handles the common pattern: capture state -> process command -> execute effects -> emit events.
@@ -25,6 +31,29 @@ pub fn dispatch_command<S: WindowSystem, M: WindowManipulator>(
     event_emitter: &EventEmitter,
     observer_manager: &RefCell<ObserverManager>,
 ) -> Response {
+    // Run the bound action as if its hotkey had been pressed
+    if let Command::TriggerBinding { key } = cmd {
+        let binding = hotkey_manager.borrow_mut().trigger(key);
+        return match binding {
+            Ok(Some(action)) => dispatch_command(
+                &action,
+                state,
+                layout_engine_manager,
+                hotkey_manager,
+                window_system,
+                manipulator,
+                event_emitter,
+                observer_manager,
+            ),
+            Ok(None) => Response::Ok,
+            Err(message) => Response::Error { message },
+        };
+    }
+
+    let name = cmd.name();
+    let _span = tracing::debug_span!("command", name = %name).entered();
+    let start = Instant::now();
+
     // Capture state before command for event emission
     let pre_state = capture_event_state(state);
 
@@ -42,6 +71,14 @@ pub fn dispatch_command<S: WindowSystem, M: WindowManipulator>(
 
     // Emit events based on state changes
     emit_state_change_events(event_emitter, state, &pre_state);
+    run_tag_change_hook(state, &pre_state, manipulator);
+    switch_tag_input_source(state, &pre_state, manipulator);
+    update_hidden_apps(state, manipulator);
+    update_window_opacity(state, manipulator);
+
+    let elapsed = start.elapsed();
+    tracing::debug!("{} took {:.2}ms", name, elapsed.as_secs_f64() * 1000.0);
+    state.borrow_mut().latency.record(&name, elapsed);
 
     response
 }
@@ -57,6 +94,111 @@ fn get_tag_view_display(cmd: &Command, state: &State) -> Option<DisplayId> {
     }
 }
 
+/// Hide apps whose windows are all on hidden tags, and unhide them once one is visible again.
+pub fn update_hidden_apps<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M) {
+    let (to_hide, to_unhide) = state.borrow_mut().app_visibility_changes();
+    for pid in to_unhide {
+        tracing::info!("Unhiding app (pid {})", pid);
+        manipulator.unhide_app(pid);
+    }
+    for pid in to_hide {
+        tracing::info!("Hiding app with no visible windows (pid {})", pid);
+        manipulator.hide_app(pid);
+    }
+}
+
+/// Make unfocused windows with an opacity rule translucent, and the focused one opaque.
+pub fn update_window_opacity<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M) {
+    let changes = state.borrow_mut().window_opacity_changes();
+    for (window_id, percent) in changes {
+        let mut state = state.borrow_mut();
+        match manipulator.set_window_alpha(window_id, percent as f32 / 100.0) {
+            Ok(()) => state.record_window_alpha(window_id, percent),
+            Err(e) if !state.window_alpha_refused => {
+                // Usually the window server refusing other apps' windows, so say it once
+                tracing::warn!("Opacity rules may not work: {}", e);
+                state.errors.record("opacity", e);
+                state.window_alpha_refused = true;
+            }
+            Err(e) => tracing::debug!("{}", e),
+        }
+    }
+}
+
+/// Apply the excluded apps to the observer manager. Apps that are no longer excluded
+/// are observed and synced right away, so their windows are managed again.
+fn sync_excluded_apps<S: WindowSystem, M: WindowManipulator>(
+    cmd: &Command,
+    state: &RefCell<State>,
+    layout_engine_manager: &RefCell<LayoutEngineManager>,
+    window_system: &S,
+    manipulator: &M,
+    event_emitter: &EventEmitter,
+    observer_manager: &RefCell<ObserverManager>,
+) {
+    let excluded_apps = state.borrow().config.excluded_apps.clone();
+    observer_manager
+        .borrow_mut()
+        .set_excluded_apps(excluded_apps);
+
+    let Command::ObserverInclude { app_id } = cmd else {
+        return;
+    };
+    let mut pids: Vec<i32> = window_system
+        .get_on_screen_windows()
+        .iter()
+        .filter(|w| w.bundle_id.as_deref().is_some_and(|id| app_id.matches(id)))
+        .map(|w| w.pid)
+        .collect();
+    pids.sort();
+    pids.dedup();
+    for pid in pids {
+        sync_and_process_new_windows(
+            state,
+            window_system,
+            layout_engine_manager,
+            manipulator,
+            event_emitter,
+            observer_manager,
+            pid,
+        );
+    }
+}
+
+/// Sync the apps with windows on a display that is no longer ignored, so they are managed again.
+fn sync_unignored_output<S: WindowSystem, M: WindowManipulator>(
+    state: &RefCell<State>,
+    layout_engine_manager: &RefCell<LayoutEngineManager>,
+    window_system: &S,
+    manipulator: &M,
+    event_emitter: &EventEmitter,
+    observer_manager: &RefCell<ObserverManager>,
+    display_id: DisplayId,
+) {
+    let pids = state
+        .borrow()
+        .on_screen_pids_for_display(window_system, display_id);
+    for pid in pids {
+        sync_and_process_new_windows(
+            state,
+            window_system,
+            layout_engine_manager,
+            manipulator,
+            event_emitter,
+            observer_manager,
+            pid,
+        );
+    }
+}
+
+/// Drop spawned processes that have exited since they were launched.
+pub fn prune_exited_processes<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M) {
+    state
+        .borrow_mut()
+        .spawned_processes
+        .retain(|p| manipulator.is_process_alive(p.pid));
+}
+
 /// This function orchestrates process_command and execute_effects.
 #[allow(clippy::too_many_arguments)]
 fn handle_ipc_command<S: WindowSystem, M: WindowManipulator>(
@@ -70,8 +212,18 @@ fn handle_ipc_command<S: WindowSystem, M: WindowManipulator>(
     cmd: &Command,
 ) -> Response {
     // Handle ListWindows with all=true specially (requires system query)
-    if let Command::ListWindows { all: true, debug } = cmd {
-        return list_all_windows(state, window_system, *debug);
+    if let Command::ListWindows {
+        all: true,
+        debug,
+        why,
+    } = cmd
+    {
+        return list_all_windows(state, window_system, *debug, *why);
+    }
+
+    if let Command::BenchAx { iterations } = cmd {
+        let report = bench_ax(state, window_system, manipulator, *iterations);
+        return Response::Bench { report };
     }
 
     // Handle tag-view commands with pre-sync to remove stale windows
@@ -91,13 +243,78 @@ fn handle_ipc_command<S: WindowSystem, M: WindowManipulator>(
         );
     }
 
+    if matches!(
+        cmd,
+        Command::ListExecs
+            | Command::Exec {
+                unique: Some(_),
+                ..
+            }
+    ) {
+        prune_exited_processes(state, manipulator);
+    }
+
     let result = process_command(
         &mut state.borrow_mut(),
         &mut hotkey_manager.borrow_mut(),
         cmd,
     );
 
-    if let Err(e) = execute_effects(result.effects, state, layout_engine_manager, manipulator) {
+    if matches!(
+        cmd,
+        Command::ObserverExclude { .. } | Command::ObserverInclude { .. }
+    ) && matches!(result.response, Response::Ok)
+    {
+        sync_excluded_apps(
+            cmd,
+            state,
+            layout_engine_manager,
+            window_system,
+            manipulator,
+            event_emitter,
+            observer_manager,
+        );
+    }
+
+    if let Command::OutputIgnore {
+        output,
+        enabled: false,
+    } = cmd
+    {
+        let display_id = state.borrow().get_target_display(output.as_ref());
+        if let (Ok(display_id), Response::Ok) = (display_id, &result.response) {
+            sync_unignored_output(
+                state,
+                layout_engine_manager,
+                window_system,
+                manipulator,
+                event_emitter,
+                observer_manager,
+                display_id,
+            );
+        }
+    }
+
+    // Retile answers with what it did on each display, so it runs its effect here
+    if let (Command::Retile { .. }, [Effect::RetileDisplays(display_ids)]) =
+        (cmd, result.effects.as_slice())
+    {
+        let effects_start = Instant::now();
+        let displays = do_retile_displays(state, layout_engine_manager, manipulator, display_ids);
+        state
+            .borrow_mut()
+            .latency
+            .record_effects(&cmd.name(), effects_start.elapsed());
+        return Response::Retile { displays };
+    }
+
+    let effects_start = Instant::now();
+    let effects_result = execute_effects(result.effects, state, layout_engine_manager, manipulator);
+    state
+        .borrow_mut()
+        .latency
+        .record_effects(&cmd.name(), effects_start.elapsed());
+    if let Err(e) = effects_result {
         return Response::Error { message: e };
     }
 
@@ -112,10 +329,12 @@ mod tests {
     use std::sync::Arc;
 
     use super::*;
+    use crate::app::focus::apply_deferred_warp;
     use crate::event::Event;
     use crate::platform::mock::{
         create_test_display, create_test_window, MockWindowManipulator, MockWindowSystem,
     };
+    use yashiki_ipc::{CursorWarpMode, Direction};
 
     fn setup_test_context() -> (
         RefCell<State>,
@@ -181,6 +400,7 @@ mod tests {
             &Command::ListWindows {
                 all: false,
                 debug: false,
+                why: false,
             },
             &state,
             &layout_manager,
@@ -220,6 +440,80 @@ mod tests {
         assert!(matches!(response, Response::State { .. }));
     }
 
+    #[test]
+    fn test_dispatch_command_records_latency() {
+        let (
+            state,
+            layout_manager,
+            hotkey_manager,
+            ws,
+            manipulator,
+            event_emitter,
+            observer_manager,
+        ) = setup_test_context();
+
+        let dispatch = |cmd| {
+            dispatch_command(
+                &cmd,
+                &state,
+                &layout_manager,
+                &hotkey_manager,
+                &ws,
+                &manipulator,
+                &event_emitter,
+                &observer_manager,
+            )
+        };
+        dispatch(Command::TagView {
+            tags: 0b10,
+            output: None,
+        });
+        let response = dispatch(Command::Stats);
+
+        let Response::Stats { commands } = response else {
+            panic!("Expected Stats response");
+        };
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command, "tag_view");
+        assert_eq!(commands[0].count, 1);
+    }
+
+    #[test]
+    fn test_dispatch_command_bench_ax() {
+        let (
+            state,
+            layout_manager,
+            hotkey_manager,
+            ws,
+            manipulator,
+            event_emitter,
+            observer_manager,
+        ) = setup_test_context();
+
+        let response = dispatch_command(
+            &Command::BenchAx { iterations: 3 },
+            &state,
+            &layout_manager,
+            &hotkey_manager,
+            &ws,
+            &manipulator,
+            &event_emitter,
+            &observer_manager,
+        );
+
+        let Response::Bench { report } = response else {
+            panic!("Expected Bench response");
+        };
+        assert_eq!(report.iterations, 3);
+        let mut pids: Vec<i32> = report.apps.iter().map(|a| a.pid).collect();
+        pids.sort();
+        assert_eq!(pids, vec![1000, 1001]);
+        assert!(report
+            .apps
+            .iter()
+            .all(|a| a.windows == 1 && a.move_resize_p50_ms.is_some()));
+    }
+
     #[test]
     fn test_dispatch_command_tag_view() {
         let (
@@ -249,4 +543,148 @@ mod tests {
         assert!(matches!(response, Response::Ok));
         assert_eq!(state.borrow().visible_tags().mask(), 0b10);
     }
+
+    #[test]
+    fn test_dispatch_command_tag_view_restores_last_focused_window() {
+        for last_focused in [100, 101] {
+            let (
+                state,
+                layout_manager,
+                hotkey_manager,
+                ws,
+                manipulator,
+                event_emitter,
+                observer_manager,
+            ) = setup_test_context();
+            state.borrow_mut().set_focused(Some(last_focused));
+
+            let view = |tags| {
+                dispatch_command(
+                    &Command::TagView { tags, output: None },
+                    &state,
+                    &layout_manager,
+                    &hotkey_manager,
+                    &ws,
+                    &manipulator,
+                    &event_emitter,
+                    &observer_manager,
+                )
+            };
+            view(0b10);
+            // macOS moved focus elsewhere (e.g. Finder) while tag 2 was shown
+            state.borrow_mut().set_focused(None);
+            view(0b01);
+
+            assert_eq!(state.borrow().focused, Some(last_focused));
+        }
+    }
+
+    #[test]
+    fn test_dispatch_command_defers_cursor_warp_during_drag() {
+        let (
+            state,
+            layout_manager,
+            hotkey_manager,
+            ws,
+            manipulator,
+            event_emitter,
+            observer_manager,
+        ) = setup_test_context();
+        state.borrow_mut().config.cursor_warp = CursorWarpMode::OnFocusChange;
+        manipulator.left_mouse_down.set(true);
+
+        let response = dispatch_command(
+            &Command::WindowFocus {
+                direction: Direction::Next,
+            },
+            &state,
+            &layout_manager,
+            &hotkey_manager,
+            &ws,
+            &manipulator,
+            &event_emitter,
+            &observer_manager,
+        );
+
+        assert!(matches!(response, Response::Ok));
+        assert_eq!(state.borrow().focused, Some(101));
+        assert!(manipulator.warps.borrow().is_empty());
+        let deferred = state.borrow().deferred_warp;
+        assert!(deferred.is_some());
+
+        // Still dragging: nothing happens yet
+        apply_deferred_warp(&state, &manipulator);
+        assert!(manipulator.warps.borrow().is_empty());
+
+        manipulator.left_mouse_down.set(false);
+        apply_deferred_warp(&state, &manipulator);
+        assert_eq!(*manipulator.warps.borrow(), vec![deferred.unwrap()]);
+        assert_eq!(state.borrow().deferred_warp, None);
+    }
+
+    #[test]
+    fn test_dispatch_command_exec_prunes_exited_processes() {
+        let (
+            state,
+            layout_manager,
+            hotkey_manager,
+            ws,
+            manipulator,
+            event_emitter,
+            observer_manager,
+        ) = setup_test_context();
+
+        state
+            .borrow_mut()
+            .spawned_processes
+            .push(crate::core::SpawnedProcess {
+                pid: 4242,
+                command: "old.sh".to_string(),
+                name: None,
+                track: true,
+            });
+        manipulator.exited.borrow_mut().push(4242);
+
+        dispatch_command(
+            &Command::Exec {
+                command: "new.sh".to_string(),
+                track: false,
+                args: None,
+                unique: None,
+            },
+            &state,
+            &layout_manager,
+            &hotkey_manager,
+            &ws,
+            &manipulator,
+            &event_emitter,
+            &observer_manager,
+        );
+
+        let state = state.borrow();
+        assert_eq!(state.spawned_processes.len(), 1);
+        assert_eq!(state.spawned_processes[0].command, "new.sh");
+    }
+
+    #[test]
+    fn test_update_window_opacity_records_only_applied_changes() {
+        let (state, _, _, _, manipulator, _, _) = setup_test_context();
+        state.borrow_mut().windows.get_mut(&101).unwrap().opacity = Some(90);
+
+        manipulator.refuse_private_api.set(true);
+        update_window_opacity(&state, &manipulator);
+        update_window_opacity(&state, &manipulator);
+        {
+            let state = state.borrow();
+            assert!(state.window_alpha.is_empty());
+            assert!(state.window_alpha_refused);
+            // Reported once, not on every retry
+            assert_eq!(state.errors.summary(Instant::now()).len(), 1);
+        }
+
+        manipulator.refuse_private_api.set(false);
+        update_window_opacity(&state, &manipulator);
+        assert_eq!(state.borrow().window_alpha.get(&101), Some(&90));
+        assert_eq!(*manipulator.alphas.borrow(), vec![(101, 0.9)]);
+    }
 }
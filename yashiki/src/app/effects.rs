@@ -6,8 +6,9 @@ use crate::layout::LayoutEngineManager;
 use crate::platform::WindowManipulator;
 use yashiki_ipc::CursorWarpMode;
 
-use super::focus::{focus_visible_window_if_needed, notify_layout_focus};
-use super::retile::{do_retile, do_retile_display};
+use super::dispatch::prune_exited_processes;
+use super::focus::{focus_visible_window_if_needed, notify_layout_focus, warp_cursor};
+use super::retile::{do_retile, do_retile_displays};
 
 /// Execute side effects.
 pub fn execute_effects<M: WindowManipulator>(
@@ -18,7 +19,8 @@ pub fn execute_effects<M: WindowManipulator>(
 ) -> Result<(), String> {
     for effect in effects {
         match effect {
-            Effect::ApplyWindowMoves(moves) => {
+            Effect::ApplyWindowMoves(mut moves) => {
+                state.borrow().order_moves_show_first(&mut moves);
                 manipulator.apply_window_moves(&moves);
             }
             Effect::FocusWindow {
@@ -45,9 +47,9 @@ pub fn execute_effects<M: WindowManipulator>(
                 };
 
                 if should_warp {
-                    if let Some(window) = state.borrow().windows.get(&window_id) {
-                        let (cx, cy) = window.center();
-                        manipulator.warp_cursor(cx, cy);
+                    let center = state.borrow().windows.get(&window_id).map(|w| w.center());
+                    if let Some((cx, cy)) = center {
+                        warp_cursor(state, manipulator, cx, cy);
                     }
                 }
 
@@ -74,13 +76,22 @@ pub fn execute_effects<M: WindowManipulator>(
             Effect::CloseWindow { window_id, pid } => {
                 manipulator.close_window(window_id, pid);
             }
+            Effect::HideApp { pid } => {
+                manipulator.hide_app(pid);
+            }
+            Effect::UnhideApp { pid } => {
+                manipulator.unhide_app(pid);
+            }
+            Effect::QuitApp { pid, force } => {
+                manipulator.quit_app(pid, force);
+            }
             Effect::ApplyFullscreen {
                 window_id,
                 pid,
                 display_id,
             } => {
                 let state = state.borrow();
-                let outer_gap = state.config.outer_gap;
+                let outer_gap = state.outer_gap_for_display(display_id);
                 if let Some(display) = state.displays.get(&display_id) {
                     manipulator.set_window_frame(
                         window_id,
@@ -96,9 +107,7 @@ pub fn execute_effects<M: WindowManipulator>(
                 do_retile(state, layout_engine_manager, manipulator);
             }
             Effect::RetileDisplays(display_ids) => {
-                for display_id in display_ids {
-                    do_retile_display(state, layout_engine_manager, manipulator, display_id);
-                }
+                do_retile_displays(state, layout_engine_manager, manipulator, &display_ids);
             }
             Effect::SendLayoutCommand { layout, cmd, args } => {
                 let layout_name = layout
@@ -109,29 +118,68 @@ pub fn execute_effects<M: WindowManipulator>(
                     return Err(format!("Layout command failed: {}", e));
                 }
             }
-            Effect::ExecCommand { command, path } => {
-                manipulator.exec_command(&command, &path)?;
-            }
-            Effect::ExecCommandTracked { command, path } => {
-                match manipulator.exec_command_tracked(&command, &path) {
-                    Ok(pid) => {
-                        state
-                            .borrow_mut()
-                            .tracked_processes
-                            .push(crate::core::TrackedProcess {
-                                pid,
-                                _command: command.clone(),
-                            });
-                        tracing::info!("Tracked process started: {} (pid={})", command, pid);
-                    }
-                    Err(e) => return Err(e),
+            Effect::SendLayoutAction { action, args } => {
+                let layout_name = state.borrow().current_layout().to_string();
+                let mut manager = layout_engine_manager.borrow_mut();
+                if let Err(e) = manager.send_action(&layout_name, action, &args) {
+                    return Err(format!("Layout action failed: {}", e));
+                }
+            }
+            Effect::PromoteWindow {
+                display_id,
+                window_id,
+            } => {
+                let layout_name = state
+                    .borrow()
+                    .current_layout_for_display(display_id)
+                    .to_string();
+                let mut manager = layout_engine_manager.borrow_mut();
+                // Engines without a main window may not know the message
+                if let Err(e) =
+                    manager.send_command(&layout_name, "promote", &[window_id.to_string()])
+                {
+                    tracing::debug!("Layout engine did not accept promote: {}", e);
+                }
+            }
+            Effect::ExecCommand {
+                command,
+                args,
+                path,
+                env,
+                track,
+                name,
+            } => {
+                let pid = manipulator.exec_command(&command, args.as_deref(), &path, &env)?;
+                if track {
+                    tracing::info!("Tracked process started: {} (pid={})", command, pid);
                 }
+                // Drop earlier processes that have exited so the list doesn't keep growing
+                prune_exited_processes(state, manipulator);
+                state
+                    .borrow_mut()
+                    .spawned_processes
+                    .push(crate::core::SpawnedProcess {
+                        pid,
+                        command,
+                        name,
+                        track,
+                    });
             }
             Effect::UpdateLayoutExecPath { path } => {
                 layout_engine_manager.borrow_mut().set_exec_path(&path);
             }
+            Effect::UpdateAnimation {
+                duration_ms,
+                easing,
+            } => {
+                manipulator.set_animation(duration_ms, easing);
+            }
             Effect::FocusVisibleWindowIfNeeded => {
-                focus_visible_window_if_needed(state, manipulator);
+                if let Some(window_id) = focus_visible_window_if_needed(state, manipulator) {
+                    if notify_layout_focus(state, layout_engine_manager, window_id) {
+                        do_retile(state, layout_engine_manager, manipulator);
+                    }
+                }
             }
             Effect::WarpCursorToDisplay { display_id } => {
                 let cursor_warp_mode = state.borrow().config.cursor_warp;
@@ -140,9 +188,13 @@ pub fn execute_effects<M: WindowManipulator>(
                     CursorWarpMode::OnOutputChange | CursorWarpMode::OnFocusChange => true,
                 };
                 if should_warp {
-                    if let Some(display) = state.borrow().displays.get(&display_id) {
-                        let (cx, cy) = display.frame.center();
-                        manipulator.warp_cursor(cx, cy);
+                    let center = state
+                        .borrow()
+                        .displays
+                        .get(&display_id)
+                        .map(|d| d.frame.center());
+                    if let Some((cx, cy)) = center {
+                        warp_cursor(state, manipulator, cx, cy);
                     }
                 }
             }
@@ -5,16 +5,16 @@ use crate::layout::LayoutEngineManager;
 use crate::platform::WindowManipulator;
 use yashiki_ipc::CursorWarpMode;
 
+/// Focus a visible window when the focused one was hidden by a tag switch or closed.
+/// Returns the newly focused window.
 pub fn focus_visible_window_if_needed<M: WindowManipulator>(
     state: &RefCell<State>,
     manipulator: &M,
-) {
-    let (window_to_focus, cursor_warp_mode) = {
+) -> Option<u32> {
+    let ((window_id, pid, (cx, cy)), cursor_warp_mode) = {
         let state = state.borrow();
         let display_id = state.focused_display;
-        let Some(display) = state.displays.get(&display_id) else {
-            return;
-        };
+        let display = state.displays.get(&display_id)?;
 
         // Get all visible windows on display (including fullscreen and floating)
         let all_visible: Vec<_> = state
@@ -22,13 +22,13 @@ pub fn focus_visible_window_if_needed<M: WindowManipulator>(
             .values()
             .filter(|w| {
                 w.display_id == display_id
-                    && w.tags.intersects(display.visible_tags)
+                    && state.shows_on_tags(w, display.visible_tags)
                     && !w.is_hidden()
             })
             .collect();
 
         if all_visible.is_empty() {
-            return;
+            return None;
         }
 
         // Check if current focus is on a visible window
@@ -38,62 +38,78 @@ pub fn focus_visible_window_if_needed<M: WindowManipulator>(
             .unwrap_or(false);
 
         if focus_is_visible {
-            return;
+            return None;
         }
 
-        // Focus the first visible window (prefer tiled, then fullscreen, then floating)
+        // Focus the window last focused on these tags, then the most recently focused one,
+        // otherwise the first visible window (prefer tiled, then fullscreen, then floating)
+        let remembered = state
+            .last_focused_on_visible_tags(display_id)
+            .or_else(|| state.recently_focused_on_display(display_id));
         let window = all_visible
             .iter()
-            .find(|w| w.is_tiled())
+            .find(|w| Some(w.id) == remembered)
+            .or_else(|| all_visible.iter().find(|w| w.is_tiled()))
             .or_else(|| all_visible.iter().find(|w| w.is_fullscreen))
             .or_else(|| all_visible.first());
 
-        match window {
-            Some(w) => (Some((w.id, w.pid, w.center())), state.config.cursor_warp),
-            None => return,
-        }
+        let w = window?;
+        ((w.id, w.pid, w.center()), state.config.cursor_warp)
     };
 
-    if let Some((window_id, pid, (cx, cy))) = window_to_focus {
-        tracing::info!("Focusing visible window {} after tag switch", window_id);
+    tracing::info!("Focusing visible window {}", window_id);
 
-        // Set focus intent BEFORE focusing to suppress spurious macOS focus changes
-        state.borrow_mut().set_focus_intent(window_id, pid);
+    // Set focus intent BEFORE focusing to suppress spurious macOS focus changes
+    state.borrow_mut().set_focus_intent(window_id, pid);
 
-        manipulator.focus_window(window_id, pid);
+    manipulator.focus_window(window_id, pid);
 
-        // Update internal state immediately after focusing
-        // This ensures emit_state_change_events will detect the focus change
-        state.borrow_mut().set_focused(Some(window_id));
+    // Update internal state immediately after focusing
+    // This ensures emit_state_change_events will detect the focus change
+    state.borrow_mut().set_focused(Some(window_id));
 
-        // Warp cursor if OnFocusChange mode (not OnOutputChange since this is not an output change)
-        if cursor_warp_mode == CursorWarpMode::OnFocusChange {
-            manipulator.warp_cursor(cx, cy);
-        }
+    // Warp cursor if OnFocusChange mode (not OnOutputChange since this is not an output change)
+    if cursor_warp_mode == CursorWarpMode::OnFocusChange {
+        warp_cursor(state, manipulator, cx, cy);
+    }
+
+    Some(window_id)
+}
+
+/// Warp the cursor, or hold the warp back while the left mouse button is down
+/// so it doesn't break a drag. Only the latest target is kept.
+pub fn warp_cursor<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M, x: i32, y: i32) {
+    if manipulator.is_left_mouse_down() {
+        tracing::debug!("Mouse button down, deferring cursor warp to ({}, {})", x, y);
+        state.borrow_mut().deferred_warp = Some((x, y));
+    } else {
+        state.borrow_mut().deferred_warp = None;
+        manipulator.warp_cursor(x, y);
+    }
+}
+
+/// Perform the cursor warp held back during a drag, once the mouse button is released
+pub fn apply_deferred_warp<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M) {
+    if manipulator.is_left_mouse_down() {
+        return;
+    }
+    let deferred = state.borrow_mut().deferred_warp.take();
+    if let Some((x, y)) = deferred {
+        manipulator.warp_cursor(x, y);
     }
 }
 
 pub fn switch_tag_for_focused_window(state: &RefCell<State>) -> Option<Vec<WindowMove>> {
-    let (focused_id, window_tags, window_display_id, is_hidden) = {
+    let (focused_id, window_tags, is_visible) = {
         let s = state.borrow();
         let focused_id = s.focused?;
         let window = s.windows.get(&focused_id)?;
-        (
-            focused_id,
-            window.tags,
-            window.display_id,
-            window.is_hidden(),
-        )
-    };
-
-    // Check if window is visible on its display's current visible tags
-    let is_visible = {
-        let s = state.borrow();
-        if let Some(display) = s.displays.get(&window_display_id) {
-            window_tags.intersects(display.visible_tags) && !is_hidden
-        } else {
-            false
-        }
+        // Check if window is visible on its display's current visible tags
+        let is_visible = s
+            .displays
+            .get(&window.display_id)
+            .is_some_and(|d| s.shows_on_tags(window, d.visible_tags) && !window.is_hidden());
+        (focused_id, window.tags, is_visible)
     };
 
     if is_visible {
@@ -122,7 +138,7 @@ pub fn notify_layout_focus(
 ) -> bool {
     let layout_name = state.borrow().current_layout().to_string();
     let mut manager = layout_engine_manager.borrow_mut();
-    match manager.send_command(&layout_name, "focus-changed", &[window_id.to_string()]) {
+    match manager.notify_focus(&layout_name, window_id) {
         Ok(needs_retile) => needs_retile,
         Err(e) => {
             tracing::warn!("Failed to notify layout engine of focus change: {}", e);
@@ -0,0 +1,51 @@
+use std::cell::RefCell;
+
+use crate::core::State;
+use crate::macos::StatusItem;
+
+/// Show/hide the menu bar indicator per config and refresh its title.
+pub fn update_menubar_indicator(state: &RefCell<State>, status_item: &RefCell<StatusItem>) {
+    let state = state.borrow();
+    let mut status_item = status_item.borrow_mut();
+    if !state.config.menubar_indicator {
+        status_item.hide();
+        return;
+    }
+    if !status_item.is_visible() {
+        status_item.show();
+    }
+    status_item.set_title(&indicator_title(&state));
+}
+
+/// Title for the focused output, e.g. "1,3 tatami"
+fn indicator_title(state: &State) -> String {
+    let visible_tags = state
+        .displays
+        .get(&state.focused_display)
+        .map(|d| d.visible_tags.mask())
+        .unwrap_or(0);
+    let tags: Vec<String> = (0..32)
+        .filter(|i| visible_tags & (1 << i) != 0)
+        .map(|i| (i + 1).to_string())
+        .collect();
+    format!("{} {}", tags.join(","), state.current_layout())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::mock::{create_test_display, MockWindowSystem};
+
+    #[test]
+    fn test_indicator_title() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        assert_eq!(indicator_title(&state), "1 tatami");
+
+        state.view_tags_on_display(0b101, 1);
+        state.set_layout_on_display(None, Some(1), "byobu".to_string());
+        assert_eq!(indicator_title(&state), "1,3 byobu");
+    }
+}
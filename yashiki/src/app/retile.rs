@@ -1,9 +1,17 @@
 use std::cell::RefCell;
+use std::time::{Duration, Instant};
 
-use crate::core::State;
-use crate::layout::LayoutEngineManager;
+use crate::core::{Rect, State};
+use crate::layout::{LayoutEngineManager, LayoutRequest};
 use crate::macos::DisplayId;
 use crate::platform::WindowManipulator;
+use yashiki_ipc::{HookContext, HookEvent, OuterGap, RetileInfo, WindowGeometry};
+
+use super::command::exec_env;
+
+/// Retiles closer together than this form one burst (drags, animated layouts): the
+/// pre-retile hook runs before its first retile and the post-retile hook after its last
+pub const HOOK_SETTLE_DELAY: Duration = Duration::from_millis(150);
 
 pub fn do_retile<M: WindowManipulator>(
     state: &RefCell<State>,
@@ -12,10 +20,7 @@ pub fn do_retile<M: WindowManipulator>(
 ) {
     // Collect display IDs first to avoid borrow issues
     let display_ids: Vec<_> = state.borrow().displays.keys().copied().collect();
-
-    for display_id in display_ids {
-        retile_single_display(state, layout_engine_manager, manipulator, display_id);
-    }
+    retile_displays(state, layout_engine_manager, manipulator, &display_ids);
 }
 
 pub fn do_retile_display<M: WindowManipulator>(
@@ -24,99 +29,365 @@ pub fn do_retile_display<M: WindowManipulator>(
     manipulator: &M,
     display_id: DisplayId,
 ) {
-    if !state.borrow().displays.contains_key(&display_id) {
-        return;
-    }
-    retile_single_display(state, layout_engine_manager, manipulator, display_id);
+    do_retile_displays(state, layout_engine_manager, manipulator, &[display_id]);
 }
 
-fn retile_single_display<M: WindowManipulator>(
+/// Retile the displays, returning what was done on each one
+pub fn do_retile_displays<M: WindowManipulator>(
     state: &RefCell<State>,
     layout_engine_manager: &RefCell<LayoutEngineManager>,
     manipulator: &M,
+    display_ids: &[DisplayId],
+) -> Vec<RetileInfo> {
+    let display_ids: Vec<_> = {
+        let state = state.borrow();
+        display_ids
+            .iter()
+            .copied()
+            .filter(|id| state.displays.contains_key(id))
+            .collect()
+    };
+    retile_displays(state, layout_engine_manager, manipulator, &display_ids)
+}
+
+/// Layout parameters of a display, captured before the engines are asked
+struct DisplayRetile {
     display_id: DisplayId,
-) {
-    // First, handle any fullscreen windows on this display
-    {
+    display_frame: Rect,
+    outer_gap: OuterGap,
+}
+
+fn retile_displays<M: WindowManipulator>(
+    state: &RefCell<State>,
+    layout_engine_manager: &RefCell<LayoutEngineManager>,
+    manipulator: &M,
+    display_ids: &[DisplayId],
+) -> Vec<RetileInfo> {
+    if state.borrow().paused {
+        tracing::debug!("Skipping retile while paused");
+        return vec![];
+    }
+    update_window_shadows(state, manipulator);
+
+    let start = Instant::now();
+    let mut summary: Vec<RetileInfo> = display_ids
+        .iter()
+        .map(|&display_id| RetileInfo {
+            display_id,
+            windows: 0,
+            moved: 0,
+            duration_ms: 0.0,
+        })
+        .collect();
+    let burst_start = state
+        .borrow()
+        .last_retile
+        .is_none_or(|last| start.duration_since(last) >= HOOK_SETTLE_DELAY);
+    if burst_start {
+        let context = HookContext {
+            event: HookEvent::PreRetile,
+            display_ids: display_ids.to_vec(),
+            displays: vec![],
+        };
+        run_retile_hook(&state.borrow(), manipulator, &context);
+    }
+    let mut pending = Vec::new();
+    let mut requests = Vec::new();
+    for &display_id in display_ids {
+        apply_fullscreen_windows(state, manipulator, display_id);
+        if let Some((retile, request)) = prepare_layout_request(&state.borrow(), display_id) {
+            pending.push(retile);
+            requests.push(request);
+        }
+    }
+    if requests.is_empty() {
+        defer_post_retile_hook(state, manipulator, &summary);
+        return summary;
+    }
+
+    // Engines that were just selected or respawned learn the current focus before laying out
+    let (focused, focused_display) = {
         let state = state.borrow();
-        let outer_gap = state.config.outer_gap;
-        if let Some(display) = state.displays.get(&display_id) {
-            let fullscreen_windows: Vec<_> = state
-                .windows
-                .values()
-                .filter(|w| {
-                    w.display_id == display_id
-                        && w.is_fullscreen
-                        && w.tags.intersects(display.visible_tags)
-                        && !w.is_hidden()
-                })
-                .map(|w| (w.id, w.pid))
-                .collect();
-
-            // Apply fullscreen with outer gap
-            for (window_id, pid) in fullscreen_windows {
-                manipulator.set_window_frame(
-                    window_id,
-                    pid,
-                    display.frame.x + outer_gap.left as i32,
-                    display.frame.y + outer_gap.top as i32,
-                    display.frame.width.saturating_sub(outer_gap.horizontal()),
-                    display.frame.height.saturating_sub(outer_gap.vertical()),
+        (state.focused, state.focused_display)
+    };
+    if let Some(window_id) = focused {
+        for (retile, request) in pending.iter().zip(&requests) {
+            if retile.display_id != focused_display {
+                continue;
+            }
+            if let Err(e) = layout_engine_manager
+                .borrow_mut()
+                .notify_focus(&request.layout, window_id)
+            {
+                tracing::warn!("Failed to notify layout engine of focus change: {}", e);
+            }
+        }
+    }
+
+    // Send every display's request before waiting for any response
+    let results = layout_engine_manager
+        .borrow_mut()
+        .request_layouts(&requests);
+    let layout_duration = start.elapsed();
+
+    for (retile, result) in pending.into_iter().zip(results) {
+        match result {
+            Ok(geometries) => {
+                // The layout wait is shared, applying geometries is this display's own
+                let apply_start = Instant::now();
+                let (windows, moved) = apply_geometries(state, manipulator, &retile, geometries);
+                if let Some(info) = summary
+                    .iter_mut()
+                    .find(|info| info.display_id == retile.display_id)
+                {
+                    info.windows = windows;
+                    info.moved = moved;
+                    info.duration_ms =
+                        (layout_duration + apply_start.elapsed()).as_secs_f64() * 1000.0;
+                }
+            }
+            Err(e) => {
+                let message = format!(
+                    "Layout request failed for display {}: {}",
+                    retile.display_id, e
                 );
+                tracing::error!("{}", message);
+                state.borrow_mut().errors.record("layout", message);
             }
         }
     }
+    defer_post_retile_hook(state, manipulator, &summary);
+    summary
+}
 
-    // Get layout parameters with immutable borrow
-    let (window_ids, usable_width, usable_height, display_frame, layout_name, outer_gap) = {
-        let state = state.borrow();
-        let Some(display) = state.displays.get(&display_id) else {
+/// Fold this retile into the pending post-retile context (latest summary per display)
+/// and push the hook back until retiles settle
+fn defer_post_retile_hook<M: WindowManipulator>(
+    state: &RefCell<State>,
+    manipulator: &M,
+    summary: &[RetileInfo],
+) {
+    let mut state = state.borrow_mut();
+    state.last_retile = Some(Instant::now());
+    if !state
+        .config
+        .retile_hooks
+        .contains_key(&HookEvent::PostRetile)
+    {
+        return;
+    }
+    let context = state
+        .pending_post_retile
+        .get_or_insert_with(|| HookContext {
+            event: HookEvent::PostRetile,
+            display_ids: vec![],
+            displays: vec![],
+        });
+    for info in summary {
+        if !context.display_ids.contains(&info.display_id) {
+            context.display_ids.push(info.display_id);
+        }
+        context.displays.retain(|d| d.display_id != info.display_id);
+        context.displays.push(info.clone());
+    }
+    manipulator.schedule_hook_flush(HOOK_SETTLE_DELAY);
+}
+
+/// Run the held-back post-retile hook once no retile happened for `HOOK_SETTLE_DELAY`,
+/// otherwise check again when that much time has passed since the last one
+pub fn flush_retile_hooks<M: WindowManipulator>(
+    state: &RefCell<State>,
+    manipulator: &M,
+    now: Instant,
+) {
+    let context = {
+        let mut state = state.borrow_mut();
+        if state.pending_post_retile.is_none() {
             return;
-        };
-        let visible_windows = state.visible_windows_on_display(display_id);
-        if visible_windows.is_empty() {
+        }
+        let since_last = state.last_retile.map_or(HOOK_SETTLE_DELAY, |last| {
+            now.saturating_duration_since(last)
+        });
+        if since_last < HOOK_SETTLE_DELAY {
+            manipulator.schedule_hook_flush(HOOK_SETTLE_DELAY - since_last);
             return;
         }
-        let window_ids: Vec<u32> = visible_windows.iter().map(|w| w.id).collect();
-        let layout_name = state.current_layout_for_display(display_id).to_string();
-        let outer_gap = state.config.outer_gap;
-        // Subtract outer gap from dimensions before sending to layout engine
-        let usable_width = display.frame.width.saturating_sub(outer_gap.horizontal());
-        let usable_height = display.frame.height.saturating_sub(outer_gap.vertical());
-        (
-            window_ids,
-            usable_width,
-            usable_height,
-            display.frame,
-            layout_name,
-            outer_gap,
-        )
+        state.pending_post_retile.take()
     };
+    if let Some(context) = context {
+        run_retile_hook(&state.borrow(), manipulator, &context);
+    }
+}
 
-    let mut manager = layout_engine_manager.borrow_mut();
-    match manager.request_layout(&layout_name, usable_width, usable_height, &window_ids) {
-        Ok(geometries) => {
-            // Update window_order based on geometries order from layout engine
-            {
-                let mut state = state.borrow_mut();
-                if let Some(display) = state.displays.get_mut(&display_id) {
-                    display.window_order = geometries.iter().map(|g| g.id).collect();
-                }
+/// Run the user's hook for a retile event, passing a `HookContext` as JSON on stdin
+fn run_retile_hook<M: WindowManipulator>(state: &State, manipulator: &M, context: &HookContext) {
+    let event = context.event;
+    let Some(hook) = state.config.retile_hooks.get(&event) else {
+        return;
+    };
+    let input = match serde_json::to_string(context) {
+        Ok(input) => input,
+        Err(e) => {
+            tracing::warn!("Failed to serialize hook context: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = manipulator.exec_hook(hook, &state.config.exec_path, &exec_env(state), &input) {
+        tracing::warn!("{:?} hook failed: {}", event, e);
+    }
+}
+
+/// Remove the shadows of tiled windows when set-window-shadows is off, and restore them
+/// once a window floats or the setting is turned back on
+fn update_window_shadows<M: WindowManipulator>(state: &RefCell<State>, manipulator: &M) {
+    let (to_remove, to_restore) = state.borrow_mut().window_shadow_changes();
+    for (window_id, enabled) in to_remove
+        .into_iter()
+        .map(|id| (id, false))
+        .chain(to_restore.into_iter().map(|id| (id, true)))
+    {
+        let mut state = state.borrow_mut();
+        match manipulator.set_window_shadow(window_id, enabled) {
+            Ok(()) => state.record_window_shadow(window_id, enabled),
+            Err(e) if !state.window_shadow_refused => {
+                // Usually the window server refusing other apps' windows, so say it once
+                tracing::warn!("Window shadows may not change: {}", e);
+                state.errors.record("window_shadows", e);
+                state.window_shadow_refused = true;
             }
-            // Add outer gap offset to geometries before applying
-            let adjusted_geometries: Vec<_> = geometries
-                .into_iter()
-                .map(|mut g| {
-                    g.x += outer_gap.left as i32;
-                    g.y += outer_gap.top as i32;
-                    g
-                })
-                .collect();
-            // Apply layout using manipulator
-            manipulator.apply_layout(display_id, &display_frame, &adjusted_geometries);
+            Err(e) => tracing::debug!("{}", e),
         }
-        Err(e) => {
-            tracing::error!("Layout request failed for display {}: {}", display_id, e);
+    }
+}
+
+fn apply_fullscreen_windows<M: WindowManipulator>(
+    state: &RefCell<State>,
+    manipulator: &M,
+    display_id: DisplayId,
+) {
+    let state = state.borrow();
+    let outer_gap = state.outer_gap_for_display(display_id);
+    let Some(display) = state.displays.get(&display_id) else {
+        return;
+    };
+    let fullscreen_windows: Vec<_> = state
+        .windows
+        .values()
+        .filter(|w| {
+            w.display_id == display_id
+                && w.is_fullscreen
+                && !w.is_native_fullscreen
+                && !w.is_app_hidden
+                && w.tags.intersects(display.visible_tags)
+                && !w.is_hidden()
+        })
+        .map(|w| (w.id, w.pid))
+        .collect();
+
+    // Apply fullscreen with outer gap
+    for (window_id, pid) in fullscreen_windows {
+        manipulator.set_window_frame(
+            window_id,
+            pid,
+            display.frame.x + outer_gap.left as i32,
+            display.frame.y + outer_gap.top as i32,
+            display.frame.width.saturating_sub(outer_gap.horizontal()),
+            display.frame.height.saturating_sub(outer_gap.vertical()),
+        );
+    }
+}
+
+fn prepare_layout_request(
+    state: &State,
+    display_id: DisplayId,
+) -> Option<(DisplayRetile, LayoutRequest)> {
+    let display = state.displays.get(&display_id)?;
+    let visible_windows = state.visible_windows_on_display(display_id);
+    if visible_windows.is_empty() {
+        return None;
+    }
+    let outer_gap = state.outer_gap_for_display(display_id);
+    // Subtract outer gap from dimensions before sending to layout engine
+    let request = LayoutRequest {
+        layout: state.current_layout_for_display(display_id).to_string(),
+        width: display.frame.width.saturating_sub(outer_gap.horizontal()),
+        height: display.frame.height.saturating_sub(outer_gap.vertical()),
+        window_ids: visible_windows.iter().map(|w| w.id).collect(),
+    };
+    let retile = DisplayRetile {
+        display_id,
+        display_frame: display.frame,
+        outer_gap,
+    };
+    Some((retile, request))
+}
+
+/// Apply the layout, returning the number of windows laid out and how many of them moved
+fn apply_geometries<M: WindowManipulator>(
+    state: &RefCell<State>,
+    manipulator: &M,
+    retile: &DisplayRetile,
+    geometries: Vec<WindowGeometry>,
+) -> (usize, usize) {
+    let display_id = retile.display_id;
+    // Update window_order based on geometries order from layout engine
+    {
+        let mut state = state.borrow_mut();
+        if let Some(display) = state.displays.get_mut(&display_id) {
+            display.window_order = geometries.iter().map(|g| g.id).collect();
+        }
+    }
+    let windows = geometries.len();
+    // Add outer gap offset to geometries before applying
+    let mut adjusted_geometries: Vec<_> = geometries
+        .into_iter()
+        .map(|mut g| {
+            g.x += retile.outer_gap.left as i32;
+            g.y += retile.outer_gap.top as i32;
+            g
+        })
+        .collect();
+    {
+        let state = state.borrow();
+        state.compensate_corners(display_id, &mut adjusted_geometries);
+        state.park_hidden_geometries(display_id, &mut adjusted_geometries);
+        state.apply_raise_policy(display_id, &mut adjusted_geometries);
+        // Manual windows keep their slot in the layout, but not the geometry
+        adjusted_geometries.retain(|g| !state.windows.get(&g.id).is_some_and(|w| w.is_manual));
+        // Windows that fight resizing are only positioned
+        for g in adjusted_geometries.iter_mut() {
+            if let Some(w) = state.windows.get(&g.id) {
+                if state.no_manage_resize(g.id) {
+                    g.width = w.frame.width;
+                    g.height = w.frame.height;
+                }
+            }
         }
     }
+    let moved = {
+        let state = state.borrow();
+        let frame = retile.display_frame;
+        adjusted_geometries
+            .iter()
+            .filter(|g| {
+                state.windows.get(&g.id).is_some_and(|w| {
+                    w.frame
+                        != Rect {
+                            x: g.x + frame.x,
+                            y: g.y + frame.y,
+                            width: g.width,
+                            height: g.height,
+                        }
+                })
+            })
+            .count()
+    };
+    // Apply layout using manipulator
+    let failures =
+        manipulator.apply_layout(display_id, &retile.display_frame, &adjusted_geometries);
+    let mut state = state.borrow_mut();
+    for message in failures {
+        state.errors.record("apply_layout", message);
+    }
+    (windows, moved)
 }
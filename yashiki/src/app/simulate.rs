@@ -0,0 +1,531 @@
+use std::ptr;
+use std::sync::atomic::AtomicPtr;
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::core::{Rect, State, Tag, WindowMove};
+use crate::effect::Effect;
+use crate::event::Event;
+use crate::macos::{
+    Bounds, CommandParser, DisplayId, DisplayInfo, HotkeyManager, ScreenCorners, WindowInfo,
+};
+use crate::platform::mock::MockWindowSystem;
+use yashiki_ipc::{ExtendedWindowAttributes, FrameInfo, OutputInfo, StateDump, WindowDetails};
+
+use super::command::process_command;
+
+/// Displays, windows and a sequence of steps replayed by `yashiki simulate`
+#[derive(Debug, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub displays: Vec<ScenarioDisplay>,
+    #[serde(default)]
+    pub windows: Vec<ScenarioWindow>,
+    #[serde(default)]
+    pub focused: Option<u32>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    /// Start from a `yashiki state-dump` instead (replaces displays, windows and focused)
+    #[serde(default)]
+    pub state: Option<StateDump>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScenarioDisplay {
+    pub id: DisplayId,
+    #[serde(default)]
+    pub x: f64,
+    #[serde(default)]
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScenarioWindow {
+    pub id: u32,
+    pub pid: i32,
+    pub app: String,
+    #[serde(default)]
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub x: f64,
+    #[serde(default)]
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Step {
+    OpenWindow(ScenarioWindow),
+    CloseWindow {
+        id: u32,
+    },
+    Focus {
+        id: u32,
+    },
+    ConnectDisplay(ScenarioDisplay),
+    DisconnectDisplay {
+        id: DisplayId,
+    },
+    /// CLI arguments, e.g. `["tag-view", "2"]`
+    Command {
+        args: Vec<String>,
+    },
+}
+
+impl ScenarioDisplay {
+    fn to_info(&self) -> DisplayInfo {
+        DisplayInfo {
+            id: self.id,
+            name: format!("Display {}", self.id),
+            frame: Bounds {
+                x: self.x,
+                y: self.y,
+                width: self.width,
+                height: self.height,
+            },
+            is_main: self.x == 0.0 && self.y == 0.0,
+            scale_factor: 1.0,
+            rotation: 0,
+            refresh_rate: 60.0,
+            uuid: None,
+            obstructed_corners: ScreenCorners::default(),
+        }
+    }
+}
+
+impl ScenarioWindow {
+    fn to_info(&self) -> WindowInfo {
+        WindowInfo {
+            pid: self.pid,
+            window_id: self.id,
+            name: self.title.clone(),
+            owner_name: self.app.clone(),
+            bundle_id: self.app_id.clone(),
+            bounds: Bounds {
+                x: self.x,
+                y: self.y,
+                width: self.width,
+                height: self.height,
+            },
+            layer: 0,
+        }
+    }
+}
+
+/// Replay a scenario through State and command processing against a mock window system.
+/// Returns a human-readable log of the moves and effects produced by each step.
+/// Effects are reported, not executed: retiles are listed without running layout engines.
+pub fn run_scenario(scenario: &Scenario, parser: CommandParser) -> Vec<String> {
+    let mut ws = match &scenario.state {
+        Some(dump) => dump_window_system(dump),
+        None => MockWindowSystem::new()
+            .with_displays(scenario.displays.iter().map(|d| d.to_info()).collect())
+            .with_windows(scenario.windows.iter().map(|w| w.to_info()).collect())
+            .with_focused(scenario.focused),
+    };
+    let pids: Vec<i32> = ws.windows.iter().map(|w| w.pid).collect();
+    ws.ax_accessible_pids.extend(pids);
+
+    let (hotkey_tx, _hotkey_rx) = std_mpsc::channel();
+    let mut hotkey_manager =
+        HotkeyManager::new(hotkey_tx, Arc::new(AtomicPtr::new(ptr::null_mut())));
+    let mut state = State::new();
+    let mut log = Vec::new();
+
+    let (moves, new_window_ids) = state.sync_all(&ws);
+    log.push(format!(
+        "[0] initial: {} windows, {} displays",
+        state.windows.len(),
+        state.displays.len()
+    ));
+    match &scenario.state {
+        // The dump already reflects the rules applied to its windows
+        Some(dump) => {
+            import_state_dump(&mut state, dump);
+            log.push(format!(
+                "  imported state dump ({} rules)",
+                dump.rules.len()
+            ));
+        }
+        None => {
+            log_moves(&mut log, &moves);
+            log_new_windows(&mut log, &mut state, new_window_ids);
+        }
+    }
+
+    for (i, step) in scenario.steps.iter().enumerate() {
+        let n = i + 1;
+        match step {
+            Step::OpenWindow(window) => {
+                log.push(format!(
+                    "[{}] open window {} ({})",
+                    n, window.id, window.app
+                ));
+                ws.ax_accessible_pids.insert(window.pid);
+                ws.add_window(window.to_info());
+                let event = Event::WindowCreated { pid: window.pid };
+                let (_, new_window_ids, moves) = state.handle_event(&ws, &event);
+                log_moves(&mut log, &moves);
+                log_new_windows(&mut log, &mut state, new_window_ids);
+            }
+            Step::CloseWindow { id } => {
+                log.push(format!("[{}] close window {}", n, id));
+                let Some(pid) = ws
+                    .windows
+                    .iter()
+                    .find(|w| w.window_id == *id)
+                    .map(|w| w.pid)
+                else {
+                    log.push(format!("  unknown window {}", id));
+                    continue;
+                };
+                ws.remove_window(*id);
+                if ws.focused_window_id == Some(*id) {
+                    ws.focused_window_id = None;
+                }
+                let (_, _, moves) = state.handle_event(&ws, &Event::WindowDestroyed { pid });
+                log_moves(&mut log, &moves);
+            }
+            Step::Focus { id } => {
+                log.push(format!("[{}] focus window {}", n, id));
+                ws.focused_window_id = Some(*id);
+                let (_, new_window_ids, _) = state.handle_event(&ws, &Event::FocusedWindowChanged);
+                log_new_windows(&mut log, &mut state, new_window_ids);
+            }
+            Step::ConnectDisplay(display) => {
+                log.push(format!("[{}] connect display {}", n, display.id));
+                ws.displays.push(display.to_info());
+                log_display_change(&mut log, &mut state, &ws);
+            }
+            Step::DisconnectDisplay { id } => {
+                log.push(format!("[{}] disconnect display {}", n, id));
+                ws.displays.retain(|d| d.id != *id);
+                log_display_change(&mut log, &mut state, &ws);
+            }
+            Step::Command { args } => {
+                let cmd = match parser(args) {
+                    Ok(cmd) => cmd,
+                    Err(e) => {
+                        log.push(format!("[{}] command {}: {}", n, args.join(" "), e));
+                        continue;
+                    }
+                };
+                let result = process_command(&mut state, &mut hotkey_manager, &cmd);
+                log.push(format!(
+                    "[{}] command {}: {}",
+                    n,
+                    args.join(" "),
+                    serde_json::to_string(&result.response).unwrap_or_default()
+                ));
+                log_effects(&mut log, &result.effects);
+            }
+        }
+    }
+
+    log.push("final:".to_string());
+    let mut windows: Vec<_> = state.windows.values().collect();
+    windows.sort_by_key(|w| w.id);
+    for w in windows {
+        log.push(format!(
+            "  window {} ({}) display={} tags={} frame=({}, {}, {}x{}){}{}",
+            w.id,
+            w.app_name,
+            w.display_id,
+            w.tags.mask(),
+            w.frame.x,
+            w.frame.y,
+            w.frame.width,
+            w.frame.height,
+            if w.is_hidden() { " hidden" } else { "" },
+            if state.focused == Some(w.id) {
+                " focused"
+            } else {
+                ""
+            }
+        ));
+    }
+    log
+}
+
+/// Mock window system with the displays and windows of a state dump, keeping their IDs
+fn dump_window_system(dump: &StateDump) -> MockWindowSystem {
+    let bounds = |f: &FrameInfo| Bounds {
+        x: f.x as f64,
+        y: f.y as f64,
+        width: f.width as f64,
+        height: f.height as f64,
+    };
+    let display_info = |o: &OutputInfo| DisplayInfo {
+        id: o.id,
+        name: o.name.clone(),
+        frame: bounds(&FrameInfo {
+            x: o.x,
+            y: o.y,
+            width: o.width,
+            height: o.height,
+        }),
+        is_main: o.is_main,
+        scale_factor: o.scale_factor,
+        rotation: o.rotation,
+        refresh_rate: o.refresh_rate,
+        uuid: o.uuid.clone(),
+        obstructed_corners: ScreenCorners::default(),
+    };
+    let window_info = |w: &WindowDetails| WindowInfo {
+        pid: w.pid,
+        window_id: w.id,
+        name: Some(w.title.clone()),
+        owner_name: w.app_name.clone(),
+        bundle_id: w.app_id.clone(),
+        bounds: bounds(&w.frame),
+        layer: w.window_level,
+    };
+
+    let mut ws = MockWindowSystem::new()
+        .with_displays(dump.outputs.iter().map(display_info).collect())
+        .with_windows(dump.windows.iter().map(window_info).collect())
+        .with_focused(dump.focused_window_id);
+    for w in &dump.windows {
+        ws.set_extended_attributes(
+            w.id,
+            ExtendedWindowAttributes {
+                ax_id: w.ax_id.clone(),
+                subrole: w.subrole.clone(),
+                title: Some(w.title.clone()),
+                window_level: w.window_level,
+                close_button: w.close_button.clone(),
+                fullscreen_button: w.fullscreen_button.clone(),
+                minimize_button: w.minimize_button.clone(),
+                zoom_button: w.zoom_button.clone(),
+                ..Default::default()
+            },
+        );
+    }
+    ws
+}
+
+/// Restore tags, displays, orders, layouts, rules and gaps of a state dump after the initial sync
+fn import_state_dump(state: &mut State, dump: &StateDump) {
+    let rect = |f: &FrameInfo| Rect {
+        x: f.x,
+        y: f.y,
+        width: f.width,
+        height: f.height,
+    };
+    for w in &dump.windows {
+        let Some(window) = state.windows.get_mut(&w.id) else {
+            continue;
+        };
+        window.tags = Tag::from_mask(w.tags);
+        window.display_id = w.output_id;
+        window.frame = rect(&w.frame);
+        window.saved_frame = w.saved_frame.as_ref().map(rect);
+        window.is_floating = w.is_floating;
+        window.is_fullscreen = w.is_fullscreen;
+        window.is_native_fullscreen = w.is_native_fullscreen;
+        window.is_manual = w.is_manual;
+        window.orphaned_from = w.orphaned_from;
+    }
+    for output in &dump.outputs {
+        let windows = &state.windows;
+        let Some(display) = state.displays.get_mut(&output.id) else {
+            continue;
+        };
+        display.visible_tags = Tag::from_mask(output.visible_tags);
+        display.window_order = output
+            .window_order
+            .iter()
+            .copied()
+            .filter(|id| windows.contains_key(id))
+            .collect();
+        display.current_layout = dump
+            .output_layouts
+            .iter()
+            .find(|(id, _)| *id == output.id)
+            .map(|(_, layout)| layout.clone());
+    }
+
+    state.focused = dump
+        .focused_window_id
+        .filter(|id| state.windows.contains_key(id));
+    if state.displays.contains_key(&dump.focused_output_id) {
+        state.focused_display = dump.focused_output_id;
+    }
+    state.default_layout = dump.default_layout.clone();
+    state.float_tags = Tag::from_mask(dump.float_tags);
+    state.stash = dump.stash.clone();
+    for rule in &dump.rules {
+        state.add_rule(rule.clone());
+    }
+    state.config.outer_gap = dump.outer_gap;
+}
+
+fn log_moves(log: &mut Vec<String>, moves: &[WindowMove]) {
+    for m in moves {
+        log.push(format!(
+            "  move {} ({}, {}) -> ({}, {})",
+            m.window_id, m.old_x, m.old_y, m.new_x, m.new_y
+        ));
+    }
+}
+
+fn log_effects(log: &mut Vec<String>, effects: &[Effect]) {
+    for effect in effects {
+        match effect {
+            Effect::ApplyWindowMoves(moves) => log_moves(log, moves),
+            other => log.push(format!("  {:?}", other)),
+        }
+    }
+}
+
+fn log_new_windows(log: &mut Vec<String>, state: &mut State, new_window_ids: Vec<u32>) {
+    for window_id in new_window_ids {
+        log.push(format!("  new window {}", window_id));
+        let effects = state.apply_rules_to_new_window(window_id);
+        log_effects(log, &effects);
+    }
+}
+
+fn log_display_change(log: &mut Vec<String>, state: &mut State, ws: &MockWindowSystem) {
+    let result = state.handle_display_change(ws);
+    log_moves(log, &result.window_moves);
+    if !result.displays_to_retile.is_empty() {
+        log.push(format!("  retile displays {:?}", result.displays_to_retile));
+    }
+    log_new_windows(log, state, result.new_window_ids);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yashiki_ipc::Command;
+
+    fn parse_json(args: &[String]) -> anyhow::Result<Command> {
+        Ok(serde_json::from_str(&args.join(" "))?)
+    }
+
+    fn scenario(json: &str) -> Scenario {
+        serde_json::from_str(json).unwrap()
+    }
+
+    const BASE: &str = r#"
+        "displays": [{"id": 1, "width": 1920, "height": 1080}],
+        "windows": [
+            {"id": 100, "pid": 1000, "app": "Safari", "width": 960, "height": 1080},
+            {"id": 101, "pid": 1001, "app": "Terminal", "x": 960, "width": 960, "height": 1080}
+        ],
+        "focused": 100
+    "#;
+
+    #[test]
+    fn test_run_scenario_tag_view_hides_windows() {
+        let s = scenario(&format!(
+            r#"{{{}, "steps": [{{"type": "command", "args": ["{{\"type\":\"tag_view\",\"tags\":2}}"]}}]}}"#,
+            BASE
+        ));
+        let log = run_scenario(&s, parse_json);
+
+        assert_eq!(log[0], "[0] initial: 2 windows, 1 displays");
+        assert!(log[1].starts_with("[1] command"));
+        assert!(log.iter().any(|l| l.starts_with("  move 100 (0, 0) -> ")));
+        assert!(log.iter().any(|l| l.starts_with("  move 101 (960, 0) -> ")));
+        assert!(log
+            .iter()
+            .any(|l| l.starts_with("  window 100 (Safari)") && l.contains(" hidden")));
+    }
+
+    #[test]
+    fn test_run_scenario_open_close_and_focus() {
+        let s = scenario(&format!(
+            r#"{{{}, "steps": [
+                {{"type": "open_window", "id": 102, "pid": 1002, "app": "Notes", "width": 400, "height": 300}},
+                {{"type": "focus", "id": 102}},
+                {{"type": "close_window", "id": 100}}
+            ]}}"#,
+            BASE
+        ));
+        let log = run_scenario(&s, parse_json);
+
+        assert!(log.contains(&"[1] open window 102 (Notes)".to_string()));
+        assert!(log.contains(&"  new window 102".to_string()));
+        assert!(log.contains(&"[3] close window 100".to_string()));
+        assert!(!log.iter().any(|l| l.starts_with("  window 100 ")));
+        assert!(log
+            .iter()
+            .any(|l| l.starts_with("  window 102 (Notes)") && l.ends_with(" focused")));
+    }
+
+    #[test]
+    fn test_run_scenario_from_state_dump() {
+        use super::super::command::state_dump;
+        use crate::platform::mock::{create_test_display, create_test_window};
+        use yashiki_ipc::{GlobPattern, RuleAction, RuleMatcher, WindowRule};
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0),
+                create_test_window(101, 1001, "Terminal", 960.0, 0.0, 960.0, 1080.0),
+            ])
+            .with_focused(Some(100));
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.windows.get_mut(&101).unwrap().tags = Tag::from_mask(0b10);
+        state.compute_layout_changes(1);
+        state.windows.get_mut(&100).unwrap().is_floating = true;
+        state.add_rule(WindowRule {
+            matcher: RuleMatcher::new(Some(GlobPattern::new("Notes")), None),
+            action: RuleAction::Float,
+            dynamic: false,
+        });
+        let dump = state_dump(&state);
+
+        // The dump survives a round trip through JSON
+        let json = serde_json::to_string(&dump).unwrap();
+        let s = Scenario {
+            state: Some(serde_json::from_str(&json).unwrap()),
+            ..scenario("{}")
+        };
+        let log = run_scenario(&s, parse_json);
+
+        assert_eq!(log[0], "[0] initial: 2 windows, 1 displays");
+        assert_eq!(log[1], "  imported state dump (1 rules)");
+        assert!(log.iter().any(
+            |l| l.starts_with("  window 101 (Terminal) display=1 tags=2 ") && l.contains(" hidden")
+        ));
+        assert!(log
+            .iter()
+            .any(|l| l.starts_with("  window 100 (Safari) display=1 tags=1 ")));
+    }
+
+    #[test]
+    fn test_run_scenario_disconnect_display() {
+        let s = scenario(
+            r#"{
+                "displays": [
+                    {"id": 1, "width": 1920, "height": 1080},
+                    {"id": 2, "x": 1920, "width": 1920, "height": 1080}
+                ],
+                "windows": [
+                    {"id": 100, "pid": 1000, "app": "Safari", "width": 960, "height": 1080},
+                    {"id": 101, "pid": 1001, "app": "Terminal", "x": 1920, "width": 960, "height": 1080}
+                ],
+                "steps": [{"type": "disconnect_display", "id": 2}]
+            }"#,
+        );
+        let log = run_scenario(&s, parse_json);
+
+        assert!(log.contains(&"[1] disconnect display 2".to_string()));
+        assert!(log
+            .iter()
+            .any(|l| l.starts_with("  window 101 (Terminal) display=1 ")));
+    }
+}
@@ -3,6 +3,9 @@ use std::collections::HashMap;
 
 use crate::core::State;
 use crate::event_emitter::EventEmitter;
+use crate::platform::WindowManipulator;
+
+use super::command::exec_env;
 
 /// Window properties tracked for change detection
 #[derive(Clone, PartialEq)]
@@ -11,12 +14,22 @@ pub struct WindowProperties {
     display_id: u32,
     is_floating: bool,
     is_fullscreen: bool,
+    is_native_fullscreen: bool,
+    /// Not expected to change; kept for events about removed windows
+    app_id: Option<String>,
+}
+
+/// Display properties tracked for change detection
+pub struct DisplayProperties {
+    visible_tags: u32,
+    current_layout: Option<String>,
+    window_order: Vec<u32>,
 }
 
 /// State captured before command execution for event comparison
 pub struct PreEventState {
-    /// Map of display_id to (visible_tags, current_layout)
-    pub displays: HashMap<u32, (u32, Option<String>)>,
+    /// Map of display_id to tracked properties
+    pub displays: HashMap<u32, DisplayProperties>,
     /// Map of window_id to tracked properties
     pub windows: HashMap<u32, WindowProperties>,
     pub focused: Option<u32>,
@@ -29,7 +42,16 @@ pub fn capture_event_state(state: &RefCell<State>) -> PreEventState {
     let displays = state
         .displays
         .iter()
-        .map(|(id, d)| (*id, (d.visible_tags.mask(), d.current_layout.clone())))
+        .map(|(id, d)| {
+            (
+                *id,
+                DisplayProperties {
+                    visible_tags: d.visible_tags.mask(),
+                    current_layout: d.current_layout.clone(),
+                    window_order: d.window_order.clone(),
+                },
+            )
+        })
         .collect();
 
     let windows = state
@@ -43,6 +65,8 @@ pub fn capture_event_state(state: &RefCell<State>) -> PreEventState {
                     display_id: w.display_id,
                     is_floating: w.is_floating,
                     is_fullscreen: w.is_fullscreen,
+                    is_native_fullscreen: w.is_native_fullscreen,
+                    app_id: w.app_id.clone(),
                 },
             )
         })
@@ -66,7 +90,7 @@ pub fn emit_state_change_events(
 
     // Check for focus changes
     if state.focused != pre.focused {
-        event_emitter.emit_window_focused(state.focused);
+        event_emitter.emit_window_focused(state.focused, &state);
     }
 
     // Check for display focus changes
@@ -74,29 +98,42 @@ pub fn emit_state_change_events(
         event_emitter.emit_display_focused(state.focused_display);
     }
 
-    // Check for tag and layout changes on each display
+    // Check for tag, layout and layout order changes on each display
     for (display_id, display) in &state.displays {
-        if let Some((pre_tags, pre_layout)) = pre.displays.get(display_id) {
+        if let Some(pre_display) = pre.displays.get(display_id) {
             let current_tags = display.visible_tags.mask();
 
             // Emit tags changed event
-            if current_tags != *pre_tags {
-                event_emitter.emit_tags_changed(*display_id, current_tags, *pre_tags);
+            if current_tags != pre_display.visible_tags {
+                event_emitter.emit_tags_changed(
+                    *display_id,
+                    current_tags,
+                    pre_display.visible_tags,
+                );
             }
 
             // Emit layout changed event
-            if display.current_layout != *pre_layout {
+            if display.current_layout != pre_display.current_layout {
                 if let Some(ref layout) = display.current_layout {
                     event_emitter.emit_layout_changed(*display_id, layout);
                 }
             }
+
+            // Emit layout order changed event
+            if display.window_order != pre_display.window_order {
+                event_emitter.emit_layout_order_changed(*display_id, &display.window_order);
+            }
         }
     }
 
     // Check for removed windows
-    for window_id in pre.windows.keys() {
+    for (window_id, pre_props) in &pre.windows {
         if !state.windows.contains_key(window_id) {
-            event_emitter.emit_window_destroyed(*window_id);
+            event_emitter.emit_window_destroyed(
+                *window_id,
+                pre_props.display_id,
+                pre_props.app_id.as_deref(),
+            );
         }
     }
 
@@ -108,12 +145,77 @@ pub fn emit_state_change_events(
                 display_id: window.display_id,
                 is_floating: window.is_floating,
                 is_fullscreen: window.is_fullscreen,
+                is_native_fullscreen: window.is_native_fullscreen,
+                app_id: window.app_id.clone(),
             };
 
             // Emit window updated event if any tracked property changed
             if current_props != *pre_props {
                 event_emitter.emit_window_updated(window, state.focused);
             }
+
+            if current_props.tags != pre_props.tags {
+                event_emitter.emit_window_tags_changed(window, pre_props.tags);
+            }
+        }
+    }
+}
+
+/// Select the input source of the focused display's tags when they changed
+pub fn switch_tag_input_source<M: WindowManipulator>(
+    state: &RefCell<State>,
+    pre: &PreEventState,
+    manipulator: &M,
+) {
+    let state = state.borrow();
+    let Some(display) = state.displays.get(&state.focused_display) else {
+        return;
+    };
+    let unchanged = pre
+        .displays
+        .get(&display.id)
+        .is_some_and(|d| d.visible_tags == display.visible_tags.mask());
+    if unchanged {
+        return;
+    }
+    let Some(input_source) = state.input_source_for_tags(display.visible_tags) else {
+        return;
+    };
+    if let Err(e) = manipulator.select_input_source(input_source) {
+        tracing::warn!("{}", e);
+    }
+}
+
+/// Run the tag change hook once for each display whose visible tags changed
+pub fn run_tag_change_hook<M: WindowManipulator>(
+    state: &RefCell<State>,
+    pre: &PreEventState,
+    manipulator: &M,
+) {
+    let state = state.borrow();
+    let Some(hook) = &state.config.tag_change_hook else {
+        return;
+    };
+
+    for (display_id, display) in &state.displays {
+        let Some(pre_display) = pre.displays.get(display_id) else {
+            continue;
+        };
+        let tags = display.visible_tags.mask();
+        if tags == pre_display.visible_tags {
+            continue;
+        }
+
+        let mut env = exec_env(&state);
+        env.push(("YASHIKI_OUTPUT".to_string(), display_id.to_string()));
+        env.push(("YASHIKI_OUTPUT_NAME".to_string(), display.name.clone()));
+        env.push(("YASHIKI_TAGS".to_string(), tags.to_string()));
+        env.push((
+            "YASHIKI_PREVIOUS_TAGS".to_string(),
+            pre_display.visible_tags.to_string(),
+        ));
+        if let Err(e) = manipulator.exec_command(hook, None, &state.config.exec_path, &env) {
+            tracing::warn!("Tag change hook failed: {}", e);
         }
     }
 }
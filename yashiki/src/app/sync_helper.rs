@@ -1,6 +1,9 @@
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::time::{Duration, Instant};
 
 use crate::core::{State, WindowId};
+use crate::event::Event;
 use crate::event_emitter::EventEmitter;
 use crate::layout::LayoutEngineManager;
 use crate::macos::{DisplayId, ObserverManager};
@@ -8,6 +11,9 @@ use crate::platform::{WindowManipulator, WindowSystem};
 
 use super::effects::execute_effects;
 
+/// How long an observer must have been silent before a window mismatch marks it stale
+pub const OBSERVER_STALE_AFTER: Duration = Duration::from_secs(30);
+
 /// Result of a sync operation
 pub struct SyncResult {
     /// Whether the state changed and retiling may be needed
@@ -35,7 +41,9 @@ pub fn sync_and_process_new_windows<W: WindowSystem, M: WindowManipulator>(
         }
     }
 
+    let native_fullscreen = native_fullscreen_ids(&state.borrow());
     let (changed, new_window_ids, rehide_moves) = state.borrow_mut().sync_pid(window_system, pid);
+    emit_native_fullscreen_changes(state, event_emitter, &native_fullscreen);
 
     // Re-hide windows that macOS moved from hide position
     if !rehide_moves.is_empty() {
@@ -54,6 +62,30 @@ pub fn sync_and_process_new_windows<W: WindowSystem, M: WindowManipulator>(
     SyncResult { changed }
 }
 
+/// Windows currently in native fullscreen
+fn native_fullscreen_ids(state: &State) -> HashSet<WindowId> {
+    state
+        .windows
+        .values()
+        .filter(|w| w.is_native_fullscreen)
+        .map(|w| w.id)
+        .collect()
+}
+
+/// Emit window updated events for windows that entered or left native fullscreen during a sync
+fn emit_native_fullscreen_changes(
+    state: &RefCell<State>,
+    event_emitter: &EventEmitter,
+    before: &HashSet<WindowId>,
+) {
+    let state = state.borrow();
+    for window in state.windows.values() {
+        if window.is_native_fullscreen != before.contains(&window.id) {
+            event_emitter.emit_window_updated(window, state.focused);
+        }
+    }
+}
+
 /// Process newly discovered windows: apply rules and emit events.
 pub fn process_new_windows<M: WindowManipulator>(
     new_window_ids: Vec<WindowId>,
@@ -63,9 +95,15 @@ pub fn process_new_windows<M: WindowManipulator>(
     event_emitter: &EventEmitter,
 ) {
     for window_id in new_window_ids {
-        let effects = state.borrow_mut().apply_rules_to_new_window(window_id);
-        if !effects.is_empty() {
-            let _ = execute_effects(effects, state, layout_engine_manager, manipulator);
+        let paused = state.borrow().paused;
+        if paused {
+            // Rules may move or hide the window, so defer them until resume
+            state.borrow_mut().deferred_new_windows.push(window_id);
+        } else {
+            let effects = state.borrow_mut().apply_rules_to_new_window(window_id);
+            if !effects.is_empty() {
+                let _ = execute_effects(effects, state, layout_engine_manager, manipulator);
+            }
         }
 
         // Emit window created event
@@ -144,9 +182,11 @@ pub fn sync_display_and_process_new_windows<W: WindowSystem, M: WindowManipulato
         }
     }
 
+    let native_fullscreen = native_fullscreen_ids(&state.borrow());
     let (changed, new_window_ids, rehide_moves) = state
         .borrow_mut()
         .sync_windows_for_display(window_system, display_id);
+    emit_native_fullscreen_changes(state, event_emitter, &native_fullscreen);
 
     if !rehide_moves.is_empty() {
         manipulator.apply_window_moves(&rehide_moves);
@@ -163,6 +203,133 @@ pub fn sync_display_and_process_new_windows<W: WindowSystem, M: WindowManipulato
     SyncResult { changed }
 }
 
+/// Coalesce a batch of observer events. Move/resize events only trigger a sync of their pid,
+/// so repeated ones for the same pid are dropped, and so are repeated title changes. While
+/// `dragging`, move/resize events are deferred into `deferred_pids` instead; once the drag
+/// is over, deferred pids are synced once.
+pub fn coalesce_observer_events(
+    events: Vec<Event>,
+    dragging: bool,
+    deferred_pids: &mut HashSet<i32>,
+) -> Vec<Event> {
+    let mut synced_pids = HashSet::new();
+    let mut retitled_pids = HashSet::new();
+    let mut coalesced: Vec<Event> = events
+        .into_iter()
+        .filter(|event| match event {
+            Event::WindowMoved { pid } | Event::WindowResized { pid } => {
+                if dragging {
+                    deferred_pids.insert(*pid);
+                    false
+                } else {
+                    synced_pids.insert(*pid)
+                }
+            }
+            Event::WindowTitleChanged { pid } => retitled_pids.insert(*pid),
+            _ => true,
+        })
+        .collect();
+
+    if !dragging {
+        coalesced.extend(
+            deferred_pids
+                .drain()
+                .filter(|pid| !synced_pids.contains(pid))
+                .map(|pid| Event::WindowMoved { pid }),
+        );
+    }
+    coalesced
+}
+
+/// Hold back move/resize events of apps with a throttle rule that were synced less than
+/// their interval ago. Held-back pids are recorded in `throttle_pending` with their due time
+/// and handed out by `take_due_throttled_pids`.
+pub fn throttle_observer_events(events: Vec<Event>, state: &mut State, now: Instant) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            let (Event::WindowMoved { pid } | Event::WindowResized { pid }) = event else {
+                return true;
+            };
+            let Some(interval) = state.sync_throttle(*pid) else {
+                return true;
+            };
+            if state.throttle_pending.contains_key(pid) {
+                return false;
+            }
+            match state.throttle_last_sync.get(pid) {
+                Some(&last) if now.duration_since(last) < interval => {
+                    state.throttle_pending.insert(*pid, last + interval);
+                    false
+                }
+                _ => {
+                    state.throttle_last_sync.insert(*pid, now);
+                    true
+                }
+            }
+        })
+        .collect()
+}
+
+/// Apps (pid and name) whose tracked windows disagree with the window server while their
+/// observer has been silent for `OBSERVER_STALE_AFTER`. A live observer would have reported
+/// the created or destroyed window, so these observers are considered dead.
+pub fn stale_observer_pids<W: WindowSystem>(
+    state: &State,
+    ws: &W,
+    last_event: impl Fn(i32) -> Option<Instant>,
+    now: Instant,
+) -> Vec<(i32, String)> {
+    let is_quiet =
+        |pid: i32| last_event(pid).is_some_and(|at| now.duration_since(at) >= OBSERVER_STALE_AFTER);
+    let infos = ws.get_on_screen_windows();
+    let on_screen: HashSet<WindowId> = infos.iter().map(|w| w.window_id).collect();
+    let mut stale = BTreeMap::new();
+
+    // Windows that appeared without a created notification
+    for info in infos.iter().filter(|w| w.layer == 0) {
+        if !state.windows.contains_key(&info.window_id)
+            && !state.ignored_windows.contains_key(&info.window_id)
+            && !state.is_on_ignored_output(&info.bounds)
+            && is_quiet(info.pid)
+        {
+            stale
+                .entry(info.pid)
+                .or_insert_with(|| info.owner_name.clone());
+        }
+    }
+
+    // Windows that closed without a destroyed notification
+    for window in state.windows.values() {
+        if on_screen.contains(&window.id)
+            || stale.contains_key(&window.pid)
+            || state.is_app_hidden(window.pid)
+            || !is_quiet(window.pid)
+            || ws.window_exists_in_ax(window.id, window.pid)
+        {
+            continue;
+        }
+        stale.insert(window.pid, window.app_name.clone());
+    }
+
+    stale.into_iter().collect()
+}
+
+/// Take the throttled pids whose interval has passed, recording them as synced now
+pub fn take_due_throttled_pids(state: &mut State, now: Instant) -> Vec<i32> {
+    let due: Vec<i32> = state
+        .throttle_pending
+        .iter()
+        .filter(|(_, &due)| due <= now)
+        .map(|(&pid, _)| pid)
+        .collect();
+    for pid in &due {
+        state.throttle_pending.remove(pid);
+        state.throttle_last_sync.insert(*pid, now);
+    }
+    due
+}
+
 #[cfg(test)]
 mod tests {
     use std::ptr;
@@ -171,7 +338,6 @@ mod tests {
     use std::sync::Arc;
 
     use super::*;
-    use crate::event::Event;
     use crate::event_emitter::EventEmitter;
     use crate::platform::mock::{
         create_test_display, create_test_window, MockWindowManipulator, MockWindowSystem,
@@ -258,4 +424,145 @@ mod tests {
         assert!(result.changed);
         assert_eq!(state.borrow().windows.len(), 1);
     }
+
+    #[test]
+    fn test_coalesce_observer_events_drops_repeated_moves() {
+        let mut deferred = HashSet::new();
+        let events = vec![
+            Event::WindowMoved { pid: 1 },
+            Event::WindowMoved { pid: 1 },
+            Event::WindowResized { pid: 1 },
+            Event::WindowMoved { pid: 2 },
+            Event::WindowTitleChanged { pid: 1 },
+            Event::WindowTitleChanged { pid: 1 },
+            Event::FocusedWindowChanged,
+        ];
+
+        let coalesced = coalesce_observer_events(events, false, &mut deferred);
+
+        assert_eq!(coalesced.len(), 4);
+        assert!(matches!(coalesced[0], Event::WindowMoved { pid: 1 }));
+        assert!(matches!(coalesced[1], Event::WindowMoved { pid: 2 }));
+        assert!(matches!(coalesced[2], Event::WindowTitleChanged { pid: 1 }));
+        assert!(matches!(coalesced[3], Event::FocusedWindowChanged));
+    }
+
+    #[test]
+    fn test_coalesce_observer_events_defers_moves_while_dragging() {
+        let mut deferred = HashSet::new();
+        let events = vec![
+            Event::WindowMoved { pid: 1 },
+            Event::WindowCreated { pid: 2 },
+            Event::WindowMoved { pid: 1 },
+        ];
+
+        let coalesced = coalesce_observer_events(events, true, &mut deferred);
+
+        assert_eq!(coalesced.len(), 1);
+        assert!(matches!(coalesced[0], Event::WindowCreated { pid: 2 }));
+        assert_eq!(deferred, HashSet::from([1]));
+
+        // Drag ended: the deferred pid is synced once
+        let coalesced = coalesce_observer_events(vec![], false, &mut deferred);
+        assert_eq!(coalesced.len(), 1);
+        assert!(matches!(coalesced[0], Event::WindowMoved { pid: 1 }));
+        assert!(deferred.is_empty());
+    }
+
+    #[test]
+    fn test_stale_observer_pids_needs_mismatch_and_silence() {
+        let mut ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0),
+                create_test_window(101, 1001, "Terminal", 960.0, 0.0, 960.0, 1080.0),
+            ]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let now = Instant::now();
+        let silent = |_| Some(now - OBSERVER_STALE_AFTER);
+        assert!(stale_observer_pids(&state, &ws, silent, now).is_empty());
+
+        // A window appeared and another closed without notifications
+        ws.add_window(create_test_window(
+            102, 1000, "Safari", 0.0, 0.0, 400.0, 300.0,
+        ));
+        ws.remove_window(101);
+        assert_eq!(
+            stale_observer_pids(&state, &ws, silent, now),
+            vec![(1000, "Safari".to_string()), (1001, "Terminal".to_string())]
+        );
+
+        // Recently active or unobserved apps are not stale
+        assert!(stale_observer_pids(&state, &ws, |_| Some(now), now).is_empty());
+        assert!(stale_observer_pids(&state, &ws, |_| None, now).is_empty());
+    }
+
+    #[test]
+    fn test_stale_observer_pids_skips_ignored_outputs() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 0.0, 960.0, 1080.0),
+            ]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_output_ignored(2, true);
+        state.sync_all(&ws);
+
+        // Unmanaged windows on an ignored display don't make their observer look dead
+        let now = Instant::now();
+        let silent = |_| Some(now - OBSERVER_STALE_AFTER);
+        assert!(!state.windows.contains_key(&101));
+        assert!(stale_observer_pids(&state, &ws, silent, now).is_empty());
+    }
+
+    #[test]
+    fn test_throttle_observer_events_holds_back_noisy_apps() {
+        use std::time::Duration;
+        use yashiki_ipc::{GlobPattern, RuleAction, RuleMatcher, WindowRule};
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Teams", 0.0, 0.0, 960.0, 1080.0),
+                create_test_window(101, 1001, "Safari", 960.0, 0.0, 960.0, 1080.0),
+            ]);
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Teams")), None),
+            RuleAction::Throttle { ms: 500 },
+        ));
+        state.sync_all(&ws);
+
+        let start = Instant::now();
+        let events = vec![
+            Event::WindowMoved { pid: 1000 },
+            Event::WindowMoved { pid: 1001 },
+        ];
+
+        // First event of a throttled app passes through
+        let passed = throttle_observer_events(events.clone(), &mut state, start);
+        assert_eq!(passed.len(), 2);
+
+        // Within the interval it is held back; unthrottled apps are unaffected
+        let later = start + Duration::from_millis(100);
+        let passed = throttle_observer_events(events, &mut state, later);
+        assert_eq!(passed.len(), 1);
+        assert!(matches!(passed[0], Event::WindowMoved { pid: 1001 }));
+        assert_eq!(
+            state.throttle_pending.get(&1000),
+            Some(&(start + Duration::from_millis(500)))
+        );
+
+        assert!(take_due_throttled_pids(&mut state, later).is_empty());
+        let due = start + Duration::from_millis(500);
+        assert_eq!(take_due_throttled_pids(&mut state, due), vec![1000]);
+        assert!(state.throttle_pending.is_empty());
+    }
 }
@@ -0,0 +1,173 @@
+use std::io::BufRead;
+
+use anyhow::{bail, Result};
+
+use crate::ipc::IpcClient;
+use yashiki_ipc::{Command, Response};
+
+/// Run newline-delimited CLI commands (`yashiki -`) over a single daemon connection.
+/// Blank lines and `#` comments are skipped, and a leading `yashiki` word is optional so
+/// existing init scripts can be piped in. Returns whether every command succeeded.
+pub fn run_batch(input: impl BufRead, parse: fn(&[String]) -> Result<Command>) -> Result<bool> {
+    let mut client = IpcClient::connect()?;
+    let mut total = 0;
+    let mut failed = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_no = index + 1;
+        let args = match batch_line_args(&line?) {
+            Ok(Some(args)) => args,
+            Ok(None) => continue,
+            Err(e) => {
+                total += 1;
+                failed += 1;
+                eprintln!("line {}: {}", line_no, e);
+                continue;
+            }
+        };
+        total += 1;
+
+        // `--wait` before a command waits for its animations, like the CLI flag
+        let wait = args.first().is_some_and(|word| word == "--wait");
+        let args = if wait { &args[1..] } else { &args[..] };
+
+        let cmd = match parse(args) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                failed += 1;
+                eprintln!("line {}: {}", line_no, e);
+                continue;
+            }
+        };
+        match client.send(&cmd)? {
+            Response::Ok => {}
+            Response::Error { message } => {
+                failed += 1;
+                eprintln!("line {}: {}", line_no, message);
+                continue;
+            }
+            response => println!("{}", serde_json::to_string(&response)?),
+        }
+        if wait {
+            client.send(&Command::Wait)?;
+        }
+    }
+
+    println!("{} commands, {} failed", total, failed);
+    Ok(failed == 0)
+}
+
+/// Words of a batch line, or None for blank lines and comments
+fn batch_line_args(line: &str) -> Result<Option<Vec<String>>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let mut args = split_words(line)?;
+    if args.first().is_some_and(|word| word == "yashiki") {
+        args.remove(0);
+    }
+    Ok((!args.is_empty()).then_some(args))
+}
+
+/// Split a line into words like a shell: whitespace separated, with single quotes,
+/// double quotes and backslash escapes
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated single quote"),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => bail!("Unterminated double quote"),
+                        },
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated double quote"),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words(r#"rule-add --title "Picture in Picture" float"#).unwrap(),
+            vec!["rule-add", "--title", "Picture in Picture", "float"]
+        );
+        assert_eq!(
+            split_words(r"exec 'open -a Safari' a\ b").unwrap(),
+            vec!["exec", "open -a Safari", "a b"]
+        );
+        assert_eq!(
+            split_words(r#"set-hook "" ''"#).unwrap(),
+            vec!["set-hook", "", ""]
+        );
+        assert_eq!(
+            split_words(r#"exec "say \"hi\" \n""#).unwrap(),
+            vec!["exec", r#"say "hi" \n"#]
+        );
+        assert!(split_words("exec 'open").is_err());
+    }
+
+    #[test]
+    fn test_batch_line_args() {
+        assert_eq!(batch_line_args("   ").unwrap(), None);
+        assert_eq!(batch_line_args("# comment").unwrap(), None);
+        assert_eq!(
+            batch_line_args("yashiki tag-view 2").unwrap(),
+            Some(vec!["tag-view".to_string(), "2".to_string()])
+        );
+        assert_eq!(
+            batch_line_args("window-zoom").unwrap(),
+            Some(vec!["window-zoom".to_string()])
+        );
+    }
+}
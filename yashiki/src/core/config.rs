@@ -1,4 +1,29 @@
-use yashiki_ipc::{AutoRaiseMode, CursorWarpMode, OuterGap};
+use std::collections::{HashMap, HashSet};
+
+use yashiki_ipc::{
+    AnimationEasing, AutoRaiseMode, CursorWarpMode, FloatVisibility, FocusFloatingMode,
+    GapScaleMode, GlobPattern, HookEvent, NewWindowTagPolicy, OrphanPolicy, OuterGap, RaisePolicy,
+    TagMode,
+};
+
+/// Bundle identifier of yashiki itself, whose windows are never managed
+pub const YASHIKI_BUNDLE_ID: &str = "dev.typester.yashiki";
+
+/// System UI and helper processes that never have windows worth managing
+const DEFAULT_EXCLUDED_APPS: &[&str] = &[
+    "com.apple.controlcenter",
+    "com.apple.notificationcenterui",
+    "com.apple.WebKit.*",
+    "com.apple.Safari.WebContent*",
+    "*.helper",
+];
+
+pub fn default_excluded_apps() -> Vec<GlobPattern> {
+    DEFAULT_EXCLUDED_APPS
+        .iter()
+        .map(|p| GlobPattern::new(*p))
+        .collect()
+}
 
 /// Application configuration settings.
 /// Grouped separately from window/display state for clarity.
@@ -8,12 +33,44 @@ pub struct Config {
     pub cursor_warp: CursorWarpMode,
     pub auto_raise_mode: AutoRaiseMode,
     pub auto_raise_delay_ms: u64,
+    pub new_window_tag: NewWindowTagPolicy,
+    pub orphan_policy: OrphanPolicy,
+    pub tag_mode: TagMode,
+    pub animation_duration_ms: u32,
+    pub animation_easing: AnimationEasing,
+    pub menubar_indicator: bool,
+    /// Hide apps whose windows are all on hidden tags
+    pub hide_apps: bool,
+    /// Remove the shadows of tiled windows
+    pub hide_shadows: bool,
+    /// Points each tile grows into the gaps it shares with other tiles
+    pub corner_compensation: u32,
+    pub gap_scale: GapScaleMode,
+    pub raise_policy: RaisePolicy,
+    pub focus_floating: FocusFloatingMode,
+    pub float_visibility: FloatVisibility,
     pub outer_gap: OuterGap,
+    /// Shell command run when a display's visible tags change
+    pub tag_change_hook: Option<String>,
+    /// Shell commands run around each retile
+    pub retile_hooks: HashMap<HookEvent, String>,
+    /// Apps (bundle identifier globs) that are neither observed nor managed
+    pub excluded_apps: Vec<GlobPattern>,
+    /// Displays (by `Display::output_key`) whose windows are left unmanaged, kept across
+    /// reconnects
+    pub ignored_outputs: HashSet<String>,
     pub init_completed: bool,
 }
 
 impl Config {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            excluded_apps: default_excluded_apps(),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_app_excluded(&self, app_id: Option<&str>) -> bool {
+        app_id.is_some_and(|id| self.excluded_apps.iter().any(|p| p.matches(id)))
     }
 }
@@ -1,4 +1,4 @@
-use crate::macos::DisplayId;
+use crate::macos::{DisplayId, ScreenCorners};
 
 use super::{Rect, Tag, WindowId};
 
@@ -8,6 +8,16 @@ pub struct Display {
     pub name: String,
     pub frame: Rect,
     pub is_main: bool,
+    /// Backing scale factor (2.0 for Retina)
+    pub scale_factor: f64,
+    /// Rotation in degrees (0, 90, 180 or 270)
+    pub rotation: u32,
+    /// Refresh rate in Hz (0 when unknown)
+    pub refresh_rate: f64,
+    /// Hardware UUID
+    pub uuid: Option<String>,
+    /// Hot corners and Dock corners, avoided when hiding windows
+    pub obstructed_corners: ScreenCorners,
     pub visible_tags: Tag,
     pub previous_visible_tags: Tag,
     pub window_order: Vec<WindowId>,
@@ -22,6 +32,11 @@ impl Display {
             name,
             frame,
             is_main,
+            scale_factor: 1.0,
+            rotation: 0,
+            refresh_rate: 0.0,
+            uuid: None,
+            obstructed_corners: ScreenCorners::default(),
             visible_tags: Tag::new(1),
             previous_visible_tags: Tag::new(1),
             window_order: Vec::new(),
@@ -29,4 +44,10 @@ impl Display {
             previous_layout: None,
         }
     }
+
+    /// Identifies the display across reconnects, which may reassign its ID: the hardware
+    /// UUID, or the ID when the UUID is unknown
+    pub fn output_key(&self) -> String {
+        self.uuid.clone().unwrap_or_else(|| self.id.to_string())
+    }
 }
@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use yashiki_ipc::ErrorInfo;
+
+/// Errors kept for `last-errors`; older ones are dropped
+const MAX_ERRORS: usize = 50;
+
+#[derive(Debug)]
+pub struct ErrorEntry {
+    /// Increasing number of the error, starting at 1
+    pub seq: u64,
+    pub source: String,
+    pub message: String,
+    pub at: Instant,
+}
+
+/// Failures that happen outside of a command's reply (layout engine errors, windows
+/// that could not be moved during a retile), reported by `last-errors` and as events
+#[derive(Debug, Default)]
+pub struct ErrorLog {
+    entries: VecDeque<ErrorEntry>,
+    last_seq: u64,
+}
+
+impl ErrorLog {
+    pub fn record(&mut self, source: &str, message: impl Into<String>) {
+        if self.entries.len() == MAX_ERRORS {
+            self.entries.pop_front();
+        }
+        self.last_seq += 1;
+        self.entries.push_back(ErrorEntry {
+            seq: self.last_seq,
+            source: source.to_string(),
+            message: message.into(),
+            at: Instant::now(),
+        });
+    }
+
+    /// Sequence number of the latest error, 0 if none was recorded
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Kept errors recorded after `seq`, oldest first
+    pub fn since(&self, seq: u64) -> impl Iterator<Item = &ErrorEntry> {
+        self.entries.iter().filter(move |e| e.seq > seq)
+    }
+
+    /// Kept errors, oldest first
+    pub fn summary(&self, now: Instant) -> Vec<ErrorInfo> {
+        self.entries
+            .iter()
+            .map(|e| ErrorInfo {
+                source: e.source.clone(),
+                message: e.message.clone(),
+                seconds_ago: now.saturating_duration_since(e.at).as_secs(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_log_keeps_recent_errors() {
+        let mut log = ErrorLog::default();
+        assert_eq!(log.last_seq(), 0);
+        for i in 0..MAX_ERRORS + 2 {
+            log.record("layout", format!("failure {}", i));
+        }
+
+        let summary = log.summary(Instant::now());
+        assert_eq!(summary.len(), MAX_ERRORS);
+        assert_eq!(summary[0].message, "failure 2");
+        assert_eq!(summary[0].source, "layout");
+        assert_eq!(log.last_seq(), MAX_ERRORS as u64 + 2);
+
+        let recent: Vec<_> = log
+            .since(log.last_seq() - 1)
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(recent, vec![format!("failure {}", MAX_ERRORS + 1)]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use yashiki_ipc::CommandLatency;
+
+/// Samples kept per command; older ones are dropped
+const MAX_SAMPLES: usize = 256;
+
+#[derive(Debug, Default)]
+struct Samples {
+    total: VecDeque<Duration>,
+    effects: VecDeque<Duration>,
+    count: u64,
+}
+
+/// Per-command latency of command handling and effect application, reported by `stats`
+#[derive(Debug, Default)]
+pub struct LatencyStats {
+    commands: HashMap<String, Samples>,
+}
+
+impl LatencyStats {
+    /// Record the full handling time of a command
+    pub fn record(&mut self, command: &str, elapsed: Duration) {
+        let samples = self.commands.entry(command.to_string()).or_default();
+        samples.count += 1;
+        push_sample(&mut samples.total, elapsed);
+    }
+
+    /// Record the time spent applying a command's effects
+    pub fn record_effects(&mut self, command: &str, elapsed: Duration) {
+        let samples = self.commands.entry(command.to_string()).or_default();
+        push_sample(&mut samples.effects, elapsed);
+    }
+
+    /// Percentiles per command, sorted by command name
+    pub fn summary(&self) -> Vec<CommandLatency> {
+        let mut summary: Vec<CommandLatency> = self
+            .commands
+            .iter()
+            .filter(|(_, s)| s.count > 0)
+            .map(|(command, s)| CommandLatency {
+                command: command.clone(),
+                count: s.count,
+                p50_ms: percentile_ms(&s.total, 50),
+                p95_ms: percentile_ms(&s.total, 95),
+                effects_p50_ms: percentile_ms(&s.effects, 50),
+                effects_p95_ms: percentile_ms(&s.effects, 95),
+            })
+            .collect();
+        summary.sort_by(|a, b| a.command.cmp(&b.command));
+        summary
+    }
+}
+
+fn push_sample(samples: &mut VecDeque<Duration>, elapsed: Duration) {
+    if samples.len() == MAX_SAMPLES {
+        samples.pop_front();
+    }
+    samples.push_back(elapsed);
+}
+
+/// Nearest-rank percentile in milliseconds, 0 when there are no samples
+pub fn percentile_ms<'a>(
+    samples: impl IntoIterator<Item = &'a Duration>,
+    percentile: usize,
+) -> f64 {
+    let mut sorted: Vec<Duration> = samples.into_iter().copied().collect();
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted.sort();
+    let rank = (percentile * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1].as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut stats = LatencyStats::default();
+        for ms in 1..=100 {
+            stats.record("tag_view", Duration::from_millis(ms));
+        }
+        stats.record_effects("tag_view", Duration::from_millis(4));
+        stats.record("get_state", Duration::from_millis(1));
+
+        let summary = stats.summary();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].command, "get_state");
+        assert_eq!(summary[0].effects_p95_ms, 0.0);
+        assert_eq!(summary[1].command, "tag_view");
+        assert_eq!(summary[1].count, 100);
+        assert_eq!(summary[1].p50_ms, 50.0);
+        assert_eq!(summary[1].p95_ms, 95.0);
+        assert_eq!(summary[1].effects_p50_ms, 4.0);
+    }
+
+    #[test]
+    fn test_latency_keeps_recent_samples() {
+        let mut stats = LatencyStats::default();
+        for _ in 0..MAX_SAMPLES {
+            stats.record("tag_view", Duration::from_millis(100));
+        }
+        for _ in 0..MAX_SAMPLES {
+            stats.record("tag_view", Duration::from_millis(2));
+        }
+
+        let summary = stats.summary();
+        assert_eq!(summary[0].count, 2 * MAX_SAMPLES as u64);
+        assert_eq!(summary[0].p95_ms, 2.0);
+    }
+}
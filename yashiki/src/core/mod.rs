@@ -1,13 +1,21 @@
 mod config;
 mod display;
+mod errors;
+mod latency;
+mod rule_suggest;
 mod rules_engine;
 mod state;
 mod tag;
+mod wakeups;
 mod window;
 
 pub use config::*;
 pub use display::*;
+pub use errors::*;
+pub use latency::*;
+pub use rule_suggest::*;
 pub use rules_engine::*;
 pub use state::*;
 pub use tag::*;
+pub use wakeups::*;
 pub use window::*;
@@ -0,0 +1,165 @@
+use yashiki_ipc::{RuleSuggestion, WindowLevelName};
+
+use super::Window;
+
+/// Windows smaller than this in either dimension are likely popups or panels
+const SMALL_WINDOW_WIDTH: u32 = 200;
+const SMALL_WINDOW_HEIGHT: u32 = 150;
+
+const NAMED_LEVELS: [WindowLevelName; 4] = [
+    WindowLevelName::Floating,
+    WindowLevelName::Modal,
+    WindowLevelName::Utility,
+    WindowLevelName::Popup,
+];
+
+/// Suggest a rule for a managed window with unusual attributes.
+/// Windows that already had rules applied are left alone.
+pub fn suggest_rule(window: &Window) -> Option<RuleSuggestion> {
+    if !window.applied_rules.is_empty() || window.is_fullscreen {
+        return None;
+    }
+
+    let app = match &window.app_id {
+        Some(app_id) => format!("--app-id {}", quote(app_id)),
+        None => format!("--app-name {}", quote(&window.app_name)),
+    };
+    let suggest = |reason: String, matcher: String, action: &str| RuleSuggestion {
+        window_id: window.id,
+        app_name: window.app_name.clone(),
+        reason,
+        command: format!("rule-add {} {} {}", app, matcher, action),
+    };
+
+    if !window.close_button.exists {
+        return Some(suggest(
+            "no close button (likely a popup)".to_string(),
+            "--close-button none".to_string(),
+            "ignore",
+        ));
+    }
+    if window.is_floating {
+        return None;
+    }
+    if window.window_level != 0 {
+        let level = NAMED_LEVELS
+            .iter()
+            .find(|name| name.to_value() == window.window_level)
+            .map(|name| format!("{:?}", name).to_lowercase())
+            .unwrap_or_else(|| "other".to_string());
+        return Some(suggest(
+            format!("window level {} is not normal", window.window_level),
+            format!("--window-level {}", level),
+            "float",
+        ));
+    }
+    if !window.fullscreen_button.exists {
+        return Some(suggest(
+            "no fullscreen button (likely a dialog or panel)".to_string(),
+            "--fullscreen-button none".to_string(),
+            "float",
+        ));
+    }
+
+    let frame = window.saved_frame.unwrap_or(window.frame);
+    if frame.width < SMALL_WINDOW_WIDTH || frame.height < SMALL_WINDOW_HEIGHT {
+        let matcher = match (&window.subrole, window.title.is_empty()) {
+            (Some(subrole), _) => format!("--subrole {}", quote(subrole)),
+            (None, false) => format!("--title {}", quote(&window.title)),
+            (None, true) => return None,
+        };
+        return Some(suggest(
+            format!("small window ({}x{})", frame.width, frame.height),
+            matcher,
+            "float",
+        ));
+    }
+
+    None
+}
+
+/// Quote a value for the shell unless it is a plain word
+fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Tag;
+    use crate::macos::{Bounds, WindowInfo};
+    use yashiki_ipc::ButtonInfo;
+
+    fn window(width: f64, height: f64) -> Window {
+        let info = WindowInfo {
+            pid: 1000,
+            window_id: 100,
+            name: Some("Find".to_string()),
+            owner_name: "Safari".to_string(),
+            bundle_id: Some("com.apple.Safari".to_string()),
+            bounds: Bounds {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+            },
+            layer: 0,
+        };
+        let mut window = Window::from_window_info(&info, Tag::new(1), 1);
+        window.close_button = ButtonInfo::new(true, Some(true));
+        window.fullscreen_button = ButtonInfo::new(true, Some(true));
+        window
+    }
+
+    #[test]
+    fn test_suggest_rule_normal_window() {
+        assert!(suggest_rule(&window(800.0, 600.0)).is_none());
+    }
+
+    #[test]
+    fn test_suggest_rule_unusual_attributes() {
+        let mut w = window(800.0, 600.0);
+        w.window_level = 19;
+        assert_eq!(
+            suggest_rule(&w).unwrap().command,
+            "rule-add --app-id com.apple.Safari --window-level utility float"
+        );
+
+        let mut w = window(800.0, 600.0);
+        w.close_button = ButtonInfo::default();
+        assert_eq!(
+            suggest_rule(&w).unwrap().command,
+            "rule-add --app-id com.apple.Safari --close-button none ignore"
+        );
+
+        let w = window(120.0, 80.0);
+        let suggestion = suggest_rule(&w).unwrap();
+        assert_eq!(suggestion.reason, "small window (120x80)");
+        assert_eq!(
+            suggestion.command,
+            "rule-add --app-id com.apple.Safari --title Find float"
+        );
+    }
+
+    #[test]
+    fn test_suggest_rule_skips_handled_windows() {
+        let mut w = window(120.0, 80.0);
+        w.is_floating = true;
+        assert!(suggest_rule(&w).is_none());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("com.apple.Safari"), "com.apple.Safari");
+        assert_eq!(quote("Google Chrome"), "\"Google Chrome\"");
+        assert_eq!(quote("a\"b"), "\"a\\\"b\"");
+    }
+}
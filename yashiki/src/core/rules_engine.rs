@@ -1,4 +1,6 @@
-use yashiki_ipc::{ExtendedWindowAttributes, RuleAction, RuleMatcher, WindowRule};
+use yashiki_ipc::{
+    Anchor, ExtendedWindowAttributes, GlobPattern, RuleAction, RuleMatcher, RuleSize, WindowRule,
+};
 
 use crate::macos::DisplayId;
 
@@ -7,21 +9,59 @@ use crate::macos::DisplayId;
 pub struct RuleApplicationResult {
     pub tags: Option<u32>,
     pub display_id: Option<DisplayId>,
-    pub position: Option<(i32, i32)>,
-    pub dimensions: Option<(u32, u32)>,
+    pub position: Option<(i32, i32, Option<Anchor>)>,
+    pub dimensions: Option<(RuleSize, RuleSize)>,
     pub is_floating: Option<bool>,
+    /// Opacity in percent while unfocused
+    pub opacity: Option<u32>,
+    /// Rules that determined at least one of the fields above
+    pub applied_rules: Vec<WindowRule>,
+}
+
+/// Bundle identifiers of transient system windows ignored by default
+const DEFAULT_IGNORED_APP_IDS: &[&str] = &[
+    "com.apple.problemreporter",        // Crash reporter
+    "com.apple.SoftwareUpdate",         // Software Update
+    "com.apple.screencaptureui",        // Screenshot toolbar
+    "com.apple.SystemProfiler",         // System Information
+    "com.apple.SecurityAgent",          // Authentication prompts
+    "com.apple.coreservices.uiagent",   // "App downloaded from the Internet" dialogs
+    "com.apple.UserNotificationCenter", // System alerts
+];
+
+/// Built-in rules, applied only to windows that match no user rule
+fn default_rules() -> Vec<WindowRule> {
+    DEFAULT_IGNORED_APP_IDS
+        .iter()
+        .map(|&app_id| {
+            WindowRule::new(
+                RuleMatcher::with_app_id(None, Some(GlobPattern::new(app_id)), None),
+                RuleAction::Ignore,
+            )
+        })
+        .collect()
 }
 
 /// Engine for managing and matching window rules.
 /// Handles rule storage, ordering, and matching logic.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct RulesEngine {
     rules: Vec<WindowRule>,
+    default_rules: Vec<WindowRule>,
+}
+
+impl Default for RulesEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RulesEngine {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            default_rules: default_rules(),
+        }
     }
 
     pub fn rules(&self) -> &[WindowRule] {
@@ -32,6 +72,10 @@ impl RulesEngine {
         self.rules.is_empty()
     }
 
+    pub fn has_dynamic_rules(&self) -> bool {
+        self.rules.iter().any(|rule| rule.dynamic)
+    }
+
     pub fn add_rule(&mut self, rule: WindowRule) {
         tracing::info!("Adding rule: {:?} -> {:?}", rule.matcher, rule.action);
         self.rules.push(rule);
@@ -57,7 +101,14 @@ impl RulesEngine {
         title: &str,
         ext: &ExtendedWindowAttributes,
     ) -> bool {
-        self.rules.iter().any(|rule| {
+        let user_matches = self.get_matching_rules(app_name, app_id, title, ext);
+        if !user_matches.is_empty() {
+            // Any matching user rule overrides the built-in defaults
+            return user_matches
+                .iter()
+                .any(|rule| matches!(rule.action, RuleAction::Ignore));
+        }
+        self.default_rules.iter().any(|rule| {
             matches!(rule.action, RuleAction::Ignore)
                 && rule.matcher.matches_extended(app_name, app_id, title, ext)
         })
@@ -103,34 +154,48 @@ impl RulesEngine {
 
         for rule in matching_rules {
             match &rule.action {
-                RuleAction::Ignore => {}
+                RuleAction::Ignore
+                | RuleAction::CloseBehavior { .. }
+                | RuleAction::NoManageResize
+                | RuleAction::Throttle { .. } => {}
                 RuleAction::Float => {
                     if result.is_floating.is_none() {
                         result.is_floating = Some(true);
+                        result.applied_rules.push(rule.clone());
                     }
                 }
                 RuleAction::NoFloat => {
                     if result.is_floating.is_none() {
                         result.is_floating = Some(false);
+                        result.applied_rules.push(rule.clone());
                     }
                 }
                 RuleAction::Tags { tags: t } => {
                     if result.tags.is_none() {
                         result.tags = Some(*t);
+                        result.applied_rules.push(rule.clone());
                     }
                 }
                 RuleAction::Output { .. } => {
                     // Output resolution requires State.resolve_output()
                     // This will be handled by the caller
                 }
-                RuleAction::Position { x, y } => {
+                RuleAction::Position { x, y, anchor } => {
                     if result.position.is_none() {
-                        result.position = Some((*x, *y));
+                        result.position = Some((*x, *y, *anchor));
+                        result.applied_rules.push(rule.clone());
                     }
                 }
                 RuleAction::Dimensions { width, height } => {
                     if result.dimensions.is_none() {
                         result.dimensions = Some((*width, *height));
+                        result.applied_rules.push(rule.clone());
+                    }
+                }
+                RuleAction::Opacity { percent } => {
+                    if result.opacity.is_none() {
+                        result.opacity = Some(*percent);
+                        result.applied_rules.push(rule.clone());
                     }
                 }
             }
@@ -163,8 +228,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         }
     }
 
@@ -181,8 +250,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Float,
+            dynamic: false,
         }
     }
 
@@ -226,6 +299,29 @@ mod tests {
         assert!(!engine.should_ignore("Firefox", None, "Window", &ext_standard));
     }
 
+    #[test]
+    fn test_default_rules_ignore_system_windows() {
+        let mut engine = RulesEngine::new();
+        let ext = ExtendedWindowAttributes::default();
+
+        assert!(engine.should_ignore(
+            "Problem Reporter",
+            Some("com.apple.problemreporter"),
+            "Report",
+            &ext
+        ));
+        assert!(!engine.should_ignore("Safari", Some("com.apple.Safari"), "Window", &ext));
+
+        // A user rule matching the window overrides the default
+        engine.add_rule(create_float_rule("System Information"));
+        assert!(!engine.should_ignore(
+            "System Information",
+            Some("com.apple.SystemProfiler"),
+            "Window",
+            &ext
+        ));
+    }
+
     #[test]
     fn test_apply_rules_float() {
         let mut engine = RulesEngine::new();
@@ -237,6 +333,23 @@ mod tests {
         assert_eq!(result.is_floating, Some(true));
     }
 
+    #[test]
+    fn test_apply_rules_records_applied_rules() {
+        let mut engine = RulesEngine::new();
+        engine.add_rule(create_float_rule("Finder"));
+        engine.add_rule(create_float_rule("Fin*"));
+
+        let ext = ExtendedWindowAttributes::default();
+        let result = engine.apply_rules("Finder", None, "Window", &ext);
+
+        // Only the first (most specific) float rule takes effect
+        assert_eq!(result.applied_rules.len(), 1);
+        assert_eq!(result.applied_rules[0], create_float_rule("Finder"));
+
+        let result = engine.apply_rules("Safari", None, "Window", &ext);
+        assert!(result.applied_rules.is_empty());
+    }
+
     #[test]
     fn test_non_normal_layer_defaults_to_float() {
         let engine = RulesEngine::new();
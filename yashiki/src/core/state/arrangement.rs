@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use super::super::window::Rect;
+use super::super::WindowId;
+use crate::macos::DisplayId;
+
+use super::super::state::{ArrangementRestoreResult, SavedPlacement, State};
+use super::layout::{
+    add_to_window_order, compute_layout_changes_for_display, remove_from_window_order,
+};
+
+/// Save the tags, display, layout order and floating frame of every window under `name`.
+/// Stashed windows are left out. Returns the number of windows saved.
+pub fn save_arrangement(state: &mut State, name: &str) -> usize {
+    let position = |state: &State, window_id: WindowId, display_id: DisplayId| {
+        state
+            .displays
+            .get(&display_id)
+            .and_then(|d| d.window_order.iter().position(|&id| id == window_id))
+            .unwrap_or(usize::MAX)
+    };
+    let mut windows: Vec<_> = state
+        .windows
+        .values()
+        .filter(|w| w.tags.mask() != 0)
+        .collect();
+    windows.sort_by_key(|w| (w.display_id, position(state, w.id, w.display_id), w.id));
+
+    let placements: Vec<SavedPlacement> = windows
+        .into_iter()
+        .map(|w| SavedPlacement {
+            app_key: w.app_key().to_string(),
+            title: w.title.clone(),
+            tags: w.tags,
+            display_id: w.display_id,
+            is_floating: w.is_floating,
+            frame: w.saved_frame.unwrap_or(w.frame),
+        })
+        .collect();
+
+    let count = placements.len();
+    tracing::info!("Saved arrangement {} with {} windows", name, count);
+    state.arrangements.insert(name.to_string(), placements);
+    count
+}
+
+/// Put open windows back where the arrangement `name` had them. Windows are matched by
+/// app and title first, then by app alone for windows whose title changed.
+/// Returns None if there is no such arrangement.
+pub fn restore_arrangement(state: &mut State, name: &str) -> Option<ArrangementRestoreResult> {
+    let placements = state.arrangements.get(name)?.clone();
+
+    // Pair saved placements with open windows
+    let mut used: HashSet<WindowId> = HashSet::new();
+    let mut matches: Vec<Option<WindowId>> = vec![None; placements.len()];
+    for exact in [true, false] {
+        for (i, placement) in placements.iter().enumerate() {
+            if matches[i].is_some() {
+                continue;
+            }
+            let candidate = state
+                .windows
+                .values()
+                .filter(|w| !used.contains(&w.id) && w.app_key() == placement.app_key)
+                .filter(|w| !exact || w.title == placement.title)
+                .map(|w| w.id)
+                .min();
+            if let Some(id) = candidate {
+                used.insert(id);
+                matches[i] = Some(id);
+            }
+        }
+    }
+
+    let mut display_ids: Vec<DisplayId> = Vec::new();
+    let mut restored: Vec<WindowId> = Vec::new();
+    for (placement, window_id) in placements.iter().zip(&matches) {
+        let Some(window_id) = *window_id else {
+            continue;
+        };
+        let target_display = state
+            .displays
+            .get(&placement.display_id)
+            .filter(|d| !state.is_output_ignored(d.id))
+            .map(|d| (d.id, d.frame.x, d.frame.y));
+        let Some(window) = state.windows.get_mut(&window_id) else {
+            continue;
+        };
+
+        if !display_ids.contains(&window.display_id) {
+            display_ids.push(window.display_id);
+        }
+        window.tags = placement.tags;
+        window.is_floating = placement.is_floating;
+        window.floated_by_tag = false;
+        if let Some((display_id, x, y)) = target_display {
+            if window.display_id != display_id {
+                window.display_id = display_id;
+                window.orphaned_from = None;
+                window.frame.x = x;
+                window.frame.y = y;
+                if let Some(ref mut saved) = window.saved_frame {
+                    saved.x = x;
+                    saved.y = y;
+                }
+            }
+        }
+        if placement.is_floating {
+            if window.is_hidden() {
+                window.saved_frame = Some(placement.frame);
+            } else {
+                window.frame = placement.frame;
+            }
+        }
+        let display_id = window.display_id;
+        if !display_ids.contains(&display_id) {
+            display_ids.push(display_id);
+        }
+
+        // Re-adding in saved order restores the layout order
+        remove_from_window_order(state, window_id);
+        add_to_window_order(state, window_id, display_id);
+        restored.push(window_id);
+    }
+
+    let mut window_moves = Vec::new();
+    for &display_id in &display_ids {
+        window_moves.extend(compute_layout_changes_for_display(state, display_id));
+    }
+
+    // Visible floating windows still need to be moved and resized to their saved frame
+    let floating_frames: Vec<(WindowId, i32, Rect)> = restored
+        .iter()
+        .filter_map(|id| state.windows.get(id))
+        .filter(|w| w.is_floating && !w.is_hidden())
+        .map(|w| (w.id, w.pid, w.frame))
+        .collect();
+
+    tracing::info!(
+        "Restored arrangement {}: {} of {} windows",
+        name,
+        restored.len(),
+        placements.len()
+    );
+    Some(ArrangementRestoreResult {
+        restored: restored.len(),
+        missing: placements.len() - restored.len(),
+        display_ids,
+        window_moves,
+        floating_frames,
+    })
+}
@@ -1,13 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::macos::DisplayId;
+use super::super::window::Rect;
+use super::super::{Window, WindowId};
+use crate::macos::{DisplayId, DisplayInfo};
 use crate::platform::WindowSystem;
-use yashiki_ipc::OutputDirection;
+use yashiki_ipc::{OrphanPolicy, OutputDirection};
 
-use super::super::state::{DisplayChangeResult, FocusOutputResult, SendToOutputResult, State};
+use super::super::state::{
+    BringWindowResult, DisplayChangeResult, FocusOutputResult, SendToOutputResult, State,
+    WindowMove,
+};
 use super::layout::{
     add_to_window_order, compute_layout_changes_for_display, remove_from_window_order,
-    visible_windows_on_display,
+    shows_on_tags, visible_windows_on_display,
 };
 use super::sync::sync_all;
 
@@ -20,6 +25,13 @@ use super::sync::sync_all;
 /// The order difference is intentional:
 /// - On reconnect: must sync first to create Display entries, then restore saved state
 /// - On disconnect: must save state before removing displays
+///
+/// In both branches, saved frames of hidden windows are rescaled after sync_all when their
+/// display's geometry changed (e.g. resolution or scaling change).
+///
+/// A main display reassignment moves the global coordinate origin, so every display keeps
+/// its size but shifts by the same offset. That case is detected first and stored window
+/// coordinates are translated by the offset instead of being rescaled per display.
 pub fn handle_display_change<W: WindowSystem>(state: &mut State, ws: &W) -> DisplayChangeResult {
     let display_infos = ws.get_all_displays();
     let current_ids: HashSet<_> = display_infos.iter().map(|d| d.id).collect();
@@ -27,10 +39,25 @@ pub fn handle_display_change<W: WindowSystem>(state: &mut State, ws: &W) -> Disp
 
     let removed_ids: Vec<_> = previous_ids.difference(&current_ids).copied().collect();
     let added_ids: HashSet<_> = current_ids.difference(&previous_ids).copied().collect();
+    let mut previous_frames: HashMap<DisplayId, Rect> =
+        state.displays.values().map(|d| (d.id, d.frame)).collect();
+
+    if let Some((dx, dy)) = main_display_shift(state, &display_infos) {
+        tracing::info!(
+            "Main display changed, shifting window coordinates by ({}, {})",
+            dx,
+            dy
+        );
+        shift_window_coordinates(state, dx, dy);
+        for frame in previous_frames.values_mut() {
+            *frame = frame.offset(dx, dy);
+        }
+    }
 
     // === Reconnect branch: no displays removed, possibly some added ===
     if removed_ids.is_empty() {
         let (rehide_moves, new_window_ids) = sync_all(state, ws);
+        rescale_saved_frames(state, &previous_frames);
         // Retile all displays when configuration changes, not just added ones.
         // Existing displays may have shifted coordinates during the disconnection period.
         let mut displays_to_retile: HashSet<DisplayId> = current_ids.clone();
@@ -120,23 +147,38 @@ pub fn handle_display_change<W: WindowSystem>(state: &mut State, ws: &W) -> Disp
     let mut window_moves = Vec::new();
     let mut affected_displays = HashSet::new();
 
-    for window in state.windows.values_mut() {
-        if removed_ids.contains(&window.display_id) {
-            tracing::info!(
-                "Moving orphaned window {} ({}) from display {} to {}",
-                window.id,
-                window.app_name,
-                window.display_id,
-                fallback_id
-            );
-            // Record the original display for potential restoration on wake.
-            // Only set if not already orphaned (preserve the first orphan source for multi-stage disconnects).
-            if window.orphaned_from.is_none() {
-                window.orphaned_from = Some(window.display_id);
-            }
-            window.display_id = fallback_id;
-            affected_displays.insert(fallback_id);
+    let mut remaining_ids: Vec<_> = current_ids.iter().copied().collect();
+    remaining_ids.sort();
+    let mut orphan_ids: Vec<WindowId> = state
+        .windows
+        .values()
+        .filter(|w| removed_ids.contains(&w.display_id))
+        .map(|w| w.id)
+        .collect();
+    orphan_ids.sort();
+
+    for (index, window_id) in orphan_ids.into_iter().enumerate() {
+        let Some(window) = state.windows.get(&window_id) else {
+            continue;
+        };
+        let target_id = orphan_target_display(state, window, index, &remaining_ids, fallback_id);
+        let Some(window) = state.windows.get_mut(&window_id) else {
+            continue;
+        };
+        tracing::info!(
+            "Moving orphaned window {} ({}) from display {} to {}",
+            window.id,
+            window.app_name,
+            window.display_id,
+            target_id
+        );
+        // Record the original display for potential restoration on wake.
+        // Only set if not already orphaned (preserve the first orphan source for multi-stage disconnects).
+        if window.orphaned_from.is_none() {
+            window.orphaned_from = Some(window.display_id);
         }
+        window.display_id = target_id;
+        affected_displays.insert(target_id);
     }
 
     if removed_ids.contains(&state.focused_display) {
@@ -157,6 +199,7 @@ pub fn handle_display_change<W: WindowSystem>(state: &mut State, ws: &W) -> Disp
     }
 
     let (rehide_moves, new_window_ids) = sync_all(state, ws);
+    rescale_saved_frames(state, &previous_frames);
 
     let added: Vec<_> = state
         .displays
@@ -182,6 +225,137 @@ pub fn handle_display_change<W: WindowSystem>(state: &mut State, ws: &W) -> Disp
     }
 }
 
+/// Offset of the global coordinate space when the main display was reassigned:
+/// the main display changed and every remaining display moved by the same amount
+/// without changing size
+fn main_display_shift(state: &State, display_infos: &[DisplayInfo]) -> Option<(i32, i32)> {
+    let previous_main = state.displays.values().find(|d| d.is_main)?.id;
+    let new_main = display_infos.iter().find(|d| d.is_main)?.id;
+    if previous_main == new_main {
+        return None;
+    }
+
+    let mut shift = None;
+    for info in display_infos {
+        let Some(old) = state.displays.get(&info.id).map(|d| d.frame) else {
+            continue;
+        };
+        let new = Rect::from_bounds(&info.frame);
+        if old.width != new.width || old.height != new.height {
+            return None;
+        }
+        let delta = (new.x - old.x, new.y - old.y);
+        if *shift.get_or_insert(delta) != delta {
+            return None;
+        }
+    }
+    shift.filter(|&delta| delta != (0, 0))
+}
+
+/// Translate every stored window coordinate (frames, saved frames, tag float frames and
+/// saved arrangements) by the given offset
+fn shift_window_coordinates(state: &mut State, dx: i32, dy: i32) {
+    for window in state.windows.values_mut() {
+        window.frame = window.frame.offset(dx, dy);
+        window.saved_frame = window.saved_frame.map(|f| f.offset(dx, dy));
+        window.tag_float_frame = window.tag_float_frame.map(|f| f.offset(dx, dy));
+    }
+    for placement in state.arrangements.values_mut().flatten() {
+        placement.frame = placement.frame.offset(dx, dy);
+    }
+}
+
+/// Rescale saved frames of hidden windows on displays whose geometry changed,
+/// so unhiding doesn't restore them off-screen
+fn rescale_saved_frames(state: &mut State, previous_frames: &HashMap<DisplayId, Rect>) {
+    for window in state.windows.values_mut() {
+        let Some(saved) = window.saved_frame else {
+            continue;
+        };
+        let (Some(old), Some(new)) = (
+            previous_frames.get(&window.display_id),
+            state.displays.get(&window.display_id).map(|d| d.frame),
+        ) else {
+            continue;
+        };
+        if *old == new || old.width == 0 || old.height == 0 {
+            continue;
+        }
+        let rescaled = rescale_rect(&saved, old, &new);
+        tracing::info!(
+            "Rescaling saved frame of window {} for display {} geometry change: {:?} -> {:?}",
+            window.id,
+            window.display_id,
+            saved,
+            rescaled
+        );
+        window.saved_frame = Some(rescaled);
+    }
+}
+
+/// Map a rect proportionally from one display frame to another, keeping it inside the new frame
+fn rescale_rect(rect: &Rect, old: &Rect, new: &Rect) -> Rect {
+    let (x, width) = rescale_axis(rect.x, rect.width, old.x, old.width, new.x, new.width);
+    let (y, height) = rescale_axis(rect.y, rect.height, old.y, old.height, new.y, new.height);
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+fn rescale_axis(
+    pos: i32,
+    size: u32,
+    old_pos: i32,
+    old_size: u32,
+    new_pos: i32,
+    new_size: u32,
+) -> (i32, u32) {
+    let size = (size as u64 * new_size as u64 / old_size as u64).min(new_size as u64) as u32;
+    let offset = (pos - old_pos) as i64 * new_size as i64 / old_size as i64;
+    let max_pos = new_pos + (new_size - size) as i32;
+    (
+        ((new_pos as i64 + offset) as i32).clamp(new_pos, max_pos),
+        size,
+    )
+}
+
+/// Pick the display an orphaned window moves to, according to the orphan policy
+fn orphan_target_display(
+    state: &State,
+    window: &Window,
+    index: usize,
+    remaining_ids: &[DisplayId],
+    fallback_id: DisplayId,
+) -> DisplayId {
+    match state.config.orphan_policy {
+        OrphanPolicy::Main => fallback_id,
+        OrphanPolicy::Spread => remaining_ids
+            .get(index % remaining_ids.len().max(1))
+            .copied()
+            .unwrap_or(fallback_id),
+        OrphanPolicy::ByTag => {
+            let shows_window_tags = |id: &DisplayId| {
+                state
+                    .displays
+                    .get(id)
+                    .is_some_and(|d| d.visible_tags.intersects(window.tags))
+            };
+            if shows_window_tags(&fallback_id) {
+                fallback_id
+            } else {
+                remaining_ids
+                    .iter()
+                    .copied()
+                    .find(|id| shows_window_tags(id))
+                    .unwrap_or(fallback_id)
+            }
+        }
+    }
+}
+
 pub fn focus_output(state: &mut State, direction: OutputDirection) -> Option<FocusOutputResult> {
     if state.displays.len() <= 1 {
         return None;
@@ -228,10 +402,15 @@ pub fn send_to_output(state: &mut State, direction: OutputDirection) -> Option<S
         return None;
     }
 
-    let mut display_ids: Vec<_> = state.displays.keys().copied().collect();
+    let source_display_id = state.windows.get(&focused_id)?.display_id;
+    let mut display_ids: Vec<_> = state
+        .displays
+        .keys()
+        .copied()
+        .filter(|id| *id == source_display_id || !is_output_ignored(state, *id))
+        .collect();
     display_ids.sort();
 
-    let source_display_id = state.windows.get(&focused_id)?.display_id;
     let current_idx = display_ids.iter().position(|&id| id == source_display_id)?;
 
     let next_idx = match direction {
@@ -245,18 +424,39 @@ pub fn send_to_output(state: &mut State, direction: OutputDirection) -> Option<S
         return None;
     }
 
-    let target_display = state.displays.get(&target_display_id)?;
-    let target_frame_x = target_display.frame.x;
-    let target_frame_y = target_display.frame.y;
-
-    // Update window's display_id and frame position
-    let window = state.windows.get_mut(&focused_id)?;
     tracing::info!(
         "Send window {} to output: {} -> {}",
-        window.id,
+        focused_id,
         source_display_id,
         target_display_id
     );
+    move_window_to_display(state, focused_id, target_display_id)?;
+
+    // Compute visibility changes for target display
+    let moves = compute_layout_changes_for_display(state, target_display_id);
+
+    // Note: focused_display is NOT changed (River-style: focus stays on source display)
+
+    Some(SendToOutputResult {
+        source_display_id,
+        target_display_id,
+        window_moves: moves,
+    })
+}
+
+/// Move a window into the layout of another display. A window belongs to exactly one
+/// display, even when its tags are visible on several of them.
+fn move_window_to_display(
+    state: &mut State,
+    window_id: WindowId,
+    target_display_id: DisplayId,
+) -> Option<()> {
+    let target_display = state.displays.get(&target_display_id)?;
+    let target_frame_x = target_display.frame.x;
+    let target_frame_y = target_display.frame.y;
+
+    // Update window's display_id and frame position
+    let window = state.windows.get_mut(&window_id)?;
     window.display_id = target_display_id;
     // User intentionally moved the window - clear orphan state
     window.orphaned_from = None;
@@ -273,17 +473,136 @@ pub fn send_to_output(state: &mut State, direction: OutputDirection) -> Option<S
     }
 
     // Update window_order (move from source to target)
-    remove_from_window_order(state, focused_id);
-    add_to_window_order(state, focused_id, target_display_id);
+    remove_from_window_order(state, window_id);
+    add_to_window_order(state, window_id, target_display_id);
+    Some(())
+}
 
-    // Compute visibility changes for target display
-    let moves = compute_layout_changes_for_display(state, target_display_id);
+/// Yank a window to the focused display and show it there, retagging it to the
+/// display's visible tags if needed. Without an ID, the most recently focused window
+/// on another display is brought.
+pub fn bring_window(
+    state: &mut State,
+    window_id: Option<WindowId>,
+) -> Result<BringWindowResult, String> {
+    let target_display_id = state.focused_display;
+    if is_output_ignored(state, target_display_id) {
+        return Err(format!("Output {} is ignored", target_display_id));
+    }
+    let window_id = match window_id {
+        Some(id) => id,
+        None => state
+            .focus_history
+            .iter()
+            .rev()
+            .copied()
+            .find(|id| {
+                state
+                    .windows
+                    .get(id)
+                    .is_some_and(|w| w.display_id != target_display_id && w.tags.mask() != 0)
+            })
+            .ok_or("No window on another output")?,
+    };
+    let (source_display_id, pid) = state
+        .windows
+        .get(&window_id)
+        .map(|w| (w.display_id, w.pid))
+        .ok_or_else(|| format!("Window not found: {}", window_id))?;
 
-    // Note: focused_display is NOT changed (River-style: focus stays on source display)
+    if source_display_id != target_display_id {
+        tracing::info!(
+            "Bring window {} to output: {} -> {}",
+            window_id,
+            source_display_id,
+            target_display_id
+        );
+        move_window_to_display(state, window_id, target_display_id)
+            .ok_or_else(|| format!("Output not found: {}", target_display_id))?;
+    }
 
-    Some(SendToOutputResult {
+    let visible_tags = state
+        .displays
+        .get(&target_display_id)
+        .map(|d| d.visible_tags)
+        .ok_or_else(|| format!("Output not found: {}", target_display_id))?;
+    let hidden = state
+        .windows
+        .get(&window_id)
+        .is_some_and(|w| !shows_on_tags(state, w, visible_tags));
+    if hidden {
+        tracing::info!("Retag window {} to {:?}", window_id, visible_tags);
+        if let Some(window) = state.windows.get_mut(&window_id) {
+            window.tags = visible_tags;
+        }
+    }
+
+    let window_moves = compute_layout_changes_for_display(state, target_display_id);
+    Ok(BringWindowResult {
+        window_id,
+        pid,
         source_display_id,
         target_display_id,
-        window_moves: moves,
+        window_moves,
     })
 }
+
+pub fn is_output_ignored(state: &State, display_id: DisplayId) -> bool {
+    state
+        .displays
+        .get(&display_id)
+        .is_some_and(|d| state.config.ignored_outputs.contains(&d.output_key()))
+}
+
+/// Stop or resume managing windows on a display. Ignoring releases its windows, moving
+/// tag-hidden ones back on screen; returns those moves.
+pub fn set_output_ignored(
+    state: &mut State,
+    display_id: DisplayId,
+    ignored: bool,
+) -> Vec<WindowMove> {
+    let Some(key) = state.displays.get(&display_id).map(|d| d.output_key()) else {
+        return vec![];
+    };
+    if !ignored {
+        state.config.ignored_outputs.remove(&key);
+        return vec![];
+    }
+    state.config.ignored_outputs.insert(key);
+
+    let mut window_ids: Vec<WindowId> = state
+        .windows
+        .values()
+        .filter(|w| w.display_id == display_id)
+        .map(|w| w.id)
+        .collect();
+    window_ids.sort();
+
+    let mut moves = Vec::new();
+    for id in window_ids {
+        let Some(window) = state.windows.remove(&id) else {
+            continue;
+        };
+        tracing::info!(
+            "Releasing window {} ({}) on ignored display {}",
+            window.id,
+            window.app_name,
+            display_id
+        );
+        if let Some(saved) = window.saved_frame {
+            moves.push(WindowMove {
+                window_id: window.id,
+                pid: window.pid,
+                old_x: window.frame.x,
+                old_y: window.frame.y,
+                new_x: saved.x,
+                new_y: saved.y,
+            });
+        }
+        remove_from_window_order(state, id);
+        if state.focused == Some(id) {
+            state.focused = None;
+        }
+    }
+    moves
+}
@@ -1,8 +1,42 @@
 use super::super::{Window, WindowId};
 use crate::macos::DisplayId;
-use yashiki_ipc::Direction;
+use yashiki_ipc::{Direction, FocusFloatingMode};
 
-use super::super::state::State;
+use super::super::state::{State, SwapFloatResult};
+use super::layout::visible_windows_on_display;
+use super::tags::last_focused_on_visible_tags;
+
+/// Number of recently focused windows remembered
+const FOCUS_HISTORY_LEN: usize = 32;
+
+/// Move the window to the most recent end of the focus history
+pub fn remember_focus_history(state: &mut State, window_id: WindowId) {
+    state.focus_history.retain(|&id| id != window_id);
+    state.focus_history.push(window_id);
+    if state.focus_history.len() > FOCUS_HISTORY_LEN {
+        state.focus_history.remove(0);
+    }
+}
+
+/// When a focused dialog closes, make its parent the most recently focused window
+/// so focus returns to it
+pub fn hand_focus_to_parent(state: &mut State, closed: &Window) {
+    if let Some(parent_id) = closed.parent_id.filter(|id| state.windows.contains_key(id)) {
+        remember_focus_history(state, parent_id);
+    }
+}
+
+/// Most recently focused window that is still visible on the display
+pub fn recently_focused_on_display(state: &State, display_id: DisplayId) -> Option<WindowId> {
+    let display = state.displays.get(&display_id)?;
+    state.focus_history.iter().rev().copied().find(|id| {
+        state.windows.get(id).is_some_and(|w| {
+            w.display_id == display_id
+                && state.shows_on_tags(w, display.visible_tags)
+                && !w.is_hidden()
+        })
+    })
+}
 
 pub fn focus_window(state: &State, direction: Direction) -> Option<(WindowId, i32)> {
     let visible_tags = state.visible_tags();
@@ -11,7 +45,7 @@ pub fn focus_window(state: &State, direction: Direction) -> Option<(WindowId, i3
         .values()
         .filter(|w| {
             w.display_id == state.focused_display
-                && w.tags.intersects(visible_tags)
+                && state.shows_on_tags(w, visible_tags)
                 && !w.is_hidden()
         })
         .collect();
@@ -20,16 +54,79 @@ pub fn focus_window(state: &State, direction: Direction) -> Option<(WindowId, i3
         return None;
     }
 
+    // Nothing to move from, e.g. a windowless app (Finder) took focus
+    if !state
+        .focused
+        .is_some_and(|id| visible.iter().any(|w| w.id == id))
+    {
+        return fallback_focus_target(state, &visible);
+    }
+
     match direction {
         Direction::Next | Direction::Prev => {
             focus_window_stack(state, &visible, direction == Direction::Next)
         }
         Direction::Left | Direction::Right | Direction::Up | Direction::Down => {
-            focus_window_directional(state, &visible, direction)
+            // The focused window stays in the list so it can be searched from
+            let non_floating: Vec<_> = visible
+                .iter()
+                .copied()
+                .filter(|w| !w.is_floating || Some(w.id) == state.focused)
+                .collect();
+            match state.config.focus_floating {
+                FocusFloatingMode::On => focus_window_directional(state, &visible, direction),
+                FocusFloatingMode::Off => focus_window_directional(state, &non_floating, direction),
+                FocusFloatingMode::TiledFirst => {
+                    focus_window_directional(state, &non_floating, direction)
+                        .or_else(|| focus_window_directional(state, &visible, direction))
+                }
+            }
         }
     }
 }
 
+/// Window to focus when no visible window has focus: the last focused one on the
+/// visible tags, then the most recently focused one, then the top of the layout
+fn fallback_focus_target(state: &State, visible: &[&Window]) -> Option<(WindowId, i32)> {
+    let display_id = state.focused_display;
+    let remembered = last_focused_on_visible_tags(state, display_id)
+        .or_else(|| recently_focused_on_display(state, display_id));
+    let top = state.displays.get(&display_id).and_then(|d| {
+        d.window_order
+            .iter()
+            .find(|id| visible.iter().any(|w| w.id == **id))
+            .copied()
+    });
+    let window = visible
+        .iter()
+        .find(|w| Some(w.id) == remembered)
+        .or_else(|| visible.iter().find(|w| Some(w.id) == top))
+        .or_else(|| visible.iter().min_by_key(|w| w.id))?;
+    tracing::info!("No focused window, falling back to window {}", window.id);
+    Some((window.id, window.pid))
+}
+
+/// Next visible window of the app after the focused one (wrapping), across all displays.
+/// Returns (window_id, pid, is_output_change).
+pub fn cycle_app_window(state: &State, app_id: &str) -> Option<(WindowId, i32, bool)> {
+    let visible: Vec<_> = state
+        .windows
+        .values()
+        .filter(|w| {
+            w.app_id.as_deref() == Some(app_id)
+                && !w.is_hidden()
+                && state
+                    .displays
+                    .get(&w.display_id)
+                    .is_some_and(|d| state.shows_on_tags(w, d.visible_tags))
+        })
+        .collect();
+
+    let (window_id, pid) = focus_window_stack(state, &visible, true)?;
+    let display_id = state.windows.get(&window_id)?.display_id;
+    Some((window_id, pid, display_id != state.focused_display))
+}
+
 fn focus_window_stack(
     state: &State,
     visible: &[&Window],
@@ -139,6 +236,121 @@ pub fn swap_window(state: &mut State, direction: Direction) -> Option<DisplayId>
     }
 }
 
+/// Move the focused tiled window to the front of its display's layout order.
+/// Returns None if it's not tiled or already first.
+pub fn zoom_window(state: &mut State) -> Option<(DisplayId, WindowId)> {
+    let focused_id = state.focused?;
+    let focused_window = state.windows.get(&focused_id)?;
+    if !focused_window.is_tiled() {
+        return None;
+    }
+
+    let display_id = focused_window.display_id;
+    if state.main_window_on_display(display_id) == Some(focused_id) {
+        return None;
+    }
+
+    let display = state.displays.get_mut(&display_id)?;
+    display.window_order.retain(|&id| id != focused_id);
+    display.window_order.insert(0, focused_id);
+    tracing::info!("Zoomed window {} to main slot", focused_id);
+    Some((display_id, focused_id))
+}
+
+/// Focus one level up: a dialog's parent window, or the main window for a stacked window
+pub fn focus_parent(state: &State) -> Option<(WindowId, i32)> {
+    let focused = state.windows.get(&state.focused?)?;
+    let parent_id = focused
+        .parent_id
+        .filter(|id| state.windows.contains_key(id))
+        .or_else(|| {
+            state
+                .main_window_on_display(focused.display_id)
+                .filter(|&id| focused.is_tiled() && id != focused.id)
+        })?;
+    let parent = state.windows.get(&parent_id)?;
+    Some((parent.id, parent.pid))
+}
+
+/// Focus one level down: the focused window's most recently focused dialog, or for the
+/// main window the most recently focused stacked window (next in layout order if none)
+pub fn focus_child(state: &State) -> Option<(WindowId, i32)> {
+    let focused = state.windows.get(&state.focused?)?;
+    let display = state.displays.get(&focused.display_id)?;
+    let is_visible = |w: &Window| {
+        w.display_id == focused.display_id
+            && state.shows_on_tags(w, display.visible_tags)
+            && !w.is_hidden()
+    };
+    let dialogs: Vec<&Window> = state
+        .windows
+        .values()
+        .filter(|w| w.parent_id == Some(focused.id) && is_visible(w))
+        .collect();
+    let child = most_recently_focused(state, &dialogs).or_else(|| {
+        if state.main_window_on_display(focused.display_id) != Some(focused.id) {
+            return None;
+        }
+        let stacked: Vec<&Window> = visible_windows_on_display(state, focused.display_id)
+            .into_iter()
+            .skip(1)
+            .collect();
+        most_recently_focused(state, &stacked)
+    })?;
+    Some((child.id, child.pid))
+}
+
+fn most_recently_focused<'a>(state: &State, candidates: &[&'a Window]) -> Option<&'a Window> {
+    state
+        .focus_history
+        .iter()
+        .rev()
+        .find_map(|id| candidates.iter().find(|w| w.id == *id).copied())
+        .or_else(|| candidates.first().copied())
+}
+
+/// Swap the focused floating window with the main tiled window of its display:
+/// the float is tiled in the main slot and the main window floats with the float's frame.
+pub fn swap_float(state: &mut State) -> Option<SwapFloatResult> {
+    let float_id = state.focused?;
+    let float = state.windows.get(&float_id)?;
+    if !float.is_floating || float.is_fullscreen {
+        return None;
+    }
+    let display_id = float.display_id;
+    let frame = float.frame;
+    let main_id = state.main_window_on_display(display_id)?;
+
+    let display = state.displays.get_mut(&display_id)?;
+    display.window_order.retain(|&id| id != float_id);
+    let main_idx = display
+        .window_order
+        .iter()
+        .position(|&id| id == main_id)
+        .unwrap_or(0);
+    display.window_order.insert(main_idx, float_id);
+
+    let float = state.windows.get_mut(&float_id)?;
+    float.is_floating = false;
+    float.floated_by_tag = false;
+    let main = state.windows.get_mut(&main_id)?;
+    main.is_floating = true;
+    let floated_pid = main.pid;
+
+    tracing::info!(
+        "Swapped floating window {} with main window {}",
+        float_id,
+        main_id
+    );
+    Some(SwapFloatResult {
+        display_id,
+        tiled_id: float_id,
+        floated_id: main_id,
+        floated_pid,
+        frame,
+    })
+}
+
 fn find_swap_target(state: &State, direction: Direction) -> Option<WindowId> {
     let visible_tags = state.visible_tags();
     let visible: Vec<_> = state
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
+
 use super::super::window::Rect;
-use super::super::{Window, WindowId};
+use super::super::{Tag, Window, WindowId};
 use crate::macos::DisplayId;
+use yashiki_ipc::{FloatVisibility, GapScaleMode, OuterGap, RaisePolicy, WindowGeometry};
 
 use super::super::state::{State, WindowMove};
 
@@ -102,6 +105,8 @@ pub fn compute_global_hide_position(state: &State) -> (i32, i32) {
 /// - top-left: offset both x and y
 ///
 /// A corner is unsafe if there's an adjacent display in the direction the window extends.
+/// Safe corners that are obstructed (a hot corner, or next to the Dock) are only used
+/// when no unobstructed safe corner is left.
 pub fn compute_hide_position_for_display(
     state: &State,
     display_id: DisplayId,
@@ -120,8 +125,10 @@ pub fn compute_hide_position_for_display(
     let has_bottom = has_bottom_adjacent_display(state, display_id, frame);
     let has_top = has_top_adjacent_display(state, display_id, frame);
 
+    let obstructed = display.obstructed_corners;
+
     // 4 corner candidates with their safety conditions
-    // (corner_x, corner_y, is_unsafe)
+    // (corner_x, corner_y, is_unsafe, is_obstructed)
     // Position is calculated so that 1px of window remains visible at the corner
     let corners = [
         // bottom-right: window at (display_right - 1, display_bottom - 1)
@@ -130,6 +137,7 @@ pub fn compute_hide_position_for_display(
             frame.x + frame.width as i32 - 1,
             frame.y + frame.height as i32 - 1,
             has_right || has_bottom,
+            obstructed.bottom_right,
         ),
         // bottom-left: window at (display_left - window_width + 1, display_bottom - 1)
         // Window extends right (into display) but only 1px visible, rest extends left
@@ -137,6 +145,7 @@ pub fn compute_hide_position_for_display(
             frame.x - window_width as i32 + 1,
             frame.y + frame.height as i32 - 1,
             has_left || has_bottom,
+            obstructed.bottom_left,
         ),
         // top-right: window at (display_right - 1, display_top - window_height + 1)
         // Window extends down (into display) but only 1px visible, rest extends up
@@ -144,20 +153,24 @@ pub fn compute_hide_position_for_display(
             frame.x + frame.width as i32 - 1,
             frame.y - window_height as i32 + 1,
             has_right || has_top,
+            obstructed.top_right,
         ),
         // top-left: offset both x and y
         (
             frame.x - window_width as i32 + 1,
             frame.y - window_height as i32 + 1,
             has_left || has_top,
+            obstructed.top_left,
         ),
     ];
 
-    // Find first safe corner
-    for (x, y, is_unsafe) in corners {
-        if !is_unsafe {
-            return (x, y);
-        }
+    // Find first safe corner, preferring unobstructed ones
+    let safe = || corners.iter().filter(|(_, _, is_unsafe, _)| !is_unsafe);
+    if let Some((x, y, _, _)) = safe()
+        .find(|(_, _, _, is_obstructed)| !is_obstructed)
+        .or_else(|| safe().next())
+    {
+        return (*x, *y);
     }
 
     // Fallback: use bottom-right (shouldn't normally reach here unless surrounded)
@@ -167,6 +180,14 @@ pub fn compute_hide_position_for_display(
     )
 }
 
+/// Whether a window belongs on screen while `visible_tags` are shown on its display
+pub fn shows_on_tags(state: &State, window: &Window, visible_tags: Tag) -> bool {
+    window.tags.intersects(visible_tags)
+        || (window.is_floating
+            && state.config.float_visibility == FloatVisibility::Always
+            && window.tags.mask() != 0)
+}
+
 pub fn compute_layout_changes_for_display(
     state: &mut State,
     display_id: DisplayId,
@@ -182,11 +203,12 @@ pub fn compute_layout_changes_for_display(
     let mut windows_to_hide: Vec<(WindowId, u32, u32)> = Vec::new();
 
     for window in state.windows.values() {
-        if window.display_id != display_id {
+        // Native fullscreen windows sit on their own Space and can't be moved
+        if window.display_id != display_id || window.is_native_fullscreen {
             continue;
         }
 
-        let should_be_visible = window.tags.intersects(visible_tags);
+        let should_be_visible = shows_on_tags(state, window, visible_tags);
         let is_visible = !window.is_hidden();
 
         tracing::debug!(
@@ -266,6 +288,17 @@ pub fn compute_layout_changes_for_display(
     moves
 }
 
+/// Put moves that show windows before moves that hide them, so a tag switch never
+/// leaves the desktop empty in between. The sort is stable within each group.
+pub fn order_moves_show_first(state: &State, moves: &mut [WindowMove]) {
+    moves.sort_by_key(|m| {
+        state
+            .windows
+            .get(&m.window_id)
+            .is_some_and(|w| w.is_hidden())
+    });
+}
+
 pub fn visible_windows_on_display(state: &State, display_id: DisplayId) -> Vec<&Window> {
     let Some(display) = state.displays.get(&display_id) else {
         return vec![];
@@ -292,6 +325,204 @@ pub fn visible_windows_on_display(state: &State, display_id: DisplayId) -> Vec<&
     windows
 }
 
+/// Move geometries the layout engine marked as hidden to the display's hide position.
+/// Geometries are relative to the display frame, as passed to `apply_layout`.
+pub fn park_hidden_geometries(
+    state: &State,
+    display_id: DisplayId,
+    geometries: &mut [WindowGeometry],
+) {
+    let Some(frame) = state.displays.get(&display_id).map(|d| d.frame) else {
+        return;
+    };
+    for geom in geometries.iter_mut().filter(|g| g.hidden) {
+        let (hide_x, hide_y) =
+            compute_hide_position_for_display(state, display_id, geom.width, geom.height);
+        geom.x = hide_x - frame.x;
+        geom.y = hide_y - frame.y;
+    }
+}
+
+/// Grow tiles into the gaps they share with other tiles by the corner compensation,
+/// so the rounded corners of neighbours look as far apart as their straight edges.
+/// Edges on the outer gap are left alone, and an edge grows at most half the gap to the
+/// nearest tile beyond it so neighbours never overlap. Geometries are relative to the display frame.
+pub fn compensate_corners(state: &State, display_id: DisplayId, geometries: &mut [WindowGeometry]) {
+    let px = state.config.corner_compensation;
+    let Some(frame) = state.displays.get(&display_id).map(|d| d.frame) else {
+        return;
+    };
+    if px == 0 {
+        return;
+    }
+    let outer_gap = outer_gap_for_display(state, display_id);
+    let left = outer_gap.left as i32;
+    let top = outer_gap.top as i32;
+    let right = frame.width as i32 - outer_gap.right as i32;
+    let bottom = frame.height as i32 - outer_gap.bottom as i32;
+    let px = px as i32;
+    let bounds: Vec<_> = geometries
+        .iter()
+        .filter(|g| !g.hidden)
+        .map(geometry_bounds)
+        .collect();
+    for geom in geometries.iter_mut().filter(|g| !g.hidden) {
+        let (x0, y0, x1, y1) = geometry_bounds(geom);
+        let beside = bounds.iter().filter(|b| b.1 < y1 && b.3 > y0);
+        let stacked = bounds.iter().filter(|b| b.0 < x1 && b.2 > x0);
+        if x0 > left {
+            let growth = edge_growth(px, beside.clone().map(|b| x0 - b.2));
+            geom.x -= growth;
+            geom.width += growth as u32;
+        }
+        if x1 < right {
+            geom.width += edge_growth(px, beside.map(|b| b.0 - x1)) as u32;
+        }
+        if y0 > top {
+            let growth = edge_growth(px, stacked.clone().map(|b| y0 - b.3));
+            geom.y -= growth;
+            geom.height += growth as u32;
+        }
+        if y1 < bottom {
+            geom.height += edge_growth(px, stacked.map(|b| b.1 - y1)) as u32;
+        }
+    }
+}
+
+/// Left, top, right and bottom edges of a geometry
+fn geometry_bounds(geom: &WindowGeometry) -> (i32, i32, i32, i32) {
+    (
+        geom.x,
+        geom.y,
+        geom.x + geom.width as i32,
+        geom.y + geom.height as i32,
+    )
+}
+
+/// `px`, limited to half of the nearest non-negative gap (tiles behind the edge give negative ones)
+fn edge_growth(px: i32, gaps: impl Iterator<Item = i32>) -> i32 {
+    gaps.filter(|gap| *gap >= 0)
+        .min()
+        .map_or(px, |gap| px.min(gap / 2))
+}
+
+/// Put the focused or main window on top of the stacking, per the raise policy.
+/// Geometries are in layout order, so the first one is the main slot.
+pub fn apply_raise_policy(state: &State, display_id: DisplayId, geometries: &mut [WindowGeometry]) {
+    let target = match state.config.raise_policy {
+        RaisePolicy::None => return,
+        RaisePolicy::FocusedTop => state.focused.filter(|id| {
+            state
+                .windows
+                .get(id)
+                .is_some_and(|w| w.display_id == display_id)
+        }),
+        RaisePolicy::MainTop => geometries.first().map(|g| g.id),
+    };
+    let top = geometries
+        .iter()
+        .filter_map(|g| g.raise_order)
+        .max()
+        .map_or(0, |order| order + 1);
+    if let Some(geom) = geometries
+        .iter_mut()
+        .find(|g| Some(g.id) == target && !g.hidden)
+    {
+        geom.raise_order = Some(top);
+    }
+}
+
+/// Outer gap in points for the display, scaled by its backing scale factor in auto mode
+pub fn outer_gap_for_display(state: &State, display_id: DisplayId) -> OuterGap {
+    let gap = state.config.outer_gap;
+    match (state.config.gap_scale, state.displays.get(&display_id)) {
+        (GapScaleMode::Auto, Some(display)) if display.scale_factor > 0.0 => {
+            gap.scaled(1.0 / display.scale_factor)
+        }
+        _ => gap,
+    }
+}
+
+pub fn app_visibility_changes(state: &mut State) -> (Vec<i32>, Vec<i32>) {
+    let mut any_visible: HashMap<i32, bool> = HashMap::new();
+    for window in state.windows.values() {
+        *any_visible.entry(window.pid).or_default() |= !window.is_hidden();
+    }
+
+    let hide_apps = state.config.hide_apps;
+    let to_unhide: Vec<i32> = state
+        .hidden_apps
+        .iter()
+        .filter(|pid| !hide_apps || any_visible.get(pid).copied().unwrap_or(true))
+        .copied()
+        .collect();
+    let to_hide: Vec<i32> = if hide_apps {
+        any_visible
+            .into_iter()
+            .filter(|(pid, visible)| !visible && !state.is_app_hidden(*pid))
+            .map(|(pid, _)| pid)
+            .collect()
+    } else {
+        vec![]
+    };
+
+    for pid in &to_unhide {
+        state.hidden_apps.remove(pid);
+    }
+    state.hidden_apps.extend(&to_hide);
+    (to_hide, to_unhide)
+}
+
+pub fn window_shadow_changes(state: &mut State) -> (Vec<WindowId>, Vec<WindowId>) {
+    // Closed windows need no restore
+    state
+        .shadowless_windows
+        .retain(|id| state.windows.contains_key(id));
+
+    let hide_shadows = state.config.hide_shadows;
+    let mut to_remove: Vec<WindowId> = if hide_shadows {
+        state
+            .windows
+            .values()
+            .filter(|w| w.is_tiled() && !state.shadowless_windows.contains(&w.id))
+            .map(|w| w.id)
+            .collect()
+    } else {
+        vec![]
+    };
+    let mut to_restore: Vec<WindowId> = state
+        .shadowless_windows
+        .iter()
+        .filter(|id| !hide_shadows || !state.windows[id].is_tiled())
+        .copied()
+        .collect();
+    to_remove.sort();
+    to_restore.sort();
+    (to_remove, to_restore)
+}
+
+pub fn window_opacity_changes(state: &mut State) -> Vec<(WindowId, u32)> {
+    // Closed windows need no restore
+    state
+        .window_alpha
+        .retain(|id, _| state.windows.contains_key(id));
+
+    let mut changes: Vec<(WindowId, u32)> = state
+        .windows
+        .values()
+        .filter_map(|w| {
+            let target = match w.opacity {
+                Some(percent) if state.focused != Some(w.id) => percent,
+                _ => 100,
+            };
+            let current = state.window_alpha.get(&w.id).copied().unwrap_or(100);
+            (target != current).then_some((w.id, target))
+        })
+        .collect();
+    changes.sort();
+    changes
+}
+
 pub fn add_to_window_order(state: &mut State, window_id: WindowId, display_id: DisplayId) {
     if let Some(display) = state.displays.get_mut(&display_id) {
         if !display.window_order.contains(&window_id) {
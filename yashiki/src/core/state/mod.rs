@@ -1,13 +1,16 @@
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use super::{Config, Display, RulesEngine, Tag, Window, WindowId};
+use super::{
+    Config, Display, ErrorLog, LatencyStats, Rect, RulesEngine, Tag, WakeupStats, Window, WindowId,
+};
 use crate::effect::Effect;
 use crate::event::Event;
 use crate::macos::DisplayId;
 use crate::platform::WindowSystem;
 use yashiki_ipc::{
-    Direction, OutputDirection, OutputSpecifier, RuleAction, RuleMatcher, WindowRule,
+    CloseBehavior, Direction, HookContext, OuterGap, OutputDirection, OutputSpecifier, RuleAction,
+    RuleMatcher, TagMode, TagOccupancy, WindowGeometry, WindowRule,
 };
 
 /// Information about a window that was ignored by rule, tracked for re-evaluation.
@@ -19,6 +22,7 @@ pub struct IgnoredWindowInfo {
     pub added_at: Instant,
 }
 
+mod arrangement;
 mod display;
 mod focus;
 mod layout;
@@ -26,6 +30,7 @@ mod rules;
 mod sync;
 mod tags;
 
+use arrangement::*;
 use display::*;
 use focus::*;
 use layout::*;
@@ -58,6 +63,65 @@ pub struct SendToOutputResult {
     pub window_moves: Vec<WindowMove>,
 }
 
+/// Result of bring_window operation
+#[derive(Debug)]
+pub struct BringWindowResult {
+    pub window_id: WindowId,
+    pub pid: i32,
+    pub source_display_id: DisplayId,
+    pub target_display_id: DisplayId,
+    pub window_moves: Vec<WindowMove>,
+}
+
+/// Result of unstash operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnstashResult {
+    pub window_id: WindowId,
+    pub pid: i32,
+    /// Display the window was stashed from, and the one it is brought back on
+    pub source_display_id: DisplayId,
+    pub target_display_id: DisplayId,
+    pub window_moves: Vec<WindowMove>,
+}
+
+/// Where a window was when its arrangement was saved
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedPlacement {
+    pub app_key: String,
+    pub title: String,
+    pub tags: Tag,
+    pub display_id: DisplayId,
+    pub is_floating: bool,
+    /// On-screen frame (the saved frame of hidden windows)
+    pub frame: Rect,
+}
+
+/// Result of restore_arrangement operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrangementRestoreResult {
+    /// Windows put back, and saved windows with no open match
+    pub restored: usize,
+    pub missing: usize,
+    /// Displays windows were moved from or to
+    pub display_ids: Vec<DisplayId>,
+    pub window_moves: Vec<WindowMove>,
+    /// Visible floating windows with the frame to move them to
+    pub floating_frames: Vec<(WindowId, i32, Rect)>,
+}
+
+/// Result of swap_float operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapFloatResult {
+    pub display_id: DisplayId,
+    /// Formerly floating window, now tiled in the main slot
+    pub tiled_id: WindowId,
+    /// Formerly main window, now floating
+    pub floated_id: WindowId,
+    pub floated_pid: i32,
+    /// Frame the floated window takes over from the formerly floating one
+    pub frame: Rect,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowMove {
     pub window_id: WindowId,
@@ -68,10 +132,15 @@ pub struct WindowMove {
     pub new_y: i32,
 }
 
+/// Process launched by `exec`, kept until it exits
 #[derive(Debug, Clone)]
-pub struct TrackedProcess {
+pub struct SpawnedProcess {
     pub pid: u32,
-    pub _command: String,
+    pub command: String,
+    /// Name given with `exec --unique`
+    pub name: Option<String>,
+    /// Terminated when yashiki quits
+    pub track: bool,
 }
 
 /// Tracks intentional focus operations to suppress spurious macOS focus changes.
@@ -123,8 +192,12 @@ pub struct State {
     pub(crate) default_tag: Tag,
     pub default_layout: String,
     pub tag_layouts: HashMap<u8, String>,
+    /// Tags whose windows always float
+    pub float_tags: Tag,
+    /// Input source selected when each tag is viewed
+    pub tag_input_sources: HashMap<u8, String>,
     pub rules_engine: RulesEngine,
-    pub tracked_processes: Vec<TrackedProcess>,
+    pub spawned_processes: Vec<SpawnedProcess>,
     pub config: Config,
     /// Windows that were ignored by rule, tracked for re-evaluation when attributes change.
     pub ignored_windows: HashMap<WindowId, IgnoredWindowInfo>,
@@ -134,6 +207,59 @@ pub struct State {
     pub focus_intent: Option<FocusIntent>,
     /// State for auto-raise (focus follows mouse) feature.
     pub auto_raise_state: AutoRaiseState,
+    /// Tags each app was last used on (keyed by app_key), for the app-sticky new window policy.
+    pub app_tags: HashMap<String, Tag>,
+    /// Last focused window per (display, tag number), restored when switching back to the tag.
+    pub tag_focus: HashMap<(DisplayId, u32), WindowId>,
+    /// Recently focused windows, most recent last. Closed windows are skipped on lookup.
+    pub focus_history: Vec<WindowId>,
+    /// Windows hidden by window-stash, most recent last. Closed windows are skipped on unstash.
+    pub stash: Vec<WindowId>,
+    /// Window arrangements saved with arrangement-save, by name
+    pub arrangements: HashMap<String, Vec<SavedPlacement>>,
+    /// Retiling and rule-driven window moves are suspended while paused.
+    pub paused: bool,
+    /// Windows discovered while paused, whose rules are applied on resume.
+    pub deferred_new_windows: Vec<WindowId>,
+    /// Window, display and app events are ignored while the screen is locked,
+    /// and a full sync runs on unlock.
+    pub screen_locked: bool,
+    /// Commands that run programs are rejected (`start --disable-exec`).
+    pub exec_disabled: bool,
+    /// Apps hidden by yashiki because all their windows are on hidden tags.
+    pub hidden_apps: HashSet<i32>,
+    /// Apps hidden with window-hide-app, most recent last.
+    pub user_hidden_apps: Vec<i32>,
+    /// Tiled windows whose shadows yashiki removed (set-window-shadows off).
+    pub shadowless_windows: HashSet<WindowId>,
+    /// The window server refused a shadow change, already reported once.
+    pub window_shadow_refused: bool,
+    /// Opacity in percent that yashiki gave windows (opacity rule), if not fully opaque.
+    pub window_alpha: HashMap<WindowId, u32>,
+    /// The window server refused an opacity change, already reported once.
+    pub window_alpha_refused: bool,
+    /// Processes whose windows are yashiki's own (overlays, helpers) and never managed.
+    pub internal_pids: HashSet<i32>,
+    /// Last sync of apps with a throttle rule, by pid
+    pub throttle_last_sync: HashMap<i32, Instant>,
+    /// Throttled apps with held-back move/resize events, and when they are due for a sync
+    pub throttle_pending: HashMap<i32, Instant>,
+    /// Main run loop wakeups, reported by `get-state`
+    pub wakeups: WakeupStats,
+    /// Command handling latencies, reported by `stats`
+    pub latency: LatencyStats,
+    /// Asynchronous failures, reported by `last-errors` and as error events
+    pub errors: ErrorLog,
+    /// When the last retile ran, so retile hooks run once per burst of retiles
+    pub last_retile: Option<Instant>,
+    /// Post-retile hook context held back until retiles settle
+    pub pending_post_retile: Option<HookContext>,
+    /// Pids whose move/resize syncs are deferred until the running drag ends
+    pub drag_deferred_pids: HashSet<i32>,
+    /// Cursor warp target held back until the running drag ends
+    pub deferred_warp: Option<(i32, i32)>,
+    /// macOS Stage Manager is on; it fights off-screen hiding and tiling
+    pub stage_manager: bool,
 }
 
 impl State {
@@ -146,13 +272,41 @@ impl State {
             default_tag: Tag::new(1),
             default_layout: "tatami".to_string(),
             tag_layouts: HashMap::new(),
+            float_tags: Tag::from_mask(0),
+            tag_input_sources: HashMap::new(),
             rules_engine: RulesEngine::new(),
-            tracked_processes: Vec::new(),
+            spawned_processes: Vec::new(),
             config: Config::new(),
             ignored_windows: HashMap::new(),
             saved_display_tags: HashMap::new(),
             focus_intent: None,
             auto_raise_state: AutoRaiseState::default(),
+            app_tags: HashMap::new(),
+            tag_focus: HashMap::new(),
+            focus_history: Vec::new(),
+            stash: Vec::new(),
+            arrangements: HashMap::new(),
+            paused: false,
+            deferred_new_windows: Vec::new(),
+            screen_locked: false,
+            exec_disabled: false,
+            hidden_apps: HashSet::new(),
+            user_hidden_apps: Vec::new(),
+            shadowless_windows: HashSet::new(),
+            window_shadow_refused: false,
+            window_alpha: HashMap::new(),
+            window_alpha_refused: false,
+            internal_pids: HashSet::new(),
+            throttle_last_sync: HashMap::new(),
+            throttle_pending: HashMap::new(),
+            wakeups: WakeupStats::default(),
+            latency: LatencyStats::default(),
+            errors: ErrorLog::default(),
+            last_retile: None,
+            pending_post_retile: None,
+            drag_deferred_pids: HashSet::new(),
+            deferred_warp: None,
+            stage_manager: false,
         }
     }
 
@@ -239,6 +393,12 @@ impl State {
         self.windows.values().any(|w| w.pid == pid)
     }
 
+    pub fn spawned_process_by_name(&self, name: &str) -> Option<&SpawnedProcess> {
+        self.spawned_processes
+            .iter()
+            .find(|p| p.name.as_deref() == Some(name))
+    }
+
     /// Find the topmost visible window at the given screen coordinates.
     /// Returns (window_id, pid) if a window is found.
     pub fn find_window_at_point(&self, x: i32, y: i32) -> Option<(WindowId, i32)> {
@@ -253,7 +413,7 @@ impl State {
             .values()
             .filter(|w| {
                 w.display_id == display.id
-                    && w.tags.intersects(display.visible_tags)
+                    && self.shows_on_tags(w, display.visible_tags)
                     && !w.is_hidden()
             })
             .find(|w| {
@@ -310,6 +470,26 @@ impl State {
         None
     }
 
+    /// Moves bringing a process's hidden windows back to where they were before hiding,
+    /// for windows yashiki stops managing while the process keeps running
+    pub fn restore_moves_for_pid(&self, pid: i32) -> Vec<WindowMove> {
+        self.windows
+            .values()
+            .filter(|w| w.pid == pid)
+            .filter_map(|w| {
+                let saved = w.saved_frame?;
+                Some(WindowMove {
+                    window_id: w.id,
+                    pid: w.pid,
+                    old_x: w.frame.x,
+                    old_y: w.frame.y,
+                    new_x: saved.x,
+                    new_y: saved.y,
+                })
+            })
+            .collect()
+    }
+
     /// Remove all windows belonging to a terminated process.
     /// Used when AppTerminated event is received - bypasses AX API checks since
     /// the process is confirmed terminated via NSWorkspace notification.
@@ -332,6 +512,9 @@ impl State {
         for id in &ignored_ids {
             self.ignored_windows.remove(id);
         }
+        self.throttle_last_sync.remove(&pid);
+        self.throttle_pending.remove(&pid);
+        self.user_hidden_apps.retain(|&p| p != pid);
 
         if window_ids.is_empty() {
             return !ignored_ids.is_empty();
@@ -411,6 +594,14 @@ impl State {
         sync_pid(self, ws, pid)
     }
 
+    pub fn on_screen_pids_for_display<W: WindowSystem>(
+        &self,
+        ws: &W,
+        display_id: DisplayId,
+    ) -> Vec<i32> {
+        on_screen_pids_for_display(self, ws, display_id)
+    }
+
     pub fn sync_windows_for_display<W: WindowSystem>(
         &mut self,
         ws: &W,
@@ -435,6 +626,31 @@ impl State {
                 let (changed, _, rehide_moves) = self.sync_pid(ws, *pid);
                 (changed, vec![], rehide_moves)
             }
+            Event::WindowTitleChanged { pid } => {
+                // Titles only matter to dynamic rules; SPA apps retitle constantly
+                if !self.rules_engine.has_dynamic_rules() {
+                    return (false, vec![], vec![]);
+                }
+                let titles: HashMap<WindowId, String> = self
+                    .windows
+                    .values()
+                    .filter(|w| w.pid == *pid)
+                    .map(|w| (w.id, w.title.clone()))
+                    .collect();
+                let (mut changed, _, mut moves) = self.sync_pid(ws, *pid);
+                for (window_id, title) in titles {
+                    if self
+                        .windows
+                        .get(&window_id)
+                        .is_some_and(|w| w.title != title)
+                    {
+                        let (rules_changed, rule_moves) = self.apply_dynamic_rules(window_id);
+                        changed |= rules_changed;
+                        moves.extend(rule_moves);
+                    }
+                }
+                (changed, vec![], moves)
+            }
             Event::FocusedWindowChanged => {
                 let (changed, new_ids) = self.sync_focused_window(ws);
                 (changed, new_ids, vec![])
@@ -453,7 +669,71 @@ impl State {
         if self.focused != window_id {
             tracing::info!("Focus changed: {:?} -> {:?}", self.focused, window_id);
             self.focused = window_id;
+            if let Some(id) = window_id {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.is_urgent = false;
+                }
+                remember_app_tags(self, id);
+                remember_tag_focus(self, id);
+                remember_focus_history(self, id);
+            }
+        }
+    }
+
+    /// Whether the app's windows are off screen because yashiki or the user hid the app
+    pub fn is_app_hidden(&self, pid: i32) -> bool {
+        self.hidden_apps.contains(&pid) || self.user_hidden_apps.contains(&pid)
+    }
+
+    /// Record that the focused window's app is being hidden with window-hide-app.
+    /// Returns the app's pid.
+    pub fn hide_focused_app(&mut self) -> Result<i32, String> {
+        let window = self
+            .focused
+            .and_then(|id| self.windows.get(&id))
+            .ok_or("No focused window")?;
+        let pid = window.pid;
+        tracing::info!("Hiding app {} (pid {})", window.app_name, pid);
+        self.user_hidden_apps.retain(|&p| p != pid);
+        self.user_hidden_apps.push(pid);
+        for window in self.windows.values_mut().filter(|w| w.pid == pid) {
+            window.is_app_hidden = true;
+        }
+        Ok(pid)
+    }
+
+    /// Bring the windows of an app hidden with window-hide-app back into layouts.
+    /// Returns false if the app was not hidden that way.
+    pub fn app_unhidden(&mut self, pid: i32) -> bool {
+        if !self.user_hidden_apps.contains(&pid) {
+            return false;
+        }
+        tracing::info!("App returned from hiding (pid {})", pid);
+        self.user_hidden_apps.retain(|&p| p != pid);
+        for window in self.windows.values_mut().filter(|w| w.pid == pid) {
+            window.is_app_hidden = false;
         }
+        true
+    }
+
+    /// Mark a window as needing attention (cleared when it is focused).
+    /// Returns false if the window is not managed.
+    pub fn set_urgent(&mut self, window_id: WindowId, urgent: bool) -> bool {
+        let Some(window) = self.windows.get_mut(&window_id) else {
+            return false;
+        };
+        // The focused window already has the user's attention
+        window.is_urgent = urgent && self.focused != Some(window_id);
+        tracing::info!("Window {} urgent: {}", window_id, window.is_urgent);
+        true
+    }
+
+    pub fn last_focused_on_visible_tags(&self, display_id: DisplayId) -> Option<WindowId> {
+        last_focused_on_visible_tags(self, display_id)
+    }
+
+    pub fn recently_focused_on_display(&self, display_id: DisplayId) -> Option<WindowId> {
+        recently_focused_on_display(self, display_id)
     }
 
     // Tag operations - delegated to state/tags.rs
@@ -474,10 +754,63 @@ impl State {
         view_tags_last(self)
     }
 
+    pub fn tag_target_displays(&self, display_id: DisplayId) -> Vec<DisplayId> {
+        tag_target_displays(self, display_id)
+    }
+
+    pub fn set_tag_mode(&mut self, mode: TagMode) -> Vec<WindowMove> {
+        set_tag_mode(self, mode)
+    }
+
+    pub fn set_float_tags(&mut self, tags: u32, enabled: bool) -> Vec<WindowMove> {
+        set_float_tags(self, tags, enabled)
+    }
+
+    pub fn set_tag_input_source(&mut self, tags: u32, input_source: Option<&str>) {
+        set_tag_input_source(self, tags, input_source)
+    }
+
+    pub fn input_source_for_tags(&self, tags: Tag) -> Option<&str> {
+        input_source_for_tags(self, tags)
+    }
+
     pub fn move_focused_to_tags(&mut self, tags: u32) -> Vec<WindowMove> {
         move_focused_to_tags(self, tags)
     }
 
+    pub fn tag_occupancy(&self) -> Vec<TagOccupancy> {
+        tag_occupancy(self)
+    }
+
+    pub fn stash_focused(&mut self) -> Vec<WindowMove> {
+        stash_focused(self)
+    }
+
+    pub fn unstash(&mut self) -> Option<UnstashResult> {
+        unstash(self)
+    }
+
+    // Arrangements - delegated to state/arrangement.rs
+
+    pub fn save_arrangement(&mut self, name: &str) -> usize {
+        save_arrangement(self, name)
+    }
+
+    pub fn restore_arrangement(&mut self, name: &str) -> Option<ArrangementRestoreResult> {
+        restore_arrangement(self, name)
+    }
+
+    pub fn distribute_tags(
+        &mut self,
+        from: u32,
+        to: u32,
+        count: Option<u32>,
+        every_other: bool,
+        display_id: DisplayId,
+    ) -> Vec<WindowMove> {
+        distribute_tags(self, from, to, count, every_other, display_id)
+    }
+
     pub fn toggle_focused_window_tags(&mut self, tags: u32) -> Vec<WindowMove> {
         toggle_focused_window_tags(self, tags)
     }
@@ -490,16 +823,40 @@ impl State {
         toggle_focused_float(self)
     }
 
+    pub fn toggle_manual(&mut self, window_id: WindowId) -> Option<(DisplayId, bool)> {
+        toggle_manual(self, window_id)
+    }
+
     // Focus operations - delegated to state/focus.rs
 
     pub fn focus_window(&self, direction: Direction) -> Option<(WindowId, i32)> {
         focus_window(self, direction)
     }
 
+    pub fn cycle_app_window(&self, app_id: &str) -> Option<(WindowId, i32, bool)> {
+        cycle_app_window(self, app_id)
+    }
+
     pub fn swap_window(&mut self, direction: Direction) -> Option<DisplayId> {
         swap_window(self, direction)
     }
 
+    pub fn zoom_window(&mut self) -> Option<(DisplayId, WindowId)> {
+        zoom_window(self)
+    }
+
+    pub fn focus_parent(&self) -> Option<(WindowId, i32)> {
+        focus_parent(self)
+    }
+
+    pub fn focus_child(&self) -> Option<(WindowId, i32)> {
+        focus_child(self)
+    }
+
+    pub fn swap_float(&mut self) -> Option<SwapFloatResult> {
+        swap_float(self)
+    }
+
     pub fn focus_output(&mut self, direction: OutputDirection) -> Option<FocusOutputResult> {
         focus_output(self, direction)
     }
@@ -508,12 +865,98 @@ impl State {
         send_to_output(self, direction)
     }
 
+    pub fn bring_window(
+        &mut self,
+        window_id: Option<WindowId>,
+    ) -> Result<BringWindowResult, String> {
+        bring_window(self, window_id)
+    }
+
+    pub fn set_output_ignored(&mut self, display_id: DisplayId, ignored: bool) -> Vec<WindowMove> {
+        set_output_ignored(self, display_id, ignored)
+    }
+
+    pub fn is_output_ignored(&self, display_id: DisplayId) -> bool {
+        is_output_ignored(self, display_id)
+    }
+
+    /// Whether a window with these on-screen bounds sits on an ignored display
+    pub fn is_on_ignored_output(&self, bounds: &crate::macos::Bounds) -> bool {
+        self.is_output_ignored(find_display_for_bounds(self, bounds))
+    }
+
     // Layout operations - delegated to state/layout.rs
 
     pub fn visible_windows_on_display(&self, display_id: DisplayId) -> Vec<&Window> {
         visible_windows_on_display(self, display_id)
     }
 
+    /// Apps to hide and to unhide so that hidden apps are exactly those with no visible window
+    pub fn app_visibility_changes(&mut self) -> (Vec<i32>, Vec<i32>) {
+        app_visibility_changes(self)
+    }
+
+    /// Windows to remove and to restore the shadow of, so that only tiled windows lose it.
+    /// Record each one that was applied with `record_window_shadow`.
+    pub fn window_shadow_changes(&mut self) -> (Vec<WindowId>, Vec<WindowId>) {
+        window_shadow_changes(self)
+    }
+
+    /// Remember a shadow change the window server accepted
+    pub fn record_window_shadow(&mut self, window_id: WindowId, enabled: bool) {
+        if enabled {
+            self.shadowless_windows.remove(&window_id);
+        } else {
+            self.shadowless_windows.insert(window_id);
+        }
+    }
+
+    /// Windows whose opacity (in percent) must change, so that only unfocused windows with
+    /// an opacity rule are translucent. Record each one that was applied with `record_window_alpha`.
+    pub fn window_opacity_changes(&mut self) -> Vec<(WindowId, u32)> {
+        window_opacity_changes(self)
+    }
+
+    /// Remember the opacity a window was actually given
+    pub fn record_window_alpha(&mut self, window_id: WindowId, percent: u32) {
+        if percent == 100 {
+            self.window_alpha.remove(&window_id);
+        } else {
+            self.window_alpha.insert(window_id, percent);
+        }
+    }
+
+    /// Window in the first (main) slot of the display's layout order
+    pub fn main_window_on_display(&self, display_id: DisplayId) -> Option<WindowId> {
+        visible_windows_on_display(self, display_id)
+            .first()
+            .map(|w| w.id)
+    }
+
+    pub fn outer_gap_for_display(&self, display_id: DisplayId) -> OuterGap {
+        outer_gap_for_display(self, display_id)
+    }
+
+    pub fn park_hidden_geometries(&self, display_id: DisplayId, geometries: &mut [WindowGeometry]) {
+        park_hidden_geometries(self, display_id, geometries)
+    }
+
+    pub fn compensate_corners(&self, display_id: DisplayId, geometries: &mut [WindowGeometry]) {
+        compensate_corners(self, display_id, geometries)
+    }
+
+    pub fn apply_raise_policy(&self, display_id: DisplayId, geometries: &mut [WindowGeometry]) {
+        apply_raise_policy(self, display_id, geometries)
+    }
+
+    pub fn order_moves_show_first(&self, moves: &mut [WindowMove]) {
+        order_moves_show_first(self, moves)
+    }
+
+    pub fn shows_on_tags(&self, window: &Window, visible_tags: Tag) -> bool {
+        shows_on_tags(self, window, visible_tags)
+    }
+
     pub(crate) fn compute_layout_changes(&mut self, display_id: DisplayId) -> Vec<WindowMove> {
         compute_layout_changes(self, display_id)
     }
@@ -540,6 +983,30 @@ impl State {
         should_ignore_window(self, app_name, app_id, title, ax_id, subrole)
     }
 
+    pub fn is_internal_app(&self, pid: i32, app_id: Option<&str>) -> bool {
+        is_internal_app(self, pid, app_id)
+    }
+
+    pub fn matching_rules(&self, window_id: WindowId) -> Vec<&WindowRule> {
+        matching_rules(self, window_id)
+    }
+
+    pub fn close_behavior(&self, window_id: WindowId) -> CloseBehavior {
+        close_behavior(self, window_id)
+    }
+
+    pub fn no_manage_resize(&self, window_id: WindowId) -> bool {
+        no_manage_resize(self, window_id)
+    }
+
+    pub fn sync_throttle(&self, pid: i32) -> Option<Duration> {
+        sync_throttle(self, pid)
+    }
+
+    pub fn applied_rule_indices(&self, window_id: WindowId) -> Vec<usize> {
+        applied_rule_indices(self, window_id)
+    }
+
     pub fn apply_rules_to_new_window(&mut self, window_id: WindowId) -> Vec<Effect> {
         apply_rules_to_new_window(self, window_id)
     }
@@ -548,6 +1015,10 @@ impl State {
         apply_rules_to_all_windows(self)
     }
 
+    pub fn apply_dynamic_rules(&mut self, window_id: WindowId) -> (bool, Vec<WindowMove>) {
+        apply_dynamic_rules(self, window_id)
+    }
+
     #[cfg(test)]
     pub fn apply_rules_to_window_extended(
         &self,
@@ -571,11 +1042,15 @@ mod tests {
     use std::time::Duration;
 
     use super::*;
+    use crate::core::Rect;
+    use crate::macos::DisplayInfo;
     use crate::platform::mock::{
         create_test_display, create_test_window, create_test_window_with_layer, MockWindowSystem,
     };
     use layout::compute_hide_position_for_display;
-    use yashiki_ipc::{ButtonInfo, ExtendedWindowAttributes};
+    use yashiki_ipc::{
+        ButtonInfo, ExtendedWindowAttributes, FloatVisibility, GapScaleMode, RaisePolicy,
+    };
 
     /// Age all ignored windows so they're no longer protected by the grace period.
     /// Used in tests that need to verify window removal behavior.
@@ -624,135 +1099,494 @@ mod tests {
     }
 
     #[test]
-    fn test_view_tags_last_toggles_back() {
+    fn test_float_only_tag_floats_and_retiles_windows() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
+        state.set_float_tags(0b10, true);
+
+        state.move_focused_to_tags(0b10);
+        let window = state.windows.get(&100).unwrap();
+        assert!(window.is_floating);
+        assert!(window.floated_by_tag);
+        assert!(window.is_hidden());
 
         state.view_tags(0b10);
-        assert_eq!(state.visible_tags().mask(), 0b10);
+        state.windows.get_mut(&100).unwrap().frame = Rect {
+            x: 200,
+            y: 150,
+            width: 640,
+            height: 480,
+        };
+        state.move_focused_to_tags(0b1);
+        let window = state.windows.get(&100).unwrap();
+        assert!(!window.is_floating);
+        assert_eq!(window.tag_float_frame.map(|f| f.x), Some(200));
 
-        state.view_tags_last();
-        assert_eq!(state.visible_tags().mask(), 0b1);
+        // Remembered floating geometry is restored on the way back
+        state.view_tags(0b1);
+        state.windows.get_mut(&100).unwrap().frame.x = 0;
+        state.move_focused_to_tags(0b10);
+        let window = state.windows.get(&100).unwrap();
+        assert!(window.is_floating);
+        assert_eq!(window.saved_frame.map(|f| (f.x, f.y)), Some((200, 150)));
+
+        state.set_float_tags(0b10, false);
+        assert!(!state.windows.get(&100).unwrap().is_floating);
     }
 
     #[test]
-    fn test_toggle_tags() {
+    fn test_toggle_float_on_float_only_tag_clears_tag_marker() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
-        let display_id = state.focused_display;
+        state.set_float_tags(0b10, true);
+        state.view_tags(0b11);
 
-        state.toggle_tags_on_display(0b10, display_id);
-        assert_eq!(state.visible_tags().mask(), 0b11);
+        state.move_focused_to_tags(0b10);
+        assert!(state.windows.get(&100).unwrap().floated_by_tag);
 
-        state.toggle_tags_on_display(0b01, display_id);
-        assert_eq!(state.visible_tags().mask(), 0b10);
+        // Unfloat and float again by hand: the window now floats by choice
+        state.toggle_focused_float();
+        state.toggle_focused_float();
+        let window = state.windows.get(&100).unwrap();
+        assert!(window.is_floating);
+        assert!(!window.floated_by_tag);
+
+        // so leaving the float-only tag keeps it floating
+        state.move_focused_to_tags(0b1);
+        assert!(state.windows.get(&100).unwrap().is_floating);
     }
 
     #[test]
-    fn test_toggle_tags_prevents_empty() {
+    fn test_float_visibility_always_keeps_floats_on_screen() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
-        let display_id = state.focused_display;
+        state.windows.get_mut(&102).unwrap().is_floating = true;
+        state.config.float_visibility = FloatVisibility::Always;
 
-        let moves = state.toggle_tags_on_display(0b01, display_id);
-        assert_eq!(state.visible_tags().mask(), 0b1);
-        assert!(moves.is_empty());
+        let moves = state.view_tags(0b10);
+        assert_eq!(moves.len(), 2);
+        assert!(state.windows.get(&100).unwrap().is_hidden());
+        assert!(!state.windows.get(&102).unwrap().is_hidden());
+        state.set_focused(Some(102));
+        assert_eq!(state.recently_focused_on_display(1), Some(102));
+
+        // Back to tagged: the float is hidden like any other window off the visible tags
+        state.config.float_visibility = FloatVisibility::Tagged;
+        let moves = state.compute_layout_changes(1);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].window_id, 102);
+        assert!(state.windows.get(&102).unwrap().is_hidden());
     }
 
     #[test]
-    fn test_move_focused_to_tags() {
+    fn test_view_tags_last_toggles_back() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
 
-        let moves = state.move_focused_to_tags(0b10);
-
-        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 0b10);
+        state.view_tags(0b10);
+        assert_eq!(state.visible_tags().mask(), 0b10);
 
-        assert!(!moves.is_empty());
+        state.view_tags_last();
+        assert_eq!(state.visible_tags().mask(), 0b1);
     }
 
     #[test]
-    fn test_focus_window_next() {
+    fn test_order_moves_show_first() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
+        state.move_focused_to_tags(0b10);
+        assert!(state.windows[&100].is_hidden());
 
-        let result = state.focus_window(Direction::Next);
-        assert!(result.is_some());
+        let mut moves = state.view_tags(0b10);
+        assert_eq!(moves.len(), 3);
+        moves.reverse();
+        state.order_moves_show_first(&mut moves);
 
-        let (window_id, _pid) = result.unwrap();
-        assert_eq!(window_id, 101);
+        assert_eq!(moves[0].window_id, 100);
+        assert!(moves[1..].iter().all(|m| m.window_id != 100));
     }
 
     #[test]
-    fn test_focus_window_prev() {
+    fn test_hide_focused_app_keeps_windows_out_of_layout() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
 
-        let result = state.focus_window(Direction::Prev);
-        assert!(result.is_some());
+        assert_eq!(state.hide_focused_app(), Ok(1000));
+        assert!(!state.windows.get(&100).unwrap().is_tiled());
+        assert!(state.is_app_hidden(1000));
 
-        let (window_id, _pid) = result.unwrap();
-        assert_eq!(window_id, 102);
+        // Hidden windows are off the on-screen list but still exist
+        let mut ws = ws;
+        ws.windows.retain(|w| w.pid != 1000);
+        state.sync_pid(&ws, 1000);
+        assert!(state.windows.contains_key(&100));
+
+        assert!(state.app_unhidden(1000));
+        assert!(state.windows.get(&100).unwrap().is_tiled());
+        assert!(state.user_hidden_apps.is_empty());
+        assert!(!state.app_unhidden(1000));
+
+        state.focused = None;
+        assert!(state.hide_focused_app().is_err());
     }
 
     #[test]
-    fn test_focus_window_directional() {
+    fn test_app_visibility_changes_hides_apps_without_visible_windows() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
 
-        let result = state.focus_window(Direction::Right);
-        assert!(result.is_some());
+        state.view_tags(0b10);
+        assert_eq!(state.app_visibility_changes(), (vec![], vec![]));
+
+        state.config.hide_apps = true;
+        let (mut to_hide, to_unhide) = state.app_visibility_changes();
+        to_hide.sort();
+        assert_eq!(to_hide, vec![1000, 1001, 1002]);
+        assert!(to_unhide.is_empty());
+        assert_eq!(state.app_visibility_changes(), (vec![], vec![]));
+
+        state.view_tags(0b1);
+        let (to_hide, mut to_unhide) = state.app_visibility_changes();
+        to_unhide.sort();
+        assert!(to_hide.is_empty());
+        assert_eq!(to_unhide, vec![1000, 1001, 1002]);
+        assert!(state.hidden_apps.is_empty());
 
-        let (window_id, _pid) = result.unwrap();
-        assert_eq!(window_id, 101);
+        state.view_tags(0b10);
+        state.app_visibility_changes();
+        state.config.hide_apps = false;
+        let (_, to_unhide) = state.app_visibility_changes();
+        assert_eq!(to_unhide.len(), 3);
+        assert!(state.hidden_apps.is_empty());
     }
 
     #[test]
-    fn test_multi_display_focus_output() {
-        let ws = MockWindowSystem::new()
-            .with_displays(vec![
-                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
-                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
-            ])
-            .with_windows(vec![
-                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
-                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
-            ])
-            .with_focused(Some(100));
-
+    fn test_window_shadow_changes_follow_tiling() {
+        let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
-
-        assert_eq!(state.focused_display, 1);
-
-        let result = state.focus_output(OutputDirection::Next);
-        assert!(result.is_some());
-        assert_eq!(state.focused_display, 2);
-
-        match result.unwrap() {
-            FocusOutputResult::Window { window_id, .. } => assert_eq!(window_id, 101),
-            FocusOutputResult::EmptyDisplay { .. } => panic!("Expected Window result"),
+        assert_eq!(state.window_shadow_changes(), (vec![], vec![]));
+
+        state.config.hide_shadows = true;
+        let (to_remove, to_restore) = state.window_shadow_changes();
+        assert_eq!(to_remove, vec![100, 101, 102]);
+        assert!(to_restore.is_empty());
+        // Not applied yet, so still wanted
+        assert_eq!(state.window_shadow_changes(), (vec![100, 101, 102], vec![]));
+        for id in to_remove {
+            state.record_window_shadow(id, false);
         }
+        assert_eq!(state.window_shadow_changes(), (vec![], vec![]));
+
+        // Floating windows get their shadow back
+        state.windows.get_mut(&101).unwrap().is_floating = true;
+        assert_eq!(state.window_shadow_changes(), (vec![], vec![101]));
+        state.record_window_shadow(101, true);
+
+        // Closed windows are forgotten without a restore
+        state.windows.remove(&102);
+        assert_eq!(state.window_shadow_changes(), (vec![], vec![]));
+        assert_eq!(state.shadowless_windows, HashSet::from([100]));
+
+        state.config.hide_shadows = false;
+        assert_eq!(state.window_shadow_changes(), (vec![], vec![100]));
+        state.record_window_shadow(100, true);
+        assert!(state.shadowless_windows.is_empty());
     }
 
     #[test]
-    fn test_focus_output_empty_display() {
-        let ws = MockWindowSystem::new()
-            .with_displays(vec![
-                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
-                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
-            ])
-            .with_windows(vec![create_test_window(
-                100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0,
-            )])
-            .with_focused(Some(100));
+    fn test_window_opacity_changes_follow_focus() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Terminal")), None),
+            RuleAction::Opacity { percent: 90 },
+        ));
+        state.sync_all(&ws);
+        state.apply_rules_to_new_window(101);
+        assert_eq!(state.windows.get(&101).unwrap().opacity, Some(90));
+
+        assert_eq!(state.window_opacity_changes(), vec![(101, 90)]);
+        // Not applied yet, so still wanted
+        assert_eq!(state.window_opacity_changes(), vec![(101, 90)]);
+        state.record_window_alpha(101, 90);
+        assert_eq!(state.window_opacity_changes(), vec![]);
+
+        // Opaque while focused
+        state.set_focused(Some(101));
+        assert_eq!(state.window_opacity_changes(), vec![(101, 100)]);
+        state.record_window_alpha(101, 100);
+        assert!(state.window_alpha.is_empty());
+        state.set_focused(Some(100));
+        assert_eq!(state.window_opacity_changes(), vec![(101, 90)]);
+        state.record_window_alpha(101, 90);
+
+        // Closed windows are forgotten without a restore
+        state.windows.remove(&101);
+        assert_eq!(state.window_opacity_changes(), vec![]);
+        assert!(state.window_alpha.is_empty());
+    }
+
+    #[test]
+    fn test_apply_rules_to_all_windows_sets_and_clears_opacity() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        let matcher = RuleMatcher::new(Some(GlobPattern::new("Terminal")), None);
+        let action = RuleAction::Opacity { percent: 90 };
+
+        state.add_rule(WindowRule::new(matcher.clone(), action.clone()));
+        state.apply_rules_to_all_windows();
+        assert_eq!(state.windows.get(&101).unwrap().opacity, Some(90));
+        assert_eq!(state.windows.get(&100).unwrap().opacity, None);
+
+        state.remove_rule(&matcher, &action);
+        state.apply_rules_to_all_windows();
+        assert_eq!(state.windows.get(&101).unwrap().opacity, None);
+    }
+
+    #[test]
+    fn test_toggle_tags() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        let display_id = state.focused_display;
+
+        state.toggle_tags_on_display(0b10, display_id);
+        assert_eq!(state.visible_tags().mask(), 0b11);
+
+        state.toggle_tags_on_display(0b01, display_id);
+        assert_eq!(state.visible_tags().mask(), 0b10);
+    }
+
+    #[test]
+    fn test_toggle_tags_prevents_empty() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        let display_id = state.focused_display;
+
+        let moves = state.toggle_tags_on_display(0b01, display_id);
+        assert_eq!(state.visible_tags().mask(), 0b1);
+        assert!(moves.is_empty());
+    }
+
+    #[test]
+    fn test_move_focused_to_tags() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let moves = state.move_focused_to_tags(0b10);
+
+        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 0b10);
+
+        assert!(!moves.is_empty());
+    }
+
+    #[test]
+    fn test_distribute_tags() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.displays.get_mut(&1).unwrap().window_order = vec![100, 101, 102];
+        let tags = |state: &State| -> Vec<u32> {
+            [100, 101, 102]
+                .iter()
+                .map(|id| state.windows[id].tags.mask())
+                .collect()
+        };
+
+        // The last windows in layout order move, and get hidden
+        let moves = state.distribute_tags(0b1, 0b10, Some(2), false, 1);
+        assert_eq!(tags(&state), vec![0b1, 0b10, 0b10]);
+        assert_eq!(moves.len(), 2);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.displays.get_mut(&1).unwrap().window_order = vec![100, 101, 102];
+        state.distribute_tags(0b1, 0b10, None, true, 1);
+        assert_eq!(tags(&state), vec![0b1, 0b10, 0b1]);
+    }
+
+    #[test]
+    fn test_focus_window_next() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let result = state.focus_window(Direction::Next);
+        assert!(result.is_some());
+
+        let (window_id, _pid) = result.unwrap();
+        assert_eq!(window_id, 101);
+    }
+
+    #[test]
+    fn test_focus_window_prev() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let result = state.focus_window(Direction::Prev);
+        assert!(result.is_some());
+
+        let (window_id, _pid) = result.unwrap();
+        assert_eq!(window_id, 102);
+    }
+
+    #[test]
+    fn test_focus_window_directional() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let result = state.focus_window(Direction::Right);
+        assert!(result.is_some());
+
+        let (window_id, _pid) = result.unwrap();
+        assert_eq!(window_id, 101);
+    }
+
+    #[test]
+    fn test_focus_window_falls_back_when_nothing_focused() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_focused(Some(102));
+        // A windowless app took focus
+        state.set_focused(None);
+
+        let result = state.focus_window(Direction::Left);
+        assert_eq!(result.map(|(id, _)| id), Some(102));
+        let result = state.focus_window(Direction::Next);
+        assert_eq!(result.map(|(id, _)| id), Some(102));
+
+        // Without any focus history, the top of the layout is used
+        state.tag_focus.clear();
+        state.focus_history.clear();
+        let top = state.displays[&1].window_order[0];
+        let result = state.focus_window(Direction::Down);
+        assert_eq!(result.map(|(id, _)| id), Some(top));
+    }
+
+    #[test]
+    fn test_focus_window_directional_floating_mode() {
+        use yashiki_ipc::FocusFloatingMode;
+
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        // A floating palette between the focused window and the tiled one to its right
+        let palette = state.windows.get_mut(&102).unwrap();
+        palette.is_floating = true;
+        palette.frame = Rect {
+            x: 600,
+            y: 0,
+            width: 400,
+            height: 1080,
+        };
+        let right = |state: &State| state.focus_window(Direction::Right).map(|(id, _)| id);
+
+        assert_eq!(right(&state), Some(102));
+        state.config.focus_floating = FocusFloatingMode::Off;
+        assert_eq!(right(&state), Some(101));
+        state.config.focus_floating = FocusFloatingMode::TiledFirst;
+        assert_eq!(right(&state), Some(101));
+
+        // Only floating windows in that direction: tiled-first still reaches them
+        state.windows.get_mut(&101).unwrap().is_floating = true;
+        assert_eq!(right(&state), Some(102));
+        state.config.focus_floating = FocusFloatingMode::Off;
+        assert_eq!(right(&state), None);
+    }
+
+    #[test]
+    fn test_multi_display_focus_output() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        assert_eq!(state.focused_display, 1);
+
+        let result = state.focus_output(OutputDirection::Next);
+        assert!(result.is_some());
+        assert_eq!(state.focused_display, 2);
+
+        match result.unwrap() {
+            FocusOutputResult::Window { window_id, .. } => assert_eq!(window_id, 101),
+            FocusOutputResult::EmptyDisplay { .. } => panic!("Expected Window result"),
+        }
+    }
+
+    #[test]
+    fn test_global_tag_mode_views_tags_on_all_displays() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.view_tags_on_display(0b10, 2);
+
+        assert_eq!(state.tag_target_displays(1), vec![1]);
+        state.set_tag_mode(TagMode::Global);
+        assert_eq!(state.tag_target_displays(1), vec![1, 2]);
+        assert_eq!(state.displays.get(&2).unwrap().visible_tags.mask(), 0b1);
+
+        let moves = state.view_tags(0b100);
+        assert_eq!(moves.len(), 2);
+        assert!(state
+            .displays
+            .values()
+            .all(|d| d.visible_tags.mask() == 0b100));
+
+        state.view_tags_last();
+        assert!(state
+            .displays
+            .values()
+            .all(|d| d.visible_tags.mask() == 0b1));
+    }
+
+    #[test]
+    fn test_focus_output_empty_display() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0,
+            )])
+            .with_focused(Some(100));
 
         let mut state = State::new();
         state.sync_all(&ws);
@@ -996,6 +1830,98 @@ mod tests {
         assert_eq!(state.displays.len(), 2);
     }
 
+    #[test]
+    fn test_handle_display_change_rescales_saved_frames() {
+        let ws1 = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 120.0, 90.0, 960.0, 540.0),
+                create_test_window(101, 1001, "Terminal", 1500.0, 900.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws1);
+        state.view_tags(0b10);
+
+        // Resolution drops to 1280x720
+        let mut ws2 = ws1;
+        ws2.displays = vec![create_test_display(1, 0.0, 0.0, 1280.0, 720.0)];
+        state.handle_display_change(&ws2);
+
+        assert_eq!(
+            state.windows.get(&100).unwrap().saved_frame,
+            Some(Rect {
+                x: 80,
+                y: 60,
+                width: 640,
+                height: 360,
+            })
+        );
+        // Kept inside the display
+        let saved = state.windows.get(&101).unwrap().saved_frame.unwrap();
+        assert_eq!((saved.x, saved.y), (747, 320));
+        assert_eq!((saved.width, saved.height), (533, 400));
+    }
+
+    #[test]
+    fn test_handle_display_change_main_display_reassigned() {
+        let ws1 = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 2560.0, 1440.0),
+            ])
+            .with_windows(vec![
+                // Hangs over the right edge of display 1
+                create_test_window(100, 1000, "Safari", 1500.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws1);
+        state.view_tags(0b10);
+        let hidden = state.windows.get(&100).unwrap().frame;
+
+        // Display 2 becomes main: the origin moves to its top-left corner
+        let mut display1 = create_test_display(1, -1920.0, 0.0, 1920.0, 1080.0);
+        display1.is_main = false;
+        let mut display2 = create_test_display(2, 0.0, 0.0, 2560.0, 1440.0);
+        display2.is_main = true;
+        let ws2 = MockWindowSystem::new()
+            .with_displays(vec![display1, display2])
+            .with_windows(vec![
+                create_test_window(
+                    100,
+                    1000,
+                    "Safari",
+                    (hidden.x - 1920) as f64,
+                    hidden.y as f64,
+                    800.0,
+                    600.0,
+                ),
+                create_test_window(101, 1001, "Terminal", 80.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(101));
+        let result = state.handle_display_change(&ws2);
+
+        // Shifted as a whole rather than squeezed back inside display 1
+        let window = state.windows.get(&100).unwrap();
+        assert_eq!(
+            window.saved_frame,
+            Some(Rect {
+                x: -420,
+                y: 100,
+                width: 800,
+                height: 600,
+            })
+        );
+        assert_eq!(window.frame, hidden.offset(-1920, 0));
+        assert_eq!(window.display_id, 1);
+        assert!(result.window_moves.is_empty());
+        assert!(state.displays.get(&2).unwrap().is_main);
+    }
+
     #[test]
     fn test_handle_display_change_display_removed() {
         let ws1 = MockWindowSystem::new()
@@ -1078,8 +2004,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         assert!(state.should_ignore_window("Firefox", None, "Menu", None, Some("AXUnknown")));
@@ -1113,8 +2043,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         assert!(state.should_ignore_window(
@@ -1150,6 +2084,44 @@ mod tests {
         assert!(!state.should_ignore_window("Safari", None, "Window", None, None));
     }
 
+    #[test]
+    fn test_sync_all_skips_excluded_apps() {
+        let mut helper = create_test_window(103, 1003, "Helper", 0.0, 0.0, 400.0, 300.0);
+        helper.bundle_id = Some("com.example.App.helper".to_string());
+        let mut ws = setup_mock_system();
+        ws.add_window(helper);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        assert!(!state.windows.contains_key(&103));
+
+        state.config.excluded_apps.clear();
+        state.sync_all(&ws);
+        assert!(state.windows.contains_key(&103));
+    }
+
+    #[test]
+    fn test_sync_all_skips_internal_windows() {
+        let mut overlay = create_test_window(103, 1003, "yashiki", 0.0, 0.0, 1920.0, 1080.0);
+        overlay.bundle_id = Some(crate::core::YASHIKI_BUNDLE_ID.to_string());
+        let mut ws = setup_mock_system();
+        ws.add_window(overlay);
+        // Unbundled builds are recognized by pid
+        ws.add_window(create_test_window(
+            104, 1004, "yashiki", 0.0, 0.0, 400.0, 300.0,
+        ));
+
+        let mut state = State::new();
+        state.internal_pids.insert(1004);
+        state.config.excluded_apps.clear();
+        state.sync_all(&ws);
+        assert!(!state.windows.contains_key(&103));
+        assert!(!state.windows.contains_key(&104));
+        assert!(!state.ignored_windows.contains_key(&104));
+        assert!(state.is_internal_app(1004, None));
+        assert!(!state.is_internal_app(1000, Some("com.apple.Safari")));
+    }
+
     #[test]
     fn test_swap_window_next() {
         let ws = setup_mock_system();
@@ -1175,28 +2147,238 @@ mod tests {
     }
 
     #[test]
-    fn test_swap_window_prev() {
+    fn test_zoom_window_moves_focused_to_front() {
         let ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
 
+        state.focused = Some(100);
+        assert_eq!(state.zoom_window(), None);
+
         state.focused = Some(101);
+        assert_eq!(state.zoom_window(), Some((1, 101)));
+        assert_eq!(state.main_window_on_display(1), Some(101));
+        assert_eq!(
+            state.displays.get(&1).unwrap().window_order.first(),
+            Some(&101)
+        );
 
-        let result = state.swap_window(Direction::Prev);
-        assert!(result.is_some());
-        assert_eq!(result.unwrap(), 1);
+        state.windows.get_mut(&100).unwrap().is_floating = true;
+        state.focused = Some(100);
+        assert_eq!(state.zoom_window(), None);
     }
 
     #[test]
-    fn test_swap_window_floating_does_nothing() {
-        let ws = setup_mock_system();
+    fn test_dialog_floats_over_parent_and_returns_focus() {
+        let mut ws = setup_mock_system();
         let mut state = State::new();
         state.sync_all(&ws);
 
-        state.windows.get_mut(&100).unwrap().is_floating = true;
-        state.focused = Some(100);
+        ws.add_window(create_test_window(
+            103, 1000, "Safari", 0.0, 0.0, 400.0, 300.0,
+        ));
+        ws.set_parent_window(103, 100);
+        let (_, new_ids, _) = state.sync_pid(&ws, 1000);
+        assert_eq!(new_ids, vec![103]);
+        assert_eq!(state.windows.get(&103).unwrap().parent_id, Some(100));
+
+        let effects = state.apply_rules_to_new_window(103);
+        assert!(state.windows.get(&103).unwrap().is_floating);
+        assert!(effects.contains(&Effect::MoveWindowToPosition {
+            window_id: 103,
+            pid: 1000,
+            x: 280,
+            y: 390,
+        }));
+
+        // Closing the focused dialog hands focus back to its parent
+        state.set_focused(Some(101));
+        state.set_focused(Some(103));
+        ws.remove_window(103);
+        state.sync_pid(&ws, 1000);
+        assert_eq!(state.focused, None);
+        assert_eq!(state.recently_focused_on_display(1), Some(100));
+    }
 
-        let result = state.swap_window(Direction::Next);
+    #[test]
+    fn test_focus_parent_and_child() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        // Stacked window -> main window, and back to the stacked window
+        state.set_focused(Some(102));
+        assert_eq!(state.focus_parent(), Some((100, 1000)));
+        state.set_focused(Some(100));
+        assert_eq!(state.focus_child(), Some((102, 1002)));
+        assert_eq!(state.focus_parent(), None);
+
+        // Dialog <-> parent window
+        let dialog = state.windows.get_mut(&101).unwrap();
+        dialog.parent_id = Some(100);
+        dialog.is_floating = true;
+        state.set_focused(Some(101));
+        assert_eq!(state.focus_parent(), Some((100, 1000)));
+        state.set_focused(Some(100));
+        assert_eq!(state.focus_child(), Some((101, 1001)));
+
+        // Stacked windows have no children
+        state.set_focused(Some(102));
+        assert_eq!(state.focus_child(), None);
+    }
+
+    #[test]
+    fn test_stash_and_unstash_windows() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        state.focused = Some(100);
+        let moves = state.stash_focused();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].window_id, 100);
+        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 0);
+        assert!(state.windows.get(&100).unwrap().is_hidden());
+
+        state.focused = Some(101);
+        state.stash_focused();
+
+        // Most recently stashed first, onto the tags now visible
+        state.displays.get_mut(&1).unwrap().visible_tags = Tag::new(3);
+        let result = state.unstash().unwrap();
+        assert_eq!(result.window_id, 101);
+        assert!(result.window_moves.iter().any(|m| m.window_id == 101));
+        assert_eq!(state.windows.get(&101).unwrap().tags, Tag::new(3));
+        assert!(!state.windows.get(&101).unwrap().is_hidden());
+
+        // Closed stashed windows are skipped
+        state.windows.remove(&100);
+        assert_eq!(state.unstash(), None);
+        assert!(state.stash.is_empty());
+    }
+
+    #[test]
+    fn test_tag_occupancy_counts_windows_per_tag() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        state.windows.get_mut(&101).unwrap().tags = Tag::from_mask(0b110);
+        state.windows.get_mut(&102).unwrap().tags = Tag::from_mask(0);
+        state.focused = Some(100);
+        assert!(state.set_urgent(101, true));
+        let occupancy = |tag, urgent| TagOccupancy {
+            tag,
+            windows: 1,
+            urgent,
+        };
+        assert_eq!(
+            state.tag_occupancy(),
+            vec![occupancy(1, 0), occupancy(2, 1), occupancy(3, 1)]
+        );
+
+        // Focusing the window clears its urgency
+        state.set_focused(Some(101));
+        assert!(!state.windows.get(&101).unwrap().is_urgent);
+        assert!(!state.set_urgent(999, true));
+    }
+
+    #[test]
+    fn test_save_and_restore_arrangement() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let float_frame = Rect {
+            x: 300,
+            y: 200,
+            width: 640,
+            height: 480,
+        };
+        state.windows.get_mut(&102).unwrap().is_floating = true;
+        state.windows.get_mut(&102).unwrap().frame = float_frame;
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        state.displays.get_mut(&1).unwrap().window_order = vec![101, 100, 102];
+        assert_eq!(state.save_arrangement("demo"), 3);
+
+        // Rearrange everything, and let Terminal's title change
+        for window in state.windows.values_mut() {
+            window.tags = Tag::new(1);
+            window.is_floating = false;
+        }
+        state.windows.get_mut(&101).unwrap().title = "vim".to_string();
+        state.displays.get_mut(&1).unwrap().window_order = vec![100, 102, 101];
+
+        let result = state.restore_arrangement("demo").unwrap();
+        assert_eq!(result.restored, 3);
+        assert_eq!(result.missing, 0);
+        assert_eq!(result.display_ids, vec![1]);
+        assert_eq!(state.windows.get(&101).unwrap().tags, Tag::new(2));
+        assert!(state.windows.get(&101).unwrap().is_hidden());
+        assert!(state.windows.get(&102).unwrap().is_floating);
+        assert_eq!(result.floating_frames, vec![(102, 1002, float_frame)]);
+        assert_eq!(
+            state.displays.get(&1).unwrap().window_order,
+            vec![101, 100, 102]
+        );
+
+        assert_eq!(state.restore_arrangement("unknown"), None);
+    }
+
+    #[test]
+    fn test_swap_float_exchanges_float_and_main() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        // Tiled windows have nothing to swap with
+        state.focused = Some(100);
+        assert_eq!(state.swap_float(), None);
+
+        let float_frame = Rect {
+            x: 300,
+            y: 200,
+            width: 640,
+            height: 480,
+        };
+        let float = state.windows.get_mut(&102).unwrap();
+        float.is_floating = true;
+        float.frame = float_frame;
+        state.focused = Some(102);
+
+        let result = state.swap_float().unwrap();
+        assert_eq!(result.display_id, 1);
+        assert_eq!(result.tiled_id, 102);
+        assert_eq!(result.floated_id, 100);
+        assert_eq!(result.frame, float_frame);
+        assert!(state.windows.get(&100).unwrap().is_floating);
+        assert!(!state.windows.get(&102).unwrap().is_floating);
+        assert_eq!(state.main_window_on_display(1), Some(102));
+    }
+
+    #[test]
+    fn test_swap_window_prev() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        state.focused = Some(101);
+
+        let result = state.swap_window(Direction::Prev);
+        assert!(result.is_some());
+        assert_eq!(result.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_swap_window_floating_does_nothing() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        state.windows.get_mut(&100).unwrap().is_floating = true;
+        state.focused = Some(100);
+
+        let result = state.swap_window(Direction::Next);
         assert!(result.is_none());
     }
 
@@ -1213,6 +2395,55 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_toggle_manual_keeps_window_in_layout() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        assert_eq!(state.toggle_manual(101), Some((1, true)));
+        let window = state.windows.get(&101).unwrap();
+        assert!(window.is_manual);
+        assert!(window.is_tiled());
+        assert!(state
+            .visible_windows_on_display(1)
+            .iter()
+            .any(|w| w.id == 101));
+
+        assert_eq!(state.toggle_manual(101), Some((1, false)));
+        assert!(!state.windows.get(&101).unwrap().is_manual);
+        assert_eq!(state.toggle_manual(999), None);
+    }
+
+    #[test]
+    fn test_cycle_app_window_wraps_and_skips_hidden() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        for id in [100, 102] {
+            state.windows.get_mut(&id).unwrap().app_id = Some("com.example.app".to_string());
+        }
+
+        assert_eq!(
+            state.cycle_app_window("com.example.app"),
+            Some((102, 1002, false))
+        );
+        state.focused = Some(102);
+        assert_eq!(
+            state.cycle_app_window("com.example.app"),
+            Some((100, 1000, false))
+        );
+
+        // Focus outside the app starts from its first window
+        state.focused = Some(101);
+        state.windows.get_mut(&100).unwrap().tags = Tag::new(2);
+        assert_eq!(
+            state.cycle_app_window("com.example.app"),
+            Some((102, 1002, false))
+        );
+        assert_eq!(state.cycle_app_window("com.example.missing"), None);
+    }
+
     #[test]
     fn test_swap_window_single_visible_does_nothing() {
         let ws = MockWindowSystem::new()
@@ -1274,8 +2505,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::NoFloat,
+            dynamic: false,
         });
 
         state.add_rule(WindowRule {
@@ -1290,8 +2525,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Float,
+            dynamic: false,
         });
 
         let ext = ExtendedWindowAttributes {
@@ -1341,8 +2580,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::NoFloat,
+            dynamic: false,
         });
 
         let ext = ExtendedWindowAttributes::default();
@@ -1361,6 +2604,27 @@ mod tests {
         assert_eq!(result.is_floating, None);
     }
 
+    #[test]
+    fn test_no_manage_resize_rule() {
+        use yashiki_ipc::{GlobPattern, RuleAction, RuleMatcher, WindowRule};
+
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        assert!(!state.no_manage_resize(101));
+
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Terminal")), None),
+            RuleAction::NoManageResize,
+        ));
+        assert!(state.no_manage_resize(101));
+        assert!(!state.no_manage_resize(100));
+        // Not a tiling decision made at window creation
+        let ext = ExtendedWindowAttributes::default();
+        let result = state.apply_rules_to_window_extended("Terminal", None, "Window", &ext);
+        assert!(result.applied_rules.is_empty());
+    }
+
     #[test]
     fn test_non_normal_layer_window_not_managed_by_default() {
         let ws = MockWindowSystem::new()
@@ -1399,8 +2663,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Float,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -1435,8 +2703,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Tags { tags: 2 },
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -1472,8 +2744,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::NoFloat,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -1485,93 +2761,614 @@ mod tests {
     }
 
     #[test]
-    fn test_normal_layer_window_managed_as_usual() {
+    fn test_normal_layer_window_managed_as_usual() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0,
+            )]);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        assert_eq!(state.windows.len(), 1);
+        let window = state.windows.get(&100).unwrap();
+        assert!(!window.is_floating);
+    }
+
+    #[test]
+    fn test_non_normal_layer_window_ignored_with_ignore_rule() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window_with_layer(
+                100, 1000, "Raycast", 100.0, 100.0, 800.0, 600.0, 8,
+            )]);
+
+        let mut state = State::new();
+
+        state.add_rule(WindowRule {
+            matcher: RuleMatcher {
+                app_name: Some(GlobPattern::new("Raycast")),
+                app_id: None,
+                title: None,
+                ax_id: None,
+                subrole: None,
+                window_level: None,
+                close_button: None,
+                fullscreen_button: None,
+                minimize_button: None,
+                zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
+            },
+            action: RuleAction::Float,
+            dynamic: false,
+        });
+        state.add_rule(WindowRule {
+            matcher: RuleMatcher {
+                app_name: Some(GlobPattern::new("Raycast")),
+                app_id: None,
+                title: None,
+                ax_id: None,
+                subrole: None,
+                window_level: None,
+                close_button: None,
+                fullscreen_button: None,
+                minimize_button: None,
+                zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
+            },
+            action: RuleAction::Ignore,
+            dynamic: false,
+        });
+
+        state.sync_all(&ws);
+
+        assert!(state.windows.is_empty());
+    }
+
+    #[test]
+    fn test_apply_rules_defaults_to_float_for_non_normal_layer() {
+        let state = State::new();
+
+        let ext = ExtendedWindowAttributes {
+            window_level: 8,
+            ..Default::default()
+        };
+        let result = state.apply_rules_to_window_extended("Raycast", None, "Window", &ext);
+
+        assert_eq!(result.is_floating, Some(true));
+    }
+
+    #[test]
+    fn test_apply_rules_normal_layer_no_default_float() {
+        let state = State::new();
+
+        let ext = ExtendedWindowAttributes {
+            window_level: 0,
+            ..Default::default()
+        };
+        let result = state.apply_rules_to_window_extended("Safari", None, "Window", &ext);
+
+        assert_eq!(result.is_floating, None);
+    }
+
+    #[test]
+    fn test_conditional_rule_uses_display_tag_context() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0,
+            )]);
+
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+                .with_conditions(Some(0b1000), None),
+            RuleAction::Float,
+        ));
+
+        state.sync_all(&ws);
+        state.apply_rules_to_new_window(100);
+        assert!(!state.windows.get(&100).unwrap().is_floating);
+
+        state.view_tags(0b1000);
+        state.apply_rules_to_new_window(100);
+        assert!(state.windows.get(&100).unwrap().is_floating);
+    }
+
+    #[test]
+    fn test_conditional_rule_uses_output_name_context() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0,
+            )]);
+
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+                .with_conditions(None, Some(GlobPattern::new("DELL*"))),
+            RuleAction::Float,
+        ));
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None)
+                .with_conditions(None, Some(GlobPattern::new("Display 1"))),
+            RuleAction::Tags { tags: 2 },
+        ));
+
+        state.sync_all(&ws);
+        state.apply_rules_to_new_window(100);
+
+        let window = state.windows.get(&100).unwrap();
+        assert!(!window.is_floating);
+        assert_eq!(window.tags.mask(), 2);
+    }
+
+    #[test]
+    fn test_when_output_id_rule_uses_window_display() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Calculator", 0.0, 0.0, 300.0, 400.0),
+                create_test_window(101, 1001, "Calculator", 1920.0, 0.0, 300.0, 400.0),
+            ]);
+
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Calculator")), None)
+                .with_conditions(None, Some(GlobPattern::new("2"))),
+            RuleAction::Float,
+        ));
+
+        state.sync_all(&ws);
+        state.apply_rules_to_new_window(100);
+        state.apply_rules_to_new_window(101);
+
+        assert!(!state.windows.get(&100).unwrap().is_floating);
+        assert!(state.windows.get(&101).unwrap().is_floating);
+    }
+
+    #[test]
+    fn test_parent_exec_rule_matches_process_ancestors() {
+        use yashiki_ipc::GlobPattern;
+
+        let mut ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "mpv", 0.0, 0.0, 960.0, 1080.0),
+                create_test_window(101, 1001, "Terminal", 960.0, 0.0, 960.0, 1080.0),
+            ]);
+        ws.set_process_ancestors(1000, &["zsh", "kitty"]);
+
+        let mut state = State::new();
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(None, None).with_parent_exec(Some(GlobPattern::new("kitty"))),
+            RuleAction::Tags { tags: 16 },
+        ));
+
+        state.sync_all(&ws);
+        state.apply_rules_to_new_window(100);
+        state.apply_rules_to_new_window(101);
+
+        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 16);
+        assert_eq!(state.windows.get(&101).unwrap().tags.mask(), 1);
+    }
+
+    #[test]
+    fn test_dynamic_rule_applies_on_title_change() {
+        use yashiki_ipc::GlobPattern;
+
+        let mut ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.add_rule(
+            WindowRule::new(
+                RuleMatcher::new(None, Some(GlobPattern::new("*Meet*"))),
+                RuleAction::Tags { tags: 4 },
+            )
+            .with_dynamic(true),
+        );
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(None, Some(GlobPattern::new("*Docs*"))),
+            RuleAction::Float,
+        ));
+
+        let retitle = |ws: &mut MockWindowSystem, window_id, title: &str| {
+            ws.set_extended_attributes(
+                window_id,
+                ExtendedWindowAttributes {
+                    title: Some(title.to_string()),
+                    ..Default::default()
+                },
+            );
+        };
+
+        // The dynamic rule moves the window to tag 3, hiding it
+        retitle(&mut ws, 100, "Google Meet");
+        let (changed, _, moves) = state.handle_event(&ws, &Event::WindowTitleChanged { pid: 1000 });
+        assert!(changed);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].window_id, 100);
+        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 4);
+
+        // Already applied: moving the window back isn't undone by further title changes
+        state.windows.get_mut(&100).unwrap().tags = Tag::from_mask(1);
+        retitle(&mut ws, 100, "Google Meet (2)");
+        let (changed, _, _) = state.handle_event(&ws, &Event::WindowTitleChanged { pid: 1000 });
+        assert!(!changed);
+        assert_eq!(state.windows.get(&100).unwrap().tags.mask(), 1);
+
+        // Static rules only apply on creation
+        retitle(&mut ws, 101, "Docs");
+        let (changed, _, _) = state.handle_event(&ws, &Event::WindowTitleChanged { pid: 1001 });
+        assert!(!changed);
+        assert!(!state.windows.get(&101).unwrap().is_floating);
+        assert_eq!(state.windows.get(&101).unwrap().title, "Docs");
+    }
+
+    #[test]
+    fn test_percentage_dimensions_and_anchored_position_rules() {
+        use yashiki_ipc::{Anchor, GlobPattern, RuleSize};
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0,
+            )]);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None),
+            RuleAction::Dimensions {
+                width: RuleSize::Percent { percent: 50 },
+                height: RuleSize::Percent { percent: 60 },
+            },
+        ));
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None),
+            RuleAction::Position {
+                x: 20,
+                y: 20,
+                anchor: Some(Anchor::TopRight),
+            },
+        ));
+
+        let effects = state.apply_rules_to_new_window(100);
+        assert!(effects.iter().any(|e| matches!(
+            e,
+            Effect::SetWindowDimensions {
+                width: 960,
+                height: 648,
+                ..
+            }
+        )));
+        assert!(effects
+            .iter()
+            .any(|e| matches!(e, Effect::MoveWindowToPosition { x: 940, y: 20, .. })));
+    }
+
+    #[test]
+    fn test_applied_rule_indices() {
+        use yashiki_ipc::GlobPattern;
+
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![create_test_window(
+                100, 1000, "Safari", 0.0, 0.0, 960.0, 1080.0,
+            )]);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let float_rule = WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None),
+            RuleAction::Float,
+        );
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Terminal")), None),
+            RuleAction::Float,
+        ));
+        state.add_rule(float_rule.clone());
+        state.add_rule(WindowRule::new(
+            RuleMatcher::new(Some(GlobPattern::new("Safari")), None),
+            RuleAction::Tags { tags: 2 },
+        ));
+        state.apply_rules_to_all_windows();
+
+        assert_eq!(state.windows.get(&100).unwrap().applied_rules.len(), 2);
+        assert_eq!(state.applied_rule_indices(100), vec![1, 2]);
+
+        // Removed rules no longer show up, remaining indices shift
+        state.remove_rule(&float_rule.matcher, &float_rule.action);
+        assert_eq!(state.applied_rule_indices(100), vec![1]);
+    }
+
+    #[test]
+    fn test_sync_all_tracks_display_rotation() {
+        let mut state = State::new();
+        state.sync_all(
+            &MockWindowSystem::new()
+                .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]),
+        );
+        assert_eq!(state.displays.get(&1).unwrap().rotation, 0);
+
+        let ws = MockWindowSystem::new().with_displays(vec![DisplayInfo {
+            rotation: 90,
+            ..create_test_display(1, 0.0, 0.0, 1080.0, 1920.0)
+        }]);
+        state.sync_all(&ws);
+        let display = state.displays.get(&1).unwrap();
+        assert_eq!(display.rotation, 90);
+        assert_eq!(display.frame.height, 1920);
+    }
+
+    #[test]
+    fn test_sync_all_tracks_display_refresh_rate_and_uuid() {
+        let mut state = State::new();
+        let ws = MockWindowSystem::new().with_displays(vec![DisplayInfo {
+            refresh_rate: 120.0,
+            uuid: Some("37D8832A-2D66-02CA-B9F7-8F30A301B230".to_string()),
+            ..create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)
+        }]);
+        state.sync_all(&ws);
+        let display = state.displays.get(&1).unwrap();
+        assert_eq!(display.refresh_rate, 120.0);
+        assert_eq!(
+            display.uuid.as_deref(),
+            Some("37D8832A-2D66-02CA-B9F7-8F30A301B230")
+        );
+    }
+
+    #[test]
+    fn test_outer_gap_for_display_scales_in_auto_mode() {
+        let ws = MockWindowSystem::new().with_displays(vec![
+            create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+            DisplayInfo {
+                scale_factor: 2.0,
+                ..create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0)
+            },
+        ]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.config.outer_gap = OuterGap::all(20);
+
+        assert_eq!(state.outer_gap_for_display(2), OuterGap::all(20));
+
+        state.config.gap_scale = GapScaleMode::Auto;
+        assert_eq!(state.outer_gap_for_display(1), OuterGap::all(20));
+        assert_eq!(state.outer_gap_for_display(2), OuterGap::all(10));
+    }
+
+    #[test]
+    fn test_park_hidden_geometries() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 1920.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        let geometry = |id, hidden| WindowGeometry {
+            id,
+            x: 0,
+            y: 0,
+            width: 800,
+            height: 600,
+            raise_order: None,
+            hidden,
+        };
+        let mut geometries = vec![geometry(100, false), geometry(101, true)];
+        state.park_hidden_geometries(1, &mut geometries);
+
+        assert_eq!((geometries[0].x, geometries[0].y), (0, 0));
+        // Relative to the display frame, at the bottom-right hide position
+        assert_eq!((geometries[1].x, geometries[1].y), (1919, 1079));
+        assert_eq!(geometries[1].width, 800);
+    }
+
+    #[test]
+    fn test_compensate_corners_grows_shared_edges() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.config.outer_gap = OuterGap::all(10);
+
+        let geometry = |id, x| WindowGeometry {
+            id,
+            x,
+            y: 10,
+            width: 945,
+            height: 1060,
+            raise_order: None,
+            hidden: false,
+        };
+        let geometries = vec![geometry(100, 10), geometry(101, 965)];
+        let compensated = |state: &State| {
+            let mut geometries = geometries.clone();
+            state.compensate_corners(1, &mut geometries);
+            geometries
+                .iter()
+                .map(|g| (g.x, g.y, g.width, g.height))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            compensated(&state),
+            vec![(10, 10, 945, 1060), (965, 10, 945, 1060)]
+        );
+        // Only the edges facing each other move; the outer gap stays
+        state.config.corner_compensation = 3;
+        assert_eq!(
+            compensated(&state),
+            vec![(10, 10, 948, 1060), (962, 10, 948, 1060)]
+        );
+    }
+
+    #[test]
+    fn test_compensate_corners_limited_by_inner_gap() {
         let ws = MockWindowSystem::new()
-            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
-            .with_windows(vec![create_test_window(
-                100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0,
-            )]);
-
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)]);
         let mut state = State::new();
         state.sync_all(&ws);
-
-        assert_eq!(state.windows.len(), 1);
-        let window = state.windows.get(&100).unwrap();
-        assert!(!window.is_floating);
+        state.config.outer_gap = OuterGap::all(10);
+        state.config.corner_compensation = 3;
+
+        let geometry = |id, x| WindowGeometry {
+            id,
+            x,
+            y: 10,
+            width: 949,
+            height: 1060,
+            raise_order: None,
+            hidden: false,
+        };
+        // A 2pt inner gap lets each neighbour grow only 1pt, so they still don't overlap
+        let mut geometries = vec![geometry(100, 10), geometry(101, 961)];
+        state.compensate_corners(1, &mut geometries);
+        assert_eq!(
+            geometries
+                .iter()
+                .map(|g| (g.x, g.y, g.width, g.height))
+                .collect::<Vec<_>>(),
+            vec![(10, 10, 950, 1060), (960, 10, 950, 1060)]
+        );
     }
 
     #[test]
-    fn test_non_normal_layer_window_ignored_with_ignore_rule() {
-        use yashiki_ipc::GlobPattern;
+    fn test_apply_raise_policy() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_focused(Some(101));
+
+        let geometry = |id, raise_order| WindowGeometry {
+            id,
+            x: 0,
+            y: 0,
+            width: 800,
+            height: 600,
+            raise_order,
+            hidden: false,
+        };
+        let geometries = vec![
+            geometry(100, None),
+            geometry(101, None),
+            geometry(102, Some(3)),
+        ];
+        let raised = |state: &State| {
+            let mut geometries = geometries.clone();
+            state.apply_raise_policy(1, &mut geometries);
+            geometries.iter().map(|g| g.raise_order).collect::<Vec<_>>()
+        };
 
-        let ws = MockWindowSystem::new()
-            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
-            .with_windows(vec![create_test_window_with_layer(
-                100, 1000, "Raycast", 100.0, 100.0, 800.0, 600.0, 8,
-            )]);
+        assert_eq!(raised(&state), vec![None, None, Some(3)]);
+        state.config.raise_policy = RaisePolicy::FocusedTop;
+        assert_eq!(raised(&state), vec![None, Some(4), Some(3)]);
+        state.config.raise_policy = RaisePolicy::MainTop;
+        assert_eq!(raised(&state), vec![Some(4), None, Some(3)]);
+    }
 
+    #[test]
+    fn test_last_focused_on_visible_tags() {
+        let ws = setup_mock_system();
         let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_focused(Some(101));
+        assert_eq!(state.last_focused_on_visible_tags(1), Some(101));
+
+        // Window moved off the tag is no longer remembered for it
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        assert_eq!(state.last_focused_on_visible_tags(1), None);
+
+        // Focusing a window that isn't visible doesn't overwrite the memory
+        state.view_tags_on_display(0b10, 1);
+        state.set_focused(Some(100));
+        state.set_focused(Some(101));
+        assert_eq!(state.last_focused_on_visible_tags(1), Some(101));
+    }
 
-        state.add_rule(WindowRule {
-            matcher: RuleMatcher {
-                app_name: Some(GlobPattern::new("Raycast")),
-                app_id: None,
-                title: None,
-                ax_id: None,
-                subrole: None,
-                window_level: None,
-                close_button: None,
-                fullscreen_button: None,
-                minimize_button: None,
-                zoom_button: None,
-            },
-            action: RuleAction::Float,
-        });
-        state.add_rule(WindowRule {
-            matcher: RuleMatcher {
-                app_name: Some(GlobPattern::new("Raycast")),
-                app_id: None,
-                title: None,
-                ax_id: None,
-                subrole: None,
-                window_level: None,
-                close_button: None,
-                fullscreen_button: None,
-                minimize_button: None,
-                zoom_button: None,
-            },
-            action: RuleAction::Ignore,
-        });
+    #[test]
+    fn test_recently_focused_on_display() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_focused(Some(102));
+        state.set_focused(Some(101));
+        state.set_focused(Some(100));
+        assert_eq!(state.recently_focused_on_display(1), Some(100));
+
+        // The focused window closes: the previous one is next in line
+        state.windows.remove(&100);
+        assert_eq!(state.recently_focused_on_display(1), Some(101));
+
+        // Windows on hidden tags are skipped
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        assert_eq!(state.recently_focused_on_display(1), Some(102));
+    }
 
+    #[test]
+    fn test_new_window_tag_policy_current() {
+        let ws = setup_mock_system();
+        let mut state = State::new();
         state.sync_all(&ws);
 
-        assert!(state.windows.is_empty());
+        assert!(state.windows.values().all(|w| w.tags.mask() == 1));
     }
 
     #[test]
-    fn test_apply_rules_defaults_to_float_for_non_normal_layer() {
-        let state = State::new();
+    fn test_new_window_tag_policy_emptiest() {
+        let mut ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.config.new_window_tag = yashiki_ipc::NewWindowTagPolicy::Emptiest;
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
 
-        let ext = ExtendedWindowAttributes {
-            window_level: 8,
-            ..Default::default()
-        };
-        let result = state.apply_rules_to_window_extended("Raycast", None, "Window", &ext);
+        ws.add_window(create_test_window(
+            103, 1003, "Finder", 0.0, 0.0, 800.0, 600.0,
+        ));
+        state.sync_all(&ws);
 
-        assert_eq!(result.is_floating, Some(true));
+        // Tags 1 and 2 are occupied, tag 3 is the lowest empty tag
+        assert_eq!(state.windows.get(&103).unwrap().tags, Tag::new(3));
     }
 
     #[test]
-    fn test_apply_rules_normal_layer_no_default_float() {
-        let state = State::new();
+    fn test_new_window_tag_policy_app_sticky() {
+        let mut ws = setup_mock_system();
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.config.new_window_tag = yashiki_ipc::NewWindowTagPolicy::AppSticky;
 
-        let ext = ExtendedWindowAttributes {
-            window_level: 0,
-            ..Default::default()
-        };
-        let result = state.apply_rules_to_window_extended("Safari", None, "Window", &ext);
+        // Safari (focused) is moved to tag 4, which is remembered for the app
+        state.move_focused_to_tags(0b1000);
+        assert_eq!(state.app_tags.get("Safari"), Some(&Tag::new(4)));
 
-        assert_eq!(result.is_floating, None);
+        ws.add_window(create_test_window(
+            103, 1000, "Safari", 0.0, 0.0, 800.0, 600.0,
+        ));
+        ws.add_window(create_test_window(
+            104, 1003, "Finder", 0.0, 0.0, 800.0, 600.0,
+        ));
+        state.sync_all(&ws);
+
+        assert_eq!(state.windows.get(&103).unwrap().tags, Tag::new(4));
+        // Unknown apps fall back to the current visible tags
+        assert_eq!(state.windows.get(&104).unwrap().tags, Tag::new(1));
     }
 
     #[test]
@@ -1680,6 +3477,152 @@ mod tests {
         assert!(state.displays.get(&2).unwrap().window_order.contains(&100));
     }
 
+    #[test]
+    fn test_bring_window_to_focused_output() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_focused(Some(101));
+        state.set_focused(Some(100));
+        state.focused_display = 1;
+        // Terminal is on tag 2 of display 2, display 1 shows tag 1
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        state.displays.get_mut(&2).unwrap().visible_tags = Tag::new(2);
+
+        // Without an ID, the last focused window on another output is brought
+        let result = state.bring_window(None).unwrap();
+        assert_eq!(result.window_id, 101);
+        assert_eq!(result.source_display_id, 2);
+        assert_eq!(result.target_display_id, 1);
+
+        let window = state.windows.get(&101).unwrap();
+        assert_eq!(window.display_id, 1);
+        assert_eq!(window.tags, Tag::new(1));
+        assert!(!window.is_hidden());
+        assert!(!state.displays.get(&2).unwrap().window_order.contains(&101));
+        assert!(state.displays.get(&1).unwrap().window_order.contains(&101));
+
+        // Nothing left on other outputs
+        assert!(state.bring_window(None).is_err());
+        assert!(state.bring_window(Some(999)).is_err());
+    }
+
+    #[test]
+    fn test_move_floating_window_to_tag_on_other_display() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 2560.0, 1440.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 300.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.windows.get_mut(&100).unwrap().is_floating = true;
+        state.displays.get_mut(&2).unwrap().visible_tags = Tag::new(2);
+
+        // Tag 2 lives on display 2, so the floating window follows it there
+        state.set_focused(Some(100));
+        let moves = state.move_focused_to_tags(Tag::new(2).mask());
+        let window = state.windows.get(&100).unwrap();
+        assert_eq!(window.display_id, 2);
+        assert!(!window.is_hidden());
+        assert_eq!((window.frame.x, window.frame.y), (2053, 133));
+        assert_eq!((window.frame.width, window.frame.height), (800, 600));
+        let m = moves.iter().find(|m| m.window_id == 100).unwrap();
+        assert_eq!((m.old_x, m.old_y, m.new_x, m.new_y), (100, 100, 2053, 133));
+        assert!(state.displays.get(&2).unwrap().window_order.contains(&100));
+
+        // Tiled windows stay in their display's layout, hidden
+        state.set_focused(Some(101));
+        state.move_focused_to_tags(Tag::new(2).mask());
+        let window = state.windows.get(&101).unwrap();
+        assert_eq!(window.display_id, 1);
+        assert!(window.is_hidden());
+    }
+
+    #[test]
+    fn test_set_output_ignored() {
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+
+        // A tag-hidden window is moved back on screen when released
+        let window = state.windows.get_mut(&101).unwrap();
+        window.saved_frame = Some(window.frame);
+        window.frame.x = 3839;
+        let moves = state.set_output_ignored(2, true);
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].window_id, moves[0].new_x), (101, 2000));
+        assert!(!state.windows.contains_key(&101));
+        assert!(state.displays.get(&2).unwrap().window_order.is_empty());
+
+        // Windows on the ignored display stay unmanaged, and it is skipped as a send target
+        state.sync_all(&ws);
+        assert!(!state.windows.contains_key(&101));
+        assert!(state.send_to_output(OutputDirection::Next).is_none());
+
+        assert!(state.set_output_ignored(2, false).is_empty());
+        state.sync_all(&ws);
+        assert!(state.windows.contains_key(&101));
+    }
+
+    #[test]
+    fn test_ignored_output_survives_display_id_change() {
+        let uuid = Some("37D8832A-2D66-02CA-B9F7-8F30A301B230".to_string());
+        let ws = MockWindowSystem::new().with_displays(vec![
+            create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+            DisplayInfo {
+                uuid: uuid.clone(),
+                ..create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0)
+            },
+        ]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        state.set_output_ignored(2, true);
+        assert!(state.is_output_ignored(2));
+
+        // Reconnected under a new display ID: still ignored through its UUID
+        let ws = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                DisplayInfo {
+                    uuid,
+                    ..create_test_display(5, 1920.0, 0.0, 1920.0, 1080.0)
+                },
+            ])
+            .with_windows(vec![create_test_window(
+                101, 1001, "Terminal", 2000.0, 100.0, 800.0, 600.0,
+            )]);
+        state.sync_all(&ws);
+        assert!(state.is_output_ignored(5));
+        assert!(!state.windows.contains_key(&101));
+    }
+
     #[test]
     fn test_send_to_output_already_hidden_becomes_visible() {
         // Window is already hidden on source display, becomes visible on target display
@@ -1855,6 +3798,40 @@ mod tests {
         assert_eq!(y2, 899);
     }
 
+    #[test]
+    fn test_per_display_hide_position_avoids_obstructed_corners() {
+        use crate::macos::ScreenCorners;
+
+        // Dock at the bottom and a top-right hot corner leave the top-left corner
+        let ws = MockWindowSystem::new().with_displays(vec![DisplayInfo {
+            obstructed_corners: ScreenCorners {
+                top_right: true,
+                bottom_left: true,
+                bottom_right: true,
+                ..Default::default()
+            },
+            ..create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)
+        }]);
+        let mut state = State::new();
+        state.sync_all(&ws);
+        assert_eq!(
+            compute_hide_position_for_display(&state, 1, 800, 600),
+            (-799, -599)
+        );
+
+        // With every corner obstructed, the first safe corner is still used
+        state
+            .displays
+            .get_mut(&1)
+            .unwrap()
+            .obstructed_corners
+            .top_left = true;
+        assert_eq!(
+            compute_hide_position_for_display(&state, 1, 800, 600),
+            (1919, 1079)
+        );
+    }
+
     #[test]
     fn test_windows_hidden_to_their_own_display() {
         // Windows on different displays should be hidden to their respective display's hide position
@@ -1940,6 +3917,64 @@ mod tests {
         assert_eq!(state.windows.get(&100).unwrap().orphaned_from, None);
     }
 
+    fn setup_three_display_orphan_test() -> (State, MockWindowSystem) {
+        let ws1 = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+                create_test_display(3, 3840.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1001, "Terminal", 3900.0, 100.0, 800.0, 600.0),
+                create_test_window(102, 1002, "VSCode", 4000.0, 100.0, 800.0, 600.0),
+            ])
+            .with_focused(Some(100));
+
+        let mut state = State::new();
+        state.sync_all(&ws1);
+        assert_eq!(state.windows.get(&101).unwrap().display_id, 3);
+        assert_eq!(state.windows.get(&102).unwrap().display_id, 3);
+
+        let ws2 = MockWindowSystem::new()
+            .with_displays(vec![
+                create_test_display(1, 0.0, 0.0, 1920.0, 1080.0),
+                create_test_display(2, 1920.0, 0.0, 1920.0, 1080.0),
+            ])
+            .with_windows(ws1.windows.clone())
+            .with_focused(Some(100));
+
+        (state, ws2)
+    }
+
+    #[test]
+    fn test_orphan_policy_spread() {
+        let (mut state, ws2) = setup_three_display_orphan_test();
+        state.config.orphan_policy = yashiki_ipc::OrphanPolicy::Spread;
+
+        state.handle_display_change(&ws2);
+
+        assert_eq!(state.windows.get(&101).unwrap().display_id, 1);
+        assert_eq!(state.windows.get(&102).unwrap().display_id, 2);
+        assert_eq!(state.windows.get(&101).unwrap().orphaned_from, Some(3));
+        assert_eq!(state.windows.get(&102).unwrap().orphaned_from, Some(3));
+    }
+
+    #[test]
+    fn test_orphan_policy_by_tag() {
+        let (mut state, ws2) = setup_three_display_orphan_test();
+        state.config.orphan_policy = yashiki_ipc::OrphanPolicy::ByTag;
+        state.view_tags_on_display(0b10, 2);
+        state.windows.get_mut(&101).unwrap().tags = Tag::new(2);
+        state.windows.get_mut(&102).unwrap().tags = Tag::new(4);
+
+        state.handle_display_change(&ws2);
+
+        // Tag 2 is visible on display 2, tag 4 is visible nowhere (fallback)
+        assert_eq!(state.windows.get(&101).unwrap().display_id, 2);
+        assert_eq!(state.windows.get(&102).unwrap().display_id, 1);
+    }
+
     #[test]
     fn test_orphan_restoration_on_display_return() {
         // When a display returns, orphaned windows should be restored to their original display
@@ -2328,8 +4363,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         // Initial sync - window should be ignored
@@ -2396,8 +4435,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2448,8 +4491,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2522,8 +4569,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         // First sync: window 238 becomes managed, window 8277 becomes ignored
@@ -2578,8 +4629,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2630,8 +4685,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2647,6 +4706,56 @@ mod tests {
         assert!(state.ignored_windows.contains_key(&100));
     }
 
+    #[test]
+    fn test_native_fullscreen_window_excluded_from_layout() {
+        let mut ws = MockWindowSystem::new()
+            .with_displays(vec![create_test_display(1, 0.0, 0.0, 1920.0, 1080.0)])
+            .with_windows(vec![
+                create_test_window(100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0),
+                create_test_window(101, 1000, "Safari", 200.0, 200.0, 800.0, 600.0),
+            ]);
+
+        let mut state = State::new();
+        state.sync_all(&ws);
+        assert!(state.windows.get(&100).unwrap().is_tiled());
+
+        // Entering native fullscreen moves the window to its own Space
+        ws.remove_window(100);
+        ws.add_ax_only_window(100, 1000);
+        ws.set_native_fullscreen(100, true);
+        let (changed, _, _) = state.sync_pid(&ws, 1000);
+        assert!(changed);
+        let window = state.windows.get(&100).unwrap();
+        assert!(window.is_native_fullscreen);
+        assert!(!window.is_tiled());
+
+        // Visiting its Space shows it on screen at display size; it stays excluded
+        ws.add_window(create_test_window(
+            100, 1000, "Safari", 0.0, 0.0, 1920.0, 1080.0,
+        ));
+        let (changed, _, _) = state.sync_pid(&ws, 1000);
+        assert!(!changed);
+        assert!(state.windows.get(&100).unwrap().is_native_fullscreen);
+
+        // Switching tags doesn't try to hide it
+        let moves = state.view_tags(0b10);
+        assert!(moves.iter().all(|m| m.window_id != 100));
+        assert!(!state.windows.get(&100).unwrap().is_hidden());
+        state.view_tags(0b1);
+
+        // Leaving fullscreen brings it back into the layout
+        ws.set_native_fullscreen(100, false);
+        ws.remove_window(100);
+        ws.add_window(create_test_window(
+            100, 1000, "Safari", 100.0, 100.0, 800.0, 600.0,
+        ));
+        let (changed, _, _) = state.sync_pid(&ws, 1000);
+        assert!(changed);
+        let window = state.windows.get(&100).unwrap();
+        assert!(!window.is_native_fullscreen);
+        assert!(window.is_tiled());
+    }
+
     #[test]
     fn test_ignored_window_removed_when_truly_gone() {
         // Window does not exist in AX API and not in get_on_screen_windows (truly closed)
@@ -2682,8 +4791,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2749,8 +4862,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -2819,8 +4936,12 @@ mod tests {
                 fullscreen_button: None,
                 minimize_button: None,
                 zoom_button: None,
+                when_tag: None,
+                when_output: None,
+                parent_exec: None,
             },
             action: RuleAction::Ignore,
+            dynamic: false,
         });
 
         state.sync_all(&ws);
@@ -1,11 +1,14 @@
 use std::collections::HashSet;
+use std::time::Duration;
 
 use super::super::{RuleApplicationResult, Tag, WindowId};
 use crate::effect::Effect;
 use crate::macos::DisplayId;
-use yashiki_ipc::{ExtendedWindowAttributes, RuleAction, RuleMatcher, WindowRule};
+use yashiki_ipc::{CloseBehavior, ExtendedWindowAttributes, RuleAction, RuleMatcher, WindowRule};
 
 use super::super::state::{State, WindowMove};
+use super::layout::compute_layout_changes_for_display;
+use super::tags::apply_float_tags;
 
 pub fn add_rule(state: &mut State, rule: WindowRule) {
     state.rules_engine.add_rule(rule);
@@ -57,6 +60,78 @@ pub fn has_matching_non_ignore_rule(
         .has_matching_non_ignore_rule(app_name, app_id, title, ext)
 }
 
+/// Fill the rule context (visible tags, output name and ID) of the given display
+pub fn with_rule_context(
+    state: &State,
+    display_id: DisplayId,
+    ext: ExtendedWindowAttributes,
+) -> ExtendedWindowAttributes {
+    let display = state.displays.get(&display_id);
+    ExtendedWindowAttributes {
+        visible_tags: display.map(|d| d.visible_tags.mask()).unwrap_or(0),
+        output_name: display.map(|d| d.name.clone()),
+        output_id: display.map(|d| d.id),
+        ..ext
+    }
+}
+
+/// Rules whose matcher currently matches the window (in precedence order)
+pub fn matching_rules(state: &State, window_id: WindowId) -> Vec<&WindowRule> {
+    let Some(window) = state.windows.get(&window_id) else {
+        return vec![];
+    };
+    let ext = with_rule_context(state, window.display_id, window.extended_attributes());
+    state.rules_engine.get_matching_rules(
+        &window.app_name,
+        window.app_id.as_deref(),
+        &window.title,
+        &ext,
+    )
+}
+
+/// What window-close does for the window (first matching close-behavior rule wins)
+pub fn close_behavior(state: &State, window_id: WindowId) -> CloseBehavior {
+    matching_rules(state, window_id)
+        .into_iter()
+        .find_map(|rule| match rule.action {
+            RuleAction::CloseBehavior { behavior } => Some(behavior),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Whether tiling should keep the window's size (a no-manage-resize rule matches)
+pub fn no_manage_resize(state: &State, window_id: WindowId) -> bool {
+    matching_rules(state, window_id)
+        .into_iter()
+        .any(|rule| rule.action == RuleAction::NoManageResize)
+}
+
+/// Minimum interval between syncs of the app's windows (first matching throttle rule wins)
+pub fn sync_throttle(state: &State, pid: i32) -> Option<Duration> {
+    let window_id = state.windows.values().find(|w| w.pid == pid)?.id;
+    matching_rules(state, window_id)
+        .into_iter()
+        .find_map(|rule| match rule.action {
+            RuleAction::Throttle { ms } => Some(Duration::from_millis(ms as u64)),
+            _ => None,
+        })
+}
+
+/// Indices (in list-rules order) of the rules applied to the window.
+/// Rules removed since they were applied are skipped.
+pub fn applied_rule_indices(state: &State, window_id: WindowId) -> Vec<usize> {
+    let Some(window) = state.windows.get(&window_id) else {
+        return vec![];
+    };
+    let rules = state.rules_engine.rules();
+    window
+        .applied_rules
+        .iter()
+        .filter_map(|applied| rules.iter().position(|r| r == applied))
+        .collect()
+}
+
 pub fn apply_rules_to_window_extended(
     state: &State,
     app_name: &str,
@@ -75,6 +150,9 @@ pub fn apply_rules_to_window_extended(
         if let RuleAction::Output { output: o } = &rule.action {
             if result.display_id.is_none() {
                 result.display_id = state.resolve_output(o);
+                if result.display_id.is_some() {
+                    result.applied_rules.push(rule.clone());
+                }
             }
         }
     }
@@ -91,7 +169,7 @@ pub fn apply_rules_to_new_window(state: &mut State, window_id: WindowId) -> Vec<
             window.app_name.clone(),
             window.app_id.clone(),
             window.title.clone(),
-            window.extended_attributes(),
+            with_rule_context(state, window.display_id, window.extended_attributes()),
             window.pid,
         )
     };
@@ -100,6 +178,7 @@ pub fn apply_rules_to_new_window(state: &mut State, window_id: WindowId) -> Vec<
         apply_rules_to_window_extended(state, &app_name, app_id.as_deref(), &title, &ext);
 
     if let Some(window) = state.windows.get_mut(&window_id) {
+        window.applied_rules = rule_result.applied_rules.clone();
         if let Some(tag_mask) = rule_result.tags {
             window.tags = Tag::from_mask(tag_mask);
             tracing::info!(
@@ -124,11 +203,122 @@ pub fn apply_rules_to_new_window(state: &mut State, window_id: WindowId) -> Vec<
                 floating
             );
         }
+        if let Some(percent) = rule_result.opacity {
+            window.opacity = Some(percent);
+            tracing::info!(
+                "Applied rule: window {} opacity set to {}%",
+                window_id,
+                percent
+            );
+        }
+    }
+
+    let is_dialog =
+        rule_result.is_floating.is_none() && float_dialog(state, window_id, &rule_result);
+    if rule_result.is_floating.is_none() && !is_dialog {
+        apply_float_tags(state, window_id);
+    }
+
+    let mut effects = rule_geometry_effects(state, window_id, pid, &rule_result);
+    if is_dialog && rule_result.position.is_none() {
+        effects.extend(center_over_parent(state, window_id, pid));
+    }
+
+    let hide_move = compute_hide_for_window(state, window_id);
+    if let Some(window_move) = hide_move {
+        effects.push(Effect::ApplyWindowMoves(vec![window_move]));
     }
 
+    effects
+}
+
+/// Float a dialog that belongs to another managed window, on its parent's display and tags
+/// unless rules placed it. Returns whether the window is such a dialog.
+fn float_dialog(
+    state: &mut State,
+    window_id: WindowId,
+    rule_result: &RuleApplicationResult,
+) -> bool {
+    let Some(parent_id) = state.windows.get(&window_id).and_then(|w| w.parent_id) else {
+        return false;
+    };
+    let Some((parent_tags, parent_display_id)) = state
+        .windows
+        .get(&parent_id)
+        .map(|p| (p.tags, p.display_id))
+    else {
+        return false;
+    };
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return false;
+    };
+    window.is_floating = true;
+    if rule_result.tags.is_none() {
+        window.tags = parent_tags;
+    }
+    if rule_result.display_id.is_none() {
+        window.display_id = parent_display_id;
+    }
+    tracing::info!(
+        "Window {} is a dialog of window {}, floating it",
+        window_id,
+        parent_id
+    );
+    true
+}
+
+/// Move a dialog to the center of its parent window
+fn center_over_parent(state: &State, window_id: WindowId, pid: i32) -> Option<Effect> {
+    let window = state.windows.get(&window_id)?;
+    let parent = state.windows.get(&window.parent_id?)?;
+    Some(Effect::MoveWindowToPosition {
+        window_id,
+        pid,
+        x: parent.frame.x + (parent.frame.width as i32 - window.frame.width as i32) / 2,
+        y: parent.frame.y + (parent.frame.height as i32 - window.frame.height as i32) / 2,
+    })
+}
+
+/// Resolve position and dimensions rules against the window's display.
+/// Percentages are of the display size; anchored positions use the final window size.
+fn rule_geometry_effects(
+    state: &State,
+    window_id: WindowId,
+    pid: i32,
+    rule_result: &RuleApplicationResult,
+) -> Vec<Effect> {
+    let Some(window) = state.windows.get(&window_id) else {
+        return vec![];
+    };
+    let display_frame = state
+        .displays
+        .get(&window.display_id)
+        .map(|d| d.frame)
+        .unwrap_or(window.frame);
+
+    let dimensions = rule_result.dimensions.map(|(width, height)| {
+        (
+            width.resolve(display_frame.width),
+            height.resolve(display_frame.height),
+        )
+    });
+    let position = rule_result.position.map(|(x, y, anchor)| match anchor {
+        Some(anchor) => anchor.resolve(
+            (x, y),
+            (
+                display_frame.x,
+                display_frame.y,
+                display_frame.width,
+                display_frame.height,
+            ),
+            dimensions.unwrap_or((window.frame.width, window.frame.height)),
+        ),
+        None => (x, y),
+    });
+
     let mut effects = Vec::new();
 
-    if let Some((x, y)) = rule_result.position {
+    if let Some((x, y)) = position {
         tracing::info!(
             "Rule requires position for window {} (pid {}): ({}, {})",
             window_id,
@@ -144,7 +334,7 @@ pub fn apply_rules_to_new_window(state: &mut State, window_id: WindowId) -> Vec<
         });
     }
 
-    if let Some((width, height)) = rule_result.dimensions {
+    if let Some((width, height)) = dimensions {
         tracing::info!(
             "Rule requires dimensions for window {} (pid {}): ({}, {})",
             window_id,
@@ -160,12 +350,74 @@ pub fn apply_rules_to_new_window(state: &mut State, window_id: WindowId) -> Vec<
         });
     }
 
-    let hide_move = compute_hide_for_window(state, window_id);
-    if let Some(window_move) = hide_move {
-        effects.push(Effect::ApplyWindowMoves(vec![window_move]));
+    effects
+}
+
+/// Re-apply dynamic rules to a window whose title changed. Rules that start matching
+/// set tags, output and floating as on creation; what they did stays once the title
+/// stops matching. Returns whether the window changed and the moves hiding or showing it.
+pub fn apply_dynamic_rules(state: &mut State, window_id: WindowId) -> (bool, Vec<WindowMove>) {
+    if state.paused {
+        return (false, vec![]);
     }
+    let Some(window) = state.windows.get(&window_id) else {
+        return (false, vec![]);
+    };
+    let ext = with_rule_context(state, window.display_id, window.extended_attributes());
+    let original_display_id = window.display_id;
+    let rule_result = apply_rules_to_window_extended(
+        state,
+        &window.app_name,
+        window.app_id.as_deref(),
+        &window.title,
+        &ext,
+    );
+    let new_rules: Vec<WindowRule> = rule_result
+        .applied_rules
+        .iter()
+        .filter(|rule| {
+            rule.dynamic && rule.action.supports_dynamic() && !window.applied_rules.contains(rule)
+        })
+        .cloned()
+        .collect();
 
-    effects
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return (false, vec![]);
+    };
+    // Forget dynamic rules that stopped matching, so they apply again when the title comes back
+    window
+        .applied_rules
+        .retain(|rule| !rule.dynamic || rule_result.applied_rules.contains(rule));
+    if new_rules.is_empty() {
+        return (false, vec![]);
+    }
+    for rule in new_rules {
+        match rule.action {
+            RuleAction::Tags { tags } => window.tags = Tag::from_mask(tags),
+            RuleAction::Output { .. } => {
+                if let Some(display_id) = rule_result.display_id {
+                    window.display_id = display_id;
+                }
+            }
+            RuleAction::Float => window.is_floating = true,
+            RuleAction::NoFloat => window.is_floating = false,
+            _ => {}
+        }
+        tracing::info!(
+            "Applied dynamic rule to window {} ({}) after title change: {:?}",
+            window_id,
+            window.title,
+            rule.action
+        );
+        window.applied_rules.push(rule);
+    }
+
+    let display_id = window.display_id;
+    let mut moves = compute_layout_changes_for_display(state, original_display_id);
+    if display_id != original_display_id {
+        moves.extend(compute_layout_changes_for_display(state, display_id));
+    }
+    (true, moves)
 }
 
 fn compute_hide_for_window(state: &mut State, window_id: WindowId) -> Option<WindowMove> {
@@ -192,7 +444,10 @@ fn compute_hide_for_window(state: &mut State, window_id: WindowId) -> Option<Win
         window_frame.height,
     );
 
-    let should_be_visible = window_tags.intersects(visible_tags);
+    let should_be_visible = state
+        .windows
+        .get(&window_id)
+        .is_some_and(|w| state.shows_on_tags(w, visible_tags));
 
     if !should_be_visible {
         tracing::info!(
@@ -225,6 +480,10 @@ pub fn apply_rules_to_all_windows(
     state: &mut State,
 ) -> (Vec<DisplayId>, Vec<Effect>, Vec<WindowId>) {
     if state.rules_engine.is_empty() {
+        // Nothing can want translucency any more (e.g. the last rule was deleted)
+        for window in state.windows.values_mut() {
+            window.opacity = None;
+        }
         return (vec![], vec![], vec![]);
     }
 
@@ -238,7 +497,7 @@ pub fn apply_rules_to_all_windows(
         .iter()
         .filter_map(|&id| {
             let window = state.windows.get(&id)?;
-            let ext = window.extended_attributes();
+            let ext = with_rule_context(state, window.display_id, window.extended_attributes());
             if should_ignore_window_extended(
                 state,
                 &window.app_name,
@@ -283,7 +542,7 @@ pub fn apply_rules_to_all_windows(
                 window.app_name.clone(),
                 window.app_id.clone(),
                 window.title.clone(),
-                window.extended_attributes(),
+                with_rule_context(state, window.display_id, window.extended_attributes()),
                 window.pid,
                 window.tags,
                 window.display_id,
@@ -301,6 +560,7 @@ pub fn apply_rules_to_all_windows(
             new_display_id.is_some() && new_display_id != Some(original_display_id);
 
         if let Some(window) = state.windows.get_mut(&window_id) {
+            window.applied_rules = rule_result.applied_rules.clone();
             if let Some(tag_mask) = rule_result.tags {
                 window.tags = Tag::from_mask(tag_mask);
                 tracing::info!(
@@ -328,6 +588,16 @@ pub fn apply_rules_to_all_windows(
                     floating
                 );
             }
+            // Follows the rules both ways, so a deleted opacity rule makes the window opaque
+            if window.opacity != rule_result.opacity {
+                window.opacity = rule_result.opacity;
+                tracing::info!(
+                    "Applied rule: window {} ({}) opacity set to {:?}",
+                    window_id,
+                    app_name,
+                    rule_result.opacity
+                );
+            }
         }
 
         if tags_changed || display_changed {
@@ -337,23 +607,7 @@ pub fn apply_rules_to_all_windows(
             }
         }
 
-        if let Some((x, y)) = rule_result.position {
-            effects.push(Effect::MoveWindowToPosition {
-                window_id,
-                pid,
-                x,
-                y,
-            });
-        }
-
-        if let Some((width, height)) = rule_result.dimensions {
-            effects.push(Effect::SetWindowDimensions {
-                window_id,
-                pid,
-                width,
-                height,
-            });
-        }
+        effects.extend(rule_geometry_effects(state, window_id, pid, &rule_result));
     }
 
     let display_ids: Vec<_> = affected_displays.into_iter().collect();
@@ -1,16 +1,21 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
-use super::super::{Display, Rect, Window, WindowId};
+use super::super::{Display, Rect, Window, WindowId, YASHIKI_BUNDLE_ID};
 use crate::macos::DisplayId;
 use crate::platform::WindowSystem;
+use yashiki_ipc::ExtendedWindowAttributes;
 
 use super::super::state::{IgnoredWindowInfo, State, WindowMove};
 
+use super::focus::hand_focus_to_parent;
 use super::layout::{
     add_to_window_order, compute_hide_position_for_display, remove_from_window_order,
 };
-use super::rules::{has_matching_non_ignore_rule, should_ignore_window_extended};
+use super::rules::{
+    has_matching_non_ignore_rule, should_ignore_window_extended, with_rule_context,
+};
+use super::tags::initial_tags_for_new_window;
 
 /// Grace period during which recently ignored windows protect managed windows from removal.
 /// This handles Firefox-style fullscreen transitions where a new ignored window appears
@@ -84,6 +89,43 @@ fn should_remove_window_if_not_transitioning<W: WindowSystem>(
     should_remove_window(ws, window_id, pid, ax_accessible)
 }
 
+/// Check whether a frame covers a whole display, as a native fullscreen window does
+fn covers_display(state: &State, frame: &Rect) -> bool {
+    state.displays.values().any(|d| d.frame == *frame)
+}
+
+/// Native fullscreen windows per pid, queried at most once per app during a sync
+type FullscreenCache = HashMap<i32, HashSet<WindowId>>;
+
+/// Refresh a window's native fullscreen flag from the AX API.
+/// Returns true if the window entered or left native fullscreen.
+fn update_native_fullscreen<W: WindowSystem>(
+    state: &mut State,
+    ws: &W,
+    cache: &mut FullscreenCache,
+    window_id: WindowId,
+) -> bool {
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return false;
+    };
+    let fullscreen = cache
+        .entry(window.pid)
+        .or_insert_with(|| ws.get_native_fullscreen_windows(window.pid))
+        .contains(&window_id);
+    if window.is_native_fullscreen == fullscreen {
+        return false;
+    }
+    tracing::info!(
+        "Window [{}] {} ({}) {} native fullscreen",
+        window.id,
+        window.title,
+        window.app_name,
+        if fullscreen { "entered" } else { "left" }
+    );
+    window.is_native_fullscreen = fullscreen;
+    true
+}
+
 /// Check if a hidden window needs to be re-hidden (returns Some if moved from hide position)
 fn check_window_rehide(
     window: &Window,
@@ -157,9 +199,27 @@ pub fn sync_all<W: WindowSystem>(state: &mut State, ws: &W) -> (Vec<WindowMove>,
                 display.name = info.name.clone();
                 display.frame = Rect::from_bounds(&info.frame);
                 display.is_main = info.is_main;
+                display.scale_factor = info.scale_factor;
+                display.refresh_rate = info.refresh_rate;
+                display.uuid = info.uuid.clone();
+                display.obstructed_corners = info.obstructed_corners;
+                let previous_rotation = std::mem::replace(&mut display.rotation, info.rotation);
+                if previous_rotation != info.rotation {
+                    tracing::info!(
+                        "Display {} rotated: {} -> {} degrees",
+                        info.id,
+                        previous_rotation,
+                        info.rotation
+                    );
+                }
             })
-            .or_insert_with(|| {
-                Display::new(
+            .or_insert_with(|| Display {
+                scale_factor: info.scale_factor,
+                rotation: info.rotation,
+                refresh_rate: info.refresh_rate,
+                uuid: info.uuid.clone(),
+                obstructed_corners: info.obstructed_corners,
+                ..Display::new(
                     info.id,
                     info.name.clone(),
                     Rect::from_bounds(&info.frame),
@@ -307,6 +367,7 @@ pub fn sync_pid<W: WindowSystem>(
     let mut changed = false;
     let mut added_window_ids = Vec::new();
     let mut rehide_moves = Vec::new();
+    let mut fullscreen_cache = FullscreenCache::new();
 
     // Check AX accessibility for this process
     let ax_accessible = ws.can_access_ax_windows(pid);
@@ -326,7 +387,12 @@ pub fn sync_pid<W: WindowSystem>(
         };
 
     // Remove managed windows that are no longer on screen
+    // (windows of hidden apps are off the list but still exist)
+    let app_hidden = state.is_app_hidden(pid);
     for id in current_ids.difference(&on_screen_ids) {
+        if app_hidden {
+            continue;
+        }
         if !should_remove_window_if_not_transitioning(
             ws,
             *id,
@@ -334,6 +400,10 @@ pub fn sync_pid<W: WindowSystem>(
             ax_accessible,
             &pids_with_new_windows,
         ) {
+            // Native fullscreen windows live on their own Space, off the on-screen list
+            if ax_accessible && update_native_fullscreen(state, ws, &mut fullscreen_cache, *id) {
+                changed = true;
+            }
             continue;
         }
 
@@ -346,6 +416,7 @@ pub fn sync_pid<W: WindowSystem>(
             );
             if state.focused == Some(*id) {
                 state.focused = None;
+                hand_focus_to_parent(state, &window);
             }
             changed = true;
         }
@@ -459,6 +530,17 @@ pub fn sync_pid<W: WindowSystem>(
                 .unwrap_or_else(|| info.name.clone().unwrap_or_default());
             let new_frame = Rect::from_bounds(&info.bounds);
 
+            // While its Space is active, a native fullscreen window is on screen at display size
+            let was_native_fullscreen = state
+                .windows
+                .get(id)
+                .is_some_and(|w| w.is_native_fullscreen);
+            if (was_native_fullscreen || covers_display(state, &new_frame))
+                && update_native_fullscreen(state, ws, &mut fullscreen_cache, *id)
+            {
+                changed = true;
+            }
+
             // Compute hide position and suppress_rehide check before mutable borrow
             let (hide_pos, suppress_rehide) = state.windows.get(id).map_or((None, false), |w| {
                 let hide = compute_hide_position_for_display(
@@ -544,8 +626,30 @@ pub fn find_display_for_bounds(state: &State, bounds: &crate::macos::Bounds) ->
     }
 }
 
+/// Pids of on-screen windows located on a display, whether managed or not
+pub fn on_screen_pids_for_display<W: WindowSystem>(
+    state: &State,
+    ws: &W,
+    display_id: DisplayId,
+) -> Vec<i32> {
+    let mut pids: Vec<i32> = ws
+        .get_on_screen_windows()
+        .iter()
+        .filter(|w| find_display_for_bounds(state, &w.bounds) == display_id)
+        .map(|w| w.pid)
+        .collect();
+    pids.sort();
+    pids.dedup();
+    pids
+}
+
+/// Whether windows of this app belong to yashiki itself
+pub fn is_internal_app(state: &State, pid: i32, app_id: Option<&str>) -> bool {
+    app_id == Some(YASHIKI_BUNDLE_ID) || state.internal_pids.contains(&pid)
+}
+
 /// Result of try_create_window:
-/// - `None`: Window should not be tracked at all (Control Center, non-normal layer without rule)
+/// - `None`: Window should not be tracked at all (Control Center, ignored output, non-normal layer without rule)
 /// - `Some(Ok(window))`: Window created successfully, should be managed
 /// - `Some(Err(info))`: Window was ignored by rule, should be tracked for re-evaluation
 pub fn try_create_window<W: WindowSystem>(
@@ -557,13 +661,23 @@ pub fn try_create_window<W: WindowSystem>(
     let app_name = &info.owner_name;
     let app_id = info.bundle_id.as_deref();
 
-    // Filter Control Center early - system UI that users never need to manage,
-    // and it creates many transient windows that slow down processing
-    if app_id == Some("com.apple.controlcenter") {
+    // Filter excluded apps early (e.g. Control Center) - system UI and helpers that users never
+    // need to manage, and that may create many transient windows that slow down processing
+    if state.config.is_app_excluded(app_id) || is_internal_app(state, info.pid, app_id) {
+        return None;
+    }
+    if state.is_output_ignored(display_id) {
         return None;
     }
 
-    let ext = ws.get_extended_attributes(info.window_id, info.pid, info.layer);
+    let ext = with_rule_context(
+        state,
+        display_id,
+        ExtendedWindowAttributes {
+            parent_execs: ws.get_process_ancestors(info.pid),
+            ..ws.get_extended_attributes(info.window_id, info.pid, info.layer)
+        },
+    );
 
     let title = ext
         .title
@@ -618,11 +732,7 @@ pub fn try_create_window<W: WindowSystem>(
         }));
     }
 
-    let initial_tag = state
-        .displays
-        .get(&display_id)
-        .map(|d| d.visible_tags)
-        .unwrap_or(state.default_tag);
+    let initial_tag = initial_tags_for_new_window(state, display_id, app_id.unwrap_or(app_name));
 
     let mut window = Window::from_window_info(info, initial_tag, display_id);
     window.title = title;
@@ -633,6 +743,10 @@ pub fn try_create_window<W: WindowSystem>(
     window.fullscreen_button = ext.fullscreen_button;
     window.minimize_button = ext.minimize_button;
     window.zoom_button = ext.zoom_button;
+    window.parent_execs = ext.parent_execs;
+    window.parent_id = ws
+        .get_parent_window(info.window_id, info.pid)
+        .filter(|id| state.windows.contains_key(id));
 
     Some(Ok(window))
 }
@@ -648,6 +762,7 @@ pub fn sync_with_window_infos<W: WindowSystem>(
     let all_current_ids: HashSet<WindowId> =
         current_ids.union(&current_ignored_ids).copied().collect();
     let mut added_window_ids = Vec::new();
+    let mut fullscreen_cache = FullscreenCache::new();
 
     // Collect PIDs that have new windows (indicates transition like fullscreen)
     // Also include PIDs that have recently added ignored windows (within grace period)
@@ -690,6 +805,10 @@ pub fn sync_with_window_infos<W: WindowSystem>(
     // Remove managed windows that are no longer on screen
     for id in current_ids.difference(&on_screen_ids) {
         if let Some(window) = state.windows.get(id) {
+            // Windows of hidden apps are off the list but still exist
+            if state.is_app_hidden(window.pid) {
+                continue;
+            }
             let ax_accessible = !inaccessible_pids.contains(&window.pid);
             if !should_remove_window_if_not_transitioning(
                 ws,
@@ -698,6 +817,9 @@ pub fn sync_with_window_infos<W: WindowSystem>(
                 ax_accessible,
                 &pids_with_new_windows,
             ) {
+                if ax_accessible {
+                    update_native_fullscreen(state, ws, &mut fullscreen_cache, *id);
+                }
                 continue;
             }
 
@@ -708,7 +830,11 @@ pub fn sync_with_window_infos<W: WindowSystem>(
                 window.app_name
             );
             remove_from_window_order(state, *id);
-            state.windows.remove(id);
+            if let Some(window) = state.windows.remove(id) {
+                if state.focused == Some(*id) {
+                    hand_focus_to_parent(state, &window);
+                }
+            }
         }
     }
 
@@ -797,6 +923,13 @@ pub fn sync_with_window_infos<W: WindowSystem>(
 
     // Update existing managed windows
     for info in window_infos {
+        let was_native_fullscreen = state
+            .windows
+            .get(&info.window_id)
+            .is_some_and(|w| w.is_native_fullscreen);
+        if was_native_fullscreen || covers_display(state, &Rect::from_bounds(&info.bounds)) {
+            update_native_fullscreen(state, ws, &mut fullscreen_cache, info.window_id);
+        }
         if let Some(window) = state.windows.get_mut(&info.window_id) {
             let ext = ws.get_extended_attributes(info.window_id, info.pid, info.layer);
             let new_title = ext
@@ -1,11 +1,141 @@
 use super::super::{Tag, WindowId};
 use crate::macos::DisplayId;
+use yashiki_ipc::{NewWindowTagPolicy, TagMode, TagOccupancy};
 
-use super::super::state::{State, WindowMove};
-use super::layout::compute_layout_changes_for_display;
+use super::super::state::{State, UnstashResult, WindowMove};
+use super::layout::{
+    add_to_window_order, compute_layout_changes_for_display, remove_from_window_order,
+};
+
+/// Number of tags considered by the emptiest new window tag policy
+const NEW_WINDOW_TAG_COUNT: u32 = 9;
+
+/// Compute initial tags for a new window according to the new window tag policy
+pub fn initial_tags_for_new_window(state: &State, display_id: DisplayId, app_key: &str) -> Tag {
+    let current = state
+        .displays
+        .get(&display_id)
+        .map(|d| d.visible_tags)
+        .unwrap_or(state.default_tag);
+    match state.config.new_window_tag {
+        NewWindowTagPolicy::Current => current,
+        NewWindowTagPolicy::Emptiest => emptiest_tag(state, display_id, current),
+        NewWindowTagPolicy::AppSticky => state.app_tags.get(app_key).copied().unwrap_or(current),
+    }
+}
+
+/// Tag with the fewest windows on the display (ties prefer visible, then lower tags)
+fn emptiest_tag(state: &State, display_id: DisplayId, current: Tag) -> Tag {
+    (1..=NEW_WINDOW_TAG_COUNT)
+        .map(Tag::new)
+        .min_by_key(|&tag| {
+            let count = state
+                .windows
+                .values()
+                .filter(|w| w.display_id == display_id && w.tags.intersects(tag))
+                .count();
+            (count, !tag.intersects(current))
+        })
+        .unwrap_or(current)
+}
+
+/// Managed and urgent windows per tag across all displays, skipping empty tags.
+/// A window on several tags counts toward each of them.
+pub fn tag_occupancy(state: &State) -> Vec<TagOccupancy> {
+    (1..=32)
+        .filter_map(|tag| {
+            let bit = Tag::new(tag);
+            let on_tag = || {
+                state
+                    .windows
+                    .values()
+                    .filter(move |w| w.tags.intersects(bit))
+            };
+            let windows = on_tag().count();
+            let urgent = on_tag().filter(|w| w.is_urgent).count();
+            (windows > 0).then_some(TagOccupancy {
+                tag,
+                windows,
+                urgent,
+            })
+        })
+        .collect()
+}
+
+/// Remember the window's tags as its app's last used tags
+pub fn remember_app_tags(state: &mut State, window_id: WindowId) {
+    if let Some(window) = state.windows.get(&window_id) {
+        state
+            .app_tags
+            .insert(window.app_key().to_string(), window.tags);
+    }
+}
+
+/// Remember the window as the last focused one for each visible tag it is on
+pub fn remember_tag_focus(state: &mut State, window_id: WindowId) {
+    let Some(window) = state.windows.get(&window_id) else {
+        return;
+    };
+    let Some(display) = state.displays.get(&window.display_id) else {
+        return;
+    };
+    let shared = Tag::from_mask(window.tags.mask() & display.visible_tags.mask());
+    for tag in shared.numbers() {
+        state.tag_focus.insert((display.id, tag), window_id);
+    }
+}
+
+/// Last focused window remembered for the display's visible tags (lowest tag first)
+pub fn last_focused_on_visible_tags(state: &State, display_id: DisplayId) -> Option<WindowId> {
+    let display = state.displays.get(&display_id)?;
+    display.visible_tags.numbers().find_map(|tag| {
+        state
+            .tag_focus
+            .get(&(display_id, tag))
+            .copied()
+            .filter(|id| {
+                state
+                    .windows
+                    .get(id)
+                    .is_some_and(|w| w.display_id == display_id && w.tags.intersects(Tag::new(tag)))
+            })
+    })
+}
 
 pub fn view_tags(state: &mut State, tags: u32) -> Vec<WindowMove> {
-    view_tags_on_display(state, tags, state.focused_display)
+    tag_target_displays(state, state.focused_display)
+        .into_iter()
+        .flat_map(|display_id| view_tags_on_display(state, tags, display_id))
+        .collect()
+}
+
+/// Displays a tag change on `display_id` applies to under the current tag mode
+pub fn tag_target_displays(state: &State, display_id: DisplayId) -> Vec<DisplayId> {
+    match state.config.tag_mode {
+        TagMode::PerOutput => vec![display_id],
+        TagMode::Global => {
+            let mut ids: Vec<_> = state.displays.keys().copied().collect();
+            ids.sort();
+            ids
+        }
+    }
+}
+
+/// Switching to global mode makes every display show the focused display's tags
+pub fn set_tag_mode(state: &mut State, mode: TagMode) -> Vec<WindowMove> {
+    tracing::info!("Set tag mode: {:?}", mode);
+    state.config.tag_mode = mode;
+    if mode == TagMode::PerOutput {
+        return vec![];
+    }
+    let Some(tags) = state
+        .displays
+        .get(&state.focused_display)
+        .map(|d| d.visible_tags.mask())
+    else {
+        return vec![];
+    };
+    view_tags(state, tags)
 }
 
 pub fn view_tags_on_display(
@@ -62,7 +192,14 @@ pub fn toggle_tags_on_display(
 }
 
 pub fn view_tags_last(state: &mut State) -> Vec<WindowMove> {
-    let Some(disp) = state.displays.get_mut(&state.focused_display) else {
+    tag_target_displays(state, state.focused_display)
+        .into_iter()
+        .flat_map(|display_id| view_tags_last_on_display(state, display_id))
+        .collect()
+}
+
+fn view_tags_last_on_display(state: &mut State, display_id: DisplayId) -> Vec<WindowMove> {
+    let Some(disp) = state.displays.get_mut(&display_id) else {
         return vec![];
     };
     if disp.visible_tags == disp.previous_visible_tags {
@@ -70,7 +207,7 @@ pub fn view_tags_last(state: &mut State) -> Vec<WindowMove> {
     }
     tracing::info!(
         "View tags last on display {}: {} -> {}, layout: {:?} -> {:?}",
-        state.focused_display,
+        display_id,
         disp.visible_tags.mask(),
         disp.previous_visible_tags.mask(),
         disp.current_layout,
@@ -78,7 +215,7 @@ pub fn view_tags_last(state: &mut State) -> Vec<WindowMove> {
     );
     std::mem::swap(&mut disp.visible_tags, &mut disp.previous_visible_tags);
     std::mem::swap(&mut disp.current_layout, &mut disp.previous_layout);
-    compute_layout_changes_for_display(state, state.focused_display)
+    compute_layout_changes_for_display(state, display_id)
 }
 
 pub fn move_focused_to_tags(state: &mut State, tags: u32) -> Vec<WindowMove> {
@@ -93,9 +230,198 @@ pub fn move_focused_to_tags(state: &mut State, tags: u32) -> Vec<WindowMove> {
     } else {
         return vec![];
     };
+    remember_app_tags(state, focused_id);
+    apply_float_tags(state, focused_id);
+    let target_display_id = floating_tag_display(state, focused_id);
+    if let Some(target_display_id) = target_display_id {
+        move_floating_to_display(state, focused_id, target_display_id);
+    }
+    let mut moves = compute_layout_changes_for_display(state, display_id);
+    if let Some(target_display_id) = target_display_id {
+        moves.extend(compute_layout_changes_for_display(state, target_display_id));
+    }
+    restore_tag_float_frame(state, focused_id);
+    moves
+}
+
+/// Another display showing the tags of a floating window that its own display doesn't
+/// show. Tiled windows stay in their display's layout.
+fn floating_tag_display(state: &State, window_id: WindowId) -> Option<DisplayId> {
+    let window = state.windows.get(&window_id)?;
+    if !window.is_floating || window.tags.mask() == 0 {
+        return None;
+    }
+    let own = state.displays.get(&window.display_id)?;
+    if window.tags.intersects(own.visible_tags) {
+        return None;
+    }
+    state
+        .displays
+        .values()
+        .filter(|d| !state.is_output_ignored(d.id))
+        .filter(|d| window.tags.intersects(d.visible_tags))
+        .map(|d| d.id)
+        .min()
+}
+
+/// Move a hidden floating window to another display, at the same relative position,
+/// so the target display's layout changes show it there
+fn move_floating_to_display(state: &mut State, window_id: WindowId, target_display_id: DisplayId) {
+    let Some(window) = state.windows.get(&window_id) else {
+        return;
+    };
+    let (Some(source), Some(target)) = (
+        state.displays.get(&window.display_id),
+        state.displays.get(&target_display_id),
+    ) else {
+        return;
+    };
+    let (source_frame, target_frame) = (source.frame, target.frame);
+
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return;
+    };
+    tracing::info!(
+        "Move floating window {} to display {} showing tags {}",
+        window_id,
+        target_display_id,
+        window.tags.mask()
+    );
+    let frame = window.saved_frame.unwrap_or(window.frame);
+    window.saved_frame = Some(frame.translated(&source_frame, &target_frame));
+    window.display_id = target_display_id;
+    window.orphaned_from = None;
+    remove_from_window_order(state, window_id);
+    add_to_window_order(state, window_id, target_display_id);
+}
+
+/// Take the focused window off every tag, so it is hidden and out of the layout
+/// until unstashed
+pub fn stash_focused(state: &mut State) -> Vec<WindowMove> {
+    let Some(focused_id) = state.focused else {
+        return vec![];
+    };
+    let Some(window) = state.windows.get_mut(&focused_id) else {
+        return vec![];
+    };
+    tracing::info!("Stash window {} ({})", window.id, window.app_name);
+    window.tags = Tag::from_mask(0);
+    let display_id = window.display_id;
+    state.stash.retain(|id| *id != focused_id);
+    state.stash.push(focused_id);
     compute_layout_changes_for_display(state, display_id)
 }
 
+/// Bring the most recently stashed window back on the focused display's visible tags
+pub fn unstash(state: &mut State) -> Option<UnstashResult> {
+    let target_display_id = state.focused_display;
+    let (target_tags, target_x, target_y) = {
+        let display = state.displays.get(&target_display_id)?;
+        (display.visible_tags, display.frame.x, display.frame.y)
+    };
+
+    // Skip windows that closed, or got tags again some other way, since they were stashed
+    let window_id = loop {
+        let id = state.stash.pop()?;
+        if state.windows.get(&id).is_some_and(|w| w.tags.mask() == 0) {
+            break id;
+        }
+    };
+
+    let window = state.windows.get_mut(&window_id)?;
+    tracing::info!(
+        "Unstash window {} ({}) to display {}",
+        window.id,
+        window.app_name,
+        target_display_id
+    );
+    window.tags = target_tags;
+    let pid = window.pid;
+    let source_display_id = window.display_id;
+    if source_display_id != target_display_id {
+        window.display_id = target_display_id;
+        window.orphaned_from = None;
+        // Show it on the target display rather than where it was stashed from
+        if let Some(ref mut saved) = window.saved_frame {
+            saved.x = target_x;
+            saved.y = target_y;
+        }
+        remove_from_window_order(state, window_id);
+        add_to_window_order(state, window_id, target_display_id);
+    }
+    apply_float_tags(state, window_id);
+    let window_moves = compute_layout_changes_for_display(state, target_display_id);
+
+    Some(UnstashResult {
+        window_id,
+        pid,
+        source_display_id,
+        target_display_id,
+        window_moves,
+    })
+}
+
+/// Move some tiled windows on the display from `from` tags to `to` tags:
+/// every other window in layout order, or the last `count`
+pub fn distribute_tags(
+    state: &mut State,
+    from: u32,
+    to: u32,
+    count: Option<u32>,
+    every_other: bool,
+    display_id: DisplayId,
+) -> Vec<WindowMove> {
+    let Some(display) = state.displays.get(&display_id) else {
+        return vec![];
+    };
+    let from_tags = Tag::from_mask(from);
+    let mut candidates: Vec<WindowId> = state
+        .windows
+        .values()
+        .filter(|w| w.display_id == display_id && w.tags.intersects(from_tags) && w.is_tiled())
+        .map(|w| w.id)
+        .collect();
+    candidates.sort_by_key(|id| {
+        display
+            .window_order
+            .iter()
+            .position(|o| o == id)
+            .map(|p| (0, p))
+            .unwrap_or((1, *id as usize))
+    });
+
+    let selected: Vec<WindowId> = if every_other {
+        candidates.into_iter().skip(1).step_by(2).collect()
+    } else {
+        let count = count.unwrap_or(0) as usize;
+        let skip = candidates.len().saturating_sub(count);
+        candidates.into_iter().skip(skip).collect()
+    };
+    if selected.is_empty() {
+        return vec![];
+    }
+
+    for &window_id in &selected {
+        if let Some(window) = state.windows.get_mut(&window_id) {
+            let new_tags = Tag::from_mask((window.tags.mask() & !from) | to);
+            tracing::info!(
+                "Distribute window {} tags: {} -> {}",
+                window_id,
+                window.tags.mask(),
+                new_tags.mask()
+            );
+            window.tags = new_tags;
+        }
+        remember_app_tags(state, window_id);
+        apply_float_tags(state, window_id);
+    }
+    let moves = compute_layout_changes_for_display(state, display_id);
+    for &window_id in &selected {
+        restore_tag_float_frame(state, window_id);
+    }
+    moves
+}
+
 pub fn toggle_focused_window_tags(state: &mut State, tags: u32) -> Vec<WindowMove> {
     let Some(focused_id) = state.focused else {
         return vec![];
@@ -117,7 +443,11 @@ pub fn toggle_focused_window_tags(state: &mut State, tags: u32) -> Vec<WindowMov
     } else {
         return vec![];
     };
-    compute_layout_changes_for_display(state, display_id)
+    remember_app_tags(state, focused_id);
+    apply_float_tags(state, focused_id);
+    let moves = compute_layout_changes_for_display(state, display_id);
+    restore_tag_float_frame(state, focused_id);
+    moves
 }
 
 pub fn toggle_focused_fullscreen(state: &mut State) -> Option<(DisplayId, bool, WindowId, i32)> {
@@ -144,6 +474,8 @@ pub fn toggle_focused_float(state: &mut State) -> Option<(DisplayId, bool, Windo
     let window = state.windows.get_mut(&focused_id)?;
 
     window.is_floating = !window.is_floating;
+    // Floating chosen by hand stays when the window leaves a float-only tag
+    window.floated_by_tag = false;
     tracing::info!(
         "Toggle floating for window {}: {}",
         window.id,
@@ -152,3 +484,95 @@ pub fn toggle_focused_float(state: &mut State) -> Option<(DisplayId, bool, Windo
 
     Some((window.display_id, window.is_floating, window.id, window.pid))
 }
+
+pub fn toggle_manual(state: &mut State, window_id: WindowId) -> Option<(DisplayId, bool)> {
+    let window = state.windows.get_mut(&window_id)?;
+
+    window.is_manual = !window.is_manual;
+    tracing::info!(
+        "Toggle manual geometry for window {}: {}",
+        window.id,
+        window.is_manual
+    );
+
+    Some((window.display_id, window.is_manual))
+}
+
+/// Set or clear the input source of every tag in the mask
+pub fn set_tag_input_source(state: &mut State, tags: u32, input_source: Option<&str>) {
+    for tag in Tag::from_mask(tags).numbers() {
+        match input_source {
+            Some(source) => {
+                state
+                    .tag_input_sources
+                    .insert(tag as u8, source.to_string());
+            }
+            None => {
+                state.tag_input_sources.remove(&(tag as u8));
+            }
+        }
+    }
+}
+
+/// Input source of the lowest tag that has one
+pub fn input_source_for_tags(state: &State, tags: Tag) -> Option<&str> {
+    tags.numbers()
+        .find_map(|tag| state.tag_input_sources.get(&(tag as u8)))
+        .map(|s| s.as_str())
+}
+
+/// Mark or unmark float-only tags and re-evaluate every window against them
+pub fn set_float_tags(state: &mut State, tags: u32, enabled: bool) -> Vec<WindowMove> {
+    let mask = if enabled {
+        state.float_tags.mask() | tags
+    } else {
+        state.float_tags.mask() & !tags
+    };
+    tracing::info!("Float-only tags: {} -> {}", state.float_tags.mask(), mask);
+    state.float_tags = Tag::from_mask(mask);
+
+    let window_ids: Vec<_> = state.windows.keys().copied().collect();
+    for &window_id in &window_ids {
+        apply_float_tags(state, window_id);
+    }
+    let display_ids: Vec<_> = state.displays.keys().copied().collect();
+    let moves = display_ids
+        .into_iter()
+        .flat_map(|display_id| compute_layout_changes_for_display(state, display_id))
+        .collect();
+    for window_id in window_ids {
+        restore_tag_float_frame(state, window_id);
+    }
+    moves
+}
+
+/// Float a window that is on a float-only tag, and re-tile one that left it
+pub fn apply_float_tags(state: &mut State, window_id: WindowId) {
+    let float_tags = state.float_tags;
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return;
+    };
+    let on_float_tag = window.tags.intersects(float_tags);
+    if on_float_tag && !window.is_floating {
+        tracing::info!("Window {} floats on float-only tag", window_id);
+        window.is_floating = true;
+        window.floated_by_tag = true;
+    } else if !on_float_tag && window.floated_by_tag {
+        tracing::info!("Window {} left float-only tag, tiling", window_id);
+        window.tag_float_frame = Some(window.saved_frame.unwrap_or(window.frame));
+        window.is_floating = false;
+        window.floated_by_tag = false;
+    }
+}
+
+/// Bring back the remembered float geometry when a hidden window is shown on a float-only tag
+fn restore_tag_float_frame(state: &mut State, window_id: WindowId) {
+    let Some(window) = state.windows.get_mut(&window_id) else {
+        return;
+    };
+    if window.floated_by_tag && window.is_hidden() {
+        if let Some(frame) = window.tag_float_frame.take() {
+            window.saved_frame = Some(frame);
+        }
+    }
+}
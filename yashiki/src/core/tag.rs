@@ -30,6 +30,11 @@ impl Tag {
         }
         Some(self.0.trailing_zeros() + 1)
     }
+
+    /// Tag numbers (1-32) of all set bits, lowest first
+    pub fn numbers(self) -> impl Iterator<Item = u32> {
+        (1..=32).filter(move |&n| self.0 & (1 << (n - 1)) != 0)
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +103,17 @@ mod tests {
         assert_eq!(Tag::new(3).toggle(Tag::new(4)).first_tag(), Some(3));
     }
 
+    #[test]
+    fn test_numbers() {
+        assert_eq!(Tag::new(3).numbers().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            Tag::from_mask(0b1010).numbers().collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        assert_eq!(Tag::new(32).numbers().collect::<Vec<_>>(), vec![32]);
+        assert_eq!(Tag::from_mask(0).numbers().count(), 0);
+    }
+
     #[test]
     fn test_equality() {
         assert_eq!(Tag::new(1), Tag::new(1));
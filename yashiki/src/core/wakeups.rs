@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Span over which the wakeup rate is averaged
+const WAKEUP_WINDOW: Duration = Duration::from_secs(10);
+
+/// Counts main run loop wakeups (source and timer callbacks) over a sliding window
+#[derive(Debug, Default)]
+pub struct WakeupStats {
+    wakeups: VecDeque<Instant>,
+}
+
+impl WakeupStats {
+    pub fn record(&mut self, now: Instant) {
+        self.prune(now);
+        self.wakeups.push_back(now);
+    }
+
+    pub fn per_second(&mut self, now: Instant) -> f64 {
+        self.prune(now);
+        self.wakeups.len() as f64 / WAKEUP_WINDOW.as_secs_f64()
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while let Some(&oldest) = self.wakeups.front() {
+            if now.duration_since(oldest) < WAKEUP_WINDOW {
+                break;
+            }
+            self.wakeups.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wakeup_rate_over_window() {
+        let start = Instant::now();
+        let mut stats = WakeupStats::default();
+        assert_eq!(stats.per_second(start), 0.0);
+
+        for i in 0..20 {
+            stats.record(start + Duration::from_millis(i * 100));
+        }
+        assert_eq!(stats.per_second(start + Duration::from_secs(2)), 2.0);
+    }
+
+    #[test]
+    fn test_wakeup_rate_drops_when_idle() {
+        let start = Instant::now();
+        let mut stats = WakeupStats::default();
+        for i in 0..20 {
+            stats.record(start + Duration::from_millis(i * 100));
+        }
+        assert_eq!(stats.per_second(start + Duration::from_secs(12)), 0.0);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::macos::{Bounds, DisplayId, WindowInfo};
 
 use super::Tag;
-use yashiki_ipc::ButtonInfo;
+use yashiki_ipc::{ButtonInfo, WindowRule};
 
 pub type WindowId = u32;
 
@@ -25,10 +25,30 @@ pub struct Window {
     pub saved_frame: Option<Rect>,
     pub is_floating: bool,
     pub is_fullscreen: bool,
+    /// In native macOS fullscreen on its own Space (left out of layouts)
+    pub is_native_fullscreen: bool,
+    /// Its app was hidden with window-hide-app (left out of layouts until it returns)
+    pub is_app_hidden: bool,
+    /// Kept in the layout, but retiles don't overwrite its geometry
+    pub is_manual: bool,
+    /// Needs attention (window-set-urgent), until focused
+    pub is_urgent: bool,
+    /// Opacity in percent while unfocused (opacity rule), fully opaque when focused
+    pub opacity: Option<u32>,
+    /// Floating because it is on a float-only tag (cleared when it leaves)
+    pub floated_by_tag: bool,
+    /// Geometry the window had when it last left a float-only tag
+    pub tag_float_frame: Option<Rect>,
     /// Display ID that this window was orphaned from during display disconnection.
     /// Some(display_id): Window was orphaned due to display removal (remembers original display)
     /// None: Window is on its intended display
     pub orphaned_from: Option<DisplayId>,
+    /// Managed window this dialog belongs to (floated and centered over it)
+    pub parent_id: Option<WindowId>,
+    /// Command names of the process's ancestors, nearest first (for `--parent-exec` rules)
+    pub parent_execs: Vec<String>,
+    /// Rules applied to this window when rules were last evaluated for it
+    pub applied_rules: Vec<WindowRule>,
 }
 
 impl Window {
@@ -52,12 +72,25 @@ impl Window {
             saved_frame: None,
             is_floating: false,
             is_fullscreen: false,
+            is_native_fullscreen: false,
+            is_app_hidden: false,
+            is_manual: false,
+            is_urgent: false,
+            opacity: None,
+            floated_by_tag: false,
+            tag_float_frame: None,
             orphaned_from: None,
+            parent_id: None,
+            parent_execs: Vec::new(),
+            applied_rules: Vec::new(),
         }
     }
 
     pub fn is_tiled(&self) -> bool {
-        !self.is_floating && !self.is_fullscreen
+        !self.is_floating
+            && !self.is_fullscreen
+            && !self.is_native_fullscreen
+            && !self.is_app_hidden
     }
 
     pub fn center(&self) -> (i32, i32) {
@@ -67,6 +100,11 @@ impl Window {
         )
     }
 
+    /// Key identifying the window's app (bundle identifier, or app name as fallback)
+    pub fn app_key(&self) -> &str {
+        self.app_id.as_deref().unwrap_or(&self.app_name)
+    }
+
     /// Check if window is hidden (has a saved frame from being moved offscreen)
     pub fn is_hidden(&self) -> bool {
         self.saved_frame.is_some()
@@ -83,6 +121,8 @@ impl Window {
             fullscreen_button: self.fullscreen_button.clone(),
             minimize_button: self.minimize_button.clone(),
             zoom_button: self.zoom_button.clone(),
+            parent_execs: self.parent_execs.clone(),
+            ..Default::default()
         }
     }
 }
@@ -111,4 +151,29 @@ impl Rect {
             self.y + self.height as i32 / 2,
         )
     }
+
+    /// This frame moved by the given amount
+    pub fn offset(&self, dx: i32, dy: i32) -> Rect {
+        Rect {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+
+    /// Move this frame from one display frame to another, keeping its relative position
+    /// and size, and keeping it on screen where it fits
+    pub fn translated(&self, from: &Rect, to: &Rect) -> Rect {
+        let place = |pos: i32, from_pos: i32, from_len: u32, to_pos: i32, to_len: u32, len: u32| {
+            let offset = (pos - from_pos) as i64 * to_len as i64 / from_len.max(1) as i64;
+            let max = to_pos + to_len.saturating_sub(len) as i32;
+            (to_pos + offset as i32).clamp(to_pos, max)
+        };
+        Rect {
+            x: place(self.x, from.x, from.width, to.x, to.width, self.width),
+            y: place(self.y, from.y, from.height, to.y, to.height, self.height),
+            width: self.width,
+            height: self.height,
+        }
+    }
 }
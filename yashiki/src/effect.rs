@@ -1,7 +1,7 @@
 use crate::core::WindowMove;
 use crate::macos::DisplayId;
 
-use yashiki_ipc::Response;
+use yashiki_ipc::{AnimationEasing, LayoutAction, Response};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Effect {
@@ -27,6 +27,16 @@ pub enum Effect {
         window_id: u32,
         pid: i32,
     },
+    HideApp {
+        pid: i32,
+    },
+    UnhideApp {
+        pid: i32,
+    },
+    QuitApp {
+        pid: i32,
+        force: bool,
+    },
     ApplyFullscreen {
         window_id: u32,
         pid: i32,
@@ -39,17 +49,31 @@ pub enum Effect {
         cmd: String,
         args: Vec<String>,
     },
-    ExecCommand {
-        command: String,
-        path: String,
+    /// Run a standard layout action on the current layout engine
+    SendLayoutAction {
+        action: LayoutAction,
+        args: Vec<String>,
     },
-    ExecCommandTracked {
+    /// Tell the display's layout engine which window occupies the main slot
+    PromoteWindow {
+        display_id: DisplayId,
+        window_id: u32,
+    },
+    ExecCommand {
         command: String,
+        args: Option<Vec<String>>,
         path: String,
+        env: Vec<(String, String)>,
+        track: bool,
+        name: Option<String>,
     },
     UpdateLayoutExecPath {
         path: String,
     },
+    UpdateAnimation {
+        duration_ms: u32,
+        easing: AnimationEasing,
+    },
     FocusVisibleWindowIfNeeded,
     WarpCursorToDisplay {
         display_id: DisplayId,
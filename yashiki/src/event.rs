@@ -5,6 +5,7 @@ pub enum Event {
     FocusedWindowChanged,
     WindowMoved { pid: i32 },
     WindowResized { pid: i32 },
+    WindowTitleChanged { pid: i32 },
     WindowMiniaturized { pid: i32 },
     WindowDeminiaturized { pid: i32 },
     ApplicationActivated { pid: i32 },
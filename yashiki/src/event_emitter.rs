@@ -1,17 +1,26 @@
+use std::cell::{Cell, RefCell};
 use std::sync::mpsc as std_mpsc;
 
 use crate::core::{Display, State, Window};
-use yashiki_ipc::{OutputInfo, StateEvent, WindowInfo};
+use yashiki_ipc::{OutputInfo, StateEvent, TagOccupancy, WindowInfo};
 
 /// Event emitter for sending state change events from the main thread to the tokio thread.
 /// Uses std::sync::mpsc for thread-safe communication.
 pub struct EventEmitter {
     tx: std_mpsc::Sender<StateEvent>,
+    /// Tag occupancy sent with the last tags state event
+    last_tags_state: RefCell<Vec<TagOccupancy>>,
+    /// Sequence number of the last error sent as an event
+    last_error_seq: Cell<u64>,
 }
 
 impl EventEmitter {
     pub fn new(tx: std_mpsc::Sender<StateEvent>) -> Self {
-        Self { tx }
+        Self {
+            tx,
+            last_tags_state: RefCell::new(Vec::new()),
+            last_error_seq: Cell::new(0),
+        }
     }
 
     /// Send an event to subscribers
@@ -29,8 +38,20 @@ impl EventEmitter {
     }
 
     /// Emit a window destroyed event
-    pub fn emit_window_destroyed(&self, window_id: u32) {
-        self.emit(StateEvent::WindowDestroyed { window_id });
+    pub fn emit_window_destroyed(&self, window_id: u32, output_id: u32, app_id: Option<&str>) {
+        self.emit(StateEvent::WindowDestroyed {
+            window_id,
+            output_id: Some(output_id),
+            app_id: app_id.map(str::to_string),
+        });
+    }
+
+    /// Emit an observer recreated event
+    pub fn emit_observer_recreated(&self, pid: i32, app_name: &str) {
+        self.emit(StateEvent::ObserverRecreated {
+            pid,
+            app_name: app_name.to_string(),
+        });
     }
 
     /// Emit a window updated event
@@ -40,9 +61,14 @@ impl EventEmitter {
         });
     }
 
-    /// Emit a window focused event
-    pub fn emit_window_focused(&self, window_id: Option<u32>) {
-        self.emit(StateEvent::WindowFocused { window_id });
+    /// Emit a window focused event, with the window's output and app looked up in state
+    pub fn emit_window_focused(&self, window_id: Option<u32>, state: &State) {
+        let window = window_id.and_then(|id| state.windows.get(&id));
+        self.emit(StateEvent::WindowFocused {
+            window_id,
+            output_id: window.map(|w| w.display_id),
+            app_id: window.and_then(|w| w.app_id.clone()),
+        });
     }
 
     /// Emit a display focused event
@@ -79,6 +105,38 @@ impl EventEmitter {
         });
     }
 
+    /// Emit a window tags changed event
+    pub fn emit_window_tags_changed(&self, window: &Window, previous_tags: u32) {
+        self.emit(StateEvent::WindowTagsChanged {
+            window_id: window.id,
+            tags: window.tags.mask(),
+            previous_tags,
+            output_id: Some(window.display_id),
+            app_id: window.app_id.clone(),
+        });
+    }
+
+    /// Emit a tags state event if per-tag window or urgent counts changed since the last one
+    pub fn emit_tags_state_if_changed(&self, state: &State) {
+        let tags = state.tag_occupancy();
+        let mut last = self.last_tags_state.borrow_mut();
+        if *last != tags {
+            *last = tags.clone();
+            self.emit(StateEvent::TagsState { tags });
+        }
+    }
+
+    /// Emit an error event for each error recorded since the last call
+    pub fn emit_new_errors(&self, state: &State) {
+        for entry in state.errors.since(self.last_error_seq.get()) {
+            self.emit(StateEvent::Error {
+                source: entry.source.clone(),
+                message: entry.message.clone(),
+            });
+        }
+        self.last_error_seq.set(state.errors.last_seq());
+    }
+
     /// Emit a layout changed event
     pub fn emit_layout_changed(&self, display_id: u32, layout: &str) {
         self.emit(StateEvent::LayoutChanged {
@@ -86,6 +144,14 @@ impl EventEmitter {
             layout: layout.to_string(),
         });
     }
+
+    /// Emit a layout order changed event
+    pub fn emit_layout_order_changed(&self, display_id: u32, window_order: &[u32]) {
+        self.emit(StateEvent::LayoutOrderChanged {
+            display_id,
+            window_order: window_order.to_vec(),
+        });
+    }
 }
 
 /// Create a snapshot event from current state
@@ -127,6 +193,8 @@ pub fn window_to_info(window: &Window, focused: Option<u32>) -> WindowInfo {
         is_focused: focused == Some(window.id),
         is_floating: window.is_floating,
         is_fullscreen: window.is_fullscreen,
+        is_native_fullscreen: window.is_native_fullscreen,
+        is_manual: window.is_manual,
         output_id: window.display_id,
         // Debug fields not included in event streaming
         status: None,
@@ -137,6 +205,7 @@ pub fn window_to_info(window: &Window, focused: Option<u32>) -> WindowInfo {
         fullscreen_button: None,
         minimize_button: None,
         zoom_button: None,
+        applied_rules: None,
     }
 }
 
@@ -152,6 +221,11 @@ pub fn display_to_info(display: &Display, focused_display: u32) -> OutputInfo {
         is_main: display.is_main,
         visible_tags: display.visible_tags.mask(),
         is_focused: focused_display == display.id,
+        scale_factor: display.scale_factor,
+        rotation: display.rotation,
+        refresh_rate: display.refresh_rate,
+        uuid: display.uuid.clone(),
+        window_order: display.window_order.clone(),
     }
 }
 
@@ -185,7 +259,17 @@ mod tests {
             saved_frame: None,
             is_floating: false,
             is_fullscreen: false,
+            is_native_fullscreen: false,
+            is_app_hidden: false,
+            is_manual: false,
+            is_urgent: false,
+            opacity: None,
+            floated_by_tag: false,
+            tag_float_frame: None,
             orphaned_from: None,
+            parent_id: None,
+            parent_execs: Vec::new(),
+            applied_rules: Vec::new(),
         }
     }
 
@@ -301,4 +385,60 @@ mod tests {
         let info = display_to_info(&display, 2);
         assert!(!info.is_focused);
     }
+
+    #[test]
+    fn test_emit_tags_state_if_changed() {
+        let (tx, rx) = std_mpsc::channel();
+        let emitter = EventEmitter::new(tx);
+        let mut state = State::new();
+        state
+            .windows
+            .insert(100, create_test_window(100, 1000, "Safari"));
+
+        emitter.emit_tags_state_if_changed(&state);
+        emitter.emit_tags_state_if_changed(&state);
+        let events: Vec<StateEvent> = rx.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            StateEvent::TagsState { tags } => {
+                assert_eq!(
+                    *tags,
+                    vec![TagOccupancy {
+                        tag: 1,
+                        windows: 1,
+                        urgent: 0,
+                    }]
+                );
+            }
+            _ => panic!("Wrong event"),
+        }
+
+        state.set_urgent(100, true);
+        emitter.emit_tags_state_if_changed(&state);
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_emit_new_errors() {
+        let (tx, rx) = std_mpsc::channel();
+        let emitter = EventEmitter::new(tx);
+        let mut state = State::new();
+        state.errors.record("layout", "engine exited");
+
+        emitter.emit_new_errors(&state);
+        emitter.emit_new_errors(&state);
+        let events: Vec<StateEvent> = rx.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            StateEvent::Error { source, message } => {
+                assert_eq!(source, "layout");
+                assert_eq!(message, "engine exited");
+            }
+            _ => panic!("Wrong event"),
+        }
+
+        state.errors.record("apply_layout", "window 100 not found");
+        emitter.emit_new_errors(&state);
+        assert_eq!(rx.try_iter().count(), 1);
+    }
 }
@@ -1,22 +1,39 @@
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 
-use yashiki_ipc::{Command, EventFilter, Response, StateEvent, SubscribeRequest};
+use crate::pid;
+use yashiki_ipc::{Command, Response, StateEvent, SubscribeRequest};
 
 const SOCKET_PATH: &str = "/tmp/yashiki.sock";
 const EVENT_SOCKET_PATH: &str = "/tmp/yashiki-events.sock";
 
+/// How long to wait for the IPC socket of a daemon that is still starting up
+const STARTUP_WAIT: Duration = Duration::from_secs(2);
+const STARTUP_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
 pub struct IpcClient {
     stream: UnixStream,
 }
 
 impl IpcClient {
+    /// Connect to the daemon. While the daemon is running but hasn't bound its socket yet
+    /// (early startup), retry briefly instead of failing.
     pub fn connect() -> Result<Self> {
-        let stream =
-            UnixStream::connect(SOCKET_PATH).context("Failed to connect to yashiki daemon")?;
-        Ok(Self { stream })
+        let start = Instant::now();
+        loop {
+            match UnixStream::connect(SOCKET_PATH) {
+                Ok(stream) => return Ok(Self { stream }),
+                Err(e) => {
+                    if start.elapsed() >= STARTUP_WAIT || pid::check_already_running().is_none() {
+                        return Err(e).context("Failed to connect to yashiki daemon");
+                    }
+                    std::thread::sleep(STARTUP_RETRY_INTERVAL);
+                }
+            }
+        }
     }
 
     pub fn send(&mut self, cmd: &Command) -> Result<Response> {
@@ -65,12 +82,7 @@ impl EventClient {
 }
 
 /// Subscribe and print events to stdout
-pub fn subscribe_and_print(snapshot: bool, filter: Option<EventFilter>) -> Result<()> {
-    let request = SubscribeRequest {
-        snapshot,
-        filter: filter.unwrap_or_default(),
-    };
-
+pub fn subscribe_and_print(request: SubscribeRequest) -> Result<()> {
     let mut client = EventClient::connect(&request)?;
 
     loop {
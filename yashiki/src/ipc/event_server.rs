@@ -1,14 +1,20 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Result;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::broadcast;
+use tokio::time::Instant;
 
-use yashiki_ipc::{StateEvent, SubscribeRequest};
+use yashiki_ipc::{EventFilter, StateEvent, SubscribeRequest};
 
 const EVENT_SOCKET_PATH: &str = "/tmp/yashiki-events.sock";
 
+/// How long coalescing subscribers collect events after the first one of a burst
+const COALESCE_WINDOW: Duration = Duration::from_millis(16);
+
 pub struct EventServer {
     socket_path: PathBuf,
     event_rx: broadcast::Receiver<StateEvent>,
@@ -94,26 +100,58 @@ impl EventServer {
         }
 
         // Stream events
+        while let Some(event) = Self::next_event(&mut event_rx, &filter, None).await {
+            if !request.coalesce {
+                Self::write_events(&mut writer, &[event]).await?;
+                continue;
+            }
+
+            let mut batch = vec![event];
+            let deadline = Instant::now() + COALESCE_WINDOW;
+            while let Some(event) = Self::next_event(&mut event_rx, &filter, Some(deadline)).await {
+                StateEvent::push_coalesced(&mut batch, event);
+            }
+            Self::write_events(&mut writer, &batch).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Next event passing the filter. None once the channel is closed or the deadline passed.
+    async fn next_event(
+        event_rx: &mut broadcast::Receiver<StateEvent>,
+        filter: &EventFilter,
+        deadline: Option<Instant>,
+    ) -> Option<StateEvent> {
         loop {
-            match event_rx.recv().await {
+            let result = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, event_rx.recv())
+                    .await
+                    .ok()?,
+                None => event_rx.recv().await,
+            };
+            match result {
                 Ok(event) => {
                     if filter.matches(&event) {
-                        let json = serde_json::to_string(&event)?;
-                        writer.write_all(json.as_bytes()).await?;
-                        writer.write_all(b"\n").await?;
-                        writer.flush().await?;
+                        return Some(event);
                     }
                 }
                 Err(broadcast::error::RecvError::Lagged(n)) => {
                     tracing::warn!("Event subscriber lagged by {} messages", n);
                     // Continue receiving
                 }
-                Err(broadcast::error::RecvError::Closed) => {
-                    break;
-                }
+                Err(broadcast::error::RecvError::Closed) => return None,
             }
         }
+    }
 
+    async fn write_events(writer: &mut OwnedWriteHalf, events: &[StateEvent]) -> Result<()> {
+        for event in events {
+            let json = serde_json::to_string(event)?;
+            writer.write_all(json.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        writer.flush().await?;
         Ok(())
     }
 }
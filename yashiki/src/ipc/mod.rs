@@ -2,6 +2,6 @@ mod client;
 mod event_server;
 mod server;
 
-pub use client::{subscribe_and_print, IpcClient};
+pub use client::{subscribe_and_print, EventClient, IpcClient};
 pub use event_server::{EventBroadcaster, EventServer};
 pub use server::IpcServer;
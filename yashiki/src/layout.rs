@@ -5,7 +5,11 @@ use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
 
 use anyhow::{Context, Result};
 
-use yashiki_ipc::layout::{LayoutMessage, LayoutResult, WindowGeometry};
+use yashiki_ipc::layout::{LayoutAction, LayoutMessage, LayoutResult, WindowGeometry};
+
+mod validate;
+
+pub use validate::{validate_engine, Outcome};
 
 fn find_layout_engine(name: &str) -> Option<PathBuf> {
     let command_name = format!("yashiki-layout-{}", name);
@@ -46,6 +50,10 @@ pub struct LayoutEngine {
     _child: Child,
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
+    /// Window last reported with `focus-changed`
+    focused: Option<u32>,
+    /// Commands reported by `capabilities`, queried on first use (empty if unsupported)
+    capabilities: Option<Vec<String>>,
 }
 
 impl LayoutEngine {
@@ -80,30 +88,27 @@ impl LayoutEngine {
             _child: child,
             stdin,
             stdout: BufReader::new(stdout),
+            focused: None,
+            capabilities: None,
         })
     }
 
-    pub fn request_layout(
-        &mut self,
-        width: u32,
-        height: u32,
-        window_ids: &[u32],
-    ) -> Result<Vec<WindowGeometry>> {
-        let msg = LayoutMessage::Layout {
+    fn write_layout_request(&mut self, width: u32, height: u32, window_ids: &[u32]) -> Result<()> {
+        self.write(&LayoutMessage::Layout {
             width,
             height,
             windows: window_ids.to_vec(),
-        };
-
-        let result = self.send(&msg)?;
+        })
+    }
 
-        match result {
+    fn read_layout_result(&mut self) -> Result<Vec<WindowGeometry>> {
+        match self.read()? {
             LayoutResult::Layout { windows } => Ok(windows),
             LayoutResult::Error { message } => {
                 anyhow::bail!("Layout engine error: {}", message)
             }
-            LayoutResult::Ok | LayoutResult::NeedsRetile => {
-                anyhow::bail!("Unexpected 'ok' or 'needs_retile' response for layout request")
+            LayoutResult::Ok | LayoutResult::NeedsRetile | LayoutResult::Capabilities { .. } => {
+                anyhow::bail!("Unexpected non-layout response for layout request")
             }
         }
     }
@@ -124,17 +129,41 @@ impl LayoutEngine {
             LayoutResult::Error { message } => {
                 anyhow::bail!("Layout engine error: {}", message)
             }
-            LayoutResult::Layout { .. } => {
-                anyhow::bail!("Unexpected 'layout' response for command")
+            LayoutResult::Layout { .. } | LayoutResult::Capabilities { .. } => {
+                anyhow::bail!("Unexpected response for command '{}'", cmd)
             }
         }
     }
 
+    /// Commands the engine supports. Engines that don't answer `capabilities` report none.
+    fn capabilities(&mut self) -> &[String] {
+        if self.capabilities.is_none() {
+            let msg = LayoutMessage::Command {
+                cmd: "capabilities".to_string(),
+                args: vec![],
+            };
+            let commands = match self.send(&msg) {
+                Ok(LayoutResult::Capabilities { commands }) => commands,
+                _ => Vec::new(),
+            };
+            self.capabilities = Some(commands);
+        }
+        self.capabilities.as_deref().unwrap_or_default()
+    }
+
     fn send(&mut self, msg: &LayoutMessage) -> Result<LayoutResult> {
+        self.write(msg)?;
+        self.read()
+    }
+
+    fn write(&mut self, msg: &LayoutMessage) -> Result<()> {
         serde_json::to_writer(&mut self.stdin, msg)?;
         writeln!(self.stdin)?;
         self.stdin.flush()?;
+        Ok(())
+    }
 
+    fn read(&mut self) -> Result<LayoutResult> {
         let mut line = String::new();
         self.stdout.read_line(&mut line)?;
 
@@ -170,21 +199,93 @@ impl LayoutEngineManager {
         Ok(self.engines.get_mut(name).unwrap())
     }
 
-    pub fn request_layout(
+    /// Request several layouts at once (one per display).
+    /// All requests are written before any response is read, so different engines compute
+    /// concurrently and each engine handles its queue without a round trip per request.
+    /// Results are returned in request order.
+    pub fn request_layouts(
         &mut self,
-        name: &str,
-        width: u32,
-        height: u32,
-        window_ids: &[u32],
-    ) -> Result<Vec<WindowGeometry>> {
-        let engine = self.get_or_spawn(name)?;
-        engine.request_layout(width, height, window_ids)
+        requests: &[LayoutRequest],
+    ) -> Vec<Result<Vec<WindowGeometry>>> {
+        let written: Vec<Result<()>> = requests
+            .iter()
+            .map(|req| {
+                self.get_or_spawn(&req.layout)?.write_layout_request(
+                    req.width,
+                    req.height,
+                    &req.window_ids,
+                )
+            })
+            .collect();
+
+        // Each engine answers its requests in order, so reading in request order matches them up
+        requests
+            .iter()
+            .zip(written)
+            .map(|(req, written)| {
+                written?;
+                self.get_or_spawn(&req.layout)?.read_layout_result()
+            })
+            .collect()
     }
 
     pub fn send_command(&mut self, name: &str, cmd: &str, args: &[String]) -> Result<bool> {
         let engine = self.get_or_spawn(name)?;
         engine.send_command(cmd, args)
     }
+
+    /// Run a standard layout action with the engine command it supports.
+    /// Without capability info, candidates are tried in order until one is accepted.
+    /// Returns Ok(true) if the engine requests a retile.
+    pub fn send_action(
+        &mut self,
+        name: &str,
+        action: LayoutAction,
+        args: &[String],
+    ) -> Result<bool> {
+        let engine = self.get_or_spawn(name)?;
+        let supported = engine.capabilities();
+        let candidates: Vec<&str> = action
+            .engine_commands()
+            .iter()
+            .copied()
+            .filter(|cmd| supported.is_empty() || supported.iter().any(|s| s == cmd))
+            .collect();
+        if candidates.is_empty() {
+            anyhow::bail!("Layout '{}' does not support {}", name, action.name());
+        }
+
+        let mut last_err = None;
+        for cmd in candidates {
+            match engine.send_command(cmd, args) {
+                Ok(needs_retile) => return Ok(needs_retile),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Send `focus-changed` unless the engine already knows this window is focused.
+    /// Spawns the engine if needed, so a newly selected engine learns the current focus.
+    /// Returns Ok(true) if the engine requests a retile.
+    pub fn notify_focus(&mut self, name: &str, window_id: u32) -> Result<bool> {
+        let engine = self.get_or_spawn(name)?;
+        if engine.focused == Some(window_id) {
+            return Ok(false);
+        }
+        let needs_retile = engine.send_command("focus-changed", &[window_id.to_string()])?;
+        engine.focused = Some(window_id);
+        Ok(needs_retile)
+    }
+}
+
+/// A layout request for one display
+#[derive(Debug, Clone)]
+pub struct LayoutRequest {
+    pub layout: String,
+    pub width: u32,
+    pub height: u32,
+    pub window_ids: Vec<u32>,
 }
 
 impl Default for LayoutEngineManager {
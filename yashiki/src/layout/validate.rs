@@ -0,0 +1,428 @@
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use yashiki_ipc::layout::{LayoutMessage, LayoutResult, WindowGeometry};
+
+/// How long the engine gets to answer one message
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// (width, height, window count) of the layout requests checked
+const LAYOUT_CASES: [(u32, u32, u32); 6] = [
+    (1920, 1080, 0),
+    (1920, 1080, 1),
+    (1920, 1080, 3),
+    (1920, 1080, 8),
+    (1080, 1920, 3),
+    (640, 400, 12),
+];
+
+/// Sent to check that unknown commands are rejected
+const UNKNOWN_COMMAND: &str = "yashiki-validate-unknown";
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Warn(String),
+    Fail(String),
+}
+
+#[derive(Debug)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub outcome: Outcome,
+}
+
+type Check = fn(&mut Harness) -> Outcome;
+
+/// Window IDs for a layout request. Not 1..n, so engines mixing up IDs and indexes fail.
+fn window_ids(count: u32) -> Vec<u32> {
+    (0..count).map(|i| 100 + i * 7).collect()
+}
+
+/// An engine process, with responses read on a separate thread so a hung engine times out
+struct Harness {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<std::io::Result<String>>,
+    /// Set once the engine timed out, went away or sent something unparsable;
+    /// later checks are skipped
+    broken: bool,
+}
+
+impl Harness {
+    fn spawn(program: &str) -> Result<Self> {
+        let mut child = Command::new(program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to spawn layout engine: {}", program))?;
+        let stdin = child.stdin.take().context("Failed to get stdin")?;
+        let stdout = child.stdout.take().context("Failed to get stdout")?;
+
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin: Some(stdin),
+            lines,
+            broken: false,
+        })
+    }
+
+    fn write(&mut self, msg: &LayoutMessage) -> Result<(), String> {
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err("stdin already closed".to_string());
+        };
+        let written = serde_json::to_writer(&mut *stdin, msg)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(stdin))
+            .and_then(|_| stdin.flush());
+        written.map_err(|e| {
+            self.broken = true;
+            format!("failed to write to the engine: {}", e)
+        })
+    }
+
+    fn read(&mut self) -> Result<LayoutResult, String> {
+        match self.lines.recv_timeout(RESPONSE_TIMEOUT) {
+            Ok(Ok(line)) => serde_json::from_str(&line).map_err(|e| {
+                // Later responses can't be matched up with their requests
+                self.broken = true;
+                format!(
+                    "not a valid response, expected one JSON object per line ({}): {}",
+                    e,
+                    line.trim()
+                )
+            }),
+            Ok(Err(e)) => {
+                self.broken = true;
+                Err(format!("failed to read the engine's stdout: {}", e))
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.broken = true;
+                Err(format!(
+                    "no response within {}s (is stdout flushed after each line?)",
+                    RESPONSE_TIMEOUT.as_secs()
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                self.broken = true;
+                Err("the engine closed stdout (did it exit?)".to_string())
+            }
+        }
+    }
+
+    fn send(&mut self, msg: &LayoutMessage) -> Result<LayoutResult, String> {
+        self.write(msg)?;
+        self.read()
+    }
+
+    fn command(&mut self, cmd: &str, args: &[&str]) -> Result<LayoutResult, String> {
+        self.send(&LayoutMessage::Command {
+            cmd: cmd.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        })
+    }
+
+    fn layout(&mut self, width: u32, height: u32, count: u32) -> Outcome {
+        let ids = window_ids(count);
+        let msg = LayoutMessage::Layout {
+            width,
+            height,
+            windows: ids.clone(),
+        };
+        match self.send(&msg) {
+            Ok(result) => check_layout_result(width, height, &ids, result),
+            Err(e) => Outcome::Fail(e),
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Run the conformance checks against a layout engine binary.
+/// Fails only if the engine can't be started; protocol problems are reported per check.
+pub fn validate_engine(program: &str) -> Result<Vec<CheckResult>> {
+    let mut harness = Harness::spawn(program)?;
+    let checks: [(&'static str, Check); 8] = [
+        ("layout", check_layouts),
+        ("pipelined-layouts", check_pipelined_layouts),
+        ("focus-changed", check_focus_changed),
+        ("promote", check_promote),
+        ("capabilities", check_capabilities),
+        ("unknown-command", check_unknown_command),
+        ("layout-after-commands", check_layout_after_commands),
+        ("eof", check_eof),
+    ];
+
+    let mut results = Vec::new();
+    for (name, check) in checks {
+        let outcome = if harness.broken {
+            Outcome::Fail("skipped after an earlier read error".to_string())
+        } else {
+            check(&mut harness)
+        };
+        results.push(CheckResult { name, outcome });
+    }
+    Ok(results)
+}
+
+fn check_layouts(harness: &mut Harness) -> Outcome {
+    let mut warnings = Vec::new();
+    for (width, height, count) in LAYOUT_CASES {
+        match harness.layout(width, height, count) {
+            Outcome::Pass => {}
+            Outcome::Warn(w) => {
+                warnings.push(format!("{}x{}, {} windows: {}", width, height, count, w))
+            }
+            Outcome::Fail(e) => {
+                return Outcome::Fail(format!("{}x{}, {} windows: {}", width, height, count, e))
+            }
+        }
+    }
+    combine_warnings(warnings)
+}
+
+/// yashiki writes the requests for all displays before reading any response
+fn check_pipelined_layouts(harness: &mut Harness) -> Outcome {
+    let counts = [1, 2, 3];
+    for count in counts {
+        let msg = LayoutMessage::Layout {
+            width: 1920,
+            height: 1080,
+            windows: window_ids(count),
+        };
+        if let Err(e) = harness.write(&msg) {
+            return Outcome::Fail(e);
+        }
+    }
+    let mut warnings = Vec::new();
+    for count in counts {
+        let result = match harness.read() {
+            Ok(result) => result,
+            Err(e) => return Outcome::Fail(e),
+        };
+        match check_layout_result(1920, 1080, &window_ids(count), result) {
+            Outcome::Pass => {}
+            Outcome::Warn(w) => warnings.push(w),
+            Outcome::Fail(e) => {
+                return Outcome::Fail(format!("response {} of {}: {}", count, counts.len(), e))
+            }
+        }
+    }
+    combine_warnings(warnings)
+}
+
+fn check_focus_changed(harness: &mut Harness) -> Outcome {
+    let id = window_ids(2)[1].to_string();
+    match harness.command("focus-changed", &[&id]) {
+        Ok(LayoutResult::Ok | LayoutResult::NeedsRetile) => Outcome::Pass,
+        Ok(other) => Outcome::Fail(format!("expected ok or needs_retile, got {:?}", other)),
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+fn check_promote(harness: &mut Harness) -> Outcome {
+    let id = window_ids(2)[1].to_string();
+    match harness.command("promote", &[&id]) {
+        Ok(LayoutResult::Ok | LayoutResult::NeedsRetile) => Outcome::Pass,
+        Ok(LayoutResult::Error { message }) => Outcome::Warn(format!(
+            "returned an error ({}); engines without a main slot should return ok",
+            message
+        )),
+        Ok(other) => Outcome::Fail(format!("expected ok or needs_retile, got {:?}", other)),
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+/// Optional, but an answer other than capabilities or an error confuses layout-action
+fn check_capabilities(harness: &mut Harness) -> Outcome {
+    match harness.command("capabilities", &[]) {
+        Ok(LayoutResult::Capabilities { .. } | LayoutResult::Error { .. }) => Outcome::Pass,
+        Ok(other) => Outcome::Fail(format!("expected capabilities or error, got {:?}", other)),
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+/// layout-action tries candidate commands until one is accepted, so unknown ones must fail
+fn check_unknown_command(harness: &mut Harness) -> Outcome {
+    match harness.command(UNKNOWN_COMMAND, &[]) {
+        Ok(LayoutResult::Error { .. }) => Outcome::Pass,
+        Ok(other) => Outcome::Fail(format!(
+            "unknown commands must return an error, got {:?}",
+            other
+        )),
+        Err(e) => Outcome::Fail(e),
+    }
+}
+
+fn check_layout_after_commands(harness: &mut Harness) -> Outcome {
+    harness.layout(1920, 1080, 4)
+}
+
+/// The engine should exit quietly when yashiki closes its stdin
+fn check_eof(harness: &mut Harness) -> Outcome {
+    drop(harness.stdin.take());
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    match harness.lines.recv_timeout(remaining) {
+        Ok(Ok(line)) => return Outcome::Fail(format!("unsolicited output: {}", line.trim())),
+        Err(RecvTimeoutError::Timeout) => {
+            return Outcome::Warn("keeps running after stdin is closed".to_string());
+        }
+        Ok(Err(_)) | Err(RecvTimeoutError::Disconnected) => {}
+    }
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = harness.child.try_wait() {
+            return Outcome::Pass;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    Outcome::Warn("closed stdout but keeps running after stdin is closed".to_string())
+}
+
+fn combine_warnings(warnings: Vec<String>) -> Outcome {
+    if warnings.is_empty() {
+        Outcome::Pass
+    } else {
+        Outcome::Warn(warnings.join("; "))
+    }
+}
+
+fn check_layout_result(width: u32, height: u32, ids: &[u32], result: LayoutResult) -> Outcome {
+    match result {
+        LayoutResult::Layout { windows } => check_geometries(width, height, ids, &windows),
+        other => Outcome::Fail(format!("expected layout, got {:?}", other)),
+    }
+}
+
+/// Check a layout response against the request: every window exactly once, a non-empty
+/// size and inside the usable area unless hidden. Overlapping windows are only a warning,
+/// since stacking layouts overlap on purpose; they should set raise_order.
+pub fn check_geometries(
+    width: u32,
+    height: u32,
+    ids: &[u32],
+    geometries: &[WindowGeometry],
+) -> Outcome {
+    let mut seen = HashSet::new();
+    for g in geometries {
+        if !ids.contains(&g.id) {
+            return Outcome::Fail(format!("window {} was not in the request", g.id));
+        }
+        if !seen.insert(g.id) {
+            return Outcome::Fail(format!("window {} laid out twice", g.id));
+        }
+        if g.width == 0 || g.height == 0 {
+            return Outcome::Fail(format!("window {} has an empty size", g.id));
+        }
+        let inside = g.x >= 0
+            && g.y >= 0
+            && g.x as i64 + g.width as i64 <= width as i64
+            && g.y as i64 + g.height as i64 <= height as i64;
+        if !g.hidden && !inside {
+            return Outcome::Fail(format!(
+                "window {} ({}x{} at {},{}) is outside the {}x{} area",
+                g.id, g.width, g.height, g.x, g.y, width, height
+            ));
+        }
+    }
+    if let Some(missing) = ids.iter().find(|id| !seen.contains(id)) {
+        return Outcome::Fail(format!("window {} missing from the layout", missing));
+    }
+
+    let visible: Vec<&WindowGeometry> = geometries.iter().filter(|g| !g.hidden).collect();
+    for (i, a) in visible.iter().enumerate() {
+        for b in &visible[i + 1..] {
+            let unordered = a.raise_order.is_none() || b.raise_order.is_none();
+            if unordered && overlaps(a, b) {
+                return Outcome::Warn(format!(
+                    "windows {} and {} overlap without raise_order",
+                    a.id, b.id
+                ));
+            }
+        }
+    }
+    Outcome::Pass
+}
+
+fn overlaps(a: &WindowGeometry, b: &WindowGeometry) -> bool {
+    let (ax2, ay2) = (a.x + a.width as i32, a.y + a.height as i32);
+    let (bx2, by2) = (b.x + b.width as i32, b.y + b.height as i32);
+    a.x < bx2 && b.x < ax2 && a.y < by2 && b.y < ay2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(id: u32, x: i32, y: i32, width: u32, height: u32) -> WindowGeometry {
+        WindowGeometry {
+            id,
+            x,
+            y,
+            width,
+            height,
+            raise_order: None,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn test_check_geometries() {
+        let ids = [1, 2];
+        let split = [geometry(1, 0, 0, 960, 1080), geometry(2, 960, 0, 960, 1080)];
+        assert_eq!(check_geometries(1920, 1080, &ids, &split), Outcome::Pass);
+
+        let missing = [geometry(1, 0, 0, 1920, 1080)];
+        assert!(matches!(
+            check_geometries(1920, 1080, &ids, &missing),
+            Outcome::Fail(_)
+        ));
+
+        let outside = [
+            geometry(1, 0, 0, 960, 1080),
+            geometry(2, 1000, 0, 960, 1080),
+        ];
+        assert!(matches!(
+            check_geometries(1920, 1080, &ids, &outside),
+            Outcome::Fail(_)
+        ));
+
+        // Overlap is only a warning, and fine with raise_order or hidden windows
+        let mut stacked = [
+            geometry(1, 0, 0, 1920, 1080),
+            geometry(2, 30, 0, 1890, 1080),
+        ];
+        assert!(matches!(
+            check_geometries(1920, 1080, &ids, &stacked),
+            Outcome::Warn(_)
+        ));
+        stacked[0].raise_order = Some(0);
+        stacked[1].raise_order = Some(1);
+        assert_eq!(check_geometries(1920, 1080, &ids, &stacked), Outcome::Pass);
+
+        let mut monocle = [geometry(1, 0, 0, 1920, 1080), geometry(2, 0, 0, 1920, 1080)];
+        monocle[1].hidden = true;
+        assert_eq!(check_geometries(1920, 1080, &ids, &monocle), Outcome::Pass);
+    }
+}
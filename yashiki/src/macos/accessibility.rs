@@ -14,6 +14,7 @@ use core_graphics::geometry::{CGPoint, CGSize};
 pub type AXError = i32;
 pub const AX_ERROR_SUCCESS: AXError = 0;
 pub const AX_ERROR_FAILURE: AXError = -25200;
+pub const AX_ERROR_CANNOT_COMPLETE: AXError = -25204;
 
 #[repr(C)]
 pub struct __AXUIElement(c_void);
@@ -56,6 +57,7 @@ extern "C" {
     ) -> AXError;
     fn AXUIElementGetPid(element: AXUIElementRef, pid: *mut i32) -> AXError;
     fn AXUIElementPerformAction(element: AXUIElementRef, action: CFStringRef) -> AXError;
+    fn AXUIElementSetMessagingTimeout(element: AXUIElementRef, timeout: f32) -> AXError;
     fn AXValueCreate(value_type: u32, value: *const c_void) -> *mut c_void;
     fn AXValueGetValue(value: *const c_void, value_type: u32, value_ptr: *mut c_void) -> bool;
 
@@ -91,12 +93,16 @@ mod action {
 
 mod attr {
     pub const WINDOWS: &str = "AXWindows";
+    pub const MAIN_WINDOW: &str = "AXMainWindow";
+    pub const PARENT: &str = "AXParent";
+    pub const ROLE: &str = "AXRole";
     pub const FOCUSED_WINDOW: &str = "AXFocusedWindow";
     pub const FOCUSED_APPLICATION: &str = "AXFocusedApplication";
     pub const TITLE: &str = "AXTitle";
     pub const POSITION: &str = "AXPosition";
     pub const SIZE: &str = "AXSize";
     pub const MINIMIZED: &str = "AXMinimized";
+    pub const FULL_SCREEN: &str = "AXFullScreen";
     pub const MAIN: &str = "AXMain";
     pub const CLOSE_BUTTON: &str = "AXCloseButton";
     pub const SUBROLE: &str = "AXSubrole";
@@ -111,6 +117,7 @@ pub mod notification {
     pub const WINDOW_CREATED: &str = "AXWindowCreated";
     pub const WINDOW_MOVED: &str = "AXWindowMoved";
     pub const WINDOW_RESIZED: &str = "AXWindowResized";
+    pub const TITLE_CHANGED: &str = "AXTitleChanged";
     pub const WINDOW_MINIATURIZED: &str = "AXWindowMiniaturized";
     pub const WINDOW_DEMINIATURIZED: &str = "AXWindowDeminiaturized";
     pub const FOCUSED_WINDOW_CHANGED: &str = "AXFocusedWindowChanged";
@@ -159,6 +166,17 @@ impl AXUIElement {
         }
     }
 
+    /// Set how long AX calls on this element wait for the app to reply.
+    /// On the system-wide element this sets the global default.
+    pub fn set_messaging_timeout(&self, seconds: f32) -> Result<(), AXError> {
+        let err = unsafe { AXUIElementSetMessagingTimeout(self.as_concrete_TypeRef(), seconds) };
+        if err == AX_ERROR_SUCCESS {
+            Ok(())
+        } else {
+            Err(err)
+        }
+    }
+
     pub fn window_id(&self) -> Option<u32> {
         let mut wid: u32 = 0;
         let err = unsafe { _AXUIElementGetWindow(self.as_concrete_TypeRef(), &mut wid) };
@@ -274,6 +292,13 @@ impl AXUIElement {
         Ok(cf.into())
     }
 
+    /// Check if this window is in native fullscreen (on its own Space)
+    pub fn is_full_screen(&self) -> Result<bool, AXError> {
+        let value = self.get_attribute(attr::FULL_SCREEN)?;
+        let cf = unsafe { CFBoolean::wrap_under_create_rule(value as *const _) };
+        Ok(cf.into())
+    }
+
     pub fn set_minimized(&self, minimized: bool) -> Result<(), AXError> {
         let value = if minimized {
             CFBoolean::true_value()
@@ -306,6 +331,22 @@ impl AXUIElement {
         Ok(result)
     }
 
+    pub fn main_window(&self) -> Result<AXUIElement, AXError> {
+        let value = self.get_attribute(attr::MAIN_WINDOW)?;
+        Ok(unsafe { AXUIElement::wrap_under_create_rule(value as AXUIElementRef) })
+    }
+
+    pub fn parent(&self) -> Result<AXUIElement, AXError> {
+        let value = self.get_attribute(attr::PARENT)?;
+        Ok(unsafe { AXUIElement::wrap_under_create_rule(value as AXUIElementRef) })
+    }
+
+    pub fn role(&self) -> Result<String, AXError> {
+        let value = self.get_attribute(attr::ROLE)?;
+        let cf = unsafe { CFString::wrap_under_create_rule(value as *const _) };
+        Ok(cf.to_string())
+    }
+
     pub fn focused_window(&self) -> Result<AXUIElement, AXError> {
         let value = self.get_attribute(attr::FOCUSED_WINDOW)?;
         Ok(unsafe { AXUIElement::wrap_under_create_rule(value as AXUIElementRef) })
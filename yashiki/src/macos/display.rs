@@ -5,14 +5,18 @@ use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::sync::OnceLock;
 
-use core_foundation::base::TCFType;
+use core_foundation::base::{CFRelease, TCFType};
 use core_foundation::runloop::{
     CFRunLoopGetMain, CFRunLoopSourceRef, CFRunLoopSourceSignal, CFRunLoopWakeUp,
 };
 use core_foundation::{
     array::CFArray, dictionary::CFDictionary, number::CFNumber, string::CFString,
 };
-use core_graphics::display::{CGDirectDisplayID, CGDisplayBounds, CGMainDisplayID};
+use core_foundation_sys::preferences::{
+    CFPreferencesAppSynchronize, CFPreferencesGetAppIntegerValue,
+};
+use core_foundation_sys::uuid::{CFUUIDCreateString, CFUUIDRef};
+use core_graphics::display::{CGDirectDisplayID, CGDisplay, CGDisplayBounds, CGMainDisplayID};
 use core_graphics::window::{
     kCGNullWindowID, kCGWindowListExcludeDesktopElements, kCGWindowListOptionOnScreenOnly,
     CGWindowListCopyWindowInfo,
@@ -30,6 +34,11 @@ extern "C" {
     ) -> i32;
 }
 
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn CGDisplayCreateUUIDFromDisplayID(display: CGDirectDisplayID) -> CFUUIDRef;
+}
+
 #[derive(Debug, Clone)]
 pub struct DisplayReconfigEvent {
     pub display_id: DisplayId,
@@ -90,6 +99,35 @@ pub struct DisplayInfo {
     pub name: String,
     pub frame: Bounds,
     pub is_main: bool,
+    pub scale_factor: f64,
+    /// Rotation in degrees (0, 90, 180 or 270)
+    pub rotation: u32,
+    /// Refresh rate in Hz (0 when the display doesn't report one)
+    pub refresh_rate: f64,
+    /// Hardware UUID, stable across reconnects and distinct for identical models
+    pub uuid: Option<String>,
+    /// Hot corners and corners next to the Dock, avoided when hiding windows
+    pub obstructed_corners: ScreenCorners,
+}
+
+/// A flag per screen corner
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScreenCorners {
+    pub top_left: bool,
+    pub top_right: bool,
+    pub bottom_left: bool,
+    pub bottom_right: bool,
+}
+
+impl ScreenCorners {
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            top_left: self.top_left || other.top_left,
+            top_right: self.top_right || other.top_right,
+            bottom_left: self.bottom_left || other.bottom_left,
+            bottom_right: self.bottom_right || other.bottom_right,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -198,6 +236,8 @@ pub fn get_all_displays() -> Vec<DisplayInfo> {
 
     let main_display_id = unsafe { CGMainDisplayID() };
     let menu_bar_heights = detect_menu_bar_heights();
+    let hot_corners = get_hot_corners();
+    let dock_corners = detect_dock_corners();
 
     // Get display names from NSScreen (names don't change with resolution)
     let display_names = get_display_names();
@@ -227,6 +267,12 @@ pub fn get_all_displays() -> Vec<DisplayInfo> {
                     height: visible_height,
                 },
                 is_main: display_id == main_display_id,
+                scale_factor: get_display_scale_factor(display_id),
+                rotation: CGDisplay::new(display_id).rotation() as u32,
+                refresh_rate: get_display_refresh_rate(display_id),
+                uuid: get_display_uuid(display_id),
+                obstructed_corners: hot_corners
+                    .union(dock_corners.get(&display_id).copied().unwrap_or_default()),
             }
         })
         .collect()
@@ -247,6 +293,60 @@ fn get_display_names() -> HashMap<DisplayId, String> {
         .collect()
 }
 
+/// Hot corners set in System Settings (an action of 0 or 1 means none)
+fn get_hot_corners() -> ScreenCorners {
+    let app_id = CFString::new("com.apple.dock");
+    // Re-read the domain so changes made in System Settings are picked up
+    unsafe { CFPreferencesAppSynchronize(app_id.as_concrete_TypeRef()) };
+    let is_set = |corner: &str| {
+        let key = CFString::new(&format!("wvous-{}-corner", corner));
+        let action = unsafe {
+            CFPreferencesGetAppIntegerValue(
+                key.as_concrete_TypeRef(),
+                app_id.as_concrete_TypeRef(),
+                std::ptr::null_mut(),
+            )
+        };
+        action > 1
+    };
+    ScreenCorners {
+        top_left: is_set("tl"),
+        top_right: is_set("tr"),
+        bottom_left: is_set("bl"),
+        bottom_right: is_set("br"),
+    }
+}
+
+/// Corners along the edge each screen's Dock is on, found where NSScreen's visibleFrame
+/// is inset from its frame (the top inset is the menu bar, not the Dock)
+fn detect_dock_corners() -> HashMap<DisplayId, ScreenCorners> {
+    let mtm = unsafe { MainThreadMarker::new_unchecked() };
+    let screens = NSScreen::screens(mtm);
+
+    screens
+        .iter()
+        .filter_map(|screen| {
+            let display_id = get_display_id_for_screen(&screen)?;
+            let frame = screen.frame();
+            let visible = screen.visibleFrame();
+            // AppKit coordinates: origin at the bottom-left, y up
+            let left = visible.origin.x - frame.origin.x > 0.5;
+            let right =
+                (frame.origin.x + frame.size.width) - (visible.origin.x + visible.size.width) > 0.5;
+            let bottom = visible.origin.y - frame.origin.y > 0.5;
+            Some((
+                display_id,
+                ScreenCorners {
+                    top_left: left,
+                    top_right: right,
+                    bottom_left: left || bottom,
+                    bottom_right: right || bottom,
+                },
+            ))
+        })
+        .collect()
+}
+
 fn get_display_id_for_screen(screen: &NSScreen) -> Option<DisplayId> {
     use objc2_foundation::NSNumber;
 
@@ -290,6 +390,39 @@ fn get_display_bounds(display_id: DisplayId) -> Bounds {
     }
 }
 
+/// Backing scale factor from the current display mode (pixels per point, 2.0 for Retina)
+fn get_display_scale_factor(display_id: DisplayId) -> f64 {
+    CGDisplay::new(display_id)
+        .display_mode()
+        .filter(|mode| mode.width() > 0)
+        .map(|mode| mode.pixel_width() as f64 / mode.width() as f64)
+        .unwrap_or(1.0)
+}
+
+/// Refresh rate of the current display mode in Hz
+fn get_display_refresh_rate(display_id: DisplayId) -> f64 {
+    CGDisplay::new(display_id)
+        .display_mode()
+        .map(|mode| mode.refresh_rate())
+        .unwrap_or(0.0)
+}
+
+/// Hardware UUID of the display as a string, e.g. `37D8832A-2D66-02CA-B9F7-8F30A301B230`
+fn get_display_uuid(display_id: DisplayId) -> Option<String> {
+    unsafe {
+        let uuid = CGDisplayCreateUUIDFromDisplayID(display_id);
+        if uuid.is_null() {
+            return None;
+        }
+        let string = CFUUIDCreateString(std::ptr::null(), uuid);
+        CFRelease(uuid as *const c_void);
+        if string.is_null() {
+            return None;
+        }
+        Some(CFString::wrap_under_create_rule(string).to_string())
+    }
+}
+
 /// Detect menu bar heights for each display by looking at Window Server windows.
 /// Menu bars are at layer 24, owned by "Window Server", thin (height < 50) and screen-wide.
 /// Returns a map of display_id -> menu_bar_height.
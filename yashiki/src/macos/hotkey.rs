@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
 use std::ptr;
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::mpsc;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use core_foundation::base::TCFType;
 use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop, CFRunLoopSource};
@@ -14,7 +16,7 @@ use core_graphics::event::{
     CGEventType, CallbackResult, EventField,
 };
 
-use yashiki_ipc::Command;
+use yashiki_ipc::{Command, HotkeyBackend};
 
 extern "C" {
     fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
@@ -79,10 +81,87 @@ pub fn format_hotkey(hotkey: &Hotkey) -> String {
     parts.join("-")
 }
 
+/// A second press of the same hotkey within this time runs its double-press binding
+const DOUBLE_PRESS_TIMEOUT: Duration = Duration::from_millis(400);
+
+/// Single- and double-press bindings, with the last press to detect repeats
+#[derive(Debug, Clone, Default)]
+struct Bindings {
+    single: HashMap<Hotkey, Command>,
+    double: HashMap<Hotkey, Command>,
+    /// Bindings (hotkey, double) whose keystroke is still delivered to the focused app
+    passthrough: HashSet<(Hotkey, bool)>,
+    /// While suspended, the hotkeys that keep working
+    suspended: Option<HashSet<Hotkey>>,
+    last_press: Option<(Hotkey, Instant)>,
+}
+
+impl Bindings {
+    fn len(&self) -> usize {
+        self.single.len() + self.double.len()
+    }
+
+    fn is_bound(&self, hotkey: &Hotkey) -> bool {
+        self.single.contains_key(hotkey) || self.double.contains_key(hotkey)
+    }
+
+    /// Whether a press of the key is handled. While suspended, only excepted keys and
+    /// keys bound to resume bindings are, so there is always a way back.
+    fn is_active(&self, hotkey: &Hotkey) -> bool {
+        let Some(except) = &self.suspended else {
+            return self.is_bound(hotkey);
+        };
+        let resumes = |cmd: &Command| {
+            matches!(
+                cmd,
+                Command::BindingsResume | Command::BindingsToggle { .. }
+            )
+        };
+        let bound_to_resume = [&self.single, &self.double]
+            .iter()
+            .any(|map| map.get(hotkey).is_some_and(resumes));
+        self.is_bound(hotkey) && (except.contains(hotkey) || bound_to_resume)
+    }
+
+    /// Whether the keystroke goes on to the focused app: only when every binding
+    /// of the key is a passthrough one
+    fn passes_through(&self, hotkey: &Hotkey) -> bool {
+        let single =
+            !self.single.contains_key(hotkey) || self.passthrough.contains(&(*hotkey, false));
+        let double =
+            !self.double.contains_key(hotkey) || self.passthrough.contains(&(*hotkey, true));
+        single && double
+    }
+
+    /// Command to run for a press of the hotkey.
+    /// A repeat within the timeout runs the double-press binding and starts over,
+    /// so a third press runs the single-press binding again.
+    fn press(&mut self, hotkey: Hotkey, now: Instant) -> Option<Command> {
+        let repeat = self.last_press.is_some_and(|(last, at)| {
+            last == hotkey && now.duration_since(at) <= DOUBLE_PRESS_TIMEOUT
+        });
+        if repeat {
+            if let Some(command) = self.double.get(&hotkey) {
+                self.last_press = None;
+                return Some(command.clone());
+            }
+        }
+        self.last_press = Some((hotkey, now));
+        self.single.get(&hotkey).cloned()
+    }
+
+    /// A press of a key that isn't handled, which breaks a double-press sequence
+    fn press_other(&mut self) {
+        self.last_press = None;
+    }
+}
+
 pub struct HotkeyManager {
-    bindings: HashMap<Hotkey, Command>,
+    bindings: Bindings,
     command_tx: mpsc::Sender<Command>,
     tap: Option<HotkeyTap>,
+    backend: HotkeyBackend,
+    started: bool,
     dirty: bool,
     runloop_source: Arc<AtomicPtr<std::ffi::c_void>>,
 }
@@ -93,57 +172,150 @@ impl HotkeyManager {
         runloop_source: Arc<AtomicPtr<std::ffi::c_void>>,
     ) -> Self {
         Self {
-            bindings: HashMap::new(),
+            bindings: Bindings::default(),
             command_tx,
             tap: None,
+            backend: HotkeyBackend::default(),
+            started: false,
             dirty: false,
             runloop_source,
         }
     }
 
-    pub fn bind(&mut self, key_str: &str, command: Command) -> Result<(), String> {
+    /// Bind a hotkey; with `double`, the command runs on a repeated press instead.
+    /// With `passthrough`, the keystroke is not swallowed.
+    pub fn bind(
+        &mut self,
+        key_str: &str,
+        command: Command,
+        double: bool,
+        passthrough: bool,
+    ) -> Result<(), String> {
         let hotkey = parse_hotkey(key_str)?;
-        tracing::info!("Binding {} to {:?}", key_str, command);
-        self.bindings.insert(hotkey, command);
+        if passthrough {
+            self.bindings.passthrough.insert((hotkey, double));
+        } else {
+            self.bindings.passthrough.remove(&(hotkey, double));
+        }
+        if double {
+            tracing::info!("Binding double {} to {:?}", key_str, command);
+            self.bindings.double.insert(hotkey, command);
+        } else {
+            tracing::info!("Binding {} to {:?}", key_str, command);
+            self.bindings.single.insert(hotkey, command);
+        }
         self.dirty = true;
         Ok(())
     }
 
+    /// Remove both the single- and double-press bindings of a hotkey
     pub fn unbind(&mut self, key_str: &str) -> Result<(), String> {
         let hotkey = parse_hotkey(key_str)?;
-        self.bindings.remove(&hotkey);
+        self.bindings.single.remove(&hotkey);
+        self.bindings.double.remove(&hotkey);
+        self.bindings
+            .passthrough
+            .retain(|(bound, _)| *bound != hotkey);
         tracing::info!("Unbound {}", key_str);
         self.dirty = true;
         Ok(())
     }
 
-    pub fn list_bindings(&self) -> Vec<(String, Command)> {
-        self.bindings
-            .iter()
-            .map(|(hotkey, cmd)| (format_hotkey(hotkey), cmd.clone()))
+    /// Bindings as (key, command, double, passthrough)
+    pub fn list_bindings(&self) -> Vec<(String, Command, bool, bool)> {
+        let single = self.bindings.single.iter().map(|b| (b, false));
+        let double = self.bindings.double.iter().map(|b| (b, true));
+        single
+            .chain(double)
+            .map(|((hotkey, cmd), double)| {
+                let passthrough = self.bindings.passthrough.contains(&(*hotkey, double));
+                (format_hotkey(hotkey), cmd.clone(), double, passthrough)
+            })
             .collect()
     }
 
+    /// Press a bound key from outside (e.g. Karabiner), returning the command to run.
+    /// None when only a double-press binding exists and this press is not a repeat.
+    pub fn trigger(&mut self, key_str: &str) -> Result<Option<Command>, String> {
+        let hotkey = parse_hotkey(key_str)?;
+        if !self.bindings.is_bound(&hotkey) {
+            return Err(format!("No binding for {}", key_str));
+        }
+        if !self.bindings.is_active(&hotkey) {
+            tracing::debug!("Bindings suspended, ignoring trigger {}", key_str);
+            return Ok(None);
+        }
+        Ok(self.bindings.press(hotkey, Instant::now()))
+    }
+
+    /// Stop handling hotkeys other than `except` and resume bindings until `resume`
+    pub fn suspend(&mut self, except: &[String]) -> Result<(), String> {
+        let except = except
+            .iter()
+            .map(|key| parse_hotkey(key))
+            .collect::<Result<HashSet<_>, _>>()?;
+        tracing::info!("Bindings suspended ({} keys excepted)", except.len());
+        self.bindings.suspended = Some(except);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn resume(&mut self) {
+        if self.bindings.suspended.take().is_some() {
+            tracing::info!("Bindings resumed");
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.bindings.suspended.is_some()
+    }
+
+    pub fn backend(&self) -> HotkeyBackend {
+        self.backend
+    }
+
+    pub fn set_backend(&mut self, backend: HotkeyBackend) {
+        if self.backend != backend {
+            tracing::info!("Hotkey backend set to {:?}", backend);
+            self.backend = backend;
+            self.dirty = true;
+        }
+    }
+
     pub fn start(&mut self) -> Result<(), String> {
-        self.tap = Some(self.create_tap()?);
+        self.started = true;
         self.dirty = false;
+        if self.backend == HotkeyBackend::External {
+            tracing::info!("Hotkey tap not started (external backend)");
+            return Ok(());
+        }
+        self.tap = Some(self.create_tap()?);
         tracing::info!("Hotkey tap started with {} bindings", self.bindings.len());
         Ok(())
     }
 
-    /// Ensure tap is up-to-date with current bindings.
+    /// Ensure tap is up-to-date with current bindings and backend.
     /// Call this periodically (e.g., in timer callback) to apply pending changes.
     pub fn ensure_tap(&mut self) -> Result<(), String> {
-        if self.dirty && self.tap.is_some() {
-            self.tap = Some(self.create_tap()?);
+        if self.dirty && self.started {
             self.dirty = false;
-            tracing::info!("Hotkey tap updated with {} bindings", self.bindings.len());
+            match self.backend {
+                HotkeyBackend::Internal => {
+                    self.tap = Some(self.create_tap()?);
+                    tracing::info!("Hotkey tap updated with {} bindings", self.bindings.len());
+                }
+                HotkeyBackend::External => {
+                    self.tap = None;
+                    tracing::info!("Hotkey tap stopped (external backend)");
+                }
+            }
         }
         Ok(())
     }
 
     fn create_tap(&self) -> Result<HotkeyTap, String> {
-        let bindings = self.bindings.clone();
+        let bindings = RefCell::new(self.bindings.clone());
         let tx = self.command_tx.clone();
         let source = Arc::clone(&self.runloop_source);
 
@@ -191,22 +363,34 @@ impl HotkeyManager {
                     modifiers,
                 };
 
-                if let Some(command) = bindings.get(&hotkey).cloned() {
-                    tracing::debug!("Hotkey matched: {:?} -> {:?}", hotkey, command);
-                    if tx.send(command).is_err() {
-                        tracing::error!("Failed to send command from hotkey");
-                    }
-                    // Signal CFRunLoopSource for immediate processing
-                    let source_ptr = source.load(Ordering::Acquire);
-                    if !source_ptr.is_null() {
-                        unsafe {
-                            CFRunLoopSourceSignal(source_ptr as CFRunLoopSourceRef);
+                let mut bindings = bindings.borrow_mut();
+                if !bindings.is_active(&hotkey) {
+                    bindings.press_other();
+                    return CallbackResult::Keep;
+                }
+
+                // Holding the key down neither repeats the command nor counts as a double press
+                let autorepeat =
+                    event.get_integer_value_field(EventField::KEYBOARD_EVENT_AUTOREPEAT) != 0;
+                if !autorepeat {
+                    if let Some(command) = bindings.press(hotkey, Instant::now()) {
+                        tracing::debug!("Hotkey matched: {:?} -> {:?}", hotkey, command);
+                        if tx.send(command).is_err() {
+                            tracing::error!("Failed to send command from hotkey");
+                        }
+                        // Signal CFRunLoopSource for immediate processing
+                        let source_ptr = source.load(Ordering::Acquire);
+                        if !source_ptr.is_null() {
+                            unsafe {
+                                CFRunLoopSourceSignal(source_ptr as CFRunLoopSourceRef);
+                            }
                         }
                     }
-                    return CallbackResult::Drop;
                 }
-
-                CallbackResult::Keep
+                if bindings.passes_through(&hotkey) {
+                    return CallbackResult::Keep;
+                }
+                CallbackResult::Drop
             },
         )
         .map_err(|_| {
@@ -395,6 +579,73 @@ fn key_code_to_str(code: u16) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_bindings_double_press() {
+        let alt_1 = parse_hotkey("alt-1").unwrap();
+        let alt_2 = parse_hotkey("alt-2").unwrap();
+        let mut bindings = Bindings::default();
+        bindings.single.insert(
+            alt_1,
+            Command::TagView {
+                tags: 1,
+                output: None,
+            },
+        );
+        bindings.double.insert(alt_1, Command::TagViewLast);
+
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let is_double = |cmd: Option<Command>| matches!(cmd, Some(Command::TagViewLast));
+
+        assert!(!is_double(bindings.press(alt_1, at(0))));
+        assert!(is_double(bindings.press(alt_1, at(200))));
+        // The repeat was consumed, so the next press starts over
+        assert!(!is_double(bindings.press(alt_1, at(300))));
+        // Too slow
+        assert!(!is_double(bindings.press(alt_1, at(1000))));
+        // Another key in between breaks the repeat
+        assert!(bindings.press(alt_2, at(1100)).is_none());
+        assert!(!is_double(bindings.press(alt_1, at(1200))));
+        // So does an unbound key
+        bindings.press_other();
+        assert!(!is_double(bindings.press(alt_1, at(1300))));
+    }
+
+    #[test]
+    fn test_bindings_passthrough() {
+        let cmd_h = parse_hotkey("cmd-h").unwrap();
+        let mut bindings = Bindings::default();
+        bindings.single.insert(cmd_h, Command::TagViewLast);
+        assert!(!bindings.passes_through(&cmd_h));
+
+        bindings.passthrough.insert((cmd_h, false));
+        assert!(bindings.passes_through(&cmd_h));
+
+        // A consuming double-press binding on the same key swallows it again
+        bindings.double.insert(cmd_h, Command::TagViewLast);
+        assert!(!bindings.passes_through(&cmd_h));
+    }
+
+    #[test]
+    fn test_bindings_suspended() {
+        let alt_1 = parse_hotkey("alt-1").unwrap();
+        let alt_2 = parse_hotkey("alt-2").unwrap();
+        let alt_escape = parse_hotkey("alt-escape").unwrap();
+        let mut bindings = Bindings::default();
+        bindings.single.insert(alt_1, Command::TagViewLast);
+        bindings.single.insert(alt_2, Command::TagViewLast);
+        bindings
+            .double
+            .insert(alt_escape, Command::BindingsToggle { except: vec![] });
+        assert!(bindings.is_active(&alt_1));
+
+        bindings.suspended = Some(HashSet::from([alt_2]));
+        assert!(!bindings.is_active(&alt_1));
+        assert!(bindings.is_active(&alt_2));
+        // Keys that resume stay active without being excepted
+        assert!(bindings.is_active(&alt_escape));
+    }
+
     #[test]
     fn test_parse_simple_key() {
         let hotkey = parse_hotkey("a").unwrap();
@@ -0,0 +1,40 @@
+use std::ffi::c_void;
+
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::TCFType;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::{CFString, CFStringRef};
+
+type TISInputSourceRef = *const c_void;
+
+#[link(name = "Carbon", kind = "framework")]
+extern "C" {
+    static kTISPropertyInputSourceID: CFStringRef;
+    fn TISCreateInputSourceList(
+        properties: CFDictionaryRef,
+        include_all_installed: u8,
+    ) -> CFArrayRef;
+    fn TISSelectInputSource(source: TISInputSourceRef) -> i32;
+}
+
+/// Select an enabled keyboard input source by ID, e.g. `com.apple.keylayout.US`
+pub fn select_input_source(id: &str) -> Result<(), String> {
+    let key = unsafe { CFString::wrap_under_get_rule(kTISPropertyInputSourceID) };
+    let properties = CFDictionary::from_CFType_pairs(&[(key, CFString::new(id))]);
+
+    let list = unsafe { TISCreateInputSourceList(properties.as_concrete_TypeRef(), 0) };
+    if list.is_null() {
+        return Err(format!("Input source not found: {}", id));
+    }
+    let sources: CFArray<*const c_void> = unsafe { CFArray::wrap_under_create_rule(list) };
+    let Some(source) = sources.get(0) else {
+        return Err(format!("Input source not found: {}", id));
+    };
+
+    let status = unsafe { TISSelectInputSource(*source) };
+    if status != 0 {
+        return Err(format!("Failed to select input source {}: {}", id, status));
+    }
+    tracing::debug!("Selected input source {}", id);
+    Ok(())
+}
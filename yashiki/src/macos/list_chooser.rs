@@ -0,0 +1,176 @@
+use anyhow::{Context, Result};
+use objc2::MainThreadOnly;
+use objc2_app_kit::{
+    NSApplication, NSApplicationActivationPolicy, NSBackingStoreType, NSEventMask, NSEventType,
+    NSFloatingWindowLevel, NSFont, NSFontWeightRegular, NSPanel, NSTextField, NSWindowStyleMask,
+    NSWindowTitleVisibility,
+};
+use objc2_foundation::{
+    MainThreadMarker, NSDate, NSDefaultRunLoopMode, NSPoint, NSRect, NSSize, NSString,
+};
+
+use crate::switcher::fuzzy_filter;
+
+const PANEL_WIDTH: f64 = 640.0;
+const PANEL_HEIGHT: f64 = 340.0;
+const PADDING: f64 = 12.0;
+const FONT_SIZE: f64 = 13.0;
+/// Result rows shown below the query line
+const MAX_ROWS: usize = 15;
+/// Labels are cut to fit the panel width at FONT_SIZE
+const MAX_LABEL_CHARS: usize = 76;
+
+const KEY_RETURN: u16 = 36;
+const KEY_TAB: u16 = 48;
+const KEY_DELETE: u16 = 51;
+const KEY_ESCAPE: u16 = 53;
+const KEY_KEYPAD_ENTER: u16 = 76;
+const KEY_DOWN: u16 = 125;
+const KEY_UP: u16 = 126;
+
+/// Show a floating panel listing `labels` with a type-to-filter query line and return the
+/// chosen index, or None when cancelled (Escape, click outside or switching away).
+/// Runs its own event loop, so it is meant for CLI processes, not the daemon.
+pub fn choose_from_list(labels: &[String]) -> Result<Option<usize>> {
+    let mtm = MainThreadMarker::new().context("The list chooser must run on the main thread")?;
+    let app = NSApplication::sharedApplication(mtm);
+    // No Dock icon or menu bar for the chooser process
+    app.setActivationPolicy(NSApplicationActivationPolicy::Accessory);
+    app.finishLaunching();
+
+    let frame = NSRect::new(
+        NSPoint::new(0.0, 0.0),
+        NSSize::new(PANEL_WIDTH, PANEL_HEIGHT),
+    );
+    let panel = NSPanel::initWithContentRect_styleMask_backing_defer(
+        NSPanel::alloc(mtm),
+        frame,
+        NSWindowStyleMask::Titled | NSWindowStyleMask::FullSizeContentView,
+        NSBackingStoreType::Buffered,
+        false,
+    );
+    // Floating level keeps the panel above everything and out of yashiki's layouts
+    panel.setLevel(NSFloatingWindowLevel);
+    panel.setTitleVisibility(NSWindowTitleVisibility::Hidden);
+    panel.setTitlebarAppearsTransparent(true);
+    panel.setHidesOnDeactivate(false);
+
+    let text = NSTextField::labelWithString(&NSString::from_str(""), mtm);
+    text.setFont(Some(&NSFont::monospacedSystemFontOfSize_weight(
+        FONT_SIZE,
+        unsafe { NSFontWeightRegular },
+    )));
+    text.setFrame(NSRect::new(
+        NSPoint::new(PADDING, PADDING),
+        NSSize::new(PANEL_WIDTH - 2.0 * PADDING, PANEL_HEIGHT - 2.0 * PADDING),
+    ));
+    if let Some(content) = panel.contentView() {
+        content.addSubview(&text);
+    }
+
+    let mut query = String::new();
+    let mut matches = fuzzy_filter(&query, labels);
+    let mut selected = 0;
+    text.setStringValue(&NSString::from_str(&render(
+        &query, labels, &matches, selected,
+    )));
+
+    panel.center();
+    panel.makeKeyAndOrderFront(None);
+    #[allow(deprecated)]
+    app.activateIgnoringOtherApps(true);
+
+    let mut was_key = false;
+    let choice = loop {
+        let Some(event) = app.nextEventMatchingMask_untilDate_inMode_dequeue(
+            NSEventMask::Any,
+            Some(&NSDate::distantFuture()),
+            unsafe { NSDefaultRunLoopMode },
+            true,
+        ) else {
+            continue;
+        };
+
+        match event.r#type() {
+            NSEventType::KeyDown => {
+                let chars = event
+                    .characters()
+                    .map(|s| s.to_string())
+                    .unwrap_or_default();
+                match event.keyCode() {
+                    KEY_ESCAPE => break None,
+                    KEY_RETURN | KEY_KEYPAD_ENTER => {
+                        if let Some(&index) = matches.get(selected) {
+                            break Some(index);
+                        }
+                    }
+                    KEY_DOWN | KEY_TAB => selected = (selected + 1).min(matches.len().max(1) - 1),
+                    KEY_UP => selected = selected.saturating_sub(1),
+                    KEY_DELETE => {
+                        query.pop();
+                        matches = fuzzy_filter(&query, labels);
+                        selected = 0;
+                    }
+                    _ => {
+                        // Function keys arrive as private-use characters
+                        query.extend(
+                            chars.chars().filter(|c| {
+                                !c.is_control() && !('\u{f700}'..='\u{f8ff}').contains(c)
+                            }),
+                        );
+                        matches = fuzzy_filter(&query, labels);
+                        selected = 0;
+                    }
+                }
+                text.setStringValue(&NSString::from_str(&render(
+                    &query, labels, &matches, selected,
+                )));
+            }
+            NSEventType::LeftMouseDown | NSEventType::RightMouseDown
+                if event
+                    .window(mtm)
+                    .is_none_or(|w| !std::ptr::eq(&*w, &**panel)) =>
+            {
+                break None;
+            }
+            _ => app.sendEvent(&event),
+        }
+
+        // Switching to another app cancels
+        if panel.isKeyWindow() {
+            was_key = true;
+        } else if was_key {
+            break None;
+        }
+    };
+
+    panel.orderOut(None);
+    Ok(choice)
+}
+
+/// Query line followed by the visible slice of matches, the selection marked with `▸`
+fn render(query: &str, labels: &[String], matches: &[usize], selected: usize) -> String {
+    let first = selected.saturating_sub(MAX_ROWS - 1);
+    let mut lines = vec![format!("> {}", query)];
+    lines.extend(
+        matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(MAX_ROWS)
+            .map(|(row, &index)| {
+                let marker = if row == selected { "▸" } else { " " };
+                let label = &labels[index];
+                if label.chars().count() > MAX_LABEL_CHARS {
+                    let cut: String = label.chars().take(MAX_LABEL_CHARS - 1).collect();
+                    format!("{} {}…", marker, cut)
+                } else {
+                    format!("{} {}", marker, label)
+                }
+            }),
+    );
+    if matches.is_empty() {
+        lines.push("  (no matches)".to_string());
+    }
+    lines.join("\n")
+}
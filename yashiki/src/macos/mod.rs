@@ -1,13 +1,25 @@
 mod accessibility;
 mod display;
 mod hotkey;
+mod input_source;
+mod list_chooser;
 mod mouse_tracker;
 mod observer;
+mod status_item;
+mod url_handler;
+mod window_alpha;
+mod window_shadow;
 mod workspace;
 
 pub use accessibility::*;
 pub use display::*;
 pub use hotkey::*;
+pub use input_source::*;
+pub use list_chooser::*;
 pub use mouse_tracker::*;
 pub use observer::*;
+pub use status_item::*;
+pub use url_handler::*;
+pub use window_alpha::*;
+pub use window_shadow::*;
 pub use workspace::*;
@@ -15,6 +15,15 @@ use core_graphics::event::{
 
 extern "C" {
     fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+    fn CGEventSourceButtonState(state_id: i32, button: u32) -> bool;
+}
+
+const COMBINED_SESSION_STATE: i32 = 0;
+const LEFT_MOUSE_BUTTON: u32 = 0;
+
+/// Whether the left mouse button is currently held (e.g. a window drag is in progress)
+pub fn is_left_mouse_down() -> bool {
+    unsafe { CGEventSourceButtonState(COMBINED_SESSION_STATE, LEFT_MOUSE_BUTTON) }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -23,18 +32,22 @@ pub struct MousePosition {
     pub y: i32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseEvent {
+    Moved(MousePosition),
+    /// Left button released (end of a drag)
+    LeftUp,
+}
+
 pub struct MouseTracker {
-    event_tx: mpsc::Sender<MousePosition>,
+    event_tx: mpsc::Sender<MouseEvent>,
     tap: Option<MouseTap>,
     runloop_source: Arc<AtomicPtr<c_void>>,
     last_position: Arc<std::sync::Mutex<Option<MousePosition>>>,
 }
 
 impl MouseTracker {
-    pub fn new(
-        event_tx: mpsc::Sender<MousePosition>,
-        runloop_source: Arc<AtomicPtr<c_void>>,
-    ) -> Self {
+    pub fn new(event_tx: mpsc::Sender<MouseEvent>, runloop_source: Arc<AtomicPtr<c_void>>) -> Self {
         Self {
             event_tx,
             tap: None,
@@ -77,7 +90,7 @@ impl MouseTracker {
             CGEventTapLocation::Session,
             CGEventTapPlacement::HeadInsertEventTap,
             CGEventTapOptions::ListenOnly,
-            vec![CGEventType::MouseMoved],
+            vec![CGEventType::MouseMoved, CGEventType::LeftMouseUp],
             move |_proxy, event_type, event| {
                 match event_type {
                     CGEventType::TapDisabledByTimeout | CGEventType::TapDisabledByUserInput => {
@@ -95,6 +108,10 @@ impl MouseTracker {
                         }
                         return CallbackResult::Keep;
                     }
+                    CGEventType::LeftMouseUp => {
+                        send_event(&tx, &source, MouseEvent::LeftUp);
+                        return CallbackResult::Keep;
+                    }
                     _ => {}
                 }
 
@@ -125,14 +142,8 @@ impl MouseTracker {
                     }
                 };
 
-                if should_send && tx.send(new_pos).is_ok() {
-                    // Signal CFRunLoopSource for immediate processing
-                    let source_ptr = source.load(Ordering::Acquire);
-                    if !source_ptr.is_null() {
-                        unsafe {
-                            CFRunLoopSourceSignal(source_ptr as CFRunLoopSourceRef);
-                        }
-                    }
+                if should_send {
+                    send_event(&tx, &source, MouseEvent::Moved(new_pos));
                 }
 
                 CallbackResult::Keep
@@ -163,6 +174,18 @@ impl MouseTracker {
     }
 }
 
+fn send_event(tx: &mpsc::Sender<MouseEvent>, source: &AtomicPtr<c_void>, event: MouseEvent) {
+    if tx.send(event).is_ok() {
+        // Signal CFRunLoopSource for immediate processing
+        let source_ptr = source.load(Ordering::Acquire);
+        if !source_ptr.is_null() {
+            unsafe {
+                CFRunLoopSourceSignal(source_ptr as CFRunLoopSourceRef);
+            }
+        }
+    }
+}
+
 struct MouseTap {
     _tap: CGEventTap<'static>,
     _source: CFRunLoopSource,
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::mpsc as std_mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use core_foundation::base::TCFType;
 use core_foundation::runloop::{
@@ -10,21 +11,30 @@ use core_foundation::runloop::{
     CFRunLoopWakeUp,
 };
 use core_foundation::string::{CFString, CFStringRef};
+use yashiki_ipc::GlobPattern;
 
 use crate::event::Event;
 use crate::macos::accessibility::{
     notification, AXObserver, AXObserverRef, AXUIElement, AXUIElementRef,
 };
 use crate::macos::display::get_on_screen_windows;
+use crate::macos::workspace::get_bundle_id_for_pid;
 
 pub struct ObserverManager {
     observers: HashMap<i32, AXObserver>,
+    /// Apps (bundle identifier globs) that never get an observer
+    excluded_apps: Vec<GlobPattern>,
+    /// Pids skipped because their app is excluded (avoids repeated bundle id lookups)
+    excluded_pids: HashSet<i32>,
+    /// When each observer was created or last delivered a notification
+    last_events: Arc<Mutex<HashMap<i32, Instant>>>,
     event_tx: std_mpsc::Sender<Event>,
     source_ptr: Arc<AtomicPtr<c_void>>,
 }
 
 struct CallbackContext {
     pid: i32,
+    last_events: Arc<Mutex<HashMap<i32, Instant>>>,
     event_tx: std_mpsc::Sender<Event>,
     source_ptr: Arc<AtomicPtr<c_void>>,
 }
@@ -33,6 +43,9 @@ impl ObserverManager {
     pub fn new(event_tx: std_mpsc::Sender<Event>, source_ptr: Arc<AtomicPtr<c_void>>) -> Self {
         Self {
             observers: HashMap::new(),
+            excluded_apps: Vec::new(),
+            excluded_pids: HashSet::new(),
+            last_events: Arc::new(Mutex::new(HashMap::new())),
             event_tx,
             source_ptr,
         }
@@ -40,7 +53,14 @@ impl ObserverManager {
 
     pub fn start(&mut self) {
         let windows = get_on_screen_windows();
-        let mut pids: Vec<i32> = windows.iter().map(|w| w.pid).collect();
+        let mut pids: Vec<i32> = Vec::new();
+        for w in &windows {
+            if self.is_excluded(w.bundle_id.as_deref()) {
+                self.excluded_pids.insert(w.pid);
+            } else {
+                pids.push(w.pid);
+            }
+        }
         pids.sort();
         pids.dedup();
 
@@ -53,8 +73,36 @@ impl ObserverManager {
         }
     }
 
+    /// Replace the excluded apps, dropping observers of apps that became excluded.
+    /// Apps that are no longer excluded get an observer on their next sync.
+    pub fn set_excluded_apps(&mut self, excluded_apps: Vec<GlobPattern>) {
+        self.excluded_apps = excluded_apps;
+        self.excluded_pids.clear();
+
+        let pids: Vec<i32> = self.observers.keys().copied().collect();
+        for pid in pids {
+            if self.is_excluded(get_bundle_id_for_pid(pid).as_deref()) {
+                self.remove_observer(pid);
+                self.excluded_pids.insert(pid);
+            }
+        }
+    }
+
+    fn is_excluded(&self, bundle_id: Option<&str>) -> bool {
+        bundle_id.is_some_and(|id| self.excluded_apps.iter().any(|p| p.matches(id)))
+    }
+
     pub fn add_observer(&mut self, pid: i32) -> Result<(), i32> {
-        if self.observers.contains_key(&pid) {
+        // Observing ourselves would make AX calls back into the main thread
+        if self.observers.contains_key(&pid)
+            || self.excluded_pids.contains(&pid)
+            || pid == std::process::id() as i32
+        {
+            return Ok(());
+        }
+        if self.is_excluded(get_bundle_id_for_pid(pid).as_deref()) {
+            tracing::debug!("Not observing excluded app (pid {})", pid);
+            self.excluded_pids.insert(pid);
             return Ok(());
         }
 
@@ -63,6 +111,7 @@ impl ObserverManager {
 
         let context = Box::new(CallbackContext {
             pid,
+            last_events: self.last_events.clone(),
             event_tx: self.event_tx.clone(),
             source_ptr: self.source_ptr.clone(),
         });
@@ -72,6 +121,7 @@ impl ObserverManager {
             notification::WINDOW_CREATED,
             notification::WINDOW_MOVED,
             notification::WINDOW_RESIZED,
+            notification::TITLE_CHANGED,
             notification::WINDOW_MINIATURIZED,
             notification::WINDOW_DEMINIATURIZED,
             notification::FOCUSED_WINDOW_CHANGED,
@@ -98,25 +148,47 @@ impl ObserverManager {
         run_loop.add_source(&source, unsafe { kCFRunLoopDefaultMode });
 
         self.observers.insert(pid, observer);
+        record_event(&self.last_events, pid);
         tracing::debug!("Added observer for pid {}", pid);
 
         Ok(())
     }
 
     pub fn remove_observer(&mut self, pid: i32) {
+        self.excluded_pids.remove(&pid);
         if self.observers.remove(&pid).is_some() {
             tracing::debug!("Removed observer for pid {}", pid);
         }
+        if let Ok(mut last_events) = self.last_events.lock() {
+            last_events.remove(&pid);
+        }
+    }
+
+    /// Replace the observer of an app that stopped delivering notifications
+    pub fn recreate_observer(&mut self, pid: i32) -> Result<(), i32> {
+        self.remove_observer(pid);
+        self.add_observer(pid)
     }
 
     pub fn has_observer(&self, pid: i32) -> bool {
         self.observers.contains_key(&pid)
     }
+
+    /// When the app's observer was created or last delivered a notification
+    pub fn last_event(&self, pid: i32) -> Option<Instant> {
+        self.last_events.lock().ok()?.get(&pid).copied()
+    }
+}
+
+fn record_event(last_events: &Mutex<HashMap<i32, Instant>>, pid: i32) {
+    if let Ok(mut last_events) = last_events.lock() {
+        last_events.insert(pid, Instant::now());
+    }
 }
 
 extern "C" fn observer_callback(
     _observer: AXObserverRef,
-    _element: AXUIElementRef,
+    element: AXUIElementRef,
     notification: CFStringRef,
     refcon: *mut c_void,
 ) {
@@ -125,6 +197,7 @@ extern "C" fn observer_callback(
     }
 
     let context = unsafe { &*(refcon as *const CallbackContext) };
+    record_event(&context.last_events, context.pid);
     let notif = unsafe { CFString::wrap_under_get_rule(notification) };
     let notif_str = notif.to_string();
 
@@ -134,6 +207,12 @@ extern "C" fn observer_callback(
         notification::FOCUSED_WINDOW_CHANGED => Some(Event::FocusedWindowChanged),
         notification::WINDOW_MOVED => Some(Event::WindowMoved { pid: context.pid }),
         notification::WINDOW_RESIZED => Some(Event::WindowResized { pid: context.pid }),
+        // Posted for every titled element (tabs, buttons, web content); only windows matter
+        notification::TITLE_CHANGED => {
+            let element = unsafe { AXUIElement::wrap_under_get_rule(element) };
+            (element.role().ok().as_deref() == Some("AXWindow"))
+                .then_some(Event::WindowTitleChanged { pid: context.pid })
+        }
         notification::WINDOW_MINIATURIZED => Some(Event::WindowMiniaturized { pid: context.pid }),
         notification::WINDOW_DEMINIATURIZED => {
             Some(Event::WindowDeminiaturized { pid: context.pid })
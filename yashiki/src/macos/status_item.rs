@@ -0,0 +1,52 @@
+use objc2::rc::Retained;
+use objc2_app_kit::{NSStatusBar, NSStatusItem, NSVariableStatusItemLength};
+use objc2_foundation::{MainThreadMarker, NSString};
+
+/// Menu bar item displaying a short text (e.g. focused output's tags and layout)
+pub struct StatusItem {
+    mtm: MainThreadMarker,
+    item: Option<Retained<NSStatusItem>>,
+    title: String,
+}
+
+impl StatusItem {
+    pub fn new(mtm: MainThreadMarker) -> Self {
+        Self {
+            mtm,
+            item: None,
+            title: String::new(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.item.is_some()
+    }
+
+    pub fn show(&mut self) {
+        if self.item.is_some() {
+            return;
+        }
+        let item = NSStatusBar::systemStatusBar().statusItemWithLength(NSVariableStatusItemLength);
+        self.item = Some(item);
+        self.title.clear();
+        tracing::info!("Menu bar indicator shown");
+    }
+
+    pub fn hide(&mut self) {
+        if let Some(item) = self.item.take() {
+            NSStatusBar::systemStatusBar().removeStatusItem(&item);
+            tracing::info!("Menu bar indicator hidden");
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if self.title == title {
+            return;
+        }
+        let Some(button) = self.item.as_ref().and_then(|item| item.button(self.mtm)) else {
+            return;
+        };
+        button.setTitle(&NSString::from_str(title));
+        self.title = title.to_string();
+    }
+}
@@ -0,0 +1,266 @@
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+
+use core_foundation::runloop::{
+    CFRunLoopGetMain, CFRunLoopSourceRef, CFRunLoopSourceSignal, CFRunLoopWakeUp,
+};
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2::{define_class, msg_send, sel, DefinedClass};
+use objc2_foundation::{
+    MainThreadMarker, NSAppleEventDescriptor, NSAppleEventManager, NSObject, NSObjectProtocol,
+};
+
+use crate::app::runs_programs;
+use yashiki_ipc::Command;
+
+/// URL scheme handled by the daemon (e.g. `yashiki://tag-view/2`)
+pub const URL_SCHEME: &str = "yashiki";
+
+// kInternetEventClass / kAEGetURL / keyDirectObject
+const INTERNET_EVENT_CLASS: u32 = u32::from_be_bytes(*b"GURL");
+const AE_GET_URL: u32 = u32::from_be_bytes(*b"GURL");
+const KEY_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+
+/// Parses CLI-style arguments (e.g. `["tag-view", "2"]`) into a command
+pub type CommandParser = fn(&[String]) -> anyhow::Result<Command>;
+
+struct Ivars {
+    command_tx: mpsc::Sender<Command>,
+    source_ptr: Arc<AtomicPtr<c_void>>,
+    parser: CommandParser,
+}
+
+define_class!(
+    #[unsafe(super(NSObject))]
+    #[ivars = Ivars]
+    struct UrlEventHandler;
+
+    unsafe impl NSObjectProtocol for UrlEventHandler {}
+
+    impl UrlEventHandler {
+        #[unsafe(method(handleGetURLEvent:withReplyEvent:))]
+        fn handle_get_url_event(
+            &self,
+            event: &NSAppleEventDescriptor,
+            _reply: &NSAppleEventDescriptor,
+        ) {
+            let descriptor: Option<Retained<NSAppleEventDescriptor>> =
+                unsafe { msg_send![event, paramDescriptorForKeyword: KEY_DIRECT_OBJECT] };
+            let Some(url) = descriptor.and_then(|d| d.stringValue()) else {
+                return;
+            };
+            let url = url.to_string();
+            tracing::info!("Received URL: {}", url);
+
+            let ivars = self.ivars();
+            match parse_url(&url, ivars.parser) {
+                Ok(cmd) => {
+                    let _ = ivars.command_tx.send(cmd);
+                    let source = ivars.source_ptr.load(Ordering::Acquire);
+                    if !source.is_null() {
+                        unsafe {
+                            CFRunLoopSourceSignal(source as CFRunLoopSourceRef);
+                            CFRunLoopWakeUp(CFRunLoopGetMain());
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring URL {}: {}", url, e),
+            }
+        }
+    }
+);
+
+impl UrlEventHandler {
+    fn new(
+        command_tx: mpsc::Sender<Command>,
+        source_ptr: Arc<AtomicPtr<c_void>>,
+        parser: CommandParser,
+        mtm: MainThreadMarker,
+    ) -> Retained<Self> {
+        let this = mtm.alloc::<Self>();
+        let this = this.set_ivars(Ivars {
+            command_tx,
+            source_ptr,
+            parser,
+        });
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+/// Receives `yashiki://` URLs (Shortcuts, Stream Deck, AppleScript `open location`)
+/// and forwards them as commands, the same way hotkeys do
+pub struct UrlHandler {
+    _handler: Retained<UrlEventHandler>,
+}
+
+impl UrlHandler {
+    pub fn new(
+        command_tx: mpsc::Sender<Command>,
+        source_ptr: Arc<AtomicPtr<c_void>>,
+        parser: CommandParser,
+        mtm: MainThreadMarker,
+    ) -> Self {
+        let handler = UrlEventHandler::new(command_tx, source_ptr, parser, mtm);
+
+        unsafe {
+            let manager = NSAppleEventManager::sharedAppleEventManager();
+            let handler_obj: &AnyObject =
+                std::mem::transmute::<&UrlEventHandler, &AnyObject>(&*handler);
+            let _: () = msg_send![
+                &manager,
+                setEventHandler: handler_obj,
+                andSelector: sel!(handleGetURLEvent:withReplyEvent:),
+                forEventClass: INTERNET_EVENT_CLASS,
+                andEventID: AE_GET_URL
+            ];
+        }
+
+        tracing::info!("URL handler registered for {}://", URL_SCHEME);
+
+        Self { _handler: handler }
+    }
+}
+
+/// Convert a URL into a command.
+/// Commands that spawn processes or change bindings are rejected, since any web page can open a URL.
+pub fn parse_url(url: &str, parser: CommandParser) -> Result<Command, String> {
+    let args = url_args(url)?;
+    let cmd = parser(&args).map_err(|e| e.to_string())?;
+    if !is_allowed_from_url(&cmd) {
+        return Err(format!("{} is not allowed from a URL", args[0]));
+    }
+    Ok(cmd)
+}
+
+/// Everything that runs programs is rejected, plus hotkey changes, hook changes
+/// (clearing included), force-quitting apps, benchmarks (they block the main thread) and quit
+fn is_allowed_from_url(cmd: &Command) -> bool {
+    !runs_programs(cmd)
+        && !matches!(
+            cmd,
+            Command::Bind { .. }
+                | Command::Unbind { .. }
+                | Command::TriggerBinding { .. }
+                | Command::SetHotkeyBackend { .. }
+                | Command::BindingsSuspend { .. }
+                | Command::BindingsToggle { .. }
+                | Command::SetTagChangeHook { .. }
+                | Command::SetHook { .. }
+                | Command::WindowClose {
+                    force_quit_app: true
+                }
+                | Command::BenchAx { .. }
+                | Command::Quit
+        )
+}
+
+/// Split `yashiki://<command>/<arg>/...` into percent-decoded CLI arguments
+fn url_args(url: &str) -> Result<Vec<String>, String> {
+    let rest = url
+        .split_once(':')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(URL_SCHEME))
+        .map(|(_, rest)| rest)
+        .ok_or_else(|| format!("Not a {}:// URL", URL_SCHEME))?;
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+
+    let args = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect::<Result<Vec<_>, _>>()?;
+    if args.is_empty() {
+        return Err("No command provided".to_string());
+    }
+    Ok(args)
+}
+
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Invalid percent-encoding in {:?}", s))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("Invalid UTF-8 in {:?}", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yashiki_ipc::HookEvent;
+
+    fn test_parser(args: &[String]) -> anyhow::Result<Command> {
+        match args[0].as_str() {
+            "tag-view" => Ok(Command::TagView {
+                tags: args[1].parse()?,
+                output: None,
+            }),
+            "quit" => Ok(Command::Quit),
+            "bench" => Ok(Command::BenchAx { iterations: 5 }),
+            "window-close" => Ok(Command::WindowClose {
+                force_quit_app: args.get(1).is_some_and(|a| a == "--force-quit-app"),
+            }),
+            "set-hook" => Ok(Command::SetHook {
+                event: HookEvent::PreRetile,
+                command: args.get(2).cloned(),
+            }),
+            other => anyhow::bail!("Unknown command: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_url_args() {
+        assert_eq!(
+            url_args("yashiki://tag-view/2").unwrap(),
+            vec!["tag-view", "2"]
+        );
+        assert_eq!(
+            url_args("Yashiki:layout-cmd/set-main-ratio/0.6/").unwrap(),
+            vec!["layout-cmd", "set-main-ratio", "0.6"]
+        );
+        assert_eq!(
+            url_args("yashiki://exec-or-focus/--app-name/Visual%20Studio%20Code?x=1").unwrap(),
+            vec!["exec-or-focus", "--app-name", "Visual Studio Code"]
+        );
+    }
+
+    #[test]
+    fn test_url_args_errors() {
+        assert!(url_args("https://tag-view/2").is_err());
+        assert!(url_args("yashiki://").is_err());
+        assert!(url_args("yashiki://tag-view/%2").is_err());
+        assert!(url_args("yashiki://tag-view/%FF").is_err());
+        // from_str_radix alone would accept a sign
+        assert!(url_args("yashiki://tag-view/%+1").is_err());
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert!(matches!(
+            parse_url("yashiki://tag-view/4", test_parser),
+            Ok(Command::TagView { tags: 4, .. })
+        ));
+        assert!(parse_url("yashiki://unknown", test_parser).is_err());
+        assert!(parse_url("yashiki://quit", test_parser).is_err());
+        // Hooks run shell commands on every retile
+        assert!(parse_url("yashiki://set-hook/pre-retile/x", test_parser).is_err());
+        // Force-quitting loses unsaved work; a plain close is fine
+        assert!(parse_url("yashiki://window-close/--force-quit-app", test_parser).is_err());
+        assert!(parse_url("yashiki://window-close", test_parser).is_ok());
+        assert!(parse_url("yashiki://bench/ax", test_parser).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+type CGSConnectionID = i32;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSMainConnectionID() -> CGSConnectionID;
+    fn CGSSetWindowAlpha(cid: CGSConnectionID, wid: u32, alpha: f32) -> i32;
+}
+
+/// Set a window's opacity (0.0 to 1.0) via a private CGS call. The window server may refuse
+/// this for other apps' windows, so callers treat failures as best-effort.
+pub fn set_window_alpha(window_id: u32, alpha: f32) -> Result<(), String> {
+    let err = unsafe { CGSSetWindowAlpha(CGSMainConnectionID(), window_id, alpha) };
+    if err != 0 {
+        return Err(format!(
+            "Failed to set opacity of window {} to {}: CGError {}",
+            window_id, alpha, err
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+type CGSConnectionID = i32;
+
+/// Window tag that disables the window server's shadow for a window
+const DISABLE_SHADOW_TAG: u64 = 1 << 3;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSMainConnectionID() -> CGSConnectionID;
+    fn CGSSetWindowTags(cid: CGSConnectionID, wid: u32, tags: *const u64, tag_size: i32) -> i32;
+    fn CGSClearWindowTags(cid: CGSConnectionID, wid: u32, tags: *const u64, tag_size: i32) -> i32;
+}
+
+/// Show or remove a window's shadow via private CGS calls. The window server may refuse
+/// this for other apps' windows, so callers treat failures as best-effort.
+pub fn set_window_shadow(window_id: u32, enabled: bool) -> Result<(), String> {
+    let tags = DISABLE_SHADOW_TAG;
+    let err = unsafe {
+        let cid = CGSMainConnectionID();
+        if enabled {
+            CGSClearWindowTags(cid, window_id, &tags, 64)
+        } else {
+            CGSSetWindowTags(cid, window_id, &tags, 64)
+        }
+    };
+    if err != 0 {
+        return Err(format!(
+            "Failed to {} shadow of window {}: CGError {}",
+            if enabled { "restore" } else { "remove" },
+            window_id,
+            err
+        ));
+    }
+    Ok(())
+}
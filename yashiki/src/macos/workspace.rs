@@ -2,16 +2,28 @@ use std::cell::RefCell;
 use std::ffi::c_void;
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::mpsc as std_mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
 use core_foundation::runloop::{
     CFRunLoopGetMain, CFRunLoopSourceRef, CFRunLoopSourceSignal, CFRunLoopWakeUp,
 };
+use core_foundation::string::CFString;
+use core_foundation_sys::preferences::{
+    CFPreferencesAppSynchronize, CFPreferencesGetAppBooleanValue,
+};
+use core_graphics::event::{CGEvent, CGEventFlags, KeyCode};
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 use objc2::rc::Retained;
 use objc2::runtime::AnyObject;
 use objc2::{define_class, msg_send, sel, DefinedClass};
 use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication, NSWorkspace};
-use objc2_foundation::{MainThreadMarker, NSNotification, NSObject, NSObjectProtocol, NSString};
+use objc2_foundation::{
+    MainThreadMarker, NSNotification, NSNotificationSuspensionBehavior, NSObject, NSObjectProtocol,
+    NSString,
+};
 
 pub fn get_frontmost_app_pid() -> Option<i32> {
     let workspace = NSWorkspace::sharedWorkspace();
@@ -20,6 +32,41 @@ pub fn get_frontmost_app_pid() -> Option<i32> {
         .map(|app| app.processIdentifier())
 }
 
+/// Whether macOS Stage Manager is turned on
+pub fn is_stage_manager_enabled() -> bool {
+    let app_id = CFString::new("com.apple.WindowManager");
+    let key = CFString::new("GlobalEnabled");
+    unsafe {
+        // Re-read the domain so toggles made in System Settings are picked up
+        CFPreferencesAppSynchronize(app_id.as_concrete_TypeRef());
+        CFPreferencesGetAppBooleanValue(
+            key.as_concrete_TypeRef(),
+            app_id.as_concrete_TypeRef(),
+            std::ptr::null_mut(),
+        ) != 0
+    }
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGSessionCopyCurrentDictionary() -> CFDictionaryRef;
+}
+
+/// Whether the login session reports its screen as locked. Lock notifications can be
+/// missed (e.g. started while locked), so this is the fallback source of truth.
+pub fn is_session_locked() -> bool {
+    let raw = unsafe { CGSessionCopyCurrentDictionary() };
+    if raw.is_null() {
+        return false;
+    }
+    let session: CFDictionary<CFString, CFType> =
+        unsafe { CFDictionary::wrap_under_create_rule(raw) };
+    session
+        .find(CFString::from_static_string("CGSSessionScreenIsLocked"))
+        .and_then(|value| value.downcast::<CFBoolean>())
+        .is_some_and(bool::from)
+}
+
 /// Get the bundle identifier for an application with the given PID.
 pub fn get_bundle_id_for_pid(pid: i32) -> Option<String> {
     let workspace = NSWorkspace::sharedWorkspace();
@@ -32,31 +79,104 @@ pub fn get_bundle_id_for_pid(pid: i32) -> Option<String> {
     None
 }
 
+fn running_application(pid: i32) -> Option<Retained<NSRunningApplication>> {
+    NSWorkspace::sharedWorkspace()
+        .runningApplications()
+        .into_iter()
+        .find(|app| app.processIdentifier() == pid)
+}
+
 #[allow(deprecated)]
 pub fn activate_application(pid: i32) -> bool {
-    let workspace = NSWorkspace::sharedWorkspace();
-    let apps = workspace.runningApplications();
-    for app in apps {
-        if app.processIdentifier() == pid {
-            return app
-                .activateWithOptions(NSApplicationActivationOptions::ActivateIgnoringOtherApps);
+    running_application(pid).is_some_and(|app| {
+        app.activateWithOptions(NSApplicationActivationOptions::ActivateIgnoringOtherApps)
+    })
+}
+
+pub fn hide_application(pid: i32) -> bool {
+    running_application(pid).is_some_and(|app| app.hide())
+}
+
+pub fn unhide_application(pid: i32) -> bool {
+    running_application(pid).is_some_and(|app| app.unhide())
+}
+
+/// Ask the application to quit, or kill it immediately when `force` is set
+pub fn quit_application(pid: i32, force: bool) -> bool {
+    running_application(pid).is_some_and(|app| {
+        if force {
+            app.forceTerminate()
+        } else {
+            app.terminate()
+        }
+    })
+}
+
+/// Send Cmd-W to the application (closes its key window)
+pub fn send_close_shortcut(pid: i32) {
+    let Ok(source) = CGEventSource::new(CGEventSourceStateID::HIDSystemState) else {
+        tracing::warn!("Failed to create event source");
+        return;
+    };
+    for key_down in [true, false] {
+        match CGEvent::new_keyboard_event(source.clone(), KeyCode::ANSI_W, key_down) {
+            Ok(event) => {
+                event.set_flags(CGEventFlags::CGEventFlagCommand);
+                event.post_to_pid(pid);
+            }
+            Err(_) => tracing::warn!("Failed to create keyboard event"),
         }
     }
-    false
 }
 
-pub fn exec_command(command: &str, path: &str) -> Result<(), String> {
-    let mut cmd = std::process::Command::new("/bin/bash");
-    cmd.arg("-c").arg(command);
+fn build_command(
+    command: &str,
+    args: Option<&[String]>,
+    path: &str,
+    env: &[(String, String)],
+) -> std::process::Command {
+    let mut cmd = match args {
+        Some(args) => {
+            let mut cmd = std::process::Command::new(command);
+            cmd.args(args);
+            cmd
+        }
+        None => {
+            let mut cmd = std::process::Command::new("/bin/bash");
+            cmd.arg("-c").arg(command);
+            cmd
+        }
+    };
+    cmd.envs(env.iter().cloned());
 
     if !path.is_empty() {
         cmd.env("PATH", path);
     }
 
-    match cmd.spawn() {
-        Ok(_) => {
-            tracing::info!("Executed command: {}", command);
-            Ok(())
+    cmd
+}
+
+/// Pids of `exec_command` children that the reaper thread hasn't collected yet.
+/// An unreaped child keeps its pid, so these can't have been reused by another process.
+static RUNNING_CHILDREN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+pub fn exec_command(
+    command: &str,
+    args: Option<&[String]>,
+    path: &str,
+    env: &[(String, String)],
+) -> Result<u32, String> {
+    match build_command(command, args, path, env).spawn() {
+        Ok(mut child) => {
+            let pid = child.id();
+            tracing::info!("Executed command: {} (pid={})", command, pid);
+            RUNNING_CHILDREN.lock().unwrap().push(pid);
+            // Reap the child when it exits so it doesn't linger as a zombie
+            std::thread::spawn(move || {
+                let _ = child.wait();
+                RUNNING_CHILDREN.lock().unwrap().retain(|&p| p != pid);
+            });
+            Ok(pid)
         }
         Err(e) => {
             let msg = format!("Failed to execute command '{}': {}", command, e);
@@ -66,26 +186,92 @@ pub fn exec_command(command: &str, path: &str) -> Result<(), String> {
     }
 }
 
-pub fn exec_command_tracked(command: &str, path: &str) -> Result<u32, String> {
-    let mut cmd = std::process::Command::new("/bin/bash");
-    cmd.arg("-c").arg(command);
-
-    if !path.is_empty() {
-        cmd.env("PATH", path);
-    }
-
+/// Run a shell command with `input` written to its stdin
+pub fn exec_hook(
+    command: &str,
+    path: &str,
+    env: &[(String, String)],
+    input: &str,
+) -> Result<u32, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut cmd = build_command(command, None, path, env);
+    cmd.stdin(Stdio::piped());
     match cmd.spawn() {
-        Ok(child) => {
+        Ok(mut child) => {
             let pid = child.id();
-            tracing::info!("Executed tracked command: {} (pid={})", command, pid);
+            tracing::debug!("Executed hook: {} (pid={})", command, pid);
+            let input = input.to_string();
+            // Write and reap off the main thread so a slow hook can't stall retiles
+            std::thread::spawn(move || {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = stdin.write_all(input.as_bytes());
+                }
+                let _ = child.wait();
+            });
             Ok(pid)
         }
-        Err(e) => {
-            let msg = format!("Failed to execute command '{}': {}", command, e);
-            tracing::error!("{}", msg);
-            Err(msg)
+        Err(e) => Err(format!("Failed to execute hook '{}': {}", command, e)),
+    }
+}
+
+/// Whether a child started by `exec_command` is still running
+pub fn is_process_alive(pid: u32) -> bool {
+    RUNNING_CHILDREN.lock().unwrap().contains(&pid)
+}
+
+/// Ancestors looked up for a process, in case pid reuse creates a parent cycle
+const MAX_PROCESS_ANCESTORS: usize = 32;
+
+/// Command names of a process's ancestors, nearest first, stopping before launchd
+pub fn process_ancestors(pid: i32) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut info = process_bsd_info(pid);
+    while let Some(current) = info {
+        let parent = current.pbi_ppid as i32;
+        if parent <= 1 || names.len() >= MAX_PROCESS_ANCESTORS {
+            break;
+        }
+        info = process_bsd_info(parent);
+        if let Some(parent_info) = &info {
+            names.push(process_name(parent_info));
         }
     }
+    names
+}
+
+fn process_bsd_info(pid: i32) -> Option<libc::proc_bsdinfo> {
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as i32;
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDTBSDINFO,
+            0,
+            &mut info as *mut libc::proc_bsdinfo as *mut c_void,
+            size,
+        )
+    };
+    (written == size).then_some(info)
+}
+
+/// Process name, falling back to the (shorter) command name when unset
+fn process_name(info: &libc::proc_bsdinfo) -> String {
+    let to_string = |chars: &[libc::c_char]| {
+        let bytes: Vec<u8> = chars
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    let name = to_string(&info.pbi_name);
+    if name.is_empty() {
+        to_string(&info.pbi_comm)
+    } else {
+        name
+    }
 }
 
 pub fn terminate_process(pid: u32) {
@@ -108,7 +294,11 @@ pub enum WorkspaceEvent {
     AppLaunched { pid: i32 },
     AppTerminated { pid: i32 },
     AppActivated { pid: i32 },
+    AppUnhidden { pid: i32 },
     DisplaysChanged,
+    ScreenLocked,
+    ScreenUnlocked,
+    DidWake,
 }
 
 struct Ivars {
@@ -160,6 +350,18 @@ define_class!(
             }
         }
 
+        #[unsafe(method(appUnhidden:))]
+        fn app_unhidden(&self, notification: &NSNotification) {
+            if let Some(pid) = get_pid_from_notification(notification) {
+                tracing::debug!("App unhidden: pid {}", pid);
+                let tx = self.ivars().event_tx.borrow();
+                if let Some(sender) = tx.as_ref() {
+                    let _: Result<(), _> = sender.send(WorkspaceEvent::AppUnhidden { pid });
+                }
+                signal_runloop_source(&self.ivars().source_ptr);
+            }
+        }
+
         #[unsafe(method(displaysChanged:))]
         fn displays_changed(&self, _notification: &NSNotification) {
             tracing::debug!("Screen parameters changed");
@@ -169,6 +371,36 @@ define_class!(
             }
             signal_runloop_source(&self.ivars().source_ptr);
         }
+
+        #[unsafe(method(screenLocked:))]
+        fn screen_locked(&self, _notification: &NSNotification) {
+            tracing::debug!("Screen locked");
+            let tx = self.ivars().event_tx.borrow();
+            if let Some(sender) = tx.as_ref() {
+                let _: Result<(), _> = sender.send(WorkspaceEvent::ScreenLocked);
+            }
+            signal_runloop_source(&self.ivars().source_ptr);
+        }
+
+        #[unsafe(method(screenUnlocked:))]
+        fn screen_unlocked(&self, _notification: &NSNotification) {
+            tracing::debug!("Screen unlocked");
+            let tx = self.ivars().event_tx.borrow();
+            if let Some(sender) = tx.as_ref() {
+                let _: Result<(), _> = sender.send(WorkspaceEvent::ScreenUnlocked);
+            }
+            signal_runloop_source(&self.ivars().source_ptr);
+        }
+
+        #[unsafe(method(didWake:))]
+        fn did_wake(&self, _notification: &NSNotification) {
+            tracing::debug!("System woke");
+            let tx = self.ivars().event_tx.borrow();
+            if let Some(sender) = tx.as_ref() {
+                let _: Result<(), _> = sender.send(WorkspaceEvent::DidWake);
+            }
+            signal_runloop_source(&self.ivars().source_ptr);
+        }
     }
 );
 
@@ -253,6 +485,24 @@ impl WorkspaceWatcher {
                 None,
             );
 
+            let unhide_name = NSString::from_str("NSWorkspaceDidUnhideApplicationNotification");
+
+            workspace_center.addObserver_selector_name_object(
+                observer_obj,
+                sel!(appUnhidden:),
+                Some(&unhide_name),
+                None,
+            );
+
+            let wake_name = NSString::from_str("NSWorkspaceDidWakeNotification");
+
+            workspace_center.addObserver_selector_name_object(
+                observer_obj,
+                sel!(didWake:),
+                Some(&wake_name),
+                None,
+            );
+
             // Register for screen change notifications using default notification center.
             // Note: This notification doesn't work without NSApplication's event loop.
             // Display changes are detected via polling in timer_callback instead.
@@ -266,6 +516,29 @@ impl WorkspaceWatcher {
                 Some(&screen_changed_name),
                 None,
             );
+
+            // Screen lock/unlock is only posted to the distributed notification center.
+            // Deliver immediately: delivery is suspended while yashiki isn't the active app.
+            let distributed_center =
+                objc2_foundation::NSDistributedNotificationCenter::defaultCenter();
+            let locked_name = NSString::from_str("com.apple.screenIsLocked");
+            let unlocked_name = NSString::from_str("com.apple.screenIsUnlocked");
+
+            distributed_center.addObserver_selector_name_object_suspensionBehavior(
+                observer_obj,
+                sel!(screenLocked:),
+                Some(&locked_name),
+                None,
+                NSNotificationSuspensionBehavior::DeliverImmediately,
+            );
+
+            distributed_center.addObserver_selector_name_object_suspensionBehavior(
+                observer_obj,
+                sel!(screenUnlocked:),
+                Some(&unlocked_name),
+                None,
+                NSNotificationSuspensionBehavior::DeliverImmediately,
+            );
         }
 
         tracing::info!("Workspace watcher started");
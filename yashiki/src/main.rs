@@ -1,4 +1,6 @@
+mod animation;
 mod app;
+mod batch;
 mod core;
 mod effect;
 mod event;
@@ -8,16 +10,20 @@ mod layout;
 mod macos;
 mod pid;
 mod platform;
+mod switcher;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use argh::FromArgs;
 use tracing_subscriber::EnvFilter;
 
 use ipc::IpcClient;
 use yashiki_ipc::{
-    AutoRaiseMode, ButtonInfo, ButtonState, Command, CursorWarpMode, Direction, EventFilter,
-    GlobPattern, OutputDirection, OutputSpecifier, Response, RuleAction, RuleMatcher, WindowLevel,
-    WindowLevelName, WindowLevelOther, WindowRule, WindowStatus,
+    Anchor, AnimationEasing, AutoRaiseMode, ButtonInfo, ButtonState, CloseBehavior, Command,
+    CursorWarpMode, Direction, EventFilter, FloatVisibility, FocusFloatingMode, GapScaleMode,
+    GlobPattern, HookEvent, HotkeyBackend, LayoutAction, NewWindowTagPolicy, OrphanPolicy,
+    OutputDirection, OutputSpecifier, RaisePolicy, Response, RuleAction, RuleMatcher, RuleSize,
+    SubscribeRequest, TagMode, WindowLevel, WindowLevelName, WindowLevelOther, WindowRule,
+    WindowStatus,
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -25,6 +31,9 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Yashiki - macOS tiling window manager
 #[derive(FromArgs)]
 struct Cli {
+    /// wait until the command's retiles and window animations have finished
+    #[argh(switch)]
+    wait: bool,
     #[argh(subcommand)]
     command: Option<SubCommand>,
 }
@@ -37,49 +46,126 @@ enum SubCommand {
     Bind(BindCmd),
     Unbind(UnbindCmd),
     ListBindings(ListBindingsCmd),
+    Trigger(TriggerCmd),
+    SetHotkeyBackend(SetHotkeyBackendCmd),
+    GetHotkeyBackend(GetHotkeyBackendCmd),
+    BindingsSuspend(BindingsSuspendCmd),
+    BindingsResume(BindingsResumeCmd),
+    BindingsToggle(BindingsToggleCmd),
     TagView(TagViewCmd),
     TagToggle(TagToggleCmd),
     TagViewLast(TagViewLastCmd),
+    TagSetFloat(TagSetFloatCmd),
+    TagSetInputSource(TagSetInputSourceCmd),
+    TagGetInputSource(TagGetInputSourceCmd),
+    TagDistribute(TagDistributeCmd),
     WindowMoveToTag(WindowMoveToTagCmd),
     WindowToggleTag(WindowToggleTagCmd),
     WindowFocus(WindowFocusCmd),
     WindowSwap(WindowSwapCmd),
+    WindowFocusParent(WindowFocusParentCmd),
+    WindowFocusChild(WindowFocusChildCmd),
+    WindowFocusId(WindowFocusIdCmd),
+    AppCycle(AppCycleCmd),
     WindowToggleFullscreen(WindowToggleFullscreenCmd),
     WindowToggleFloat(WindowToggleFloatCmd),
+    WindowToggleManual(WindowToggleManualCmd),
+    WindowSetUrgent(WindowSetUrgentCmd),
+    WindowZoom(WindowZoomCmd),
+    WindowBring(WindowBringCmd),
+    WindowSwapFloat(WindowSwapFloatCmd),
+    WindowStash(WindowStashCmd),
+    WindowUnstash(WindowUnstashCmd),
     WindowClose(WindowCloseCmd),
+    WindowHideApp(WindowHideAppCmd),
+    WindowUnhideApp(WindowUnhideAppCmd),
     OutputFocus(OutputFocusCmd),
     OutputSend(OutputSendCmd),
+    OutputIgnore(OutputIgnoreCmd),
     Retile(RetileCmd),
     LayoutSetDefault(LayoutSetDefaultCmd),
     LayoutSet(LayoutSetCmd),
     LayoutGet(LayoutGetCmd),
     LayoutCmd(LayoutCmdCmd),
+    LayoutAction(LayoutActionCmd),
     ListWindows(ListWindowsCmd),
     ListOutputs(ListOutputsCmd),
     GetState(GetStateCmd),
+    StateDump(StateDumpCmd),
+    Stats(StatsCmd),
+    LastErrors(LastErrorsCmd),
+    Bench(BenchCmd),
     FocusedWindow(FocusedWindowCmd),
+    FocusedOutput(FocusedOutputCmd),
+    WindowInfo(WindowInfoCmd),
     Exec(ExecCmd),
     ExecOrFocus(ExecOrFocusCmd),
+    ListExecs(ListExecsCmd),
     ExecPath(ExecPathCmd),
     SetExecPath(SetExecPathCmd),
     AddExecPath(AddExecPathCmd),
     RuleAdd(RuleAddCmd),
     RuleDel(RuleDelCmd),
     ListRules(ListRulesCmd),
+    RuleSuggest(RuleSuggestCmd),
     SetCursorWarp(SetCursorWarpCmd),
     GetCursorWarp(GetCursorWarpCmd),
     SetAutoRaise(SetAutoRaiseCmd),
     GetAutoRaise(GetAutoRaiseCmd),
+    SetNewWindowTag(SetNewWindowTagCmd),
+    GetNewWindowTag(GetNewWindowTagCmd),
+    SetOrphanPolicy(SetOrphanPolicyCmd),
+    GetOrphanPolicy(GetOrphanPolicyCmd),
+    SetTagMode(SetTagModeCmd),
+    GetTagMode(GetTagModeCmd),
+    SetAnimation(SetAnimationCmd),
+    GetAnimation(GetAnimationCmd),
+    SetMenubarIndicator(SetMenubarIndicatorCmd),
+    GetMenubarIndicator(GetMenubarIndicatorCmd),
+    SetHideApps(SetHideAppsCmd),
+    GetHideApps(GetHideAppsCmd),
+    SetWindowShadows(SetWindowShadowsCmd),
+    GetWindowShadows(GetWindowShadowsCmd),
+    SetCornerCompensation(SetCornerCompensationCmd),
+    GetCornerCompensation(GetCornerCompensationCmd),
+    ObserverExclude(ObserverExcludeCmd),
+    ObserverInclude(ObserverIncludeCmd),
+    ListObserverExcludes(ListObserverExcludesCmd),
+    SetGapScale(SetGapScaleCmd),
+    GetGapScale(GetGapScaleCmd),
+    SetFocusIncludeFloating(SetFocusIncludeFloatingCmd),
+    GetFocusIncludeFloating(GetFocusIncludeFloatingCmd),
+    SetFloatVisibility(SetFloatVisibilityCmd),
+    GetFloatVisibility(GetFloatVisibilityCmd),
+    SetRaisePolicy(SetRaisePolicyCmd),
+    GetRaisePolicy(GetRaisePolicyCmd),
     SetOuterGap(SetOuterGapCmd),
     GetOuterGap(GetOuterGapCmd),
+    SetTagChangeHook(SetTagChangeHookCmd),
+    GetTagChangeHook(GetTagChangeHookCmd),
+    SetHook(SetHookCmd),
+    GetHook(GetHookCmd),
     Subscribe(SubscribeCmd),
+    Switcher(SwitcherCmd),
+    SendJson(SendJsonCmd),
+    LayoutValidate(LayoutValidateCmd),
+    #[cfg(feature = "simulate")]
+    Simulate(SimulateCmd),
+    ArrangementSave(ArrangementSaveCmd),
+    ArrangementRestore(ArrangementRestoreCmd),
+    Pause(PauseCmd),
+    Resume(ResumeCmd),
     Quit(QuitCmd),
 }
 
 /// Start the yashiki daemon
 #[derive(FromArgs)]
 #[argh(subcommand, name = "start")]
-struct StartCmd {}
+struct StartCmd {
+    /// reject commands that run programs (exec, exec-or-focus, exec paths, tag change hook)
+    #[argh(switch)]
+    disable_exec: bool,
+}
 
 /// Show version information
 #[derive(FromArgs)]
@@ -90,6 +176,12 @@ struct VersionCmd {}
 #[derive(FromArgs)]
 #[argh(subcommand, name = "bind")]
 struct BindCmd {
+    /// run the command on a second press of the key (within 400ms) instead
+    #[argh(switch)]
+    double: bool,
+    /// still deliver the keystroke to the focused app
+    #[argh(switch)]
+    passthrough: bool,
     /// hotkey (e.g., alt-1, cmd-shift-h)
     #[argh(positional)]
     key: String,
@@ -112,6 +204,52 @@ struct UnbindCmd {
 #[argh(subcommand, name = "list-bindings")]
 struct ListBindingsCmd {}
 
+/// Run the command bound to a hotkey (for Karabiner, skhd, etc.)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "trigger")]
+struct TriggerCmd {
+    /// bound hotkey (e.g., alt-1)
+    #[argh(positional)]
+    key: String,
+}
+
+/// Set who listens for hotkeys
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-hotkey-backend")]
+struct SetHotkeyBackendCmd {
+    /// backend: internal, external
+    #[argh(positional)]
+    backend: String,
+}
+
+/// Get current hotkey backend
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-hotkey-backend")]
+struct GetHotkeyBackendCmd {}
+
+/// Suspend hotkeys so games and VMs receive all keys
+#[derive(FromArgs)]
+#[argh(subcommand, name = "bindings-suspend")]
+struct BindingsSuspendCmd {
+    /// hotkeys that keep working while suspended
+    #[argh(positional)]
+    except: Vec<String>,
+}
+
+/// Resume suspended hotkeys
+#[derive(FromArgs)]
+#[argh(subcommand, name = "bindings-resume")]
+struct BindingsResumeCmd {}
+
+/// Suspend or resume hotkeys
+#[derive(FromArgs)]
+#[argh(subcommand, name = "bindings-toggle")]
+struct BindingsToggleCmd {
+    /// hotkeys that keep working while suspended
+    #[argh(positional)]
+    except: Vec<String>,
+}
+
 /// Switch to specific tags (bitmask)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "tag-view")]
@@ -141,6 +279,60 @@ struct TagToggleCmd {
 #[argh(subcommand, name = "tag-view-last")]
 struct TagViewLastCmd {}
 
+/// Mark tags as float-only: windows moved there float, moving them back re-tiles (bitmask)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "tag-set-float")]
+struct TagSetFloatCmd {
+    /// unmark the tags instead
+    #[argh(switch)]
+    off: bool,
+    /// tags bitmask (e.g., 256 for tag 9)
+    #[argh(positional)]
+    tags: u32,
+}
+
+/// Select a keyboard input source whenever the tags are viewed (bitmask)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "tag-set-input-source")]
+struct TagSetInputSourceCmd {
+    /// tags bitmask (e.g., 4 for tag 3)
+    #[argh(positional)]
+    tags: u32,
+    /// input source ID, e.g. com.apple.keylayout.US (omit to clear)
+    #[argh(positional)]
+    input_source: Option<String>,
+}
+
+/// Get the input source selected for the tags (bitmask)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "tag-get-input-source")]
+struct TagGetInputSourceCmd {
+    /// tags bitmask (e.g., 4 for tag 3)
+    #[argh(positional)]
+    tags: u32,
+}
+
+/// Move some tiled windows from overcrowded tags to other tags (bitmasks)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "tag-distribute")]
+struct TagDistributeCmd {
+    /// tags bitmask to take windows from
+    #[argh(option)]
+    from: u32,
+    /// tags bitmask to move windows to
+    #[argh(option)]
+    to: u32,
+    /// number of windows to move, from the end of the layout order
+    #[argh(option)]
+    count: Option<u32>,
+    /// move every other window instead
+    #[argh(switch)]
+    every_other: bool,
+    /// output (display) ID or name
+    #[argh(option)]
+    output: Option<String>,
+}
+
 /// Move focused window to tags (bitmask)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "window-move-to-tag")]
@@ -177,6 +369,34 @@ struct WindowSwapCmd {
     direction: String,
 }
 
+/// Focus a dialog's parent window, or the main window from a stacked window
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-focus-parent")]
+struct WindowFocusParentCmd {}
+
+/// Focus the focused window's dialog, or the last stacked window from the main window
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-focus-child")]
+struct WindowFocusChildCmd {}
+
+/// Focus a window by ID, switching to its tag if it is hidden
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-focus-id")]
+struct WindowFocusIdCmd {
+    /// window ID
+    #[argh(positional)]
+    id: u32,
+}
+
+/// Focus the next visible window of an app, wrapping around
+#[derive(FromArgs)]
+#[argh(subcommand, name = "app-cycle")]
+struct AppCycleCmd {
+    /// bundle identifier (e.g. com.googlecode.iterm2)
+    #[argh(option)]
+    app_id: String,
+}
+
 /// Toggle fullscreen for focused window (AeroSpace-style, not macOS native)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "window-toggle-fullscreen")]
@@ -187,10 +407,78 @@ struct WindowToggleFullscreenCmd {}
 #[argh(subcommand, name = "window-toggle-float")]
 struct WindowToggleFloatCmd {}
 
-/// Close the focused window
+/// Toggle whether retiles leave a window's geometry alone (it stays in the layout)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-toggle-manual")]
+struct WindowToggleManualCmd {
+    /// window ID (default: focused window)
+    #[argh(option)]
+    id: Option<u32>,
+}
+
+/// Mark a window as needing attention until it is focused (shown in tags_state events)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-set-urgent")]
+struct WindowSetUrgentCmd {
+    /// window ID (default: focused window)
+    #[argh(option)]
+    id: Option<u32>,
+    /// state: on, off
+    #[argh(positional)]
+    state: String,
+}
+
+/// Move a window to the focused output and show it there
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-bring")]
+struct WindowBringCmd {
+    /// window ID (default: most recently focused window on another output)
+    #[argh(option)]
+    id: Option<u32>,
+}
+
+/// Move focused window to the main slot of the layout
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-zoom")]
+struct WindowZoomCmd {}
+
+/// Swap the focused floating window with the main tiled window
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-swap-float")]
+struct WindowSwapFloatCmd {}
+
+/// Hide the focused window off every tag until it is unstashed
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-stash")]
+struct WindowStashCmd {}
+
+/// Bring the most recently stashed window back on the current tags
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-unstash")]
+struct WindowUnstashCmd {}
+
+/// Close the focused window (falls back to Cmd-W if the close button is unavailable)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "window-close")]
-struct WindowCloseCmd {}
+struct WindowCloseCmd {
+    /// force quit the window's application instead
+    #[argh(switch)]
+    force_quit_app: bool,
+}
+
+/// Hide the focused window's application (its windows leave the layout until it returns)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-hide-app")]
+struct WindowHideAppCmd {}
+
+/// Unhide the application most recently hidden with window-hide-app
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-unhide-app")]
+struct WindowUnhideAppCmd {
+    /// unhide every application hidden with window-hide-app
+    #[argh(switch)]
+    all: bool,
+}
 
 /// Focus the next or previous display
 #[derive(FromArgs)]
@@ -210,6 +498,18 @@ struct OutputSendCmd {
     direction: String,
 }
 
+/// Stop (on) or resume (off) managing windows on a display
+#[derive(FromArgs)]
+#[argh(subcommand, name = "output-ignore")]
+struct OutputIgnoreCmd {
+    /// output (display) ID or name (default: focused display)
+    #[argh(option)]
+    output: Option<String>,
+    /// state: on, off
+    #[argh(positional)]
+    state: String,
+}
+
 /// Re-apply the current layout
 #[derive(FromArgs)]
 #[argh(subcommand, name = "retile")]
@@ -217,6 +517,9 @@ struct RetileCmd {
     /// output (display) ID or name
     #[argh(option)]
     output: Option<String>,
+    /// retile every output (the default without --output)
+    #[argh(switch)]
+    all: bool,
 }
 
 /// Set the default layout engine
@@ -270,6 +573,19 @@ struct LayoutCmdCmd {
     args: Vec<String>,
 }
 
+/// Run a standard layout action, mapped to the current layout engine's command
+#[derive(FromArgs)]
+#[argh(subcommand, name = "layout-action")]
+struct LayoutActionCmd {
+    /// main-ratio-inc, main-ratio-dec, main-count-inc, main-count-dec, gap-inc, gap-dec
+    /// or orientation-toggle
+    #[argh(positional)]
+    action: String,
+    /// arguments passed to the engine command (e.g. the step)
+    #[argh(positional, greedy)]
+    args: Vec<String>,
+}
+
 /// List all managed windows
 #[derive(FromArgs)]
 #[argh(subcommand, name = "list-windows")]
@@ -280,6 +596,9 @@ struct ListWindowsCmd {
     /// show debug info (ax_id, subrole, window_level, buttons)
     #[argh(switch)]
     debug: bool,
+    /// show which rules were applied (indices in list-rules order)
+    #[argh(switch)]
+    why: bool,
 }
 
 /// List all displays/outputs
@@ -292,11 +611,59 @@ struct ListOutputsCmd {}
 #[argh(subcommand, name = "get-state")]
 struct GetStateCmd {}
 
+/// Dump the full state as JSON for bug reports (developer tool)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "state-dump")]
+struct StateDumpCmd {}
+
+/// Show per-command latency percentiles (p50/p95)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "stats")]
+struct StatsCmd {}
+
+/// Show recent asynchronous failures (layout engine errors, windows that could not be moved)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "last-errors")]
+struct LastErrorsCmd {}
+
+/// Measure AX throughput per app (developer tool)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "bench")]
+struct BenchCmd {
+    /// what to measure (ax)
+    #[argh(positional)]
+    target: String,
+    /// runs per call (default: 5, at most 100)
+    #[argh(option)]
+    iterations: Option<u32>,
+}
+
 /// Get the focused window ID
 #[derive(FromArgs)]
 #[argh(subcommand, name = "focused-window")]
 struct FocusedWindowCmd {}
 
+/// Get the focused display ID and name
+#[derive(FromArgs)]
+#[argh(subcommand, name = "focused-output")]
+struct FocusedOutputCmd {
+    /// print the full output info (frame, tags) as JSON
+    #[argh(switch)]
+    json: bool,
+}
+
+/// Show every tracked attribute of a window as JSON
+#[derive(FromArgs)]
+#[argh(subcommand, name = "window-info")]
+struct WindowInfoCmd {
+    /// window ID
+    #[argh(option)]
+    id: Option<u32>,
+    /// use the focused window (default)
+    #[argh(switch)]
+    focused: bool,
+}
+
 /// Execute a shell command
 #[derive(FromArgs)]
 #[argh(subcommand, name = "exec")]
@@ -304,23 +671,43 @@ struct ExecCmd {
     /// track process and terminate on yashiki quit
     #[argh(switch, long = "track")]
     track: bool,
-    /// shell command to execute
-    #[argh(positional)]
-    command: String,
+    /// run the command directly with its arguments instead of via the shell
+    #[argh(switch, long = "no-shell")]
+    no_shell: bool,
+    /// skip launching if a process started with this name is still running
+    #[argh(option)]
+    unique: Option<String>,
+    /// shell command to execute (or program and arguments with --no-shell)
+    #[argh(positional, greedy)]
+    command: Vec<String>,
 }
 
 /// Focus an app if running, otherwise execute a command to launch it
 #[derive(FromArgs)]
 #[argh(subcommand, name = "exec-or-focus")]
 struct ExecOrFocusCmd {
-    /// application name to focus
+    /// application name to focus (exact)
+    #[argh(option)]
+    app_name: Option<String>,
+    /// bundle identifier to focus (glob, e.g. com.mitchellh.ghostty)
     #[argh(option)]
-    app_name: String,
+    app_id: Option<String>,
+    /// only focus a window with this title (glob)
+    #[argh(option)]
+    title: Option<String>,
+    /// pull a window on a hidden tag to the current tag instead of switching to its tag
+    #[argh(switch)]
+    bring: bool,
     /// shell command to execute if app is not running
     #[argh(positional)]
     command: String,
 }
 
+/// List running processes launched by exec
+#[derive(FromArgs)]
+#[argh(subcommand, name = "list-execs")]
+struct ListExecsCmd {}
+
 /// Get the current exec path
 #[derive(FromArgs)]
 #[argh(subcommand, name = "exec-path")]
@@ -381,6 +768,18 @@ struct RuleAddCmd {
     /// zoom button state (exists, none, enabled, disabled)
     #[argh(option)]
     zoom_button: Option<String>,
+    /// only match while the window's output shows these tags (bitmask)
+    #[argh(option)]
+    when_tag: Option<u32>,
+    /// only match when the window is on this output (name glob or ID)
+    #[argh(option)]
+    when_output: Option<String>,
+    /// only match windows whose process was launched by this command (glob)
+    #[argh(option)]
+    parent_exec: Option<String>,
+    /// also apply when a window's title changes to match (float, no-float, tags, output)
+    #[argh(switch)]
+    dynamic: bool,
     /// action and arguments (e.g., "float", "tags 2", "dimensions 800 600")
     #[argh(positional, greedy)]
     action: Vec<String>,
@@ -420,6 +819,15 @@ struct RuleDelCmd {
     /// zoom button state (exists, none, enabled, disabled)
     #[argh(option)]
     zoom_button: Option<String>,
+    /// only match while the window's output shows these tags (bitmask)
+    #[argh(option)]
+    when_tag: Option<u32>,
+    /// only match when the window is on this output (name glob or ID)
+    #[argh(option)]
+    when_output: Option<String>,
+    /// only match windows whose process was launched by this command (glob)
+    #[argh(option)]
+    parent_exec: Option<String>,
     /// action to remove (e.g., "float", "tags")
     #[argh(positional, greedy)]
     action: Vec<String>,
@@ -430,6 +838,11 @@ struct RuleDelCmd {
 #[argh(subcommand, name = "list-rules")]
 struct ListRulesCmd {}
 
+/// Suggest rules for managed windows with unusual attributes
+#[derive(FromArgs)]
+#[argh(subcommand, name = "rule-suggest")]
+struct RuleSuggestCmd {}
+
 /// Set cursor warp mode (mouse follows focus)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "set-cursor-warp")]
@@ -461,6 +874,197 @@ struct SetAutoRaiseCmd {
 #[argh(subcommand, name = "get-auto-raise")]
 struct GetAutoRaiseCmd {}
 
+/// Set which tags new windows are assigned to
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-new-window-tag")]
+struct SetNewWindowTagCmd {
+    /// policy: current, emptiest, app-sticky
+    #[argh(positional)]
+    policy: String,
+}
+
+/// Get current new window tag policy
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-new-window-tag")]
+struct GetNewWindowTagCmd {}
+
+/// Set where windows go when their display is disconnected
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-orphan-policy")]
+struct SetOrphanPolicyCmd {
+    /// policy: main, spread, by-tag
+    #[argh(positional)]
+    policy: String,
+}
+
+/// Get current orphan policy
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-orphan-policy")]
+struct GetOrphanPolicyCmd {}
+
+/// Set whether tag changes apply to one display or all displays
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-tag-mode")]
+struct SetTagModeCmd {
+    /// mode: per-output, global
+    #[argh(positional)]
+    mode: String,
+}
+
+/// Get current tag mode
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-tag-mode")]
+struct GetTagModeCmd {}
+
+/// Set window move animation (duration 0 disables)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-animation")]
+struct SetAnimationCmd {
+    /// settings: duration <ms> [easing <linear|ease-out|ease-in-out>]
+    #[argh(positional, greedy)]
+    args: Vec<String>,
+}
+
+/// Get current animation settings
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-animation")]
+struct GetAnimationCmd {}
+
+/// Show or hide the menu bar indicator (focused output's tags and layout)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-menubar-indicator")]
+struct SetMenubarIndicatorCmd {
+    /// state: on, off
+    #[argh(positional)]
+    state: String,
+}
+
+/// Get current menu bar indicator state
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-menubar-indicator")]
+struct GetMenubarIndicatorCmd {}
+
+/// Hide apps whose windows are all on hidden tags
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-hide-apps")]
+struct SetHideAppsCmd {
+    /// state: on, off
+    #[argh(positional)]
+    state: String,
+}
+
+/// Get current app hiding state
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-hide-apps")]
+struct GetHideAppsCmd {}
+
+/// Show or remove the shadows of tiled windows (best-effort)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-window-shadows")]
+struct SetWindowShadowsCmd {
+    /// state: on, off
+    #[argh(positional)]
+    state: String,
+}
+
+/// Get whether tiled windows have shadows
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-window-shadows")]
+struct GetWindowShadowsCmd {}
+
+/// Grow tiles into the gaps between them to even out rounded corners (0 turns it off)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-corner-compensation")]
+struct SetCornerCompensationCmd {
+    /// points each tile grows into a shared gap
+    #[argh(positional)]
+    px: u32,
+}
+
+/// Get the current corner compensation
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-corner-compensation")]
+struct GetCornerCompensationCmd {}
+
+/// Stop observing and managing an app (e.g. helper processes)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "observer-exclude")]
+struct ObserverExcludeCmd {
+    /// bundle identifier glob (e.g. com.apple.Safari.WebContent, *.helper)
+    #[argh(option)]
+    app_id: String,
+}
+
+/// Observe and manage a previously excluded app again
+#[derive(FromArgs)]
+#[argh(subcommand, name = "observer-include")]
+struct ObserverIncludeCmd {
+    /// bundle identifier glob, as given to observer-exclude
+    #[argh(option)]
+    app_id: String,
+}
+
+/// List apps excluded from observation and management
+#[derive(FromArgs)]
+#[argh(subcommand, name = "list-observer-excludes")]
+struct ListObserverExcludesCmd {}
+
+/// Set gap scaling (auto treats gap values as pixels on each display)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-gap-scale")]
+struct SetGapScaleCmd {
+    /// mode: off, auto
+    #[argh(positional)]
+    mode: String,
+}
+
+/// Get current gap scale mode
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-gap-scale")]
+struct GetGapScaleCmd {}
+
+/// Set whether directional window-focus reaches floating windows
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-focus-include-floating")]
+struct SetFocusIncludeFloatingCmd {
+    /// mode: on, off, tiled-first
+    #[argh(positional)]
+    mode: String,
+}
+
+/// Get whether directional window-focus reaches floating windows
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-focus-include-floating")]
+struct GetFocusIncludeFloatingCmd {}
+
+/// Set whether floating windows follow tags or stay visible
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-float-visibility")]
+struct SetFloatVisibilityCmd {
+    /// mode: tagged, always
+    #[argh(positional)]
+    mode: String,
+}
+
+/// Get whether floating windows follow tags or stay visible
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-float-visibility")]
+struct GetFloatVisibilityCmd {}
+
+/// Set which window is raised after each retile
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-raise-policy")]
+struct SetRaisePolicyCmd {
+    /// policy: none, focused-top, main-top
+    #[argh(positional)]
+    policy: String,
+}
+
+/// Get current raise policy
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-raise-policy")]
+struct GetRaisePolicyCmd {}
+
 /// Set the outer gap (gap between windows and screen edges)
 #[derive(FromArgs)]
 #[argh(subcommand, name = "set-outer-gap")]
@@ -475,6 +1079,46 @@ struct SetOuterGapCmd {
 #[argh(subcommand, name = "get-outer-gap")]
 struct GetOuterGapCmd {}
 
+/// Set a shell command to run when a display's visible tags change
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-tag-change-hook")]
+struct SetTagChangeHookCmd {
+    /// shell command to run (omit to clear)
+    #[argh(positional)]
+    command: Option<String>,
+}
+
+/// Get the tag change hook command
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-tag-change-hook")]
+struct GetTagChangeHookCmd {}
+
+/// Set a shell command to run around each retile, with a JSON context on stdin
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-hook")]
+struct SetHookCmd {
+    /// hook event: pre-retile, post-retile
+    #[argh(positional)]
+    event: String,
+    /// shell command to run (omit to clear)
+    #[argh(positional)]
+    command: Option<String>,
+}
+
+/// Get the command for a retile hook
+#[derive(FromArgs)]
+#[argh(subcommand, name = "get-hook")]
+struct GetHookCmd {
+    /// hook event: pre-retile, post-retile
+    #[argh(positional)]
+    event: String,
+}
+
+/// Pick a window across all tags from a filterable list and focus it
+#[derive(FromArgs)]
+#[argh(subcommand, name = "switcher")]
+struct SwitcherCmd {}
+
 /// Subscribe to state change events
 #[derive(FromArgs)]
 #[argh(subcommand, name = "subscribe")]
@@ -482,17 +1126,94 @@ struct SubscribeCmd {
     /// request a snapshot on connection
     #[argh(switch)]
     snapshot: bool,
-    /// filter events (comma-separated: window,focus,display,tags,layout)
+    /// batch bursts of events (16ms), keeping only the latest of repeated updates
+    #[argh(switch)]
+    coalesce: bool,
+    /// filter events (comma-separated: window,focus,display,tags,layout,error)
     #[argh(option)]
     filter: Option<String>,
+    /// only events on this output (display ID)
+    #[argh(option)]
+    output: Option<u32>,
+    /// only window and focus events of apps matching this bundle identifier (glob)
+    #[argh(option)]
+    app_id: Option<String>,
+}
+
+/// Send a raw JSON command to the daemon and print the JSON response
+#[derive(FromArgs)]
+#[argh(subcommand, name = "send-json")]
+struct SendJsonCmd {
+    /// command as JSON (e.g. '{"type":"tag_view","tags":2}')
+    #[argh(positional)]
+    json: String,
+}
+
+/// Run protocol conformance checks against a layout engine (no daemon needed)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "layout-validate")]
+struct LayoutValidateCmd {
+    /// layout engine executable (path or command name)
+    #[argh(positional)]
+    engine: String,
+}
+
+/// Replay a scenario (JSON of displays, windows and steps) against a mock window system
+#[cfg(feature = "simulate")]
+#[derive(FromArgs)]
+#[argh(subcommand, name = "simulate")]
+struct SimulateCmd {
+    /// scenario JSON file
+    #[argh(positional)]
+    scenario: String,
+    /// start from a state-dump JSON file instead of the scenario's displays and windows
+    #[argh(option)]
+    state: Option<String>,
+}
+
+/// Save the tags, displays, layout order and floating frames of open windows
+#[derive(FromArgs)]
+#[argh(subcommand, name = "arrangement-save")]
+struct ArrangementSaveCmd {
+    /// arrangement name
+    #[argh(positional)]
+    name: String,
+}
+
+/// Put open windows back where a saved arrangement had them
+#[derive(FromArgs)]
+#[argh(subcommand, name = "arrangement-restore")]
+struct ArrangementRestoreCmd {
+    /// arrangement name
+    #[argh(positional)]
+    name: String,
 }
 
+/// Suspend retiling and rule-driven window moves (e.g. during screen recording)
+#[derive(FromArgs)]
+#[argh(subcommand, name = "pause")]
+struct PauseCmd {}
+
+/// Resume retiling and apply changes made while paused
+#[derive(FromArgs)]
+#[argh(subcommand, name = "resume")]
+struct ResumeCmd {}
+
 /// Quit the yashiki daemon
 #[derive(FromArgs)]
 #[argh(subcommand, name = "quit")]
 struct QuitCmd {}
 
 fn main() -> Result<()> {
+    // `yashiki -` runs newline-delimited commands from stdin over one connection
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args == ["-"] {
+        if !batch::run_batch(std::io::stdin().lock(), parse_command)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let cli: Cli = argh::from_env();
 
     match cli.command {
@@ -507,14 +1228,14 @@ fn main() -> Result<()> {
             }
             Ok(())
         }
-        Some(SubCommand::Start(_)) => {
+        Some(SubCommand::Start(cmd)) => {
             // Start daemon
             tracing_subscriber::fmt()
                 .with_env_filter(EnvFilter::from_default_env())
                 .init();
 
             tracing::info!("yashiki starting");
-            app::App::run()
+            app::App::run(parse_command, cmd.disable_exec)
         }
         Some(SubCommand::Version(_)) => {
             println!("v{}", VERSION);
@@ -522,17 +1243,37 @@ fn main() -> Result<()> {
         }
         Some(SubCommand::Subscribe(cmd)) => {
             // Subscribe to events (separate from normal IPC)
-            let filter = cmd.filter.map(|f| parse_event_filter(&f));
-            ipc::subscribe_and_print(cmd.snapshot, filter)
+            let filter = EventFilter {
+                output: cmd.output,
+                app_id: cmd.app_id.map(GlobPattern::new),
+                ..cmd
+                    .filter
+                    .map(|f| parse_event_filter(&f))
+                    .unwrap_or_default()
+            };
+            ipc::subscribe_and_print(SubscribeRequest {
+                snapshot: cmd.snapshot,
+                filter,
+                coalesce: cmd.coalesce,
+            })
         }
-        Some(subcmd) => run_cli(subcmd),
+        Some(SubCommand::Switcher(_)) => switcher::run_switcher(),
+        Some(SubCommand::SendJson(cmd)) => send_json(&cmd.json),
+        Some(SubCommand::LayoutValidate(cmd)) => layout_validate(&cmd.engine),
+        #[cfg(feature = "simulate")]
+        Some(SubCommand::Simulate(cmd)) => simulate(&cmd.scenario, cmd.state.as_deref()),
+        Some(subcmd) => run_cli(subcmd, cli.wait),
     }
 }
 
-fn run_cli(subcmd: SubCommand) -> Result<()> {
+fn run_cli(subcmd: SubCommand, wait: bool) -> Result<()> {
+    let json = matches!(&subcmd, SubCommand::FocusedOutput(cmd) if cmd.json);
     let cmd = to_command(subcmd)?;
     let mut client = IpcClient::connect()?;
     let response = client.send(&cmd)?;
+    if wait && !matches!(response, Response::Error { .. }) {
+        client.send(&Command::Wait)?;
+    }
 
     match response {
         Response::Ok => {}
@@ -547,6 +1288,7 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                 if let Some(status) = &w.status {
                     match status {
                         WindowStatus::Ignored => flags.push("ignored".to_string()),
+                        WindowStatus::Internal => flags.push("internal".to_string()),
                         WindowStatus::Managed => {}
                     }
                 }
@@ -559,6 +1301,12 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                 if w.is_fullscreen {
                     flags.push("full".to_string());
                 }
+                if w.is_native_fullscreen {
+                    flags.push("native-full".to_string());
+                }
+                if w.is_manual {
+                    flags.push("manual".to_string());
+                }
                 let flag_str = if flags.is_empty() {
                     String::new()
                 } else {
@@ -620,6 +1368,15 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                     }
                     println!("  {}", debug_parts.join(", "));
                 }
+                // Applied rules if present (indices in list-rules order)
+                if let Some(applied) = &w.applied_rules {
+                    let rules: Vec<String> = applied.iter().map(|i| format!("#{}", i)).collect();
+                    if rules.is_empty() {
+                        println!("  rules: -");
+                    } else {
+                        println!("  rules: {}", rules.join(", "));
+                    }
+                }
             }
         }
         Response::Outputs { outputs } => {
@@ -633,8 +1390,22 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                     o.id, o.name, o.width, o.height, o.x, o.y, main_marker, focused_marker
                 );
                 println!("  visible_tags: {}", o.visible_tags);
+                println!("  scale_factor: {}", o.scale_factor);
+                if o.rotation != 0 {
+                    println!("  rotation: {}", o.rotation);
+                }
+                if o.refresh_rate > 0.0 {
+                    println!("  refresh_rate: {}", o.refresh_rate);
+                }
+                if let Some(uuid) = &o.uuid {
+                    println!("  uuid: {}", uuid);
+                }
+                println!("  window_order: {:?}", o.window_order);
             }
         }
+        Response::StateDump { dump } => {
+            println!("{}", serde_json::to_string_pretty(&dump)?);
+        }
         Response::State { state } => {
             println!("Visible tags: {}", state.visible_tags);
             println!("Focused window: {:?}", state.focused_window_id);
@@ -644,10 +1415,119 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                 "Current layout: {}",
                 state.current_layout.as_deref().unwrap_or("(default)")
             );
+            println!("Window order: {:?}", state.window_order);
+            if !state.tag_occupancy.is_empty() {
+                let occupancy: Vec<String> = state
+                    .tag_occupancy
+                    .iter()
+                    .map(|t| {
+                        if t.urgent > 0 {
+                            format!("{}:{}!{}", t.tag, t.windows, t.urgent)
+                        } else {
+                            format!("{}:{}", t.tag, t.windows)
+                        }
+                    })
+                    .collect();
+                println!("Occupied tags: {}", occupancy.join(" "));
+            }
+            if state.paused {
+                println!("Paused: true");
+            }
+            if state.bindings_suspended {
+                println!("Bindings: suspended");
+            }
+            if state.screen_locked {
+                println!("Screen: locked (window events ignored until unlock)");
+            }
+            println!("Wakeups/sec: {:.1}", state.wakeups_per_second);
+            if state.float_tags != 0 {
+                println!("Float-only tags: {}", state.float_tags);
+            }
+            if state.stage_manager {
+                println!("Stage Manager: on (conflicts with yashiki, turn it off in System Settings > Desktop & Dock)");
+            }
+        }
+        Response::Retile { displays } => {
+            for d in displays {
+                println!(
+                    "Display {}: {} windows, {} moved, {:.1}ms",
+                    d.display_id, d.windows, d.moved, d.duration_ms
+                );
+            }
+        }
+        Response::Stats { commands } => {
+            println!(
+                "{:<28} {:>6} {:>9} {:>9} {:>12} {:>12}",
+                "COMMAND", "COUNT", "P50 ms", "P95 ms", "EFFECTS P50", "EFFECTS P95"
+            );
+            for c in commands {
+                println!(
+                    "{:<28} {:>6} {:>9.2} {:>9.2} {:>12.2} {:>12.2}",
+                    c.command, c.count, c.p50_ms, c.p95_ms, c.effects_p50_ms, c.effects_p95_ms
+                );
+            }
+        }
+        Response::LastErrors { errors } => {
+            if errors.is_empty() {
+                println!("No errors");
+            }
+            for e in errors {
+                println!("{}s ago [{}] {}", e.seconds_ago, e.source, e.message);
+            }
+        }
+        Response::Bench { report } => {
+            println!(
+                "On-screen window list: p50 {:.2}ms, max {:.2}ms ({} runs)",
+                report.on_screen_p50_ms, report.on_screen_max_ms, report.iterations
+            );
+            println!(
+                "{:<24} {:>7} {:>7} {:>10} {:>10} {:>11} {:>11}",
+                "APP", "PID", "WINDOWS", "ATTRS P50", "ATTRS MAX", "MOVE P50", "MOVE MAX"
+            );
+            let ms = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.2}", v));
+            for a in report.apps {
+                println!(
+                    "{:<24} {:>7} {:>7} {:>10.2} {:>10.2} {:>11} {:>11}",
+                    a.app_name,
+                    a.pid,
+                    a.windows,
+                    a.attributes_p50_ms,
+                    a.attributes_max_ms,
+                    ms(a.move_resize_p50_ms),
+                    ms(a.move_resize_max_ms)
+                );
+            }
         }
         Response::Bindings { bindings } => {
             for b in bindings {
-                println!("{} -> {}", b.key, b.action);
+                let mut flags = Vec::new();
+                if b.double {
+                    flags.push("double");
+                }
+                if b.passthrough {
+                    flags.push("passthrough");
+                }
+                if flags.is_empty() {
+                    println!("{} -> {}", b.key, b.action);
+                } else {
+                    println!("{} ({}) -> {}", b.key, flags.join(", "), b.action);
+                }
+            }
+        }
+        Response::Execs { execs } => {
+            for e in execs {
+                let mut flags = Vec::new();
+                if let Some(name) = &e.name {
+                    flags.push(format!("unique={}", name));
+                }
+                if e.track {
+                    flags.push("tracked".to_string());
+                }
+                if flags.is_empty() {
+                    println!("{}: {}", e.pid, e.command);
+                } else {
+                    println!("{}: {} ({})", e.pid, e.command, flags.join(", "));
+                }
             }
         }
         Response::WindowId { id } => {
@@ -657,12 +1537,28 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                 std::process::exit(1);
             }
         }
+        Response::Output { output } => {
+            if json {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                println!("{}\t{}", output.id, output.name);
+            }
+        }
+        Response::WindowDetails { window } => {
+            println!("{}", serde_json::to_string_pretty(&window)?);
+        }
         Response::Layout { layout } => {
             println!("{}", layout);
         }
         Response::ExecPath { path } => {
             println!("{}", path);
         }
+        Response::RuleSuggestions { suggestions } => {
+            for s in suggestions {
+                println!("# {} (window {}): {}", s.app_name, s.window_id, s.reason);
+                println!("yashiki {}", s.command);
+            }
+        }
         Response::Rules { rules } => {
             for r in rules {
                 let mut matchers = Vec::new();
@@ -681,44 +1577,215 @@ fn run_cli(subcmd: SubCommand) -> Result<()> {
                 if let Some(subrole) = &r.subrole {
                     matchers.push(format!("--subrole {}", subrole));
                 }
+                if let Some(when_tag) = r.when_tag {
+                    matchers.push(format!("--when-tag {}", when_tag));
+                }
+                if let Some(when_output) = &r.when_output {
+                    matchers.push(format!("--when-output {}", when_output));
+                }
+                if let Some(parent_exec) = &r.parent_exec {
+                    matchers.push(format!("--parent-exec {}", parent_exec));
+                }
+                if r.dynamic {
+                    matchers.push("--dynamic".to_string());
+                }
                 if matchers.is_empty() {
                     matchers.push("*".to_string());
                 }
                 println!("{} -> {}", matchers.join(" "), r.action);
             }
         }
-        Response::CursorWarp { mode } => {
+        Response::CursorWarp { mode } => {
+            let mode_str = match mode {
+                CursorWarpMode::Disabled => "disabled",
+                CursorWarpMode::OnOutputChange => "on-output-change",
+                CursorWarpMode::OnFocusChange => "on-focus-change",
+            };
+            println!("{}", mode_str);
+        }
+        Response::AutoRaise { mode, delay_ms } => {
+            let mode_str = match mode {
+                AutoRaiseMode::Disabled => "disabled",
+                AutoRaiseMode::Enabled => "enabled",
+            };
+            if delay_ms > 0 {
+                println!("{} (delay: {}ms)", mode_str, delay_ms);
+            } else {
+                println!("{}", mode_str);
+            }
+        }
+        Response::NewWindowTag { policy } => {
+            let policy_str = match policy {
+                NewWindowTagPolicy::Current => "current",
+                NewWindowTagPolicy::Emptiest => "emptiest",
+                NewWindowTagPolicy::AppSticky => "app-sticky",
+            };
+            println!("{}", policy_str);
+        }
+        Response::OrphanPolicy { policy } => {
+            let policy_str = match policy {
+                OrphanPolicy::Main => "main",
+                OrphanPolicy::Spread => "spread",
+                OrphanPolicy::ByTag => "by-tag",
+            };
+            println!("{}", policy_str);
+        }
+        Response::TagMode { mode } => {
+            let mode_str = match mode {
+                TagMode::PerOutput => "per-output",
+                TagMode::Global => "global",
+            };
+            println!("{}", mode_str);
+        }
+        Response::Animation {
+            duration_ms,
+            easing,
+        } => {
+            if duration_ms == 0 {
+                println!("disabled");
+            } else {
+                let easing_str = match easing {
+                    AnimationEasing::Linear => "linear",
+                    AnimationEasing::EaseOut => "ease-out",
+                    AnimationEasing::EaseInOut => "ease-in-out",
+                };
+                println!("{}ms {}", duration_ms, easing_str);
+            }
+        }
+        Response::MenubarIndicator { enabled }
+        | Response::HideApps { enabled }
+        | Response::WindowShadows { enabled } => {
+            println!("{}", if enabled { "on" } else { "off" });
+        }
+        Response::CornerCompensation { px } => {
+            println!("{}", px);
+        }
+        Response::ObserverExcludes { app_ids } => {
+            for app_id in app_ids {
+                println!("{}", app_id);
+            }
+        }
+        Response::GapScale { mode } => {
+            let mode_str = match mode {
+                GapScaleMode::Off => "off",
+                GapScaleMode::Auto => "auto",
+            };
+            println!("{}", mode_str);
+        }
+        Response::FocusIncludeFloating { mode } => {
             let mode_str = match mode {
-                CursorWarpMode::Disabled => "disabled",
-                CursorWarpMode::OnOutputChange => "on-output-change",
-                CursorWarpMode::OnFocusChange => "on-focus-change",
+                FocusFloatingMode::On => "on",
+                FocusFloatingMode::Off => "off",
+                FocusFloatingMode::TiledFirst => "tiled-first",
             };
             println!("{}", mode_str);
         }
-        Response::AutoRaise { mode, delay_ms } => {
+        Response::FloatVisibility { mode } => {
             let mode_str = match mode {
-                AutoRaiseMode::Disabled => "disabled",
-                AutoRaiseMode::Enabled => "enabled",
+                FloatVisibility::Tagged => "tagged",
+                FloatVisibility::Always => "always",
             };
-            if delay_ms > 0 {
-                println!("{} (delay: {}ms)", mode_str, delay_ms);
-            } else {
-                println!("{}", mode_str);
-            }
+            println!("{}", mode_str);
+        }
+        Response::RaisePolicy { policy } => {
+            let policy_str = match policy {
+                RaisePolicy::None => "none",
+                RaisePolicy::FocusedTop => "focused-top",
+                RaisePolicy::MainTop => "main-top",
+            };
+            println!("{}", policy_str);
         }
         Response::OuterGap { outer_gap } => {
             println!("{}", outer_gap);
         }
+        Response::InputSource { input_source } => {
+            if let Some(input_source) = input_source {
+                println!("{}", input_source);
+            }
+        }
+        Response::TagChangeHook { command } | Response::Hook { command } => {
+            if let Some(command) = command {
+                println!("{}", command);
+            }
+        }
+        Response::HotkeyBackend { backend } => {
+            let backend_str = match backend {
+                HotkeyBackend::Internal => "internal",
+                HotkeyBackend::External => "external",
+            };
+            println!("{}", backend_str);
+        }
+    }
+
+    Ok(())
+}
+
+fn send_json(json: &str) -> Result<()> {
+    let cmd: Command = serde_json::from_str(json).context("Invalid command JSON")?;
+    let response = IpcClient::connect()?.send(&cmd)?;
+    println!("{}", serde_json::to_string(&response)?);
+    if matches!(response, Response::Error { .. }) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn layout_validate(engine: &str) -> Result<()> {
+    let results = layout::validate_engine(engine)?;
+    let (mut failed, mut warned) = (0, 0);
+    for r in &results {
+        match &r.outcome {
+            layout::Outcome::Pass => println!("ok    {}", r.name),
+            layout::Outcome::Warn(message) => {
+                warned += 1;
+                println!("warn  {}: {}", r.name, message);
+            }
+            layout::Outcome::Fail(message) => {
+                failed += 1;
+                println!("FAIL  {}: {}", r.name, message);
+            }
+        }
+    }
+    println!(
+        "{} checks, {} failed, {} warnings",
+        results.len(),
+        failed,
+        warned
+    );
+    if failed > 0 {
+        std::process::exit(1);
     }
+    Ok(())
+}
 
+#[cfg(feature = "simulate")]
+fn simulate(path: &str, state_path: Option<&str>) -> Result<()> {
+    let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let mut scenario: app::Scenario =
+        serde_json::from_str(&json).context("Invalid scenario JSON")?;
+    if let Some(state_path) = state_path {
+        let json = std::fs::read_to_string(state_path)
+            .with_context(|| format!("Failed to read {}", state_path))?;
+        scenario.state = Some(serde_json::from_str(&json).context("Invalid state dump JSON")?);
+    }
+    for line in app::run_scenario(&scenario, parse_command) {
+        println!("{}", line);
+    }
     Ok(())
 }
 
 fn to_command(subcmd: SubCommand) -> Result<Command> {
     match subcmd {
-        SubCommand::Start(_) | SubCommand::Version(_) | SubCommand::Subscribe(_) => {
+        SubCommand::Start(_)
+        | SubCommand::Version(_)
+        | SubCommand::Subscribe(_)
+        | SubCommand::Switcher(_)
+        | SubCommand::SendJson(_)
+        | SubCommand::LayoutValidate(_) => {
             unreachable!("handled in main")
         }
+        #[cfg(feature = "simulate")]
+        SubCommand::Simulate(_) => unreachable!("handled in main"),
         SubCommand::Bind(cmd) => {
             if cmd.action.is_empty() {
                 bail!("bind requires a command to bind");
@@ -727,10 +1794,20 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
             Ok(Command::Bind {
                 key: cmd.key,
                 action: Box::new(action),
+                double: cmd.double,
+                passthrough: cmd.passthrough,
             })
         }
         SubCommand::Unbind(cmd) => Ok(Command::Unbind { key: cmd.key }),
         SubCommand::ListBindings(_) => Ok(Command::ListBindings),
+        SubCommand::Trigger(cmd) => Ok(Command::TriggerBinding { key: cmd.key }),
+        SubCommand::SetHotkeyBackend(cmd) => Ok(Command::SetHotkeyBackend {
+            backend: parse_hotkey_backend(&cmd.backend)?,
+        }),
+        SubCommand::GetHotkeyBackend(_) => Ok(Command::GetHotkeyBackend),
+        SubCommand::BindingsSuspend(cmd) => Ok(Command::BindingsSuspend { except: cmd.except }),
+        SubCommand::BindingsResume(_) => Ok(Command::BindingsResume),
+        SubCommand::BindingsToggle(cmd) => Ok(Command::BindingsToggle { except: cmd.except }),
         SubCommand::TagView(cmd) => Ok(Command::TagView {
             tags: cmd.tags,
             output: parse_output_specifier(cmd.output),
@@ -740,6 +1817,22 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
             output: parse_output_specifier(cmd.output),
         }),
         SubCommand::TagViewLast(_) => Ok(Command::TagViewLast),
+        SubCommand::TagSetFloat(cmd) => Ok(Command::TagSetFloat {
+            tags: cmd.tags,
+            enabled: !cmd.off,
+        }),
+        SubCommand::TagSetInputSource(cmd) => Ok(Command::TagSetInputSource {
+            tags: cmd.tags,
+            input_source: cmd.input_source,
+        }),
+        SubCommand::TagGetInputSource(cmd) => Ok(Command::TagGetInputSource { tags: cmd.tags }),
+        SubCommand::TagDistribute(cmd) => Ok(Command::TagDistribute {
+            from: cmd.from,
+            to: cmd.to,
+            count: cmd.count,
+            every_other: cmd.every_other,
+            output: parse_output_specifier(cmd.output),
+        }),
         SubCommand::WindowMoveToTag(cmd) => Ok(Command::WindowMoveToTag { tags: cmd.tags }),
         SubCommand::WindowToggleTag(cmd) => Ok(Command::WindowToggleTag { tags: cmd.tags }),
         SubCommand::WindowFocus(cmd) => Ok(Command::WindowFocus {
@@ -748,17 +1841,42 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
         SubCommand::WindowSwap(cmd) => Ok(Command::WindowSwap {
             direction: parse_direction(&cmd.direction)?,
         }),
+        SubCommand::WindowFocusParent(_) => Ok(Command::WindowFocusParent),
+        SubCommand::WindowFocusChild(_) => Ok(Command::WindowFocusChild),
+        SubCommand::WindowFocusId(cmd) => Ok(Command::WindowFocusId { window_id: cmd.id }),
+        SubCommand::AppCycle(cmd) => Ok(Command::AppCycle { app_id: cmd.app_id }),
         SubCommand::WindowToggleFullscreen(_) => Ok(Command::WindowToggleFullscreen),
         SubCommand::WindowToggleFloat(_) => Ok(Command::WindowToggleFloat),
-        SubCommand::WindowClose(_) => Ok(Command::WindowClose),
+        SubCommand::WindowToggleManual(cmd) => {
+            Ok(Command::WindowToggleManual { window_id: cmd.id })
+        }
+        SubCommand::WindowSetUrgent(cmd) => Ok(Command::WindowSetUrgent {
+            window_id: cmd.id,
+            enabled: parse_on_off(&cmd.state)?,
+        }),
+        SubCommand::WindowZoom(_) => Ok(Command::WindowZoom),
+        SubCommand::WindowBring(cmd) => Ok(Command::WindowBring { window_id: cmd.id }),
+        SubCommand::WindowSwapFloat(_) => Ok(Command::WindowSwapFloat),
+        SubCommand::WindowStash(_) => Ok(Command::WindowStash),
+        SubCommand::WindowUnstash(_) => Ok(Command::WindowUnstash),
+        SubCommand::WindowClose(c) => Ok(Command::WindowClose {
+            force_quit_app: c.force_quit_app,
+        }),
+        SubCommand::WindowHideApp(_) => Ok(Command::WindowHideApp),
+        SubCommand::WindowUnhideApp(c) => Ok(Command::WindowUnhideApp { all: c.all }),
         SubCommand::OutputFocus(cmd) => Ok(Command::OutputFocus {
             direction: parse_output_direction(&cmd.direction)?,
         }),
         SubCommand::OutputSend(cmd) => Ok(Command::OutputSend {
             direction: parse_output_direction(&cmd.direction)?,
         }),
+        SubCommand::OutputIgnore(cmd) => Ok(Command::OutputIgnore {
+            output: parse_output_specifier(cmd.output),
+            enabled: parse_on_off(&cmd.state)?,
+        }),
         SubCommand::Retile(cmd) => Ok(Command::Retile {
             output: parse_output_specifier(cmd.output),
+            all: cmd.all,
         }),
         SubCommand::LayoutSetDefault(cmd) => Ok(Command::LayoutSetDefault { layout: cmd.layout }),
         SubCommand::LayoutSet(cmd) => Ok(Command::LayoutSet {
@@ -775,21 +1893,27 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
             cmd: cmd.cmd,
             args: cmd.args,
         }),
+        SubCommand::LayoutAction(cmd) => Ok(Command::LayoutAction {
+            action: parse_layout_action(&cmd.action)?,
+            args: cmd.args,
+        }),
         SubCommand::ListWindows(cmd) => Ok(Command::ListWindows {
             all: cmd.all,
             debug: cmd.debug,
+            why: cmd.why,
         }),
         SubCommand::ListOutputs(_) => Ok(Command::ListOutputs),
         SubCommand::GetState(_) => Ok(Command::GetState),
+        SubCommand::StateDump(_) => Ok(Command::StateDump),
+        SubCommand::Stats(_) => Ok(Command::Stats),
+        SubCommand::LastErrors(_) => Ok(Command::LastErrors),
+        SubCommand::Bench(cmd) => bench_command(&cmd),
         SubCommand::FocusedWindow(_) => Ok(Command::FocusedWindow),
-        SubCommand::Exec(cmd) => Ok(Command::Exec {
-            command: cmd.command,
-            track: cmd.track,
-        }),
-        SubCommand::ExecOrFocus(cmd) => Ok(Command::ExecOrFocus {
-            app_name: cmd.app_name,
-            command: cmd.command,
-        }),
+        SubCommand::FocusedOutput(_) => Ok(Command::FocusedOutput),
+        SubCommand::WindowInfo(cmd) => window_info_command(&cmd),
+        SubCommand::Exec(cmd) => exec_command(cmd),
+        SubCommand::ExecOrFocus(cmd) => exec_or_focus_command(cmd),
+        SubCommand::ListExecs(_) => Ok(Command::ListExecs),
         SubCommand::ExecPath(_) => Ok(Command::GetExecPath),
         SubCommand::SetExecPath(cmd) => Ok(Command::SetExecPath { path: cmd.path }),
         SubCommand::AddExecPath(cmd) => Ok(Command::AddExecPath {
@@ -844,10 +1968,12 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
                 fullscreen_button,
                 minimize_button,
                 zoom_button,
-            );
+            )
+            .with_conditions(cmd.when_tag, cmd.when_output.map(GlobPattern::new))
+            .with_parent_exec(cmd.parent_exec.map(GlobPattern::new));
             let action = parse_rule_action(&cmd.action)?;
             Ok(Command::RuleAdd {
-                rule: WindowRule::new(matcher, action),
+                rule: WindowRule::new(matcher, action).with_dynamic(cmd.dynamic),
             })
         }
         SubCommand::RuleDel(cmd) => {
@@ -898,11 +2024,14 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
                 fullscreen_button,
                 minimize_button,
                 zoom_button,
-            );
+            )
+            .with_conditions(cmd.when_tag, cmd.when_output.map(GlobPattern::new))
+            .with_parent_exec(cmd.parent_exec.map(GlobPattern::new));
             let action = parse_rule_action(&cmd.action)?;
             Ok(Command::RuleDel { matcher, action })
         }
         SubCommand::ListRules(_) => Ok(Command::ListRules),
+        SubCommand::RuleSuggest(_) => Ok(Command::RuleSuggest),
         SubCommand::SetCursorWarp(cmd) => {
             let mode = parse_cursor_warp_mode(&cmd.mode)?;
             Ok(Command::SetCursorWarp { mode })
@@ -914,6 +2043,57 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
             Ok(Command::SetAutoRaise { mode, delay_ms })
         }
         SubCommand::GetAutoRaise(_) => Ok(Command::GetAutoRaise),
+        SubCommand::SetNewWindowTag(cmd) => Ok(Command::SetNewWindowTag {
+            policy: parse_new_window_tag_policy(&cmd.policy)?,
+        }),
+        SubCommand::GetNewWindowTag(_) => Ok(Command::GetNewWindowTag),
+        SubCommand::SetOrphanPolicy(cmd) => Ok(Command::SetOrphanPolicy {
+            policy: parse_orphan_policy(&cmd.policy)?,
+        }),
+        SubCommand::GetOrphanPolicy(_) => Ok(Command::GetOrphanPolicy),
+        SubCommand::SetTagMode(cmd) => Ok(Command::SetTagMode {
+            mode: parse_tag_mode(&cmd.mode)?,
+        }),
+        SubCommand::GetTagMode(_) => Ok(Command::GetTagMode),
+        SubCommand::SetAnimation(cmd) => parse_animation_args(&cmd.args),
+        SubCommand::GetAnimation(_) => Ok(Command::GetAnimation),
+        SubCommand::SetMenubarIndicator(cmd) => Ok(Command::SetMenubarIndicator {
+            enabled: parse_on_off(&cmd.state)?,
+        }),
+        SubCommand::GetMenubarIndicator(_) => Ok(Command::GetMenubarIndicator),
+        SubCommand::SetHideApps(cmd) => Ok(Command::SetHideApps {
+            enabled: parse_on_off(&cmd.state)?,
+        }),
+        SubCommand::GetHideApps(_) => Ok(Command::GetHideApps),
+        SubCommand::SetWindowShadows(cmd) => Ok(Command::SetWindowShadows {
+            enabled: parse_on_off(&cmd.state)?,
+        }),
+        SubCommand::GetWindowShadows(_) => Ok(Command::GetWindowShadows),
+        SubCommand::SetCornerCompensation(cmd) => Ok(Command::SetCornerCompensation { px: cmd.px }),
+        SubCommand::GetCornerCompensation(_) => Ok(Command::GetCornerCompensation),
+        SubCommand::ObserverExclude(cmd) => Ok(Command::ObserverExclude {
+            app_id: GlobPattern::new(cmd.app_id),
+        }),
+        SubCommand::ObserverInclude(cmd) => Ok(Command::ObserverInclude {
+            app_id: GlobPattern::new(cmd.app_id),
+        }),
+        SubCommand::ListObserverExcludes(_) => Ok(Command::ListObserverExcludes),
+        SubCommand::SetGapScale(cmd) => Ok(Command::SetGapScale {
+            mode: parse_gap_scale_mode(&cmd.mode)?,
+        }),
+        SubCommand::GetGapScale(_) => Ok(Command::GetGapScale),
+        SubCommand::SetFocusIncludeFloating(cmd) => Ok(Command::SetFocusIncludeFloating {
+            mode: parse_focus_floating_mode(&cmd.mode)?,
+        }),
+        SubCommand::GetFocusIncludeFloating(_) => Ok(Command::GetFocusIncludeFloating),
+        SubCommand::SetFloatVisibility(cmd) => Ok(Command::SetFloatVisibility {
+            mode: parse_float_visibility(&cmd.mode)?,
+        }),
+        SubCommand::GetFloatVisibility(_) => Ok(Command::GetFloatVisibility),
+        SubCommand::SetRaisePolicy(cmd) => Ok(Command::SetRaisePolicy {
+            policy: parse_raise_policy(&cmd.policy)?,
+        }),
+        SubCommand::GetRaisePolicy(_) => Ok(Command::GetRaisePolicy),
         SubCommand::SetOuterGap(cmd) => {
             if cmd.values.is_empty() {
                 bail!("set-outer-gap requires at least one value");
@@ -927,6 +2107,21 @@ fn to_command(subcmd: SubCommand) -> Result<Command> {
             Ok(Command::SetOuterGap { values: cmd.values })
         }
         SubCommand::GetOuterGap(_) => Ok(Command::GetOuterGap),
+        SubCommand::SetTagChangeHook(cmd) => Ok(Command::SetTagChangeHook {
+            command: cmd.command,
+        }),
+        SubCommand::GetTagChangeHook(_) => Ok(Command::GetTagChangeHook),
+        SubCommand::SetHook(cmd) => Ok(Command::SetHook {
+            event: parse_hook_event(&cmd.event)?,
+            command: cmd.command,
+        }),
+        SubCommand::GetHook(cmd) => Ok(Command::GetHook {
+            event: parse_hook_event(&cmd.event)?,
+        }),
+        SubCommand::ArrangementSave(cmd) => Ok(Command::ArrangementSave { name: cmd.name }),
+        SubCommand::ArrangementRestore(cmd) => Ok(Command::ArrangementRestore { name: cmd.name }),
+        SubCommand::Pause(_) => Ok(Command::Pause),
+        SubCommand::Resume(_) => Ok(Command::Resume),
         SubCommand::Quit(_) => Ok(Command::Quit),
     }
 }
@@ -953,6 +2148,8 @@ fn parse_command(args: &[String]) -> Result<Command> {
             Ok(Command::Bind {
                 key: cmd.key,
                 action: Box::new(action),
+                double: cmd.double,
+                passthrough: cmd.passthrough,
             })
         }
         "unbind" => {
@@ -960,6 +2157,26 @@ fn parse_command(args: &[String]) -> Result<Command> {
             Ok(Command::Unbind { key: cmd.key })
         }
         "list-bindings" => Ok(Command::ListBindings),
+        "trigger" => {
+            let cmd: TriggerCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::TriggerBinding { key: cmd.key })
+        }
+        "set-hotkey-backend" => {
+            let cmd: SetHotkeyBackendCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetHotkeyBackend {
+                backend: parse_hotkey_backend(&cmd.backend)?,
+            })
+        }
+        "get-hotkey-backend" => Ok(Command::GetHotkeyBackend),
+        "bindings-suspend" => {
+            let cmd: BindingsSuspendCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::BindingsSuspend { except: cmd.except })
+        }
+        "bindings-resume" => Ok(Command::BindingsResume),
+        "bindings-toggle" => {
+            let cmd: BindingsToggleCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::BindingsToggle { except: cmd.except })
+        }
         "tag-view" => {
             let cmd: TagViewCmd = from_argh(cmd_name, &cmd_args)?;
             Ok(Command::TagView {
@@ -975,6 +2192,34 @@ fn parse_command(args: &[String]) -> Result<Command> {
             })
         }
         "tag-view-last" => Ok(Command::TagViewLast),
+        "tag-set-float" => {
+            let cmd: TagSetFloatCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::TagSetFloat {
+                tags: cmd.tags,
+                enabled: !cmd.off,
+            })
+        }
+        "tag-set-input-source" => {
+            let cmd: TagSetInputSourceCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::TagSetInputSource {
+                tags: cmd.tags,
+                input_source: cmd.input_source,
+            })
+        }
+        "tag-get-input-source" => {
+            let cmd: TagGetInputSourceCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::TagGetInputSource { tags: cmd.tags })
+        }
+        "tag-distribute" => {
+            let cmd: TagDistributeCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::TagDistribute {
+                from: cmd.from,
+                to: cmd.to,
+                count: cmd.count,
+                every_other: cmd.every_other,
+                output: parse_output_specifier(cmd.output),
+            })
+        }
         "window-move-to-tag" => {
             let cmd: WindowMoveToTagCmd = from_argh(cmd_name, &cmd_args)?;
             Ok(Command::WindowMoveToTag { tags: cmd.tags })
@@ -995,9 +2240,48 @@ fn parse_command(args: &[String]) -> Result<Command> {
                 direction: parse_direction(&cmd.direction)?,
             })
         }
+        "window-focus-parent" => Ok(Command::WindowFocusParent),
+        "window-focus-child" => Ok(Command::WindowFocusChild),
+        "window-focus-id" => {
+            let cmd: WindowFocusIdCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowFocusId { window_id: cmd.id })
+        }
+        "app-cycle" => {
+            let cmd: AppCycleCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::AppCycle { app_id: cmd.app_id })
+        }
         "window-toggle-fullscreen" => Ok(Command::WindowToggleFullscreen),
         "window-toggle-float" => Ok(Command::WindowToggleFloat),
-        "window-close" => Ok(Command::WindowClose),
+        "window-toggle-manual" => {
+            let cmd: WindowToggleManualCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowToggleManual { window_id: cmd.id })
+        }
+        "window-set-urgent" => {
+            let cmd: WindowSetUrgentCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowSetUrgent {
+                window_id: cmd.id,
+                enabled: parse_on_off(&cmd.state)?,
+            })
+        }
+        "window-zoom" => Ok(Command::WindowZoom),
+        "window-bring" => {
+            let cmd: WindowBringCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowBring { window_id: cmd.id })
+        }
+        "window-swap-float" => Ok(Command::WindowSwapFloat),
+        "window-stash" => Ok(Command::WindowStash),
+        "window-unstash" => Ok(Command::WindowUnstash),
+        "window-close" => {
+            let cmd: WindowCloseCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowClose {
+                force_quit_app: cmd.force_quit_app,
+            })
+        }
+        "window-hide-app" => Ok(Command::WindowHideApp),
+        "window-unhide-app" => {
+            let cmd: WindowUnhideAppCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::WindowUnhideApp { all: cmd.all })
+        }
         "output-focus" => {
             let cmd: OutputFocusCmd = from_argh(cmd_name, &cmd_args)?;
             Ok(Command::OutputFocus {
@@ -1010,10 +2294,18 @@ fn parse_command(args: &[String]) -> Result<Command> {
                 direction: parse_output_direction(&cmd.direction)?,
             })
         }
+        "output-ignore" => {
+            let cmd: OutputIgnoreCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::OutputIgnore {
+                output: parse_output_specifier(cmd.output),
+                enabled: parse_on_off(&cmd.state)?,
+            })
+        }
         "retile" => {
             let cmd: RetileCmd = from_argh(cmd_name, &cmd_args)?;
             Ok(Command::Retile {
                 output: parse_output_specifier(cmd.output),
+                all: cmd.all,
             })
         }
         "layout-set-default" => {
@@ -1043,30 +2335,45 @@ fn parse_command(args: &[String]) -> Result<Command> {
                 args: cmd.args,
             })
         }
+        "layout-action" => {
+            let cmd: LayoutActionCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::LayoutAction {
+                action: parse_layout_action(&cmd.action)?,
+                args: cmd.args,
+            })
+        }
         "list-windows" => {
             let cmd: ListWindowsCmd = from_argh(cmd_name, &cmd_args)?;
             Ok(Command::ListWindows {
                 all: cmd.all,
                 debug: cmd.debug,
+                why: cmd.why,
             })
         }
         "list-outputs" => Ok(Command::ListOutputs),
         "get-state" => Ok(Command::GetState),
+        "state-dump" => Ok(Command::StateDump),
+        "stats" => Ok(Command::Stats),
+        "last-errors" => Ok(Command::LastErrors),
+        "bench" => {
+            let cmd: BenchCmd = from_argh(cmd_name, &cmd_args)?;
+            bench_command(&cmd)
+        }
         "focused-window" => Ok(Command::FocusedWindow),
+        "focused-output" => Ok(Command::FocusedOutput),
+        "window-info" => {
+            let cmd: WindowInfoCmd = from_argh(cmd_name, &cmd_args)?;
+            window_info_command(&cmd)
+        }
         "exec" => {
             let cmd: ExecCmd = from_argh(cmd_name, &cmd_args)?;
-            Ok(Command::Exec {
-                command: cmd.command,
-                track: cmd.track,
-            })
+            exec_command(cmd)
         }
         "exec-or-focus" => {
             let cmd: ExecOrFocusCmd = from_argh(cmd_name, &cmd_args)?;
-            Ok(Command::ExecOrFocus {
-                app_name: cmd.app_name,
-                command: cmd.command,
-            })
+            exec_or_focus_command(cmd)
         }
+        "list-execs" => Ok(Command::ListExecs),
         "exec-path" => Ok(Command::GetExecPath),
         "set-exec-path" => {
             let cmd: SetExecPathCmd = from_argh(cmd_name, &cmd_args)?;
@@ -1128,10 +2435,12 @@ fn parse_command(args: &[String]) -> Result<Command> {
                 fullscreen_button,
                 minimize_button,
                 zoom_button,
-            );
+            )
+            .with_conditions(cmd.when_tag, cmd.when_output.map(GlobPattern::new))
+            .with_parent_exec(cmd.parent_exec.map(GlobPattern::new));
             let action = parse_rule_action(&cmd.action)?;
             Ok(Command::RuleAdd {
-                rule: WindowRule::new(matcher, action),
+                rule: WindowRule::new(matcher, action).with_dynamic(cmd.dynamic),
             })
         }
         "rule-del" => {
@@ -1183,11 +2492,14 @@ fn parse_command(args: &[String]) -> Result<Command> {
                 fullscreen_button,
                 minimize_button,
                 zoom_button,
-            );
+            )
+            .with_conditions(cmd.when_tag, cmd.when_output.map(GlobPattern::new))
+            .with_parent_exec(cmd.parent_exec.map(GlobPattern::new));
             let action = parse_rule_action(&cmd.action)?;
             Ok(Command::RuleDel { matcher, action })
         }
         "list-rules" => Ok(Command::ListRules),
+        "rule-suggest" => Ok(Command::RuleSuggest),
         "set-cursor-warp" => {
             let cmd: SetCursorWarpCmd = from_argh(cmd_name, &cmd_args)?;
             let mode = parse_cursor_warp_mode(&cmd.mode)?;
@@ -1201,6 +2513,99 @@ fn parse_command(args: &[String]) -> Result<Command> {
             Ok(Command::SetAutoRaise { mode, delay_ms })
         }
         "get-auto-raise" => Ok(Command::GetAutoRaise),
+        "set-new-window-tag" => {
+            let cmd: SetNewWindowTagCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetNewWindowTag {
+                policy: parse_new_window_tag_policy(&cmd.policy)?,
+            })
+        }
+        "get-new-window-tag" => Ok(Command::GetNewWindowTag),
+        "set-orphan-policy" => {
+            let cmd: SetOrphanPolicyCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetOrphanPolicy {
+                policy: parse_orphan_policy(&cmd.policy)?,
+            })
+        }
+        "get-orphan-policy" => Ok(Command::GetOrphanPolicy),
+        "set-tag-mode" => {
+            let cmd: SetTagModeCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetTagMode {
+                mode: parse_tag_mode(&cmd.mode)?,
+            })
+        }
+        "get-tag-mode" => Ok(Command::GetTagMode),
+        "set-animation" => {
+            let cmd: SetAnimationCmd = from_argh(cmd_name, &cmd_args)?;
+            parse_animation_args(&cmd.args)
+        }
+        "get-animation" => Ok(Command::GetAnimation),
+        "set-menubar-indicator" => {
+            let cmd: SetMenubarIndicatorCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetMenubarIndicator {
+                enabled: parse_on_off(&cmd.state)?,
+            })
+        }
+        "get-menubar-indicator" => Ok(Command::GetMenubarIndicator),
+        "set-hide-apps" => {
+            let cmd: SetHideAppsCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetHideApps {
+                enabled: parse_on_off(&cmd.state)?,
+            })
+        }
+        "get-hide-apps" => Ok(Command::GetHideApps),
+        "set-window-shadows" => {
+            let cmd: SetWindowShadowsCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetWindowShadows {
+                enabled: parse_on_off(&cmd.state)?,
+            })
+        }
+        "get-window-shadows" => Ok(Command::GetWindowShadows),
+        "set-corner-compensation" => {
+            let cmd: SetCornerCompensationCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetCornerCompensation { px: cmd.px })
+        }
+        "get-corner-compensation" => Ok(Command::GetCornerCompensation),
+        "observer-exclude" => {
+            let cmd: ObserverExcludeCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::ObserverExclude {
+                app_id: GlobPattern::new(cmd.app_id),
+            })
+        }
+        "observer-include" => {
+            let cmd: ObserverIncludeCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::ObserverInclude {
+                app_id: GlobPattern::new(cmd.app_id),
+            })
+        }
+        "list-observer-excludes" => Ok(Command::ListObserverExcludes),
+        "set-gap-scale" => {
+            let cmd: SetGapScaleCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetGapScale {
+                mode: parse_gap_scale_mode(&cmd.mode)?,
+            })
+        }
+        "get-gap-scale" => Ok(Command::GetGapScale),
+        "set-focus-include-floating" => {
+            let cmd: SetFocusIncludeFloatingCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetFocusIncludeFloating {
+                mode: parse_focus_floating_mode(&cmd.mode)?,
+            })
+        }
+        "get-focus-include-floating" => Ok(Command::GetFocusIncludeFloating),
+        "set-float-visibility" => {
+            let cmd: SetFloatVisibilityCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetFloatVisibility {
+                mode: parse_float_visibility(&cmd.mode)?,
+            })
+        }
+        "get-float-visibility" => Ok(Command::GetFloatVisibility),
+        "set-raise-policy" => {
+            let cmd: SetRaisePolicyCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetRaisePolicy {
+                policy: parse_raise_policy(&cmd.policy)?,
+            })
+        }
+        "get-raise-policy" => Ok(Command::GetRaisePolicy),
         "set-outer-gap" => {
             let cmd: SetOuterGapCmd = from_argh(cmd_name, &cmd_args)?;
             if cmd.values.is_empty() {
@@ -1215,6 +2620,36 @@ fn parse_command(args: &[String]) -> Result<Command> {
             Ok(Command::SetOuterGap { values: cmd.values })
         }
         "get-outer-gap" => Ok(Command::GetOuterGap),
+        "set-tag-change-hook" => {
+            let cmd: SetTagChangeHookCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetTagChangeHook {
+                command: cmd.command,
+            })
+        }
+        "get-tag-change-hook" => Ok(Command::GetTagChangeHook),
+        "set-hook" => {
+            let cmd: SetHookCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::SetHook {
+                event: parse_hook_event(&cmd.event)?,
+                command: cmd.command,
+            })
+        }
+        "get-hook" => {
+            let cmd: GetHookCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::GetHook {
+                event: parse_hook_event(&cmd.event)?,
+            })
+        }
+        "arrangement-save" => {
+            let cmd: ArrangementSaveCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::ArrangementSave { name: cmd.name })
+        }
+        "arrangement-restore" => {
+            let cmd: ArrangementRestoreCmd = from_argh(cmd_name, &cmd_args)?;
+            Ok(Command::ArrangementRestore { name: cmd.name })
+        }
+        "pause" => Ok(Command::Pause),
+        "resume" => Ok(Command::Resume),
         "quit" => Ok(Command::Quit),
         _ => bail!("Unknown command: {}", cmd_name),
     }
@@ -1263,6 +2698,209 @@ fn parse_auto_raise_mode(s: &str) -> Result<AutoRaiseMode> {
     }
 }
 
+fn parse_new_window_tag_policy(s: &str) -> Result<NewWindowTagPolicy> {
+    match s.to_lowercase().as_str() {
+        "current" => Ok(NewWindowTagPolicy::Current),
+        "emptiest" => Ok(NewWindowTagPolicy::Emptiest),
+        "app-sticky" => Ok(NewWindowTagPolicy::AppSticky),
+        _ => bail!(
+            "Unknown new window tag policy: {} (use current, emptiest, app-sticky)",
+            s
+        ),
+    }
+}
+
+fn parse_orphan_policy(s: &str) -> Result<OrphanPolicy> {
+    match s.to_lowercase().as_str() {
+        "main" => Ok(OrphanPolicy::Main),
+        "spread" => Ok(OrphanPolicy::Spread),
+        "by-tag" => Ok(OrphanPolicy::ByTag),
+        _ => bail!("Unknown orphan policy: {} (use main, spread, by-tag)", s),
+    }
+}
+
+fn parse_tag_mode(s: &str) -> Result<TagMode> {
+    match s.to_lowercase().as_str() {
+        "per-output" => Ok(TagMode::PerOutput),
+        "global" => Ok(TagMode::Global),
+        _ => bail!("Unknown tag mode: {} (use per-output, global)", s),
+    }
+}
+
+fn parse_layout_action(s: &str) -> Result<LayoutAction> {
+    LayoutAction::from_name(&s.to_lowercase()).ok_or_else(|| {
+        let names: Vec<_> = LayoutAction::ALL.iter().map(|a| a.name()).collect();
+        anyhow::anyhow!("Unknown layout action: {} (use {})", s, names.join(", "))
+    })
+}
+
+fn parse_animation_args(args: &[String]) -> Result<Command> {
+    let mut duration_ms = None;
+    let mut easing = AnimationEasing::default();
+    let mut iter = args.iter();
+    while let Some(key) = iter.next() {
+        let Some(value) = iter.next() else {
+            bail!("set-animation: missing value for '{}'", key);
+        };
+        match key.as_str() {
+            "duration" => {
+                let ms = value.strip_suffix("ms").unwrap_or(value);
+                duration_ms = Some(
+                    ms.parse::<u32>()
+                        .map_err(|_| anyhow::anyhow!("Invalid duration: {}", value))?,
+                );
+            }
+            "easing" => easing = parse_animation_easing(value)?,
+            _ => bail!(
+                "set-animation: unknown setting '{}' (use duration, easing)",
+                key
+            ),
+        }
+    }
+    let Some(duration_ms) = duration_ms else {
+        bail!("set-animation requires duration (e.g. set-animation duration 120ms)");
+    };
+    Ok(Command::SetAnimation {
+        duration_ms,
+        easing,
+    })
+}
+
+fn parse_animation_easing(s: &str) -> Result<AnimationEasing> {
+    match s.to_lowercase().as_str() {
+        "linear" => Ok(AnimationEasing::Linear),
+        "ease-out" => Ok(AnimationEasing::EaseOut),
+        "ease-in-out" => Ok(AnimationEasing::EaseInOut),
+        _ => bail!("Unknown easing: {} (use linear, ease-out, ease-in-out)", s),
+    }
+}
+
+fn window_info_command(cmd: &WindowInfoCmd) -> Result<Command> {
+    if cmd.focused && cmd.id.is_some() {
+        bail!("window-info: use either --id or --focused, not both");
+    }
+    Ok(Command::WindowInfo { window_id: cmd.id })
+}
+
+fn bench_command(cmd: &BenchCmd) -> Result<Command> {
+    match cmd.target.as_str() {
+        "ax" => {
+            let iterations = cmd.iterations.unwrap_or(5);
+            if !(1..=app::MAX_BENCH_ITERATIONS).contains(&iterations) {
+                bail!(
+                    "Invalid iterations: {} (use 1 to {})",
+                    iterations,
+                    app::MAX_BENCH_ITERATIONS
+                );
+            }
+            Ok(Command::BenchAx { iterations })
+        }
+        _ => bail!("Unknown bench target: {} (use ax)", cmd.target),
+    }
+}
+
+fn exec_or_focus_command(cmd: ExecOrFocusCmd) -> Result<Command> {
+    if cmd.app_name.is_none() && cmd.app_id.is_none() && cmd.title.is_none() {
+        bail!("exec-or-focus requires --app-name, --app-id or --title");
+    }
+    Ok(Command::ExecOrFocus {
+        app_name: cmd.app_name,
+        app_id: cmd.app_id.map(GlobPattern::new),
+        title: cmd.title.map(GlobPattern::new),
+        command: cmd.command,
+        bring: cmd.bring,
+    })
+}
+
+fn exec_command(cmd: ExecCmd) -> Result<Command> {
+    let mut command = cmd.command.into_iter();
+    let Some(program) = command.next() else {
+        bail!("exec requires a command");
+    };
+    let args: Vec<String> = command.collect();
+    if cmd.no_shell {
+        return Ok(Command::Exec {
+            command: program,
+            track: cmd.track,
+            args: Some(args),
+            unique: cmd.unique,
+        });
+    }
+    if !args.is_empty() {
+        bail!("exec takes a single shell command (use --no-shell to pass arguments)");
+    }
+    Ok(Command::Exec {
+        command: program,
+        track: cmd.track,
+        args: None,
+        unique: cmd.unique,
+    })
+}
+
+fn parse_hotkey_backend(s: &str) -> Result<HotkeyBackend> {
+    match s.to_lowercase().as_str() {
+        "internal" => Ok(HotkeyBackend::Internal),
+        "external" => Ok(HotkeyBackend::External),
+        _ => bail!("Unknown hotkey backend: {} (use internal, external)", s),
+    }
+}
+
+fn parse_gap_scale_mode(s: &str) -> Result<GapScaleMode> {
+    match s.to_lowercase().as_str() {
+        "off" => Ok(GapScaleMode::Off),
+        "auto" => Ok(GapScaleMode::Auto),
+        _ => bail!("Unknown gap scale mode: {} (use off, auto)", s),
+    }
+}
+
+fn parse_float_visibility(s: &str) -> Result<FloatVisibility> {
+    match s.to_lowercase().as_str() {
+        "tagged" => Ok(FloatVisibility::Tagged),
+        "always" => Ok(FloatVisibility::Always),
+        _ => bail!("Unknown float visibility: {} (use tagged, always)", s),
+    }
+}
+
+fn parse_focus_floating_mode(s: &str) -> Result<FocusFloatingMode> {
+    match s.to_lowercase().as_str() {
+        "on" => Ok(FocusFloatingMode::On),
+        "off" => Ok(FocusFloatingMode::Off),
+        "tiled-first" => Ok(FocusFloatingMode::TiledFirst),
+        _ => bail!(
+            "Unknown focus floating mode: {} (use on, off, tiled-first)",
+            s
+        ),
+    }
+}
+
+fn parse_raise_policy(s: &str) -> Result<RaisePolicy> {
+    match s.to_lowercase().as_str() {
+        "none" => Ok(RaisePolicy::None),
+        "focused-top" => Ok(RaisePolicy::FocusedTop),
+        "main-top" => Ok(RaisePolicy::MainTop),
+        _ => bail!(
+            "Unknown raise policy: {} (use none, focused-top, main-top)",
+            s
+        ),
+    }
+}
+
+fn parse_hook_event(s: &str) -> Result<HookEvent> {
+    match s.to_lowercase().as_str() {
+        "pre-retile" => Ok(HookEvent::PreRetile),
+        "post-retile" => Ok(HookEvent::PostRetile),
+        _ => bail!("Unknown hook event: {} (use pre-retile, post-retile)", s),
+    }
+}
+
+fn parse_on_off(s: &str) -> Result<bool> {
+    match s.to_lowercase().as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => bail!("Unknown state: {} (use on, off)", s),
+    }
+}
+
 fn parse_window_level(s: &str) -> Result<WindowLevel> {
     match s.to_lowercase().as_str() {
         "normal" => Ok(WindowLevel::Named(WindowLevelName::Normal)),
@@ -1341,8 +2979,21 @@ fn parse_rule_action(args: &[String]) -> Result<RuleAction> {
             Ok(RuleAction::Output { output })
         }
         "position" => {
+            if let [spec] = action_args {
+                let Some((anchor, x, y)) = Anchor::parse_position(spec) else {
+                    bail!(
+                        "Invalid position: {} (use x y, or an anchor like center or top-right+20+20)",
+                        spec
+                    );
+                };
+                return Ok(RuleAction::Position {
+                    x,
+                    y,
+                    anchor: Some(anchor),
+                });
+            }
             if action_args.len() < 2 {
-                bail!("position action requires x and y arguments");
+                bail!("position action requires x and y arguments, or an anchor");
             }
             let x = action_args[0]
                 .parse::<i32>()
@@ -1350,27 +3001,81 @@ fn parse_rule_action(args: &[String]) -> Result<RuleAction> {
             let y = action_args[1]
                 .parse::<i32>()
                 .map_err(|_| anyhow::anyhow!("Invalid y position: {}", action_args[1]))?;
-            Ok(RuleAction::Position { x, y })
+            Ok(RuleAction::Position { x, y, anchor: None })
         }
+        // Shorthand for `position center`
+        "center" => Ok(RuleAction::Position {
+            x: 0,
+            y: 0,
+            anchor: Some(Anchor::Center),
+        }),
         "dimensions" => {
             if action_args.len() < 2 {
                 bail!("dimensions action requires width and height arguments");
             }
-            let width = action_args[0]
-                .parse::<u32>()
-                .map_err(|_| anyhow::anyhow!("Invalid width: {}", action_args[0]))?;
-            let height = action_args[1]
-                .parse::<u32>()
-                .map_err(|_| anyhow::anyhow!("Invalid height: {}", action_args[1]))?;
+            let width = RuleSize::parse(&action_args[0])
+                .ok_or_else(|| anyhow::anyhow!("Invalid width: {}", action_args[0]))?;
+            let height = RuleSize::parse(&action_args[1])
+                .ok_or_else(|| anyhow::anyhow!("Invalid height: {}", action_args[1]))?;
             Ok(RuleAction::Dimensions { width, height })
         }
+        "close-behavior" => {
+            if action_args.is_empty() {
+                bail!("close-behavior action requires close, hide, or quit");
+            }
+            let behavior = match action_args[0].to_lowercase().as_str() {
+                "close" => CloseBehavior::Close,
+                "hide" => CloseBehavior::Hide,
+                "quit" => CloseBehavior::Quit,
+                other => bail!("Invalid close behavior: {} (use close, hide, quit)", other),
+            };
+            Ok(RuleAction::CloseBehavior { behavior })
+        }
+        "no-manage-resize" => Ok(RuleAction::NoManageResize),
+        "throttle" => {
+            if action_args.is_empty() {
+                bail!("throttle action requires an interval (e.g. 500ms, 1s)");
+            }
+            let ms = parse_interval_ms(&action_args[0])
+                .ok_or_else(|| anyhow::anyhow!("Invalid interval: {}", action_args[0]))?;
+            Ok(RuleAction::Throttle { ms })
+        }
+        "opacity" => {
+            if action_args.is_empty() {
+                bail!("opacity action requires a value between 0 and 1 (e.g. 0.9)");
+            }
+            let percent = action_args[0]
+                .parse::<f32>()
+                .ok()
+                .filter(|v| *v > 0.0 && *v <= 1.0)
+                .map(|v| (v * 100.0).round() as u32)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Invalid opacity: {} (use a value above 0 and up to 1)",
+                        action_args[0]
+                    )
+                })?;
+            Ok(RuleAction::Opacity { percent })
+        }
         _ => bail!(
-            "Unknown rule action: {} (use ignore, float, no-float, tags, output, position, dimensions)",
+            "Unknown rule action: {} (use ignore, float, no-float, tags, output, position, dimensions, close-behavior, no-manage-resize, throttle, opacity)",
             action_name
         ),
     }
 }
 
+/// Parse an interval like `500ms`, `1s` or `500` (milliseconds)
+fn parse_interval_ms(s: &str) -> Option<u32> {
+    let s = s.trim().to_lowercase();
+    if let Some(ms) = s.strip_suffix("ms") {
+        ms.parse().ok()
+    } else if let Some(secs) = s.strip_suffix('s') {
+        secs.parse::<u32>().ok()?.checked_mul(1000)
+    } else {
+        s.parse().ok()
+    }
+}
+
 fn parse_event_filter(s: &str) -> EventFilter {
     let mut filter = EventFilter::default();
     for part in s.split(',') {
@@ -1380,6 +3085,7 @@ fn parse_event_filter(s: &str) -> EventFilter {
             "display" => filter.display = true,
             "tags" => filter.tags = true,
             "layout" => filter.layout = true,
+            "error" => filter.error = true,
             _ => {}
         }
     }
@@ -1,10 +1,22 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use core_foundation_sys::date::CFAbsoluteTimeGetCurrent;
+use core_foundation_sys::runloop::{CFRunLoopTimerRef, CFRunLoopTimerSetNextFireDate};
 use core_graphics::geometry::{CGPoint, CGSize};
 
+use crate::animation::{Animator, FrameUpdate};
 use crate::core::{Rect, WindowMove};
 use crate::macos::{
-    activate_application, get_frontmost_app_pid, AXUIElement, DisplayId, DisplayInfo, WindowInfo,
+    activate_application, get_frontmost_app_pid, hide_application, quit_application,
+    send_close_shortcut, unhide_application, AXUIElement, DisplayId, DisplayInfo, WindowInfo,
+    AX_ERROR_CANNOT_COMPLETE,
 };
-use yashiki_ipc::{ButtonInfo, ExtendedWindowAttributes, WindowGeometry};
+use yashiki_ipc::{AnimationEasing, ButtonInfo, ExtendedWindowAttributes, WindowGeometry};
 
 pub struct FocusedWindowInfo {
     pub window_id: u32,
@@ -30,6 +42,13 @@ pub trait WindowSystem {
     /// Used for window-level checks during transitions (e.g., fullscreen).
     /// Returns true if the window is found via AX API.
     fn window_exists_in_ax(&self, window_id: u32, pid: i32) -> bool;
+    /// Windows of the app in native macOS fullscreen (AXFullScreen), queried in one pass.
+    fn get_native_fullscreen_windows(&self, pid: i32) -> HashSet<u32>;
+    /// Window a dialog belongs to: its AX parent when that is a window,
+    /// otherwise the app's main window for dialog subroles.
+    fn get_parent_window(&self, window_id: u32, pid: i32) -> Option<u32>;
+    /// Command names of the process's ancestors, nearest first
+    fn get_process_ancestors(&self, pid: i32) -> Vec<String>;
 }
 
 /// macOS implementation of WindowSystem
@@ -90,6 +109,7 @@ impl WindowSystem for MacOSWindowSystem {
                     fullscreen_button: ButtonInfo::new(fullscreen_exists, fullscreen_enabled),
                     minimize_button: ButtonInfo::new(minimize_exists, minimize_enabled),
                     zoom_button: ButtonInfo::new(zoom_exists, zoom_enabled),
+                    ..Default::default()
                 };
             }
         }
@@ -129,6 +149,46 @@ impl WindowSystem for MacOSWindowSystem {
             }
         }
     }
+
+    fn get_native_fullscreen_windows(&self, pid: i32) -> HashSet<u32> {
+        let app = AXUIElement::application(pid);
+        app.windows()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|w| w.is_full_screen().unwrap_or(false))
+            .filter_map(|w| w.window_id())
+            .collect()
+    }
+
+    fn get_parent_window(&self, window_id: u32, pid: i32) -> Option<u32> {
+        let app = AXUIElement::application(pid);
+        let ax_win = app
+            .windows()
+            .ok()?
+            .into_iter()
+            .find(|w| w.window_id() == Some(window_id))?;
+
+        // Sheets and some panels report their owning window as AX parent
+        if let Ok(parent) = ax_win.parent() {
+            if parent.role().ok().as_deref() == Some("AXWindow") {
+                return parent.window_id().filter(|&id| id != window_id);
+            }
+        }
+
+        // Other dialogs hang off the application; they belong to its main window
+        let subrole = ax_win.subrole().ok()?;
+        if subrole != "AXDialog" && subrole != "AXSystemDialog" {
+            return None;
+        }
+        app.main_window()
+            .ok()?
+            .window_id()
+            .filter(|&id| id != window_id)
+    }
+
+    fn get_process_ancestors(&self, pid: i32) -> Vec<String> {
+        crate::macos::process_ancestors(pid)
+    }
 }
 
 impl Default for MacOSWindowSystem {
@@ -141,83 +201,269 @@ impl Default for MacOSWindowSystem {
 /// This abstraction allows mocking in tests.
 pub trait WindowManipulator {
     fn apply_window_moves(&self, moves: &[WindowMove]);
-    fn apply_layout(&self, display_id: DisplayId, frame: &Rect, geometries: &[WindowGeometry]);
+    /// Move windows into their layout frames, returning a message for each one that failed
+    fn apply_layout(
+        &self,
+        display_id: DisplayId,
+        frame: &Rect,
+        geometries: &[WindowGeometry],
+    ) -> Vec<String>;
     fn focus_window(&self, window_id: u32, pid: i32);
     fn move_window_to_position(&self, window_id: u32, pid: i32, x: i32, y: i32);
     fn set_window_dimensions(&self, window_id: u32, pid: i32, width: u32, height: u32);
     fn set_window_frame(&self, window_id: u32, pid: i32, x: i32, y: i32, width: u32, height: u32);
     fn close_window(&self, window_id: u32, pid: i32);
-    fn exec_command(&self, command: &str, path: &str) -> Result<(), String>;
-    fn exec_command_tracked(&self, command: &str, path: &str) -> Result<u32, String>;
+    fn hide_app(&self, pid: i32);
+    fn unhide_app(&self, pid: i32);
+    fn quit_app(&self, pid: i32, force: bool);
+    /// Spawn a command and return its pid. The child is reaped in the background once it exits.
+    fn exec_command(
+        &self,
+        command: &str,
+        args: Option<&[String]>,
+        path: &str,
+        env: &[(String, String)],
+    ) -> Result<u32, String>;
+    /// Spawn a shell command with `input` on its stdin, without waiting for it
+    fn exec_hook(
+        &self,
+        command: &str,
+        path: &str,
+        env: &[(String, String)],
+        input: &str,
+    ) -> Result<u32, String>;
+    /// Call `flush_retile_hooks` once `delay` has passed
+    fn schedule_hook_flush(&self, delay: Duration);
+    /// Whether a child started by `exec_command` hasn't exited yet
+    fn is_process_alive(&self, pid: u32) -> bool;
     fn terminate_process(&self, pid: u32);
     fn warp_cursor(&self, x: i32, y: i32);
+    /// Whether the left mouse button is held, i.e. a drag may be in progress
+    fn is_left_mouse_down(&self) -> bool;
+    fn select_input_source(&self, id: &str) -> Result<(), String>;
+    /// Show or remove a window's shadow (best-effort, private API)
+    fn set_window_shadow(&self, window_id: u32, enabled: bool) -> Result<(), String>;
+    /// Set a window's opacity from 0.0 to 1.0 (best-effort, private API)
+    fn set_window_alpha(&self, window_id: u32, alpha: f32) -> Result<(), String>;
+    fn set_animation(&self, duration_ms: u32, easing: AnimationEasing);
+}
+
+/// Far-future offset used to park run loop timers while idle
+pub const TIMER_PARKED: f64 = 1.0e9;
+
+/// How long an app that timed out is skipped before it is tried again
+const UNRESPONSIVE_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Consecutive timeouts before an app is considered unresponsive, so one slow reply
+/// doesn't skip a busy app for the whole backoff
+const UNRESPONSIVE_AFTER_FAILURES: u32 = 3;
+
+/// Consecutive AX timeouts per app, keyed by pid with the time of the last one
+#[derive(Default)]
+struct UnresponsiveApps {
+    failures: HashMap<i32, (u32, Instant)>,
+}
+
+impl UnresponsiveApps {
+    /// Whether the app timed out often enough, and recently enough, to be skipped
+    fn is_backed_off(&self, pid: i32, now: Instant) -> bool {
+        self.failures.get(&pid).is_some_and(|&(count, at)| {
+            count >= UNRESPONSIVE_AFTER_FAILURES && now.duration_since(at) < UNRESPONSIVE_BACKOFF
+        })
+    }
+
+    fn record_failure(&mut self, pid: i32, now: Instant) {
+        let count = self.failures.get(&pid).map_or(0, |&(count, _)| count);
+        self.failures.insert(pid, (count + 1, now));
+    }
+
+    fn record_success(&mut self, pid: i32) {
+        self.failures.remove(&pid);
+    }
 }
 
 /// macOS implementation of WindowManipulator
-pub struct MacOSWindowManipulator;
+pub struct MacOSWindowManipulator {
+    animator: RefCell<Animator>,
+    animation_timer: Arc<AtomicPtr<c_void>>,
+    hook_timer: Arc<AtomicPtr<c_void>>,
+    /// Apps whose AX calls timed out
+    unresponsive: RefCell<UnresponsiveApps>,
+}
 
-impl WindowManipulator for MacOSWindowManipulator {
-    fn apply_window_moves(&self, moves: &[WindowMove]) {
-        use std::collections::HashMap;
+impl MacOSWindowManipulator {
+    pub fn new(
+        animation_timer: Arc<AtomicPtr<c_void>>,
+        hook_timer: Arc<AtomicPtr<c_void>>,
+    ) -> Self {
+        Self {
+            animator: RefCell::new(Animator::default()),
+            animation_timer,
+            hook_timer,
+            unresponsive: RefCell::new(UnresponsiveApps::default()),
+        }
+    }
 
-        let mut by_pid: HashMap<i32, Vec<&WindowMove>> = HashMap::new();
-        for m in moves {
-            by_pid.entry(m.pid).or_default().push(m);
+    /// Get the AX windows of an app, skipping apps that recently stopped responding
+    /// so one hung app cannot stall every effect for the messaging timeout.
+    fn app_windows(&self, pid: i32) -> Option<Vec<AXUIElement>> {
+        if self
+            .unresponsive
+            .borrow()
+            .is_backed_off(pid, Instant::now())
+        {
+            tracing::debug!("App (pid {}) not responding, skipped", pid);
+            return None;
         }
 
-        for (pid, pid_moves) in by_pid {
-            let app = AXUIElement::application(pid);
-            let ax_windows = match app.windows() {
-                Ok(w) => w,
-                Err(e) => {
-                    tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
+        match AXUIElement::application(pid).windows() {
+            Ok(w) => {
+                self.unresponsive.borrow_mut().record_success(pid);
+                Some(w)
+            }
+            Err(AX_ERROR_CANNOT_COMPLETE) => {
+                tracing::warn!("App (pid {}) not responding", pid);
+                self.unresponsive
+                    .borrow_mut()
+                    .record_failure(pid, Instant::now());
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
+                None
+            }
+        }
+    }
+
+    /// Whether window animations are still running
+    pub fn is_animating(&self) -> bool {
+        self.animator.borrow().is_active()
+    }
+
+    /// Advance running animations. Called from the animation CFRunLoopTimer.
+    pub fn step_animations(&self) {
+        let updates = self.animator.borrow_mut().step(Instant::now());
+        self.apply_frame_updates(&updates);
+        if !self.animator.borrow().is_active() {
+            self.set_animation_timer_fire_date(TIMER_PARKED);
+        }
+    }
+
+    fn start_animation(&self, window_id: u32, pid: i32, from: Rect, to: Rect) {
+        let was_active = self.animator.borrow().is_active();
+        self.animator
+            .borrow_mut()
+            .animate(window_id, pid, from, to, Instant::now());
+        if !was_active && self.animator.borrow().is_active() {
+            self.set_animation_timer_fire_date(0.0);
+        }
+    }
+
+    fn set_animation_timer_fire_date(&self, offset: f64) {
+        let timer = self.animation_timer.load(Ordering::Acquire) as CFRunLoopTimerRef;
+        if !timer.is_null() {
+            unsafe { CFRunLoopTimerSetNextFireDate(timer, CFAbsoluteTimeGetCurrent() + offset) };
+        }
+    }
+
+    fn apply_frame_updates(&self, updates: &[FrameUpdate]) {
+        let mut by_pid: HashMap<i32, Vec<&FrameUpdate>> = HashMap::new();
+        for u in updates {
+            by_pid.entry(u.pid).or_default().push(u);
+        }
+
+        for (pid, pid_updates) in by_pid {
+            let Some(ax_windows) = self.app_windows(pid) else {
+                continue;
+            };
+            for u in pid_updates {
+                let Some(ax_win) = ax_windows
+                    .iter()
+                    .find(|w| w.window_id() == Some(u.window_id))
+                else {
                     continue;
+                };
+                let _ = ax_win.set_position(CGPoint::new(u.frame.x as f64, u.frame.y as f64));
+                if u.resize {
+                    let _ =
+                        ax_win.set_size(CGSize::new(u.frame.width as f64, u.frame.height as f64));
                 }
+            }
+        }
+    }
+
+    fn current_frame(ax_win: &AXUIElement) -> Option<Rect> {
+        let pos = ax_win.position().ok()?;
+        let size = ax_win.size().ok()?;
+        Some(Rect {
+            x: pos.x as i32,
+            y: pos.y as i32,
+            width: size.width as u32,
+            height: size.height as u32,
+        })
+    }
+}
+
+impl WindowManipulator for MacOSWindowManipulator {
+    fn apply_window_moves(&self, moves: &[WindowMove]) {
+        // Apply in the given order (shows before hides), fetching each app's windows once
+        let mut ax_windows_by_pid: HashMap<i32, Option<Vec<AXUIElement>>> = HashMap::new();
+
+        for m in moves {
+            let Some(ax_windows) = ax_windows_by_pid
+                .entry(m.pid)
+                .or_insert_with(|| self.app_windows(m.pid))
+            else {
+                continue;
             };
 
-            for m in pid_moves {
-                let mut found = false;
-                for ax_win in &ax_windows {
-                    if let Some(wid) = ax_win.window_id() {
-                        if wid == m.window_id {
-                            let new_pos = CGPoint::new(m.new_x as f64, m.new_y as f64);
-                            if let Err(e) = ax_win.set_position(new_pos) {
-                                tracing::warn!(
-                                    "Failed to move window (id={}, pid={}, to=({}, {})): {}",
-                                    m.window_id,
-                                    m.pid,
-                                    m.new_x,
-                                    m.new_y,
-                                    e
-                                );
-                            } else {
-                                tracing::debug!(
-                                    "Moved window (id={}, pid={}) to ({}, {})",
-                                    m.window_id,
-                                    m.pid,
-                                    m.new_x,
-                                    m.new_y
-                                );
-                            }
-                            found = true;
-                            break;
+            let mut found = false;
+            for ax_win in ax_windows.iter() {
+                if let Some(wid) = ax_win.window_id() {
+                    if wid == m.window_id {
+                        found = true;
+                        // Hides and shows are never animated: sync and rehide checks would
+                        // read the in-between frames as the user moving the window
+                        self.animator.borrow_mut().cancel(m.window_id);
+                        let new_pos = CGPoint::new(m.new_x as f64, m.new_y as f64);
+                        if let Err(e) = ax_win.set_position(new_pos) {
+                            tracing::warn!(
+                                "Failed to move window (id={}, pid={}, to=({}, {})): {}",
+                                m.window_id,
+                                m.pid,
+                                m.new_x,
+                                m.new_y,
+                                e
+                            );
+                        } else {
+                            tracing::debug!(
+                                "Moved window (id={}, pid={}) to ({}, {})",
+                                m.window_id,
+                                m.pid,
+                                m.new_x,
+                                m.new_y
+                            );
                         }
+                        break;
                     }
                 }
-                if !found {
-                    tracing::warn!(
-                        "Could not find AX window for id {} (pid {})",
-                        m.window_id,
-                        m.pid
-                    );
-                }
+            }
+            if !found {
+                tracing::warn!(
+                    "Could not find AX window for id {} (pid {})",
+                    m.window_id,
+                    m.pid
+                );
             }
         }
     }
 
-    fn apply_layout(&self, display_id: DisplayId, frame: &Rect, geometries: &[WindowGeometry]) {
-        use std::collections::HashMap;
-
+    fn apply_layout(
+        &self,
+        display_id: DisplayId,
+        frame: &Rect,
+        geometries: &[WindowGeometry],
+    ) -> Vec<String> {
         let offset_x = frame.x;
         let offset_y = frame.y;
 
@@ -238,14 +484,13 @@ impl WindowManipulator for MacOSWindowManipulator {
             }
         }
 
+        // Windows the layout engine wants stacked, as (raise_order, window_id, element)
+        let mut raises: Vec<(u32, u32, AXUIElement)> = Vec::new();
+        let mut failures = Vec::new();
+
         for (pid, windows) in by_pid {
-            let app = AXUIElement::application(pid);
-            let ax_windows = match app.windows() {
-                Ok(w) => w,
-                Err(e) => {
-                    tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                    continue;
-                }
+            let Some(ax_windows) = self.app_windows(pid) else {
+                continue;
             };
 
             for (window_id, geom) in windows {
@@ -255,22 +500,49 @@ impl WindowManipulator for MacOSWindowManipulator {
                         if wid == window_id {
                             let new_x = geom.x + offset_x;
                             let new_y = geom.y + offset_y;
+                            found = true;
+
+                            if let Some(order) = geom.raise_order.filter(|_| !geom.hidden) {
+                                raises.push((order, window_id, ax_win.clone()));
+                            }
+
+                            if self.animator.borrow().is_enabled() {
+                                if let Some(from) = Self::current_frame(ax_win) {
+                                    let to = Rect {
+                                        x: new_x,
+                                        y: new_y,
+                                        width: geom.width,
+                                        height: geom.height,
+                                    };
+                                    self.start_animation(window_id, pid, from, to);
+                                    break;
+                                }
+                            }
+
                             let new_pos = CGPoint::new(new_x as f64, new_y as f64);
                             let new_size = CGSize::new(geom.width as f64, geom.height as f64);
 
                             if let Err(e) = ax_win.set_position(new_pos) {
-                                tracing::warn!(
+                                let message = format!(
                                     "Failed to set position for window {}: {}",
-                                    window_id,
-                                    e
+                                    window_id, e
                                 );
+                                tracing::warn!("{}", message);
+                                failures.push(message);
                             }
-                            if let Err(e) = ax_win.set_size(new_size) {
-                                tracing::warn!(
-                                    "Failed to set size for window {}: {}",
-                                    window_id,
-                                    e
-                                );
+                            // Skip no-op resizes; some apps re-layout on every size write
+                            let same_size = ax_win.size().is_ok_and(|s| {
+                                s.width as u32 == geom.width && s.height as u32 == geom.height
+                            });
+                            if !same_size {
+                                if let Err(e) = ax_win.set_size(new_size) {
+                                    let message = format!(
+                                        "Failed to set size for window {}: {}",
+                                        window_id, e
+                                    );
+                                    tracing::warn!("{}", message);
+                                    failures.push(message);
+                                }
                             }
 
                             tracing::debug!(
@@ -283,30 +555,34 @@ impl WindowManipulator for MacOSWindowManipulator {
                                 geom.width,
                                 geom.height
                             );
-                            found = true;
                             break;
                         }
                     }
                 }
                 if !found {
-                    tracing::warn!(
+                    let message = format!(
                         "Could not find AX window for id {} (pid {}) when applying layout",
-                        window_id,
-                        pid
+                        window_id, pid
                     );
+                    tracing::warn!("{}", message);
+                    failures.push(message);
                 }
             }
         }
+
+        // Raise lowest order first so the highest ends up on top
+        raises.sort_by_key(|(order, _, _)| *order);
+        for (_, window_id, ax_win) in raises {
+            if let Err(e) = ax_win.raise() {
+                tracing::warn!("Failed to raise window {}: {}", window_id, e);
+            }
+        }
+        failures
     }
 
     fn focus_window(&self, window_id: u32, pid: i32) {
-        let app = AXUIElement::application(pid);
-        let ax_windows = match app.windows() {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                return;
-            }
+        let Some(ax_windows) = self.app_windows(pid) else {
+            return;
         };
 
         // Check if this app is already frontmost - if so, skip activate to avoid
@@ -347,13 +623,9 @@ impl WindowManipulator for MacOSWindowManipulator {
     }
 
     fn move_window_to_position(&self, window_id: u32, pid: i32, x: i32, y: i32) {
-        let app = AXUIElement::application(pid);
-        let ax_windows = match app.windows() {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                return;
-            }
+        self.animator.borrow_mut().cancel(window_id);
+        let Some(ax_windows) = self.app_windows(pid) else {
+            return;
         };
 
         for ax_win in &ax_windows {
@@ -384,13 +656,9 @@ impl WindowManipulator for MacOSWindowManipulator {
     }
 
     fn set_window_dimensions(&self, window_id: u32, pid: i32, width: u32, height: u32) {
-        let app = AXUIElement::application(pid);
-        let ax_windows = match app.windows() {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                return;
-            }
+        self.animator.borrow_mut().cancel(window_id);
+        let Some(ax_windows) = self.app_windows(pid) else {
+            return;
         };
 
         for ax_win in &ax_windows {
@@ -421,13 +689,9 @@ impl WindowManipulator for MacOSWindowManipulator {
     }
 
     fn set_window_frame(&self, window_id: u32, pid: i32, x: i32, y: i32, width: u32, height: u32) {
-        let app = AXUIElement::application(pid);
-        let ax_windows = match app.windows() {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                return;
-            }
+        self.animator.borrow_mut().cancel(window_id);
+        let Some(ax_windows) = self.app_windows(pid) else {
+            return;
         };
 
         for ax_win in &ax_windows {
@@ -476,56 +740,91 @@ impl WindowManipulator for MacOSWindowManipulator {
     }
 
     fn close_window(&self, window_id: u32, pid: i32) {
-        let app = AXUIElement::application(pid);
-        let ax_windows = match app.windows() {
-            Ok(w) => w,
-            Err(e) => {
-                tracing::warn!("Failed to get windows for pid {}: {}", pid, e);
-                return;
-            }
+        let Some(ax_windows) = self.app_windows(pid) else {
+            return;
         };
 
-        for ax_win in &ax_windows {
-            if let Some(wid) = ax_win.window_id() {
-                if wid == window_id {
-                    match ax_win.close_button() {
-                        Ok(close_btn) => {
-                            if let Err(e) = close_btn.press() {
-                                tracing::warn!(
-                                    "Failed to press close button for window {}: {}",
-                                    window_id,
-                                    e
-                                );
-                            } else {
-                                tracing::info!("Closed window {} (pid {})", window_id, pid);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Failed to get close button for window {}: {}",
-                                window_id,
-                                e
-                            );
-                        }
-                    }
+        let Some(ax_win) = ax_windows.iter().find(|w| w.window_id() == Some(window_id)) else {
+            tracing::warn!(
+                "Could not find AX window for id {} (pid {})",
+                window_id,
+                pid
+            );
+            return;
+        };
+
+        match ax_win.close_button() {
+            Ok(close_btn) if close_btn.is_enabled().unwrap_or(true) => match close_btn.press() {
+                Ok(()) => {
+                    tracing::info!("Closed window {} (pid {})", window_id, pid);
                     return;
                 }
-            }
+                Err(e) => tracing::warn!(
+                    "Failed to press close button for window {}: {}",
+                    window_id,
+                    e
+                ),
+            },
+            Ok(_) => tracing::info!("Close button of window {} is disabled", window_id),
+            Err(e) => tracing::info!("Window {} has no close button: {}", window_id, e),
         }
 
-        tracing::warn!(
-            "Could not find AX window for id {} (pid {})",
-            window_id,
-            pid
-        );
+        tracing::info!("Sending Cmd-W to pid {} for window {}", pid, window_id);
+        send_close_shortcut(pid);
+    }
+
+    fn hide_app(&self, pid: i32) {
+        if !hide_application(pid) {
+            tracing::warn!("Failed to hide application (pid {})", pid);
+        }
     }
 
-    fn exec_command(&self, command: &str, path: &str) -> Result<(), String> {
-        crate::macos::exec_command(command, path)
+    fn unhide_app(&self, pid: i32) {
+        if !unhide_application(pid) {
+            tracing::warn!("Failed to unhide application (pid {})", pid);
+        }
+    }
+
+    fn quit_app(&self, pid: i32, force: bool) {
+        if !quit_application(pid, force) {
+            tracing::warn!("Failed to quit application (pid {})", pid);
+        }
+    }
+
+    fn exec_command(
+        &self,
+        command: &str,
+        args: Option<&[String]>,
+        path: &str,
+        env: &[(String, String)],
+    ) -> Result<u32, String> {
+        crate::macos::exec_command(command, args, path, env)
+    }
+
+    fn exec_hook(
+        &self,
+        command: &str,
+        path: &str,
+        env: &[(String, String)],
+        input: &str,
+    ) -> Result<u32, String> {
+        crate::macos::exec_hook(command, path, env, input)
+    }
+
+    fn schedule_hook_flush(&self, delay: Duration) {
+        let timer = self.hook_timer.load(Ordering::Acquire) as CFRunLoopTimerRef;
+        if !timer.is_null() {
+            unsafe {
+                CFRunLoopTimerSetNextFireDate(
+                    timer,
+                    CFAbsoluteTimeGetCurrent() + delay.as_secs_f64(),
+                )
+            };
+        }
     }
 
-    fn exec_command_tracked(&self, command: &str, path: &str) -> Result<u32, String> {
-        crate::macos::exec_command_tracked(command, path)
+    fn is_process_alive(&self, pid: u32) -> bool {
+        crate::macos::is_process_alive(pid)
     }
 
     fn terminate_process(&self, pid: u32) {
@@ -543,20 +842,45 @@ impl WindowManipulator for MacOSWindowManipulator {
             tracing::debug!("Warped cursor to ({}, {})", x, y);
         }
     }
+
+    fn is_left_mouse_down(&self) -> bool {
+        crate::macos::is_left_mouse_down()
+    }
+
+    fn select_input_source(&self, id: &str) -> Result<(), String> {
+        crate::macos::select_input_source(id)
+    }
+
+    fn set_window_shadow(&self, window_id: u32, enabled: bool) -> Result<(), String> {
+        crate::macos::set_window_shadow(window_id, enabled)
+    }
+
+    fn set_window_alpha(&self, window_id: u32, alpha: f32) -> Result<(), String> {
+        crate::macos::set_window_alpha(window_id, alpha)
+    }
+
+    fn set_animation(&self, duration_ms: u32, easing: AnimationEasing) {
+        self.animator.borrow_mut().configure(duration_ms, easing);
+    }
 }
 
 impl Default for MacOSWindowManipulator {
     fn default() -> Self {
-        Self
+        Self::new(
+            Arc::new(AtomicPtr::new(std::ptr::null_mut())),
+            Arc::new(AtomicPtr::new(std::ptr::null_mut())),
+        )
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "simulate"))]
+#[cfg_attr(not(test), allow(dead_code))]
 pub mod mock {
+    use std::cell::Cell;
     use std::collections::{HashMap, HashSet};
 
     use super::*;
-    use crate::macos::{Bounds, DisplayId};
+    use crate::macos::{Bounds, DisplayId, ScreenCorners};
 
     pub struct MockWindowSystem {
         pub windows: Vec<WindowInfo>,
@@ -569,6 +893,12 @@ pub mod mock {
         /// Used to simulate transitioning windows during fullscreen.
         /// Stored as (window_id, pid).
         pub ax_only_windows: HashSet<(u32, i32)>,
+        /// Windows in native fullscreen (window IDs)
+        pub native_fullscreen_windows: HashSet<u32>,
+        /// Parent window of dialogs (window_id -> parent window_id)
+        pub parent_windows: HashMap<u32, u32>,
+        /// Ancestor command names of processes (pid -> names, nearest first)
+        pub process_ancestors: HashMap<i32, Vec<String>>,
     }
 
     impl Default for MockWindowSystem {
@@ -580,6 +910,9 @@ pub mod mock {
                 ax_accessible_pids: HashSet::from([1, 2, 3, 4, 5, 100, 1000, 1001, 1002]),
                 custom_extended_attributes: HashMap::new(),
                 ax_only_windows: HashSet::new(),
+                native_fullscreen_windows: HashSet::new(),
+                parent_windows: HashMap::new(),
+                process_ancestors: HashMap::new(),
             }
         }
     }
@@ -629,6 +962,23 @@ pub mod mock {
         pub fn add_ax_only_window(&mut self, window_id: u32, pid: i32) {
             self.ax_only_windows.insert((window_id, pid));
         }
+
+        pub fn set_native_fullscreen(&mut self, window_id: u32, fullscreen: bool) {
+            if fullscreen {
+                self.native_fullscreen_windows.insert(window_id);
+            } else {
+                self.native_fullscreen_windows.remove(&window_id);
+            }
+        }
+
+        pub fn set_parent_window(&mut self, window_id: u32, parent_id: u32) {
+            self.parent_windows.insert(window_id, parent_id);
+        }
+
+        pub fn set_process_ancestors(&mut self, pid: i32, names: &[&str]) {
+            self.process_ancestors
+                .insert(pid, names.iter().map(|s| s.to_string()).collect());
+        }
     }
 
     impl WindowSystem for MockWindowSystem {
@@ -678,6 +1028,25 @@ pub mod mock {
                 .any(|w| w.window_id == window_id && w.pid == pid)
                 || self.ax_only_windows.contains(&(window_id, pid))
         }
+
+        fn get_native_fullscreen_windows(&self, pid: i32) -> HashSet<u32> {
+            self.native_fullscreen_windows
+                .iter()
+                .copied()
+                .filter(|&id| self.window_exists_in_ax(id, pid))
+                .collect()
+        }
+
+        fn get_parent_window(&self, window_id: u32, _pid: i32) -> Option<u32> {
+            self.parent_windows.get(&window_id).copied()
+        }
+
+        fn get_process_ancestors(&self, pid: i32) -> Vec<String> {
+            self.process_ancestors
+                .get(&pid)
+                .cloned()
+                .unwrap_or_default()
+        }
     }
 
     pub fn create_test_display(
@@ -697,6 +1066,11 @@ pub mod mock {
                 height,
             },
             is_main: id == 1,
+            scale_factor: 1.0,
+            rotation: 0,
+            refresh_rate: 60.0,
+            uuid: None,
+            obstructed_corners: ScreenCorners::default(),
         }
     }
 
@@ -738,13 +1112,33 @@ pub mod mock {
         }
     }
 
+    /// Command and environment passed to exec_command
+    pub type ExecutedCommand = (String, Vec<(String, String)>);
+
     /// Mock implementation of WindowManipulator for tests
     #[derive(Default)]
-    pub struct MockWindowManipulator;
+    pub struct MockWindowManipulator {
+        pub executed: RefCell<Vec<ExecutedCommand>>,
+        /// Command and stdin passed to exec_hook
+        pub hooks: RefCell<Vec<(String, String)>>,
+        /// Delays passed to schedule_hook_flush
+        pub hook_flushes: RefCell<Vec<Duration>>,
+        /// Pids reported as exited by is_process_alive
+        pub exited: RefCell<Vec<u32>>,
+        pub input_sources: RefCell<Vec<String>>,
+        /// Window shadow changes (window_id, enabled)
+        pub shadows: RefCell<Vec<(u32, bool)>>,
+        /// Window opacity changes (window_id, alpha)
+        pub alphas: RefCell<Vec<(u32, f32)>>,
+        /// Fail set_window_shadow and set_window_alpha like a refusing window server
+        pub refuse_private_api: Cell<bool>,
+        pub warps: RefCell<Vec<(i32, i32)>>,
+        pub left_mouse_down: Cell<bool>,
+    }
 
     impl MockWindowManipulator {
         pub fn new() -> Self {
-            Self
+            Self::default()
         }
     }
 
@@ -755,7 +1149,8 @@ pub mod mock {
             _display_id: DisplayId,
             _frame: &crate::core::Rect,
             _geometries: &[WindowGeometry],
-        ) {
+        ) -> Vec<String> {
+            vec![]
         }
         fn focus_window(&self, _window_id: u32, _pid: i32) {}
         fn move_window_to_position(&self, _window_id: u32, _pid: i32, _x: i32, _y: i32) {}
@@ -771,13 +1166,91 @@ pub mod mock {
         ) {
         }
         fn close_window(&self, _window_id: u32, _pid: i32) {}
-        fn exec_command(&self, _command: &str, _path: &str) -> Result<(), String> {
-            Ok(())
+        fn hide_app(&self, _pid: i32) {}
+        fn unhide_app(&self, _pid: i32) {}
+        fn quit_app(&self, _pid: i32, _force: bool) {}
+        fn exec_command(
+            &self,
+            command: &str,
+            _args: Option<&[String]>,
+            _path: &str,
+            env: &[(String, String)],
+        ) -> Result<u32, String> {
+            self.executed
+                .borrow_mut()
+                .push((command.to_string(), env.to_vec()));
+            Ok(0)
         }
-        fn exec_command_tracked(&self, _command: &str, _path: &str) -> Result<u32, String> {
+        fn exec_hook(
+            &self,
+            command: &str,
+            _path: &str,
+            _env: &[(String, String)],
+            input: &str,
+        ) -> Result<u32, String> {
+            self.hooks
+                .borrow_mut()
+                .push((command.to_string(), input.to_string()));
             Ok(0)
         }
+        fn schedule_hook_flush(&self, delay: Duration) {
+            self.hook_flushes.borrow_mut().push(delay);
+        }
+        fn is_process_alive(&self, pid: u32) -> bool {
+            !self.exited.borrow().contains(&pid)
+        }
         fn terminate_process(&self, _pid: u32) {}
-        fn warp_cursor(&self, _x: i32, _y: i32) {}
+        fn warp_cursor(&self, x: i32, y: i32) {
+            self.warps.borrow_mut().push((x, y));
+        }
+        fn is_left_mouse_down(&self) -> bool {
+            self.left_mouse_down.get()
+        }
+        fn select_input_source(&self, id: &str) -> Result<(), String> {
+            self.input_sources.borrow_mut().push(id.to_string());
+            Ok(())
+        }
+        fn set_window_shadow(&self, window_id: u32, enabled: bool) -> Result<(), String> {
+            if self.refuse_private_api.get() {
+                return Err(format!("Refused shadow for window {}", window_id));
+            }
+            self.shadows.borrow_mut().push((window_id, enabled));
+            Ok(())
+        }
+        fn set_window_alpha(&self, window_id: u32, alpha: f32) -> Result<(), String> {
+            if self.refuse_private_api.get() {
+                return Err(format!("Refused opacity for window {}", window_id));
+            }
+            self.alphas.borrow_mut().push((window_id, alpha));
+            Ok(())
+        }
+        fn set_animation(&self, _duration_ms: u32, _easing: AnimationEasing) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unresponsive_apps_backs_off_after_consecutive_failures() {
+        let mut apps = UnresponsiveApps::default();
+        let start = Instant::now();
+
+        // A single timeout doesn't skip the app
+        apps.record_failure(1000, start);
+        assert!(!apps.is_backed_off(1000, start));
+
+        // A success in between resets the count
+        apps.record_success(1000);
+        for _ in 1..UNRESPONSIVE_AFTER_FAILURES {
+            apps.record_failure(1000, start);
+        }
+        assert!(!apps.is_backed_off(1000, start));
+
+        apps.record_failure(1000, start);
+        assert!(apps.is_backed_off(1000, start));
+        assert!(!apps.is_backed_off(1001, start));
+        assert!(!apps.is_backed_off(1000, start + UNRESPONSIVE_BACKOFF));
     }
 }
@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+
+use crate::ipc::{EventClient, IpcClient};
+use crate::macos;
+use yashiki_ipc::{Command, Response, StateEvent, SubscribeRequest, WindowInfo};
+
+/// A window offered by the switcher
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwitcherEntry {
+    pub window_id: u32,
+    pub label: String,
+}
+
+/// Pick a window across all tags from a native list (`yashiki switcher`) and focus it
+pub fn run_switcher() -> Result<()> {
+    let mut events = EventClient::connect(&SubscribeRequest::with_snapshot())?;
+    let StateEvent::Snapshot { windows, .. } = events.next_event()? else {
+        bail!("Expected a state snapshot from the daemon");
+    };
+    drop(events);
+
+    let entries = switcher_entries(windows);
+    if entries.is_empty() {
+        eprintln!("No windows");
+        return Ok(());
+    }
+    let labels: Vec<String> = entries.iter().map(|e| e.label.clone()).collect();
+    let Some(index) = macos::choose_from_list(&labels)? else {
+        return Ok(());
+    };
+
+    let cmd = Command::WindowFocusId {
+        window_id: entries[index].window_id,
+    };
+    match IpcClient::connect()?.send(&cmd)? {
+        Response::Error { message } => bail!(message),
+        _ => Ok(()),
+    }
+}
+
+/// Entries ordered by tag then app, with the focused window last so the first entry
+/// is always somewhere else
+pub fn switcher_entries(mut windows: Vec<WindowInfo>) -> Vec<SwitcherEntry> {
+    windows.sort_by_key(|w| {
+        (
+            w.is_focused,
+            // Stashed windows (no tags) after tagged ones
+            if w.tags == 0 {
+                u32::MAX
+            } else {
+                w.tags.trailing_zeros()
+            },
+            w.app_name.to_lowercase(),
+            w.id,
+        )
+    });
+    windows
+        .into_iter()
+        .map(|w| SwitcherEntry {
+            window_id: w.id,
+            label: entry_label(&w),
+        })
+        .collect()
+}
+
+fn entry_label(w: &WindowInfo) -> String {
+    let tags = if w.tags == 0 {
+        "-".to_string()
+    } else {
+        (0..32)
+            .filter(|bit| w.tags & (1 << bit) != 0)
+            .map(|bit| (bit + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    if w.title.is_empty() {
+        format!("{:<5} {}", tags, w.app_name)
+    } else {
+        format!("{:<5} {} — {}", tags, w.app_name, w.title)
+    }
+}
+
+/// Indices of the labels matching `query`, best match first (ties keep list order)
+pub fn fuzzy_filter(query: &str, labels: &[String]) -> Vec<usize> {
+    let mut matches: Vec<(usize, u32)> = labels
+        .iter()
+        .enumerate()
+        .filter_map(|(i, label)| fuzzy_score(query, label).map(|score| (i, score)))
+        .collect();
+    matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    matches.into_iter().map(|(i, _)| i).collect()
+}
+
+/// Score `text` against a space-separated query. Every term's characters must appear in
+/// order (case-insensitive); consecutive characters and word starts score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    query.split_whitespace().try_fold(0, |total, term| {
+        Some(total + term_score(&term.to_lowercase(), &text)?)
+    })
+}
+
+fn term_score(term: &str, text: &[char]) -> Option<u32> {
+    let mut score = 0;
+    let mut start = 0;
+    let mut prev = None;
+    for c in term.chars() {
+        let i = start + text[start..].iter().position(|&t| t == c)?;
+        score += 1;
+        if i > 0 && prev == Some(i - 1) {
+            score += 5;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        prev = Some(i);
+        start = i + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, app_name: &str, title: &str, tags: u32, is_focused: bool) -> WindowInfo {
+        WindowInfo {
+            id,
+            pid: id as i32,
+            title: title.to_string(),
+            app_name: app_name.to_string(),
+            app_id: None,
+            tags,
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+            is_focused,
+            is_floating: false,
+            is_fullscreen: false,
+            is_native_fullscreen: false,
+            is_manual: false,
+            output_id: 1,
+            status: None,
+            ax_id: None,
+            subrole: None,
+            window_level: None,
+            close_button: None,
+            fullscreen_button: None,
+            minimize_button: None,
+            zoom_button: None,
+            applied_rules: None,
+        }
+    }
+
+    #[test]
+    fn test_switcher_entries_order_and_labels() {
+        let entries = switcher_entries(vec![
+            window(1, "Safari", "Apple", 0b1, true),
+            window(2, "Terminal", "zsh", 0b10, false),
+            window(3, "Notes", "", 0, false),
+            window(4, "Mail", "Inbox", 0b101, false),
+        ]);
+        let ids: Vec<u32> = entries.iter().map(|e| e.window_id).collect();
+        assert_eq!(ids, vec![4, 2, 3, 1]);
+        assert_eq!(entries[0].label, "1,3   Mail — Inbox");
+        assert_eq!(entries[2].label, "-     Notes");
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("sfr", "Safari").is_some());
+        assert!(fuzzy_score("xyz", "Safari").is_none());
+        // Every term must match
+        assert!(fuzzy_score("saf term", "Safari").is_none());
+        // Consecutive and word-start matches rank higher
+        assert!(fuzzy_score("term", "Terminal") > fuzzy_score("term", "the rmdir"));
+        assert!(fuzzy_score("s", "Safari") > fuzzy_score("s", "Terminal — zsh"));
+    }
+
+    #[test]
+    fn test_fuzzy_filter_ranks_best_first() {
+        let labels = vec![
+            "1     Safari — Start Page".to_string(),
+            "2     Terminal — zsh".to_string(),
+            "3     Slack".to_string(),
+        ];
+        assert_eq!(fuzzy_filter("", &labels), vec![0, 1, 2]);
+        assert_eq!(fuzzy_filter("sa", &labels), vec![0, 2]);
+        assert_eq!(fuzzy_filter("zsh", &labels), vec![1]);
+    }
+}